@@ -18,6 +18,10 @@ fn main() {
         ("medium", "example-pdbs/1yyf.cif"),
         ("big", "example-pdbs/pTLS-6484.cif"),
     ];
+    let mut contents = Vec::with_capacity(pdb_names.len());
+    for (name, path) in &pdb_names {
+        contents.push((*name, std::fs::read(path).unwrap()))
+    }
     let mut models = Vec::with_capacity(pdb_names.len());
     for (name, path) in &pdb_names {
         models.push((*name, open_pdb(path, StrictnessLevel::Loose).unwrap().0))
@@ -26,6 +30,18 @@ fn main() {
 
     // Do the benchmarking
     results.extend(measure_multiple(bench_open, &pdb_names, "Open PDB"));
+    results.extend(measure_multiple(bench_open_raw, &contents, "Open PDB raw"));
+    results.extend(measure_multiple(
+        bench_open_bytes,
+        &contents,
+        "Open PDB bytes",
+    ));
+    #[cfg(feature = "mmap")]
+    results.extend(measure_multiple(
+        bench_open_mmap,
+        &pdb_names,
+        "Open PDB mmap",
+    ));
     results.extend(measure_multiple(bench_open, &mmcif_names, "Open mmCIF"));
     results.extend(measure_multiple(
         bench_transformation,
@@ -59,6 +75,21 @@ fn bench_open(filename: &str) {
     let (_pdb, _errors) = open(filename, StrictnessLevel::Loose).unwrap();
 }
 
+fn bench_open_raw(contents: Vec<u8>) {
+    let input = std::io::BufReader::new(contents.as_slice());
+    let (_pdb, _errors) = open_pdb_raw(input, Context::none(), StrictnessLevel::Loose).unwrap();
+}
+
+fn bench_open_bytes(contents: Vec<u8>) {
+    let (_pdb, _errors) =
+        open_pdb_bytes(&contents, Context::none(), StrictnessLevel::Loose).unwrap();
+}
+
+#[cfg(feature = "mmap")]
+fn bench_open_mmap(filename: &str) {
+    let (_pdb, _errors) = open_pdb_mmap(filename, StrictnessLevel::Loose).unwrap();
+}
+
 fn bench_transformation(mut pdb: PDB) {
     let transformation = TransformationMatrix::rotation_x(90.0);
     pdb.apply_transformation(&transformation);
@@ -0,0 +1,827 @@
+use super::lexer::parse_numeric;
+use super::lexitem::*;
+use crate::error::*;
+use crate::msgpack::*;
+use rmpv::Value as Message;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// The version of the BinaryCIF format that is written
+const VERSION: &str = "0.3.0";
+
+/// The data type of an array of signed 8 bit integers in a ByteArray encoding
+const INT8: i64 = 1;
+/// The data type of an array of signed 16 bit integers in a ByteArray encoding
+const INT16: i64 = 2;
+/// The data type of an array of signed 32 bit integers in a ByteArray encoding
+const INT32: i64 = 3;
+/// The data type of an array of unsigned 8 bit integers in a ByteArray encoding
+const UINT8: i64 = 4;
+/// The data type of an array of unsigned 16 bit integers in a ByteArray encoding
+const UINT16: i64 = 5;
+/// The data type of an array of unsigned 32 bit integers in a ByteArray encoding
+const UINT32: i64 = 6;
+/// The data type of an array of 32 bit floats in a ByteArray encoding
+const FLOAT32: i64 = 32;
+/// The data type of an array of 64 bit floats in a ByteArray encoding
+const FLOAT64: i64 = 33;
+
+/// An array in one of the stages of decoding a column
+#[derive(Debug, Clone, PartialEq)]
+enum Array {
+    /// The raw bytes
+    Bytes(Vec<u8>),
+    /// Integer values
+    Integers(Vec<i64>),
+    /// Floating point values
+    Floats(Vec<f64>),
+    /// Textual values
+    Strings(Vec<String>),
+}
+
+/// Parse/lex a BinaryCIF file containing any number of data blocks into CIF intermediate structures
+pub(crate) fn parse_binary_blocks(bytes: &[u8]) -> Result<Vec<DataBlock>, PDBError> {
+    let invalid = |message: String| {
+        PDBError::new(
+            ErrorLevel::BreakingError,
+            "Invalid BinaryCIF",
+            message,
+            Context::none(),
+        )
+    };
+    let file = rmpv::decode::read_value(&mut &bytes[..])
+        .map_err(|e| invalid(format!("The file is not valid MessagePack. Error: {e}")))?;
+    array(field(&file, "dataBlocks").map_err(invalid)?)
+        .and_then(|blocks| blocks.iter().map(parse_block).collect())
+        .map_err(invalid)
+}
+
+/// Parse a data block
+fn parse_block(block: &Message) -> Result<DataBlock, String> {
+    let mut output = DataBlock::new(text(field(block, "header")?)?);
+    for category in array(field(block, "categories")?)? {
+        let name = text(field(category, "name")?)?;
+        let name = name.strip_prefix('_').unwrap_or(name);
+        let row_count = usize::try_from(integer(field(category, "rowCount")?)?)
+            .map_err(|_| format!("The row count of category '{name}' is negative"))?;
+        let mut columns = Vec::new();
+        let mut rows: Vec<Vec<Value>> = (0..row_count).map(|_| Vec::new()).collect();
+        for column in array(field(category, "columns")?)? {
+            let column_name = text(field(column, "name")?)?;
+            let values = parse_column(column)?;
+            if values.len() != row_count {
+                return Err(format!(
+                    "Column '{name}.{column_name}' has {} values but the category has {row_count} rows",
+                    values.len()
+                ));
+            }
+            columns.push(column_name);
+            for (row, value) in rows.iter_mut().zip(values) {
+                row.push(value);
+            }
+        }
+        output.add_category(name, &columns, rows);
+    }
+    Ok(output)
+}
+
+/// Parse the values of a column, textual values that look like numbers are read as numbers, like
+/// unquoted values in CIF files
+fn parse_column(column: &Message) -> Result<Vec<Value>, String> {
+    let values: Vec<Value> = match decode(field(column, "data")?)? {
+        Array::Bytes(bytes) => bytes
+            .into_iter()
+            .map(|b| Value::from(usize::from(b)))
+            .collect(),
+        #[allow(clippy::cast_precision_loss)]
+        Array::Integers(integers) => integers
+            .into_iter()
+            .map(|n| Value::Numeric(n as f64))
+            .collect(),
+        Array::Floats(floats) => floats.into_iter().map(Value::Numeric).collect(),
+        Array::Strings(strings) => strings
+            .into_iter()
+            .map(|text| {
+                if text.is_empty() {
+                    Value::Text(text)
+                } else {
+                    parse_numeric(&text).unwrap_or(Value::Text(text))
+                }
+            })
+            .collect(),
+    };
+    match get(column, "mask").filter(|mask| !mask.is_nil()) {
+        Some(mask) => {
+            let mask = integers(decode(mask)?)?;
+            if mask.len() != values.len() {
+                return Err(
+                    "The mask of a column does not have the same length as its data".into(),
+                );
+            }
+            Ok(values
+                .into_iter()
+                .zip(mask)
+                .map(|(value, mask)| match mask {
+                    1 => Value::Inapplicable,
+                    2 => Value::Unknown,
+                    _ => value,
+                })
+                .collect())
+        }
+        None => Ok(values),
+    }
+}
+
+/// Decode encoded data, a map with the data and the list of encodings applied to it
+fn decode(encoded: &Message) -> Result<Array, String> {
+    decode_with(
+        Array::Bytes(binary(field(encoded, "data")?)?.to_vec()),
+        array(field(encoded, "encoding")?)?,
+    )
+}
+
+/// Decode the data by undoing the given encodings, the last encoding is undone first
+fn decode_with(data: Array, encodings: &[Message]) -> Result<Array, String> {
+    encodings.iter().rev().try_fold(data, decode_step)
+}
+
+/// Undo a single encoding
+#[allow(clippy::cast_precision_loss)]
+fn decode_step(data: Array, encoding: &Message) -> Result<Array, String> {
+    match text(field(encoding, "kind")?)? {
+        "ByteArray" => from_bytes(&bytes(data)?, integer(field(encoding, "type")?)?),
+        "FixedPoint" => {
+            let factor = number(field(encoding, "factor")?)?;
+            Ok(Array::Floats(
+                integers(data)?
+                    .into_iter()
+                    .map(|n| n as f64 / factor)
+                    .collect(),
+            ))
+        }
+        "IntervalQuantization" => {
+            let min = number(field(encoding, "min")?)?;
+            let max = number(field(encoding, "max")?)?;
+            let steps = number(field(encoding, "numSteps")?)?;
+            let delta = (max - min) / (steps - 1.0);
+            Ok(Array::Floats(
+                integers(data)?
+                    .into_iter()
+                    .map(|n| min + delta * n as f64)
+                    .collect(),
+            ))
+        }
+        "RunLength" => {
+            let pairs = integers(data)?;
+            if pairs.len() % 2 != 0 {
+                return Err("Run length encoded data should consist of pairs".into());
+            }
+            let size = usize::try_from(integer(field(encoding, "srcSize")?)?)
+                .map_err(|_| "Run length encoded data has a negative size".to_string())?;
+            let mut output = Vec::new();
+            for pair in pairs.chunks_exact(2) {
+                let count = usize::try_from(pair[1])
+                    .map_err(|_| "Run length encoded data has a negative length".to_string())?;
+                if count > size - output.len() {
+                    return Err(format!(
+                        "Run length encoded data is longer than its size {size}"
+                    ));
+                }
+                output.extend(std::iter::repeat(pair[0]).take(count));
+            }
+            Ok(Array::Integers(output))
+        }
+        "Delta" => {
+            let mut current = integer(field(encoding, "origin")?)?;
+            let mut output = integers(data)?;
+            for value in &mut output {
+                current = current
+                    .checked_add(*value)
+                    .ok_or("Delta encoded data overflows")?;
+                *value = current;
+            }
+            Ok(Array::Integers(output))
+        }
+        "IntegerPacking" => {
+            let unsigned = field(encoding, "isUnsigned")?.as_bool().unwrap_or(false);
+            let (upper, lower) = packing_limits(integer(field(encoding, "byteCount")?)?, unsigned)?;
+            let mut output = Vec::new();
+            let mut current: i64 = 0;
+            for value in integers(data)? {
+                current = current
+                    .checked_add(value)
+                    .ok_or("Integer packed data overflows")?;
+                if value != upper && value != lower {
+                    output.push(current);
+                    current = 0;
+                }
+            }
+            Ok(Array::Integers(output))
+        }
+        "StringArray" => {
+            let string_data = text(field(encoding, "stringData")?)?;
+            let offsets = integers(decode_with(
+                Array::Bytes(binary(field(encoding, "offsets")?)?.to_vec()),
+                array(field(encoding, "offsetEncoding")?)?,
+            )?)?;
+            let indices = integers(decode_with(data, array(field(encoding, "dataEncoding")?)?)?)?;
+            // The offsets are given in characters, so these are mapped to byte positions
+            let positions: Vec<usize> = string_data
+                .char_indices()
+                .map(|(position, _)| position)
+                .chain(std::iter::once(string_data.len()))
+                .collect();
+            let position = |offset: i64| {
+                usize::try_from(offset)
+                    .ok()
+                    .and_then(|offset| positions.get(offset).copied())
+                    .ok_or_else(|| format!("String offset {offset} is out of range"))
+            };
+            let strings = offsets
+                .windows(2)
+                .map(|pair| {
+                    string_data
+                        .get(position(pair[0])?..position(pair[1])?)
+                        .ok_or_else(|| {
+                            format!(
+                                "String offsets {} to {} are not a valid range",
+                                pair[0], pair[1]
+                            )
+                        })
+                })
+                .collect::<Result<Vec<&str>, String>>()?;
+            indices
+                .into_iter()
+                .map(|index| {
+                    if index < 0 {
+                        Ok(String::new())
+                    } else {
+                        usize::try_from(index)
+                            .ok()
+                            .and_then(|index| strings.get(index))
+                            .map(|text| (*text).to_string())
+                            .ok_or_else(|| format!("String index {index} is out of range"))
+                    }
+                })
+                .collect::<Result<Vec<String>, String>>()
+                .map(Array::Strings)
+        }
+        kind => Err(format!("Unknown encoding '{kind}'")),
+    }
+}
+
+/// Read the little endian values of the given data type
+fn from_bytes(bytes: &[u8], data_type: i64) -> Result<Array, String> {
+    /// Read all values of the given size with the given conversion function
+    fn read<const N: usize, T>(bytes: &[u8], convert: impl Fn([u8; N]) -> T) -> Vec<T> {
+        bytes
+            .chunks_exact(N)
+            .map(|chunk| {
+                let mut array = [0; N];
+                array.copy_from_slice(chunk);
+                convert(array)
+            })
+            .collect()
+    }
+    let size = match data_type {
+        INT8 | UINT8 => 1,
+        INT16 | UINT16 => 2,
+        INT32 | UINT32 | FLOAT32 => 4,
+        FLOAT64 => 8,
+        _ => return Err(format!("Unknown data type {data_type}")),
+    };
+    if bytes.len() % size != 0 {
+        return Err(format!(
+            "The length of a byte array is not a multiple of the size of its data type ({size})"
+        ));
+    }
+    Ok(match data_type {
+        INT8 => Array::Integers(read(bytes, |b| i64::from(i8::from_le_bytes(b)))),
+        INT16 => Array::Integers(read(bytes, |b| i64::from(i16::from_le_bytes(b)))),
+        INT32 => Array::Integers(read(bytes, |b| i64::from(i32::from_le_bytes(b)))),
+        UINT8 => Array::Integers(read(bytes, |b| i64::from(u8::from_le_bytes(b)))),
+        UINT16 => Array::Integers(read(bytes, |b| i64::from(u16::from_le_bytes(b)))),
+        UINT32 => Array::Integers(read(bytes, |b| i64::from(u32::from_le_bytes(b)))),
+        // Use the shortest representation of the single precision number, to get 1.1 instead of 1.100000023841858
+        FLOAT32 => Array::Floats(read(bytes, |b| {
+            let number = f32::from_le_bytes(b);
+            number
+                .to_string()
+                .parse()
+                .unwrap_or_else(|_| f64::from(number))
+        })),
+        _ => Array::Floats(read(bytes, f64::from_le_bytes)),
+    })
+}
+
+/// Get the upper and lower limits of the integer packing encoding with the given number of bytes
+fn packing_limits(byte_count: i64, unsigned: bool) -> Result<(i64, i64), String> {
+    match (byte_count, unsigned) {
+        (1, true) => Ok((0xFF, -1)),
+        (1, false) => Ok((0x7F, -0x80)),
+        (2, true) => Ok((0xFFFF, -1)),
+        (2, false) => Ok((0x7FFF, -0x8000)),
+        _ => Err(format!(
+            "Invalid byte count for integer packing {byte_count}"
+        )),
+    }
+}
+
+/// Get the bytes from a partially decoded array
+fn bytes(data: Array) -> Result<Vec<u8>, String> {
+    match data {
+        Array::Bytes(bytes) => Ok(bytes),
+        _ => Err("Expected bytes to decode".to_string()),
+    }
+}
+
+/// Get the integers from a partially decoded array
+fn integers(data: Array) -> Result<Vec<i64>, String> {
+    match data {
+        Array::Integers(integers) => Ok(integers),
+        _ => Err("Expected integers to decode".to_string()),
+    }
+}
+
+/// Write the given data blocks as a BinaryCIF file to the given sink
+pub(crate) fn write_binary_blocks(
+    blocks: &[DataBlock],
+    sink: &mut impl Write,
+) -> std::io::Result<()> {
+    let file = map(vec![
+        ("version", VERSION.into()),
+        ("encoder", "pdbtbx".into()),
+        (
+            "dataBlocks",
+            Message::Array(blocks.iter().map(encode_block).collect()),
+        ),
+    ]);
+    rmpv::encode::write_value(sink, &file).map_err(std::io::Error::from)
+}
+
+/// Create a MessagePack map with the given fields
+fn map(fields: Vec<(&str, Message)>) -> Message {
+    Message::Map(
+        fields
+            .into_iter()
+            .map(|(key, value)| (key.into(), value))
+            .collect(),
+    )
+}
+
+/// Encode a data block, save frames cannot be represented in BinaryCIF so these are not written
+fn encode_block(block: &DataBlock) -> Message {
+    map(vec![
+        ("header", block.name.as_str().into()),
+        (
+            "categories",
+            Message::Array(block.categories().iter().map(encode_category).collect()),
+        ),
+    ])
+}
+
+/// Encode a category with all its columns
+fn encode_category(category: &Category<'_>) -> Message {
+    let columns = category
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            let values: Vec<&Value> = category.rows.iter().map(|row| row[index]).collect();
+            let mask: Vec<i64> = values
+                .iter()
+                .map(|value| match value {
+                    Value::Inapplicable => 1,
+                    Value::Unknown => 2,
+                    _ => 0,
+                })
+                .collect();
+            map(vec![
+                ("name", (*name).into()),
+                ("data", encode_values(&values)),
+                (
+                    "mask",
+                    if mask.iter().any(|m| *m != 0) {
+                        encoded_data(encode_integers(&mask))
+                    } else {
+                        Message::Nil
+                    },
+                ),
+            ])
+        })
+        .collect();
+    map(vec![
+        ("name", format!("_{}", category.name).into()),
+        ("columns", Message::Array(columns)),
+        ("rowCount", category.len().into()),
+    ])
+}
+
+/// Create the encoded data map from the given encodings and data
+fn encoded_data((encoding, data): (Vec<Message>, Vec<u8>)) -> Message {
+    map(vec![
+        ("encoding", Message::Array(encoding)),
+        ("data", data.into()),
+    ])
+}
+
+/// Encode the values of a column. Columns with only numbers (and missing values) are encoded as
+/// integers, fixed point numbers, or floats. All other columns are encoded as strings.
+fn encode_values(values: &[&Value]) -> Message {
+    let numeric = values
+        .iter()
+        .all(|value| matches!(value, Value::Numeric(_)) || value.is_null());
+    if !numeric {
+        return encode_strings(values);
+    }
+    let numbers: Vec<f64> = values
+        .iter()
+        .map(|value| value.as_f64().unwrap_or(0.0))
+        .collect();
+    if let Some(integers) = fixed_point(&numbers, 1.0) {
+        return encoded_data(encode_integers(&integers));
+    }
+    for factor in [10.0, 100.0, 1_000.0, 10_000.0, 100_000.0, 1_000_000.0] {
+        if let Some(integers) = fixed_point(&numbers, factor) {
+            let (mut encoding, data) = encode_integers(&integers);
+            encoding.insert(
+                0,
+                map(vec![
+                    ("kind", "FixedPoint".into()),
+                    ("factor", factor.into()),
+                    ("srcType", FLOAT64.into()),
+                ]),
+            );
+            return encoded_data((encoding, data));
+        }
+    }
+    encoded_data((
+        vec![byte_array(FLOAT64)],
+        numbers.iter().flat_map(|n| n.to_le_bytes()).collect(),
+    ))
+}
+
+/// Get the numbers multiplied by the given factor as integers, if this is exact and fits in the
+/// 32 bit integers used by BinaryCIF
+#[allow(clippy::cast_possible_truncation, clippy::float_cmp)]
+fn fixed_point(numbers: &[f64], factor: f64) -> Option<Vec<i64>> {
+    numbers
+        .iter()
+        .map(|number| {
+            let scaled = (number * factor).round();
+            (scaled / factor == *number && scaled.abs() < f64::from(i32::MAX))
+                .then_some(scaled as i64)
+        })
+        .collect()
+}
+
+/// Encode the values of a column as strings, numbers are written like in CIF files
+#[allow(clippy::cast_possible_wrap)]
+fn encode_strings(values: &[&Value]) -> Message {
+    let mut indices = HashMap::new();
+    let mut string_data = String::new();
+    let mut offsets = vec![0];
+    let mut length = 0;
+    let data: Vec<i64> = values
+        .iter()
+        .map(|value| {
+            let text = match value {
+                Value::Text(text) => text.clone(),
+                Value::Inapplicable | Value::Unknown => String::new(),
+                number => number.to_string(),
+            };
+            let next = indices.len();
+            *indices.entry(text).or_insert_with_key(|text| {
+                string_data.push_str(text);
+                length += text.chars().count();
+                offsets.push(length as i64);
+                next as i64
+            })
+        })
+        .collect();
+    let (data_encoding, data) = encode_integers(&data);
+    let (offset_encoding, offsets) = encode_integers(&offsets);
+    encoded_data((
+        vec![map(vec![
+            ("kind", "StringArray".into()),
+            ("dataEncoding", Message::Array(data_encoding)),
+            ("stringData", string_data.into()),
+            ("offsetEncoding", Message::Array(offset_encoding)),
+            ("offsets", offsets.into()),
+        ])],
+        data,
+    ))
+}
+
+/// Encode integers, with delta and/or run length encoding if that gives smaller data, and
+/// integer packing to store the numbers in as few bytes as possible. The integers should fit in
+/// 32 bit integers.
+fn encode_integers(values: &[i64]) -> (Vec<Message>, Vec<u8>) {
+    let origin = values.first().copied().unwrap_or(0);
+    let deltas: Vec<i64> = std::iter::once(0)
+        .chain(values.windows(2).map(|pair| pair[1] - pair[0]))
+        .take(values.len())
+        .collect();
+    let delta = map(vec![
+        ("kind", "Delta".into()),
+        ("origin", origin.into()),
+        ("srcType", INT32.into()),
+    ]);
+    let options = [
+        (Vec::new(), values.to_vec()),
+        run_length(values),
+        (vec![delta.clone()], deltas.clone()),
+        {
+            let (encoding, data) = run_length(&deltas);
+            (std::iter::once(delta).chain(encoding).collect(), data)
+        },
+    ];
+    options
+        .into_iter()
+        .filter(|(_, data)| {
+            data.iter()
+                .all(|n| (i64::from(i32::MIN)..=i64::from(i32::MAX)).contains(n))
+        })
+        .map(|(mut encoding, data)| {
+            let (packing, bytes) = pack(&data);
+            encoding.extend(packing);
+            (encoding, bytes)
+        })
+        .min_by_key(|(_, bytes)| bytes.len())
+        .unwrap_or_else(|| pack(values))
+}
+
+/// Run length encode the given integers
+fn run_length(values: &[i64]) -> (Vec<Message>, Vec<i64>) {
+    let mut output: Vec<i64> = Vec::new();
+    for value in values {
+        match output.as_mut_slice() {
+            [.., last, count] if last == value => *count += 1,
+            _ => output.extend([*value, 1]),
+        }
+    }
+    (
+        vec![map(vec![
+            ("kind", "RunLength".into()),
+            ("srcType", INT32.into()),
+            ("srcSize", values.len().into()),
+        ])],
+        output,
+    )
+}
+
+/// Store the integers in as few bytes as possible, with integer packing into 8 or 16 bit
+/// integers, or as 32 bit integers if that is smaller
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_possible_wrap,
+    clippy::cast_sign_loss
+)]
+fn pack(values: &[i64]) -> (Vec<Message>, Vec<u8>) {
+    let unsigned = values.iter().all(|n| *n >= 0);
+    let packed_length = |byte_count| {
+        // The limits are known to be valid for one and two bytes
+        let (upper, lower) = packing_limits(byte_count, unsigned).unwrap_or((1, -1));
+        values
+            .iter()
+            .map(|n| {
+                if *n >= 0 {
+                    n / upper + 1
+                } else {
+                    n / lower + 1
+                }
+            })
+            .sum::<i64>()
+            * byte_count
+    };
+    let one = packed_length(1);
+    let two = packed_length(2);
+    let four = values.len() as i64 * 4;
+    if four <= one && four <= two {
+        return (
+            vec![byte_array(INT32)],
+            values
+                .iter()
+                .flat_map(|n| (*n as i32).to_le_bytes())
+                .collect(),
+        );
+    }
+    let byte_count = if one <= two { 1 } else { 2 };
+    let (upper, lower) = packing_limits(byte_count, unsigned).unwrap_or((1, -1));
+    let mut packed = Vec::new();
+    for value in values {
+        let mut value = *value;
+        if value >= 0 {
+            while value >= upper {
+                packed.push(upper);
+                value -= upper;
+            }
+        } else {
+            while value <= lower {
+                packed.push(lower);
+                value -= lower;
+            }
+        }
+        packed.push(value);
+    }
+    let (data_type, bytes) = match (byte_count, unsigned) {
+        (1, true) => (UINT8, packed.iter().map(|n| *n as u8).collect()),
+        (1, false) => (INT8, packed.iter().map(|n| *n as i8 as u8).collect()),
+        (_, true) => (
+            UINT16,
+            packed
+                .iter()
+                .flat_map(|n| (*n as u16).to_le_bytes())
+                .collect(),
+        ),
+        (_, false) => (
+            INT16,
+            packed
+                .iter()
+                .flat_map(|n| (*n as i16).to_le_bytes())
+                .collect(),
+        ),
+    };
+    (
+        vec![
+            map(vec![
+                ("kind", "IntegerPacking".into()),
+                ("byteCount", byte_count.into()),
+                ("isUnsigned", unsigned.into()),
+                ("srcSize", values.len().into()),
+            ]),
+            byte_array(data_type),
+        ],
+        bytes,
+    )
+}
+
+/// The ByteArray encoding for the given data type
+fn byte_array(data_type: i64) -> Message {
+    map(vec![
+        ("kind", "ByteArray".into()),
+        ("type", data_type.into()),
+    ])
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    /// Encode and decode the given integers
+    fn round_trip(values: &[i64]) -> Vec<i64> {
+        let (encoding, data) = encode_integers(values);
+        integers(decode_with(Array::Bytes(data), &encoding).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn integer_encodings() {
+        for values in [
+            vec![],
+            vec![0],
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10],
+            vec![1, 1, 1, 1, 1, 2, 2, 2, 2, 2],
+            vec![-200, 127, 128, 255, 256, -128, -129, 70_000, -70_000],
+            vec![i64::from(i32::MAX), i64::from(i32::MIN), 0],
+        ] {
+            assert_eq!(round_trip(&values), values);
+        }
+    }
+
+    #[test]
+    fn decoding() {
+        let encoding = |fields: Vec<(&str, Message)>| map(fields);
+        // Float32 values are read as their shortest representation
+        let floats: Vec<u8> = [1.1_f32, -2.5]
+            .iter()
+            .flat_map(|f| f.to_le_bytes())
+            .collect();
+        assert_eq!(
+            decode_with(Array::Bytes(floats), &[byte_array(FLOAT32)]),
+            Ok(Array::Floats(vec![1.1, -2.5]))
+        );
+        // Interval quantization of unsigned bytes
+        let quantized = decode_with(
+            Array::Bytes(vec![0, 2, 4]),
+            &[
+                encoding(vec![
+                    ("kind", "IntervalQuantization".into()),
+                    ("min", 1.0.into()),
+                    ("max", 2.0.into()),
+                    ("numSteps", 5.into()),
+                    ("srcType", FLOAT32.into()),
+                ]),
+                byte_array(UINT8),
+            ],
+        );
+        assert_eq!(quantized, Ok(Array::Floats(vec![1.0, 1.5, 2.0])));
+        // Packed signed bytes, 127 + 3 and -128 + -2
+        let packed = decode_with(
+            Array::Bytes(vec![127, 3, 128, 254, 5]),
+            &[
+                encoding(vec![
+                    ("kind", "IntegerPacking".into()),
+                    ("byteCount", 1.into()),
+                    ("isUnsigned", false.into()),
+                    ("srcSize", 3.into()),
+                ]),
+                byte_array(INT8),
+            ],
+        );
+        assert_eq!(packed, Ok(Array::Integers(vec![130, -130, 5])));
+        assert!(decode_with(
+            Array::Bytes(vec![0]),
+            &[encoding(vec![("kind", "Unknown".into())])]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn invalid_encodings() {
+        let encoding = |fields: Vec<(&str, Message)>| map(fields);
+        let run_length = |pairs: Vec<i64>, size: i64| {
+            decode_with(
+                Array::Integers(pairs),
+                &[encoding(vec![
+                    ("kind", "RunLength".into()),
+                    ("srcType", INT32.into()),
+                    ("srcSize", size.into()),
+                ])],
+            )
+        };
+        assert_eq!(
+            run_length(vec![7, 2, 8, 1], 3),
+            Ok(Array::Integers(vec![7, 7, 8]))
+        );
+        assert!(run_length(vec![7, 2, 8, 2], 3).is_err());
+        assert!(run_length(vec![7, i64::from(i32::MAX)], 3).is_err());
+        assert!(run_length(vec![7, -1], 3).is_err());
+        assert!(run_length(vec![7, 1], -1).is_err());
+        let delta = decode_with(
+            Array::Integers(vec![1, 1]),
+            &[encoding(vec![
+                ("kind", "Delta".into()),
+                ("origin", (i64::MAX - 1).into()),
+                ("srcType", INT32.into()),
+            ])],
+        );
+        assert!(delta.is_err());
+        let strings = |offsets: Vec<u8>| {
+            decode_with(
+                Array::Integers(vec![0, 1]),
+                &[encoding(vec![
+                    ("kind", "StringArray".into()),
+                    ("dataEncoding", Message::Array(Vec::new())),
+                    ("stringData", "ABC".into()),
+                    ("offsetEncoding", Message::Array(vec![byte_array(UINT8)])),
+                    ("offsets", Message::Binary(offsets)),
+                ])],
+            )
+        };
+        assert_eq!(
+            strings(vec![0, 2, 3]),
+            Ok(Array::Strings(vec!["AB".to_string(), "C".to_string()]))
+        );
+        assert!(strings(vec![0, 2, 1]).is_err());
+        assert!(strings(vec![0, 2, 4]).is_err());
+    }
+
+    #[test]
+    fn packing() {
+        let (encoding, data) = encode_integers(&(1..1000).collect::<Vec<_>>());
+        // Delta and run length encoding make this a single pair of bytes, after the first value
+        assert!(data.len() < 10);
+        assert_eq!(encoding[0].as_map().unwrap()[0].1.as_str(), Some("Delta"));
+    }
+
+    #[test]
+    fn columns() {
+        let column = |values: Vec<Value>| {
+            let references: Vec<&Value> = values.iter().collect();
+            let encoded = map(vec![
+                ("name", "x".into()),
+                ("data", encode_values(&references)),
+            ]);
+            parse_column(&encoded).unwrap()
+        };
+        for values in [
+            vec![
+                Value::Numeric(1.5),
+                Value::Numeric(-2.25),
+                Value::Numeric(3.0),
+            ],
+            vec![Value::Numeric(0.1 + 0.2), Value::Numeric(1e-300)],
+            vec![
+                Value::Text("ALA".to_string()),
+                Value::Text("GLY".to_string()),
+                Value::Text("ALA".to_string()),
+                Value::Text("Å".to_string()),
+                Value::Text("".to_string()),
+            ],
+            vec![Value::Text("A".to_string()), Value::Numeric(12.0)],
+        ] {
+            assert_eq!(column(values.clone()), values);
+        }
+    }
+}
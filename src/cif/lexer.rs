@@ -1,20 +1,26 @@
 #![allow(clippy::unwrap_used)]
 use super::lexitem::*;
 use crate::error::*;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
-/// Parse/lex a CIF file into CIF intermediate structure
-pub fn lex_cif(text: &str) -> Result<DataBlock, PDBError> {
-    parse_main(&mut Position {
+/// The minimal number of rows of a loop converted at once, when converting in parallel
+#[cfg(feature = "rayon")]
+const LOOP_CHUNK_SIZE: usize = 1024;
+
+/// Parse/lex a CIF file containing any number of data blocks into CIF intermediate structures
+pub(crate) fn lex_cif_blocks(text: &str) -> Result<Vec<DataBlock>, PDBError> {
+    let mut input = Position {
         text,
         line: 1,
         column: 1,
-    })
-}
-
-/// Parse a CIF file
-fn parse_main(input: &mut Position<'_>) -> Result<DataBlock, PDBError> {
-    trim_comments_and_whitespace(input);
-    parse_data_block(input)
+    };
+    let mut blocks = Vec::new();
+    trim_comments_and_whitespace(&mut input);
+    while !input.text.is_empty() {
+        blocks.push(parse_data_block(&mut input)?);
+    }
+    Ok(blocks)
 }
 
 /// Parse a data block, the main item of a CIF file
@@ -34,7 +40,8 @@ fn parse_data_block(input: &mut Position<'_>) -> Result<DataBlock, PDBError> {
     };
     loop {
         trim_comments_and_whitespace(input);
-        if input.text.is_empty() {
+        // A new data block closes this one, use a branching position to not consume the keyword
+        if input.text.is_empty() || start_with(&mut input.clone(), "data_").is_some() {
             return Ok(block);
         }
         let item = parse_data_item_or_save_frame(input)?;
@@ -87,16 +94,26 @@ fn parse_data_item(input: &mut Position<'_>) -> Result<DataItem, PDBError> {
             trim_comments_and_whitespace(input);
         }
 
-        while let Ok(value) = parse_value(input) {
+        while let Ok(value) = lex_value(input) {
             values.push(value);
         }
 
         let columns = loop_value.header.len();
         if values.len() % columns == 0 {
-            loop_value.data = Vec::with_capacity(values.len() / columns);
-            let mut iter = values.into_iter().peekable();
-            while iter.peek().is_some() {
-                loop_value.data.push((&mut iter).take(columns).collect());
+            // The values are only converted when the loop is known to be valid, this is the bulk
+            // of the work for large loops (like atom_site) so it is done in parallel if possible
+            let to_row = |row: &[RawValue<'_>]| row.iter().map(RawValue::to_value).collect();
+            #[cfg(feature = "rayon")]
+            {
+                loop_value.data = values
+                    .par_chunks(columns)
+                    .with_min_len(LOOP_CHUNK_SIZE)
+                    .map(to_row)
+                    .collect();
+            }
+            #[cfg(not(feature = "rayon"))]
+            {
+                loop_value.data = values.chunks(columns).map(to_row).collect();
             }
         } else {
             return Err(PDBError::new(
@@ -135,6 +152,11 @@ fn parse_data_item(input: &mut Position<'_>) -> Result<DataItem, PDBError> {
 
 /// Parse a value for a data item or inside a loop
 fn parse_value(input: &mut Position<'_>) -> Result<Value, PDBError> {
+    lex_value(input).map(|value| value.to_value())
+}
+
+/// Find the next value for a data item or inside a loop, without converting it into a [`Value`]
+fn lex_value<'a>(input: &mut Position<'a>) -> Result<RawValue<'a>, PDBError> {
     let start = *input;
     trim_comments_and_whitespace(input);
     if input.text.is_empty() {
@@ -163,29 +185,24 @@ fn parse_value(input: &mut Position<'_>) -> Result<Value, PDBError> {
         if let Some(value) = parse_numeric(parse_identifier(&mut branch)) {
             input.text = branch.text;
             input.column = branch.column;
-            Ok(value)
+            Ok(RawValue::Value(value))
         } else {
             input.text = &input.text[1..];
             input.column += 1;
-            Ok(Value::Inapplicable)
+            Ok(RawValue::Value(Value::Inapplicable))
         }
     } else if input.text.starts_with('?') {
         input.text = &input.text[1..];
         input.column += 1;
-        Ok(Value::Unknown)
+        Ok(RawValue::Value(Value::Unknown))
     } else if input.text.starts_with('\'') {
-        parse_enclosed(input, '\'').map(|text| Value::Text(text.to_string()))
+        parse_enclosed(input, '\'').map(RawValue::Text)
     } else if input.text.starts_with('\"') {
-        parse_enclosed(input, '\"').map(|text| Value::Text(text.to_string()))
+        parse_enclosed(input, '\"').map(RawValue::Text)
     } else if input.text.starts_with(';') {
-        parse_multiline_string(input).map(|text| Value::Text(text.to_string()))
+        parse_multiline_string(input).map(RawValue::Text)
     } else if is_ordinary(input.text.chars().next().unwrap()) {
-        let text = parse_identifier(input);
-        if let Some(value) = parse_numeric(text) {
-            Ok(value)
-        } else {
-            Ok(Value::Text(text.to_string()))
-        }
+        Ok(RawValue::Ordinary(parse_identifier(input)))
     } else {
         Err(PDBError::new(
             ErrorLevel::BreakingError,
@@ -196,27 +213,49 @@ fn parse_value(input: &mut Position<'_>) -> Result<Value, PDBError> {
     }
 }
 
+/// A value as found in the text, converting it into a [`Value`] is done separately so that the
+/// values of large loops can be converted in parallel
+enum RawValue<'a> {
+    /// A value that is already known
+    Value(Value),
+    /// A quoted or multiline piece of text
+    Text(&'a str),
+    /// An unquoted piece of text, which could be a number
+    Ordinary(&'a str),
+}
+
+impl RawValue<'_> {
+    /// Convert into a value, an unquoted piece of text is read as a number if possible
+    fn to_value(&self) -> Value {
+        match self {
+            Self::Value(value) => value.clone(),
+            Self::Text(text) => Value::Text((*text).to_string()),
+            Self::Ordinary(text) => {
+                parse_numeric(text).unwrap_or_else(|| Value::Text((*text).to_string()))
+            }
+        }
+    }
+}
+
+/// Determine if the given text would be read as a numeric value
+pub(super) fn is_numeric(text: &str) -> bool {
+    !text.is_empty() && parse_numeric(text).is_some()
+}
+
 /// Parse a numeric value from a string which is expected to be of non zero length and not containing whitespace
-fn parse_numeric(text: &str) -> Option<Value> {
+pub(super) fn parse_numeric(text: &str) -> Option<Value> {
     let mut chars_to_remove = 0;
     let first_char = text.chars().next().unwrap();
     // Parse a possible sign
-    let mut minus = false;
-    if first_char == '-' {
-        minus = true;
-        chars_to_remove += 1;
-    } else if first_char == '+' {
+    if first_char == '-' || first_char == '+' {
         chars_to_remove += 1;
     }
 
     // Parse the integer part
     let mut integer_set = false;
-    let mut value = 0;
     for c in text.chars().skip(chars_to_remove) {
-        if let Some(num) = c.to_digit(10) {
+        if c.is_ascii_digit() {
             integer_set = true;
-            value *= 10;
-            value += num;
             chars_to_remove += 1;
         } else {
             break;
@@ -225,15 +264,11 @@ fn parse_numeric(text: &str) -> Option<Value> {
 
     // Now take the decimal part
     let mut decimal_set = false;
-    let mut decimal = 0.0;
     if text.len() > chars_to_remove && text.chars().nth(chars_to_remove).unwrap() == '.' {
         chars_to_remove += 1;
-        let mut power: f64 = 1.0;
         for c in text.chars().skip(chars_to_remove) {
             if c.is_ascii_digit() {
                 decimal_set = true;
-                power *= 10.0;
-                decimal += c.to_digit(10).unwrap() as f64 / power;
                 chars_to_remove += 1;
             } else {
                 break;
@@ -242,8 +277,6 @@ fn parse_numeric(text: &str) -> Option<Value> {
     }
 
     // Now take the exponent
-    let mut exponent_set = false;
-    let mut exponent = 0;
     if text.len() > chars_to_remove {
         let next_char = text.chars().nth(chars_to_remove).unwrap();
         if next_char == 'e' || next_char == 'E' {
@@ -253,35 +286,24 @@ fn parse_numeric(text: &str) -> Option<Value> {
                 return None; // No number after the exponent
             }
             let exp_first_char = text.chars().nth(chars_to_remove).unwrap();
-            let mut exp_minus = false;
-            if exp_first_char == '-' {
-                exp_minus = true;
-                chars_to_remove += 1;
-            } else if exp_first_char == '+' {
+            if exp_first_char == '-' || exp_first_char == '+' {
                 chars_to_remove += 1;
             }
 
             // Parse the integer part
-            let mut exp_value = 0;
             for c in text.chars().skip(chars_to_remove) {
-                if let Some(num) = c.to_digit(10) {
-                    exponent_set = true;
-                    exp_value *= 10;
-                    exp_value += num;
+                if c.is_ascii_digit() {
                     chars_to_remove += 1;
                 } else {
                     break;
                 }
             }
-            #[allow(clippy::cast_possible_wrap)]
-            if exp_minus {
-                exponent = -(exp_value as i32);
-            } else {
-                exponent = exp_value as i32;
-            }
         }
     }
 
+    // The number itself is parsed by the standard library to get the closest floating point value
+    let number_end = chars_to_remove;
+
     // Take the uncertainty
     let mut uncertainty_set = false;
     let mut uncertainty = 0;
@@ -306,13 +328,7 @@ fn parse_numeric(text: &str) -> Option<Value> {
     if (!integer_set && !decimal_set) || text.len() != chars_to_remove {
         None
     } else {
-        let mut number = value as f64 + decimal;
-        if minus {
-            number *= -1.0;
-        }
-        if exponent_set {
-            number *= 10_f64.powi(exponent);
-        }
+        let number = text[..number_end].parse().ok()?;
         if uncertainty_set {
             Some(Value::NumericWithUncertainty(number, uncertainty))
         } else {
@@ -1283,6 +1299,20 @@ mod tests {
         )
     }
 
+    #[test]
+    fn lex_multiple_data_blocks() {
+        let res = lex_cif_blocks(
+            "# comment\ndata_HOH\n_chem_comp.id HOH\n#\ndata_ACT\nloop_\n_a.b\n_a.c\n1 2\n3 4\n",
+        )
+        .unwrap();
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].name, "HOH");
+        assert_eq!(res[0].items.len(), 1);
+        assert_eq!(res[1].name, "ACT");
+        assert!(matches!(&res[1].items[0], Item::DataItem(DataItem::Loop(l)) if l.data.len() == 2));
+        assert!(lex_cif_blocks("").unwrap().is_empty());
+    }
+
     fn close(a: f64, b: f64) -> bool {
         let dif = a / b;
         (1.0 - dif) > -0.000000000000001 && (dif - 1.0) < 0.000000000000001
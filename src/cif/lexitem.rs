@@ -0,0 +1,351 @@
+/// A top level data block for a CIF file, a file can contain multiple data blocks
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataBlock {
+    /// The name
+    pub name: String,
+    /// The Items
+    pub items: Vec<Item>,
+}
+
+/// An Item in a CIF file
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    /// A data item
+    DataItem(DataItem),
+    /// A saveframe
+    SaveFrame(SaveFrame),
+}
+
+/// A save frame in a CIF file
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveFrame {
+    /// The name
+    pub name: String,
+    /// The Data Items
+    pub items: Vec<DataItem>,
+}
+
+/// A data item, either a Single data item or a Loop
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataItem {
+    /// A Single data item
+    Single(Single),
+    /// A Loop
+    Loop(Loop),
+}
+
+/// A single data item, consisting of a tag with a value
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Single {
+    /// The Tag or Name
+    pub name: String,
+    /// The value
+    pub content: Value,
+}
+
+/// A loop consisting of a header with tags and a body with values
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Loop {
+    /// The header with the names for the columns
+    pub header: Vec<String>,
+    /// The data itself, the length of each inner vec (can be seen as a row) should be equal to the length of the header
+    pub data: Vec<Vec<Value>>,
+}
+
+/// A value for a CIF record
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A value that is inapplicable
+    Inapplicable,
+    /// A value that is unknown
+    Unknown,
+    /// A numeric value, integers are represented as floats
+    Numeric(f64),
+    /// A numeric value with a set uncertainty, written as 'number(uncertainty)' eg 42.0(9)
+    NumericWithUncertainty(f64, u32),
+    /// A textual value, possibly containing whitespace and newlines
+    Text(String),
+}
+
+impl DataBlock {
+    /// Create a new empty data block with the given name
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            items: Vec::new(),
+        }
+    }
+
+    /// Get the save frames in this data block
+    pub fn save_frames(&self) -> impl DoubleEndedIterator<Item = &SaveFrame> + '_ {
+        self.items.iter().filter_map(|item| match item {
+            Item::SaveFrame(frame) => Some(frame),
+            Item::DataItem(_) => None,
+        })
+    }
+
+    /// Get the data items in this data block, not including the data items in save frames
+    pub fn data_items(&self) -> impl DoubleEndedIterator<Item = &DataItem> + '_ {
+        self.items.iter().filter_map(|item| match item {
+            Item::DataItem(data_item) => Some(data_item),
+            Item::SaveFrame(_) => None,
+        })
+    }
+
+    /// Get all categories in this data block as tables, in the order of first appearance.
+    /// The categories in save frames are not included.
+    pub fn categories(&self) -> Vec<Category<'_>> {
+        categories(self.data_items())
+    }
+
+    /// Get a category by its name (eg `atom_site`, without leading underscore) as a table
+    pub fn category(&self, name: &str) -> Option<Category<'_>> {
+        category(self.data_items(), name)
+    }
+
+    /// Get the value of a single data item by its full tag (eg `entry.id`, without leading
+    /// underscore). For a category given as a loop this gives the value in the first row.
+    pub fn value(&self, tag: &str) -> Option<&Value> {
+        let (name, column) = tag.split_once('.')?;
+        self.category(name)?.get(0, column)
+    }
+
+    /// Add a single data item with the given tag (eg `entry.id`, without leading underscore)
+    pub fn add_single(&mut self, tag: impl Into<String>, value: impl Into<Value>) {
+        self.items.push(Item::DataItem(DataItem::Single(Single {
+            name: tag.into(),
+            content: value.into(),
+        })));
+    }
+
+    /// Add a loop
+    pub fn add_loop(&mut self, l: Loop) {
+        self.items.push(Item::DataItem(DataItem::Loop(l)));
+    }
+
+    /// Add a category with the given columns (without the category prefix) and rows. A category
+    /// with a single row is added as single data items, otherwise it is added as a loop.
+    /// Categories without rows are not added.
+    pub fn add_category(&mut self, name: &str, columns: &[&str], rows: Vec<Vec<Value>>) {
+        let tags = columns.iter().map(|column| format!("{name}.{column}"));
+        if rows.len() == 1 {
+            for (tag, value) in tags.zip(rows.into_iter().flatten()) {
+                self.add_single(tag, value);
+            }
+        } else if !rows.is_empty() {
+            let mut l = Loop::new(tags);
+            for row in rows {
+                l.add_row(row);
+            }
+            self.add_loop(l);
+        }
+    }
+}
+
+impl Loop {
+    /// Create a new empty loop with the given tags (eg `atom_site.id`, without leading underscore)
+    pub fn new(header: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            header: header.into_iter().map(Into::into).collect(),
+            data: Vec::new(),
+        }
+    }
+
+    /// Add a row of values, it should contain a value for every tag in the header
+    pub fn add_row(&mut self, row: Vec<Value>) {
+        debug_assert_eq!(row.len(), self.header.len());
+        self.data.push(row);
+    }
+}
+
+impl SaveFrame {
+    /// Get all categories in this save frame as tables, in the order of first appearance
+    pub fn categories(&self) -> Vec<Category<'_>> {
+        categories(self.items.iter())
+    }
+
+    /// Get a category by its name (eg `chem_comp_atom`, without leading underscore) as a table
+    pub fn category(&self, name: &str) -> Option<Category<'_>> {
+        category(self.items.iter(), name)
+    }
+}
+
+impl Item {
+    /// Get the name of the category of this item, save frames do not have a category
+    pub fn category(&self) -> Option<&str> {
+        match self {
+            Self::DataItem(item) => Some(item.category()),
+            Self::SaveFrame(_) => None,
+        }
+    }
+}
+
+impl DataItem {
+    /// Get the name of the category of this data item (eg `atom_site` for `_atom_site.id`)
+    pub fn category(&self) -> &str {
+        let tag = match self {
+            Self::Single(single) => single.name.as_str(),
+            Self::Loop(l) => l.header.first().map_or("", String::as_str),
+        };
+        tag.split_once('.').map_or(tag, |(category, _)| category)
+    }
+}
+
+impl Value {
+    /// Get the text of a textual value
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Self::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Get the number of a numeric value, ignoring the uncertainty if given
+    pub const fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Numeric(number) | Self::NumericWithUncertainty(number, _) => Some(*number),
+            _ => None,
+        }
+    }
+
+    /// Determine if this value is inapplicable (`.`) or unknown (`?`)
+    pub const fn is_null(&self) -> bool {
+        matches!(self, Self::Inapplicable | Self::Unknown)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(number: f64) -> Self {
+        Self::Numeric(number)
+    }
+}
+
+impl From<isize> for Value {
+    #[allow(clippy::cast_precision_loss)]
+    fn from(number: isize) -> Self {
+        Self::Numeric(number as f64)
+    }
+}
+
+impl From<usize> for Value {
+    #[allow(clippy::cast_precision_loss)]
+    fn from(number: usize) -> Self {
+        Self::Numeric(number as f64)
+    }
+}
+
+/// A missing value is seen as unknown (`?`)
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Unknown, Into::into)
+    }
+}
+
+/// A category of a data block or save frame as a table. A category given as single data items
+/// (`_cell.length_a 10.0`) is seen as a table with a single row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Category<'a> {
+    /// The name of the category, without leading underscore
+    pub name: &'a str,
+    /// The names of the columns, without the category prefix
+    pub columns: Vec<&'a str>,
+    /// The rows, each containing a value for every column
+    pub rows: Vec<Vec<&'a Value>>,
+}
+
+impl<'a> Category<'a> {
+    /// Get the number of rows in this category
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Determine if this category has no rows
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Get the index of the column with the given name
+    pub fn column_index(&self, column: &str) -> Option<usize> {
+        self.columns.iter().position(|c| *c == column)
+    }
+
+    /// Get all values in the column with the given name
+    pub fn column(&self, column: &str) -> Option<impl Iterator<Item = &'a Value> + '_> {
+        let index = self.column_index(column)?;
+        Some(self.rows.iter().map(move |row| row[index]))
+    }
+
+    /// Get the value in the given row in the column with the given name
+    pub fn get(&self, row: usize, column: &str) -> Option<&'a Value> {
+        let index = self.column_index(column)?;
+        self.rows.get(row).map(|row| row[index])
+    }
+}
+
+/// Split a tag in its category and column name, tags without a dot are seen as a category with an empty column name
+fn split_tag(tag: &str) -> (&str, &str) {
+    tag.split_once('.').unwrap_or((tag, ""))
+}
+
+/// Collect the given data items into categories, in the order of first appearance
+fn categories<'a>(items: impl Iterator<Item = &'a DataItem>) -> Vec<Category<'a>> {
+    let mut output: Vec<Category<'a>> = Vec::new();
+    for item in items {
+        match item {
+            DataItem::Loop(l) => {
+                let name = l.header.first().map_or("", |tag| split_tag(tag).0);
+                output.push(Category {
+                    name,
+                    columns: l.header.iter().map(|tag| split_tag(tag).1).collect(),
+                    rows: l.data.iter().map(|row| row.iter().collect()).collect(),
+                });
+            }
+            DataItem::Single(single) => {
+                let (name, column) = split_tag(&single.name);
+                if let Some(category) = output
+                    .iter_mut()
+                    .find(|c| c.name == name && c.rows.len() == 1)
+                {
+                    category.columns.push(column);
+                    category.rows[0].push(&single.content);
+                } else {
+                    output.push(Category {
+                        name,
+                        columns: vec![column],
+                        rows: vec![vec![&single.content]],
+                    });
+                }
+            }
+        }
+    }
+    output
+}
+
+/// Get the category with the given name from the given data items
+pub(crate) fn category<'a>(
+    items: impl Iterator<Item = &'a DataItem>,
+    name: &str,
+) -> Option<Category<'a>> {
+    categories(items.filter(|item| item.category() == name))
+        .into_iter()
+        .next()
+}
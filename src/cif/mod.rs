@@ -0,0 +1,169 @@
+//! Generic reading and writing of CIF files, independent of the dictionary used (like mmCIF/PDBx
+//! or the chemical component dictionary). A CIF file consists of data blocks, which contain
+//! data items and save frames. Data items are single values (`_cell.length_a 10.0`) or loops
+//! (tables), and are grouped in categories. Use [`DataBlock::category`] to get a category as a
+//! table independent of how it was written.
+//!
+//! Data blocks can also be built from scratch, with [`DataBlock::add_single`],
+//! [`DataBlock::add_loop`], and [`DataBlock::add_category`], and written with [`write`] or
+//! [`save`]. Text is quoted as needed, numbers are written with the shortest representation
+//! that is read back as the same number, and the values of loops are aligned in columns.
+//!
+//! ```rust
+//! use pdbtbx::cif;
+//! let blocks = cif::open("example-pdbs/1ubq.cif").unwrap();
+//! let cell = blocks[0].category("cell").unwrap();
+//! assert_eq!(cell.get(0, "length_a").and_then(cif::Value::as_f64), Some(50.84));
+//! let atoms = blocks[0].category("atom_site").unwrap();
+//! assert_eq!(atoms.len(), 660);
+//! println!("{}", blocks[0]); // Write the data block back as CIF
+//! ```
+//!
+//! With the `bcif` feature data blocks can also be read from and written to BinaryCIF, the
+//! MessagePack based binary encoding of CIF, see [`parse_binary`] and [`write_binary`].
+
+/// Read and write BinaryCIF files
+#[cfg(feature = "bcif")]
+mod binary;
+/// Parse CIF files into intermediate structure
+mod lexer;
+/// The CIF intermediate structure
+mod lexitem;
+/// Write the CIF intermediate structure
+mod writer;
+
+#[cfg(feature = "bcif")]
+pub(crate) use binary::parse_binary_blocks;
+pub(crate) use lexer::lex_cif_blocks;
+pub use lexitem::*;
+
+use crate::error::*;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+
+/// Parse the given CIF text into its data blocks.
+///
+/// # Errors
+/// It returns a [`PDBError`] if the text is not valid CIF.
+pub fn parse(text: &str) -> Result<Vec<DataBlock>, PDBError> {
+    lex_cif_blocks(text)
+}
+
+/// Open the given CIF file and parse it into its data blocks, see [`parse`].
+///
+/// # Errors
+/// It returns a [`PDBError`] if the file could not be read or is not valid CIF.
+pub fn open(filename: impl AsRef<str>) -> Result<Vec<DataBlock>, PDBError> {
+    let filename = filename.as_ref();
+    let mut file = if let Ok(f) = File::open(filename) {
+        f
+    } else {
+        return Err(PDBError::new(ErrorLevel::BreakingError, "Could not open file", "Could not open the specified file, make sure the path is correct, you have permission, and that it is not open in another program.", Context::show(filename)));
+    };
+    let mut contents = String::new();
+    if let Err(e) = file.read_to_string(&mut contents) {
+        return Err(PDBError::new(
+            ErrorLevel::BreakingError,
+            "Error while reading file",
+            format!("Error: {e}"),
+            Context::show(filename),
+        ));
+    }
+    parse(&contents)
+}
+
+/// Write the given data blocks as a CIF file to the given sink.
+///
+/// # Errors
+/// It returns the underlying error if writing to the sink fails.
+pub fn write(blocks: &[DataBlock], mut sink: impl Write) -> std::io::Result<()> {
+    for block in blocks {
+        write!(sink, "{block}")?;
+    }
+    sink.flush()
+}
+
+/// Save the given data blocks as a CIF file, see [`write`].
+///
+/// # Errors
+/// It returns a [`PDBError`] if the file could not be created or written to.
+pub fn save(blocks: &[DataBlock], filename: impl AsRef<str>) -> Result<(), PDBError> {
+    let filename = filename.as_ref();
+    let file = if let Ok(f) = File::create(filename) {
+        f
+    } else {
+        return Err(PDBError::new(ErrorLevel::BreakingError, "Could not open file", "Could not open the file for writing, make sure you have permission for this file and no other program is currently using it.", Context::show(filename)));
+    };
+    write(blocks, BufWriter::new(file)).map_err(|e| {
+        PDBError::new(
+            ErrorLevel::BreakingError,
+            "Error while writing file",
+            format!("Error: {e}"),
+            Context::show(filename),
+        )
+    })
+}
+
+/// Parse the given BinaryCIF bytes into its data blocks. BinaryCIF does not store whether a
+/// textual value was quoted, so textual values that look like numbers are read as numbers, like
+/// unquoted values in CIF files.
+///
+/// # Errors
+/// It returns a [`PDBError`] if the bytes are not valid BinaryCIF.
+#[cfg(feature = "bcif")]
+pub fn parse_binary(bytes: &[u8]) -> Result<Vec<DataBlock>, PDBError> {
+    parse_binary_blocks(bytes)
+}
+
+/// Open the given BinaryCIF file and parse it into its data blocks, see [`parse_binary`].
+///
+/// # Errors
+/// It returns a [`PDBError`] if the file could not be read or is not valid BinaryCIF.
+#[cfg(feature = "bcif")]
+pub fn open_binary(filename: impl AsRef<str>) -> Result<Vec<DataBlock>, PDBError> {
+    let filename = filename.as_ref();
+    match std::fs::read(filename) {
+        Ok(bytes) => parse_binary(&bytes),
+        Err(e) => Err(PDBError::new(
+            ErrorLevel::BreakingError,
+            "Could not open file",
+            format!("Could not open the specified file, make sure the path is correct, you have permission, and that it is not open in another program. Error: {e}"),
+            Context::show(filename),
+        )),
+    }
+}
+
+/// Write the given data blocks as a BinaryCIF file to the given sink. The columns are encoded
+/// with the encoding giving the smallest output. Save frames cannot be represented in BinaryCIF
+/// so these are not written.
+///
+/// # Errors
+/// It returns the underlying error if writing to the sink fails.
+#[cfg(feature = "bcif")]
+pub fn write_binary(blocks: &[DataBlock], mut sink: impl Write) -> std::io::Result<()> {
+    binary::write_binary_blocks(blocks, &mut sink)?;
+    sink.flush()
+}
+
+/// Save the given data blocks as a BinaryCIF file, see [`write_binary`].
+///
+/// # Errors
+/// It returns a [`PDBError`] if the file could not be created or written to.
+#[cfg(feature = "bcif")]
+pub fn save_binary(blocks: &[DataBlock], filename: impl AsRef<str>) -> Result<(), PDBError> {
+    let filename = filename.as_ref();
+    let file = if let Ok(f) = File::create(filename) {
+        f
+    } else {
+        return Err(PDBError::new(ErrorLevel::BreakingError, "Could not open file", "Could not open the file for writing, make sure you have permission for this file and no other program is currently using it.", Context::show(filename)));
+    };
+    write_binary(blocks, BufWriter::new(file)).map_err(|e| {
+        PDBError::new(
+            ErrorLevel::BreakingError,
+            "Error while writing file",
+            format!("Error: {e}"),
+            Context::show(filename),
+        )
+    })
+}
@@ -0,0 +1,322 @@
+use super::lexitem::*;
+use std::fmt;
+
+/// The reserved words of CIF, values starting with these have to be quoted
+const RESERVED: &[&str] = &["data_", "loop_", "save_", "global_", "stop_"];
+
+/// Determine if a piece of text has to be quoted to be read back as the same textual value
+fn needs_quotes(text: &str) -> bool {
+    let lowercase = text.to_ascii_lowercase();
+    text.is_empty()
+        || text.starts_with(['_', '#', '$', '\'', '"', '[', ']', ';', '.', '?'])
+        || text.contains(|c: char| c.is_ascii_whitespace())
+        || RESERVED.iter().any(|word| lowercase.starts_with(word))
+        || super::lexer::is_numeric(text)
+}
+
+/// Determine if an item is the last item of its category given the next item, used to separate
+/// categories with `#` lines
+fn ends_category(item: &Item, next: Option<&Item>) -> bool {
+    next.map_or(true, |next| {
+        next.category().is_none() || next.category() != item.category()
+    })
+}
+
+/// Format a number with the shortest representation that is read back as the same number. Very
+/// large and very small numbers are written in scientific notation, and numbers that are not
+/// finite are written as unknown (`?`) as CIF cannot represent these.
+fn format_number(number: f64) -> String {
+    let size = number.abs();
+    if !number.is_finite() {
+        "?".to_string()
+    } else if size != 0.0 && !(1e-5..1e16).contains(&size) {
+        format!("{number:e}")
+    } else {
+        format!("{number}")
+    }
+}
+
+/// Get the widths to align the values of single data items to, consecutive single data items of
+/// the same category are aligned, all other items get a width of zero
+fn single_widths(items: &[Option<&DataItem>]) -> Vec<usize> {
+    let single = |index: usize| match items[index] {
+        Some(DataItem::Single(single)) => Some(single),
+        _ => None,
+    };
+    let mut widths = vec![0; items.len()];
+    let mut start = 0;
+    while start < items.len() {
+        let mut end = start + 1;
+        if let Some(first) = single(start) {
+            let category = items[start].map(DataItem::category);
+            while end < items.len()
+                && single(end).is_some()
+                && items[end].map(DataItem::category) == category
+            {
+                end += 1;
+            }
+            let width = (start..end)
+                .filter_map(single)
+                .map(|single| single.name.len())
+                .max()
+                .unwrap_or(first.name.len());
+            widths[start..end].fill(width);
+        }
+        start = end;
+    }
+    widths
+}
+
+/// Write a data item, padding the tag of a single data item to the given width
+fn write_data_item(f: &mut fmt::Formatter<'_>, item: &DataItem, width: usize) -> fmt::Result {
+    match item {
+        DataItem::Single(single) => write!(f, "_{:width$} {}", single.name, single.content),
+        DataItem::Loop(l) => write!(f, "{l}"),
+    }
+}
+
+impl fmt::Display for Value {
+    /// Write the value as it would be written in a CIF file, textual values are quoted when
+    /// needed and written as multi line text fields if they contain newlines or both quotes
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inapplicable => write!(f, "."),
+            Self::Unknown => write!(f, "?"),
+            Self::Numeric(number) => write!(f, "{}", format_number(*number)),
+            Self::NumericWithUncertainty(number, uncertainty) => {
+                write!(f, "{}({uncertainty})", format_number(*number))
+            }
+            Self::Text(text) => {
+                if text.contains(['\n', '\r']) || (text.contains('\'') && text.contains('"')) {
+                    // Text fields read from a file keep the line break before the closing `;`
+                    if text.ends_with(['\n', '\r']) {
+                        write!(f, "\n;{text};")
+                    } else {
+                        write!(f, "\n;{text}\n;")
+                    }
+                } else if !needs_quotes(text) {
+                    write!(f, "{text}")
+                } else if text.contains('\'') {
+                    write!(f, "\"{text}\"")
+                } else {
+                    write!(f, "'{text}'")
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Single {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "_{} {}", self.name, self.content)
+    }
+}
+
+impl fmt::Display for Loop {
+    /// Write the loop with every row on its own line and the columns aligned
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "loop_")?;
+        for tag in &self.header {
+            write!(f, "\n_{tag}")?;
+        }
+        let rows: Vec<Vec<String>> = self
+            .data
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect();
+        // Text fields span multiple lines so these are not taken into account for the alignment
+        let mut widths = vec![0; self.header.len()];
+        for row in &rows {
+            for (width, value) in widths.iter_mut().zip(row) {
+                if !value.contains('\n') {
+                    *width = (*width).max(value.len());
+                }
+            }
+        }
+        for row in &rows {
+            writeln!(f)?;
+            for (index, value) in row.iter().enumerate() {
+                if index != 0 {
+                    write!(f, " ")?;
+                }
+                if index + 1 == row.len() || value.contains('\n') {
+                    write!(f, "{value}")?;
+                } else {
+                    write!(f, "{:width$}", value, width = widths[index])?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DataItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Single(single) => write!(f, "{single}"),
+            Self::Loop(l) => write!(f, "{l}"),
+        }
+    }
+}
+
+impl fmt::Display for SaveFrame {
+    /// Write the save frame with the values of single data items of the same category aligned
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "save_{}", self.name)?;
+        let items: Vec<_> = self.items.iter().map(Some).collect();
+        for (item, width) in self.items.iter().zip(single_widths(&items)) {
+            writeln!(f)?;
+            write_data_item(f, item, width)?;
+        }
+        write!(f, "\nsave_")
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DataItem(item) => write!(f, "{item}"),
+            Self::SaveFrame(frame) => write!(f, "{frame}"),
+        }
+    }
+}
+
+impl fmt::Display for DataBlock {
+    /// Write the data block as a CIF file, with the values of single data items of the same
+    /// category aligned and a `#` line after every category
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "data_{}\n#", self.name)?;
+        let data_items: Vec<_> = self
+            .items
+            .iter()
+            .map(|item| match item {
+                Item::DataItem(data_item) => Some(data_item),
+                Item::SaveFrame(_) => None,
+            })
+            .collect();
+        let widths = single_widths(&data_items);
+        for (index, item) in self.items.iter().enumerate() {
+            match item {
+                Item::DataItem(data_item) => write_data_item(f, data_item, widths[index])?,
+                Item::SaveFrame(frame) => write!(f, "{frame}")?,
+            }
+            writeln!(f)?;
+            if ends_category(item, self.items.get(index + 1)) {
+                writeln!(f, "#")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::cif::parse;
+
+    #[test]
+    fn quoting() {
+        let text = |t: &str| Value::Text(t.to_string()).to_string();
+        assert_eq!(text("ALA"), "ALA");
+        assert_eq!(text("C1'"), "C1'");
+        assert_eq!(text("'C1"), "\"'C1\"");
+        assert_eq!(text("HELIX 1"), "'HELIX 1'");
+        assert_eq!(text("it's a test"), "\"it's a test\"");
+        assert_eq!(text("1.5"), "'1.5'");
+        assert_eq!(text("?"), "'?'");
+        assert_eq!(text(".A"), "'.A'");
+        assert_eq!(text(""), "''");
+        assert_eq!(text("data_x"), "'data_x'");
+        assert_eq!(text("line\nbreak"), "\n;line\nbreak\n;");
+        assert_eq!(text("line\nbreak\n"), "\n;line\nbreak\n;");
+        assert_eq!(Value::Numeric(1.5).to_string(), "1.5");
+        assert_eq!(Value::NumericWithUncertainty(42.0, 9).to_string(), "42(9)");
+        assert_eq!(Value::Inapplicable.to_string(), ".");
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(Value::Numeric(1.0).to_string(), "1");
+        assert_eq!(Value::Numeric(-0.176).to_string(), "-0.176");
+        assert_eq!(Value::Numeric(1.5e-7).to_string(), "1.5e-7");
+        assert_eq!(Value::Numeric(2e20).to_string(), "2e20");
+        assert_eq!(Value::Numeric(f64::NAN).to_string(), "?");
+        for number in [0.1 + 0.2, 1e-300, 123_456.789, -1e16] {
+            let text = format!("data_x _a.b {}", Value::Numeric(number));
+            assert_eq!(
+                parse(&text).unwrap()[0].value("a.b"),
+                Some(&Value::Numeric(number))
+            );
+        }
+    }
+
+    #[test]
+    fn alignment() {
+        let mut block = DataBlock::new("TEST");
+        block.add_category(
+            "cell",
+            &["length_a", "Z_PDB"],
+            vec![vec![10.5.into(), None::<usize>.into()]],
+        );
+        block.add_category(
+            "struct_ncs_oper",
+            &["id", "code"],
+            vec![
+                vec![1_usize.into(), "given".into()],
+                vec![10_usize.into(), "generated".into()],
+            ],
+        );
+        block.add_category("empty", &["id"], Vec::new());
+        assert_eq!(
+            block.to_string(),
+            "data_TEST
+#
+_cell.length_a 10.5
+_cell.Z_PDB    ?
+#
+loop_
+_struct_ncs_oper.id
+_struct_ncs_oper.code
+1  given
+10 generated
+#
+"
+        );
+        let mut buffer = Vec::new();
+        crate::cif::write(&[block.clone()], &mut buffer).unwrap();
+        assert_eq!(
+            parse(&String::from_utf8(buffer).unwrap()).unwrap(),
+            vec![block]
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let block = DataBlock {
+            name: "TEST".to_string(),
+            items: vec![
+                Item::DataItem(DataItem::Single(Single {
+                    name: "entry.id".to_string(),
+                    content: Value::Text("TEST".to_string()),
+                })),
+                Item::DataItem(DataItem::Loop(Loop {
+                    header: vec!["a.b".to_string(), "a.c".to_string()],
+                    data: vec![
+                        vec![Value::Numeric(1.0), Value::Text("x y".to_string())],
+                        vec![Value::Unknown, Value::Text("a\n'b\"\n".to_string())],
+                    ],
+                })),
+                Item::SaveFrame(SaveFrame {
+                    name: "frame".to_string(),
+                    items: vec![DataItem::Single(Single {
+                        name: "d.e".to_string(),
+                        content: Value::Text("5".to_string()),
+                    })],
+                }),
+            ],
+        };
+        let text = format!("{block}{block}");
+        assert_eq!(parse(&text).unwrap(), vec![block.clone(), block]);
+    }
+}
@@ -110,7 +110,7 @@ impl Context {
         } else {
             Context::Line {
                 linenumber: pos.line,
-                line: pos.text.lines().into_iter().next().unwrap().to_string(),
+                line: pos.text.lines().next().unwrap().to_string(),
                 offset: 0,
                 length: 3,
             }
@@ -132,7 +132,6 @@ impl Context {
                 lines: start
                     .text
                     .lines()
-                    .into_iter()
                     .take(end.line - start.line)
                     .map(ToString::to_string)
                     .collect::<Vec<String>>(),
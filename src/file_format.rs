@@ -0,0 +1,329 @@
+use crate::check_extension;
+use crate::error::*;
+use std::fs::File;
+use std::io::{Read, Write};
+
+/// The file formats that can be opened, see [`crate::open`], and saved, see [`crate::save`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileFormat {
+    /// PDB files, with the extensions `.pdb`, `.ent`, `.brk` or `.pdbqt`
+    Pdb,
+    /// mmCIF/PDBx files, with the extensions `.cif`, `.mmcif` or `.mcif`
+    Mmcif,
+    /// BinaryCIF files, with the extension `.bcif` (needs the `bcif` feature)
+    Bcif,
+    /// MMTF files, with the extension `.mmtf` (needs the `mmtf` feature), these can only be opened
+    Mmtf,
+}
+
+/// The compression formats that can be used for files, determined by the last extension of
+/// the file name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompressionFormat {
+    /// Gzip compression, with the extension `.gz` (needs the `compression` feature)
+    Gzip,
+    /// Bzip2 compression, with the extension `.bz2` (needs the `bzip2` feature)
+    Bzip2,
+    /// Zstandard compression, with the extension `.zst` (needs the `zstd` feature)
+    Zstd,
+    /// XZ compression, with the extension `.xz` (needs the `xz` feature)
+    Xz,
+}
+
+/// A function to decompress a file
+pub(crate) type Decoder = fn(File) -> std::io::Result<Box<dyn Read>>;
+
+/// A function to compress a file
+pub(crate) type Encoder = fn(File) -> Box<dyn Write>;
+
+/// The record names that can start a PDB file, used to recognise PDB files by their contents
+const PDB_RECORDS: &[&str] = &[
+    "HEADER", "OBSLTE", "TITLE", "SPLIT", "CAVEAT", "COMPND", "SOURCE", "KEYWDS", "EXPDTA",
+    "NUMMDL", "MDLTYP", "AUTHOR", "REVDAT", "SPRSDE", "JRNL", "REMARK", "DBREF", "SEQADV",
+    "SEQRES", "MODRES", "HET", "HETNAM", "HETSYN", "FORMUL", "HELIX", "SHEET", "SSBOND", "LINK",
+    "CISPEP", "SITE", "CRYST1", "ORIGX1", "SCALE1", "MTRIX1", "MODEL", "ATOM", "HETATM", "ROOT",
+];
+
+impl FileFormat {
+    /// Determine the file format based on the extension of the given file name, ignoring the
+    /// case of the extension and any compression extension (`.gz`, `.bz2`, `.zst` or `.xz`).
+    /// So `1UBQ.cif.gz`, `pdb1ubq.ent` and `1ubq.MMCIF` are all recognised.
+    pub fn from_filename(filename: impl AsRef<str>) -> Option<Self> {
+        let filename = CompressionFormat::strip_extension(filename.as_ref()).0;
+        [
+            (Self::Pdb, &["pdb", "ent", "brk", "pdbqt"][..]),
+            (Self::Mmcif, &["cif", "mmcif", "mcif"][..]),
+            (Self::Bcif, &["bcif"][..]),
+            (Self::Mmtf, &["mmtf"][..]),
+        ]
+        .iter()
+        .find(|(_, extensions)| extensions.iter().any(|e| check_extension(filename, e)))
+        .map(|(format, _)| *format)
+    }
+
+    /// Determine the file format based on the first bytes of a file. Textual files starting
+    /// with `data_` (or a comment) are mmCIF files and files starting with a PDB record name
+    /// are PDB files. Binary files starting with a MessagePack map are BinaryCIF files if the
+    /// `dataBlocks` key is found, otherwise they are assumed to be MMTF files.
+    pub fn detect(start: &[u8]) -> Option<Self> {
+        // A MessagePack map, with text as the first key
+        let key = match start.first() {
+            Some(0x80..=0x8f) => start.get(1),
+            Some(0xde) => start.get(3),
+            Some(0xdf) => start.get(5),
+            _ => None,
+        };
+        if let Some(0xa0..=0xbf | 0xd9) = key {
+            return if start.windows(10).any(|window| window == b"dataBlocks") {
+                Some(Self::Bcif)
+            } else {
+                Some(Self::Mmtf)
+            };
+        }
+        let text = String::from_utf8_lossy(start);
+        let line = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+        if line.starts_with('#')
+            || line
+                .get(..5)
+                .map_or(false, |start| start.eq_ignore_ascii_case("data_"))
+        {
+            Some(Self::Mmcif)
+        } else if PDB_RECORDS.iter().any(|record| {
+            line.starts_with(record)
+                && line[record.len()..]
+                    .chars()
+                    .next()
+                    .map_or(true, |c| !c.is_ascii_alphanumeric())
+        }) {
+            Some(Self::Pdb)
+        } else {
+            None
+        }
+    }
+
+    /// Get the error that the given feature is needed to open or save this format
+    #[cfg(not(all(feature = "bcif", feature = "mmtf")))]
+    pub(crate) fn feature_error(self, feature: &str, context: Context) -> PDBError {
+        PDBError::new(
+            ErrorLevel::BreakingError,
+            "Format not supported",
+            format!("Enable the `{feature}` feature of pdbtbx to use {self:?} files."),
+            context,
+        )
+    }
+}
+
+impl CompressionFormat {
+    /// Determine the compression format based on the last extension of the given file name,
+    /// ignoring case. Returns `None` for uncompressed files.
+    pub fn from_filename(filename: impl AsRef<str>) -> Option<Self> {
+        Self::strip_extension(filename.as_ref()).1
+    }
+
+    /// Split off the compression extension, if any
+    pub(crate) fn strip_extension(filename: &str) -> (&str, Option<Self>) {
+        [
+            (Self::Gzip, "gz"),
+            (Self::Bzip2, "bz2"),
+            (Self::Zstd, "zst"),
+            (Self::Xz, "xz"),
+        ]
+        .iter()
+        .find(|(_, extension)| check_extension(filename, extension))
+        .map_or((filename, None), |(format, extension)| {
+            (
+                &filename[..filename.len() - extension.len() - 1],
+                Some(*format),
+            )
+        })
+    }
+
+    /// Get the error that the needed feature for this compression format is not enabled
+    #[cfg(not(all(
+        feature = "compression",
+        feature = "bzip2",
+        feature = "zstd",
+        feature = "xz"
+    )))]
+    fn feature_error(self, context: Context) -> PDBError {
+        PDBError::new(
+            ErrorLevel::BreakingError,
+            "Compression not supported",
+            format!(
+                "Enable the `{}` feature of pdbtbx to use {self:?} compressed files.",
+                self.feature()
+            ),
+            context,
+        )
+    }
+
+    /// Get the function to decompress a file in this format, fails if the needed feature is not enabled
+    #[cfg_attr(
+        all(
+            feature = "compression",
+            feature = "bzip2",
+            feature = "zstd",
+            feature = "xz"
+        ),
+        allow(unused_variables)
+    )]
+    pub(crate) fn decoder(self, context: Context) -> Result<Decoder, PDBError> {
+        match self {
+            #[cfg(feature = "compression")]
+            Self::Gzip => Ok(|file| Ok(Box::new(flate2::read::MultiGzDecoder::new(file)))),
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2 => Ok(|file| Ok(Box::new(bzip2::read::MultiBzDecoder::new(file)))),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Ok(|file| {
+                let decoder: Box<dyn Read> = Box::new(zstd::stream::read::Decoder::new(file)?);
+                Ok(decoder)
+            }),
+            #[cfg(feature = "xz")]
+            Self::Xz => Ok(|file| Ok(Box::new(xz2::read::XzDecoder::new_multi_decoder(file)))),
+            #[cfg(not(all(
+                feature = "compression",
+                feature = "bzip2",
+                feature = "zstd",
+                feature = "xz"
+            )))]
+            _ => Err(self.feature_error(context)),
+        }
+    }
+
+    /// Get the function to compress a file in this format, fails if the needed feature is not enabled
+    #[cfg_attr(
+        all(
+            feature = "compression",
+            feature = "bzip2",
+            feature = "zstd",
+            feature = "xz"
+        ),
+        allow(unused_variables)
+    )]
+    pub(crate) fn encoder(self, context: Context) -> Result<Encoder, PDBError> {
+        match self {
+            #[cfg(feature = "compression")]
+            Self::Gzip => Ok(|file| {
+                Box::new(flate2::write::GzEncoder::new(
+                    file,
+                    flate2::Compression::default(),
+                ))
+            }),
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2 => Ok(|file| {
+                Box::new(bzip2::write::BzEncoder::new(
+                    file,
+                    bzip2::Compression::default(),
+                ))
+            }),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Ok(|file| {
+                // Creating the encoder only fails if the compression context cannot be allocated
+                Box::new(
+                    zstd::stream::write::Encoder::new(file, 0)
+                        .expect("Could not allocate a zstd compression context")
+                        .auto_finish(),
+                )
+            }),
+            #[cfg(feature = "xz")]
+            Self::Xz => Ok(|file| Box::new(xz2::write::XzEncoder::new(file, 6))),
+            #[cfg(not(all(
+                feature = "compression",
+                feature = "bzip2",
+                feature = "zstd",
+                feature = "xz"
+            )))]
+            _ => Err(self.feature_error(context)),
+        }
+    }
+
+    /// The cargo feature needed for this compression format
+    #[cfg(not(all(
+        feature = "compression",
+        feature = "bzip2",
+        feature = "zstd",
+        feature = "xz"
+    )))]
+    const fn feature(self) -> &'static str {
+        match self {
+            Self::Gzip => "compression",
+            Self::Bzip2 => "bzip2",
+            Self::Zstd => "zstd",
+            Self::Xz => "xz",
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extensions() {
+        assert_eq!(FileFormat::from_filename("1ubq.pdb"), Some(FileFormat::Pdb));
+        assert_eq!(
+            FileFormat::from_filename("PDB1UBQ.ENT"),
+            Some(FileFormat::Pdb)
+        );
+        assert_eq!(
+            FileFormat::from_filename("ligand.pdbqt"),
+            Some(FileFormat::Pdb)
+        );
+        assert_eq!(
+            FileFormat::from_filename("1ubq.MMCIF.gz"),
+            Some(FileFormat::Mmcif)
+        );
+        assert_eq!(
+            FileFormat::from_filename("1ubq.bcif.zst"),
+            Some(FileFormat::Bcif)
+        );
+        assert_eq!(
+            FileFormat::from_filename("1ubq.mmtf"),
+            Some(FileFormat::Mmtf)
+        );
+        assert_eq!(FileFormat::from_filename("1ubq.gz"), None);
+        assert_eq!(FileFormat::from_filename("1ubq.txt"), None);
+        assert_eq!(
+            CompressionFormat::from_filename("1ubq.cif.BZ2"),
+            Some(CompressionFormat::Bzip2)
+        );
+        assert_eq!(
+            CompressionFormat::from_filename("1ubq.pdb.xz"),
+            Some(CompressionFormat::Xz)
+        );
+        assert_eq!(CompressionFormat::from_filename("1ubq.pdb"), None);
+    }
+
+    #[test]
+    fn contents() {
+        assert_eq!(
+            FileFormat::detect(b"HEADER    PROTEIN\nATOM      1  N"),
+            Some(FileFormat::Pdb)
+        );
+        assert_eq!(
+            FileFormat::detect(b"\nATOM      1  N   MET A   1"),
+            Some(FileFormat::Pdb)
+        );
+        assert_eq!(FileFormat::detect(b"REMARK\n"), Some(FileFormat::Pdb));
+        assert_eq!(FileFormat::detect(b"REMARKS\n"), None);
+        assert_eq!(
+            FileFormat::detect(b"data_1UBQ\n#\n"),
+            Some(FileFormat::Mmcif)
+        );
+        assert_eq!(
+            FileFormat::detect(b"# comment\ndata_1UBQ"),
+            Some(FileFormat::Mmcif)
+        );
+        assert_eq!(
+            FileFormat::detect(b"\x83\xa7version\xa50.3.0\xaadataBlocks\x90"),
+            Some(FileFormat::Bcif)
+        );
+        assert_eq!(
+            FileFormat::detect(b"\xde\x00\x20\xabmmtfVersion"),
+            Some(FileFormat::Mmtf)
+        );
+        assert_eq!(FileFormat::detect(b"\x89PNG\r\n"), None);
+        assert_eq!(FileFormat::detect(b""), None);
+    }
+}
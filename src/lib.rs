@@ -49,11 +49,37 @@
 //! the parallel version of an iterator by prefixing the name with `par_`. Among other the looping iterators,
 //! like `atoms()`, `residues()` and `atoms_with_hierarchy()` are implemented as parallel iterators. The Rayon
 //! implementations are gated behind the `rayon` [feature](https://doc.rust-lang.org/cargo/reference/features.html)
-//! which is enabled by default.
+//! which is enabled by default. With this feature the loops of CIF files, like the `atom_site` loop with the
+//! atoms of mmCIF files, are also parsed in parallel.
 //!
 //! ## Serialization
 //! Enable the `serde` feature for [Serde](https://crates.io/crates/serde) support.
 //!
+//! ## Regular expressions
+//! Enable the `regex` feature to search for atom names with [regular expressions](https://crates.io/crates/regex),
+//! see `Term::AtomNameRegex`.
+//!
+//! ## BinaryCIF
+//! Enable the `bcif` feature to read and write [BinaryCIF](https://github.com/molstar/BinaryCIF) files, with
+//! [`open_bcif`] and [`save_bcif`] or with [`open`] and [`save`] for files with the `.bcif` extension. The
+//! generic CIF data blocks can be read and written as BinaryCIF as well, see [`cif::parse_binary`].
+//!
+//! ## MMTF
+//! Enable the `mmtf` feature to read [MMTF](https://mmtf.rcsb.org) files, with [`open_mmtf`] or with [`open`]
+//! for files with the `.mmtf` extension. The groups are read as residues and the bonds are kept, see [`PDB::bonds`].
+//!
+//! ## File formats and compression
+//! [`open`] and [`save`] determine the format of a file from its extension, including common aliases like
+//! `.ent` and `.mmcif`, and [`open`] falls back to the contents of a file if the extension is unknown, see
+//! [`FileFormat`]. Files ending in `.gz` are (de)compressed with the `compression` feature, which is enabled
+//! by default. Enable the `bzip2`, `zstd` or `xz` features for `.bz2`, `.zst` and `.xz` files, see
+//! [`CompressionFormat`].
+//!
+//! ## Memory mapped files
+//! Use [`open_pdb_bytes`] to parse the contents of a PDB file that is already in memory, and enable the `mmap`
+//! feature to use [`open_pdb_mmap`], which memory maps the file with [memmap2](https://crates.io/crates/memmap2)
+//! and parses its contents.
+//!
 //! ## Spatial lookup of atoms
 //! Enable the `rstar` feature for [rstar](https://crates.io/crates/rstar) support. This enables you to generate
 //! R*trees making it possible to do very fast lookup for atoms with spatial queries. So for example finding close
@@ -123,8 +149,14 @@ println!("There are {} backbone atoms within 3.5Aͦ of the atom at index 42", to
 #![allow(clippy::upper_case_acronyms)] // Allow PDB (and derived) names to be used
 #![cfg_attr(feature = "unstable-doc-cfg", feature(doc_cfg))]
 
+pub mod cif;
 /// To save and display errors
 mod error;
+/// To determine the format and compression of files
+mod file_format;
+/// Helpers to read MessagePack based file formats
+#[cfg(any(feature = "bcif", feature = "mmtf"))]
+mod msgpack;
 /// To open PDB files
 mod read;
 /// Reference tables for constants
@@ -143,12 +175,14 @@ mod validate;
 pub mod general_docs;
 
 pub use error::*;
+pub use file_format::{CompressionFormat, FileFormat};
 pub use read::*;
+pub use reference_tables::{get_residue_template, ResidueTemplate};
 pub use save::*;
 pub use strictness_level::StrictnessLevel;
 pub use structs::*;
 pub use transformation::*;
-pub use validate::{validate, validate_pdb};
+pub use validate::{validate, validate_geometry, validate_pdb};
 
 /// Helper function to check extensions in filenames
 fn check_extension(filename: impl AsRef<str>, extension: impl AsRef<str>) -> bool {
@@ -0,0 +1,51 @@
+use rmpv::Value as Message;
+
+/// Get a field from a MessagePack map
+pub(crate) fn get<'a>(map: &'a Message, key: &str) -> Option<&'a Message> {
+    map.as_map()?
+        .iter()
+        .find(|(k, _)| k.as_str() == Some(key))
+        .map(|(_, value)| value)
+}
+
+/// Get a required field from a MessagePack map
+pub(crate) fn field<'a>(map: &'a Message, key: &str) -> Result<&'a Message, String> {
+    get(map, key).ok_or_else(|| format!("Missing field '{key}'"))
+}
+
+/// Get the text of a MessagePack value
+pub(crate) fn text(value: &Message) -> Result<&str, String> {
+    value
+        .as_str()
+        .ok_or_else(|| format!("Expected text but found {value}"))
+}
+
+/// Get the items of a MessagePack array
+pub(crate) fn array(value: &Message) -> Result<&[Message], String> {
+    value
+        .as_array()
+        .map(Vec::as_slice)
+        .ok_or_else(|| format!("Expected an array but found {value}"))
+}
+
+/// Get the bytes of a MessagePack binary value
+pub(crate) fn binary(value: &Message) -> Result<&[u8], String> {
+    value
+        .as_slice()
+        .ok_or_else(|| "Expected binary data".to_string())
+}
+
+/// Get an integer from a MessagePack value
+pub(crate) fn integer(value: &Message) -> Result<i64, String> {
+    value
+        .as_i64()
+        .ok_or_else(|| format!("Expected an integer but found {value}"))
+}
+
+/// Get a number from a MessagePack value
+#[cfg(feature = "bcif")]
+pub(crate) fn number(value: &Message) -> Result<f64, String> {
+    value
+        .as_f64()
+        .ok_or_else(|| format!("Expected a number but found {value}"))
+}
@@ -1,19 +1,16 @@
-use std::io::{BufRead, Read, Seek};
+use std::io::{BufRead, BufReader, Read};
 
+use super::mmcif::open_mmcif_bufread;
 use super::*;
 use crate::error::*;
 use crate::structs::PDB;
-use crate::StrictnessLevel;
-
-#[cfg(feature = "compression")]
-use super::mmcif::open_mmcif_bufread;
-#[cfg(feature = "compression")]
-use flate2::read::GzDecoder;
-#[cfg(feature = "compression")]
-use std::fs;
+use crate::{CompressionFormat, FileFormat, StrictnessLevel};
 
-/// Open an atomic data file, either PDB or mmCIF/PDBx. The correct type will be
-/// determined based on the file extension.
+/// Open an atomic data file, either PDB, mmCIF/PDBx, BinaryCIF (with the `bcif` feature) or MMTF (with the
+/// `mmtf` feature). The type is determined based on the file extension, which allows for common aliases like
+/// `.ent` and `.mmcif`, see [`FileFormat::from_filename`]. If the extension is not known the type is
+/// determined based on the contents of the file, see [`FileFormat::detect`]. Compressed files are
+/// decompressed while reading, see [`CompressionFormat`] for the supported formats and the features needed.
 ///
 /// # Errors
 /// Returns a `PDBError` if a `BreakingError` is found. Otherwise it returns the PDB with all errors/warnings found while parsing it.
@@ -25,22 +22,40 @@ pub fn open(
     filename: impl AsRef<str>,
     level: StrictnessLevel,
 ) -> Result<(PDB, Vec<PDBError>), Vec<PDBError>> {
-    if check_extension(&filename, "pdb") {
-        open_pdb(filename, level)
-    } else if check_extension(&filename, "cif") {
-        open_mmcif(filename, level)
-    } else {
-        Err(vec![PDBError::new(
+    let filename = filename.as_ref();
+    let context = Context::show(filename);
+    let file = std::fs::File::open(filename).map_err(|_| {
+        vec![PDBError::new(
             ErrorLevel::BreakingError,
-            "Incorrect extension",
-            "Could not determine the type of the given file, make it .pdb or .cif",
-            Context::show(filename.as_ref()),
-        )])
-    }
+            "Could not open file",
+            "Could not open the specified file, make sure the path is correct, you have permission, and that it is not open in another program.",
+            context.clone(),
+        )]
+    })?;
+    let reader: Box<dyn Read> = match CompressionFormat::from_filename(filename) {
+        Some(compression) => {
+            let decoder = compression.decoder(context.clone()).map_err(|e| vec![e])?;
+            decoder(file).map_err(|e| {
+                vec![PDBError::new(
+                    ErrorLevel::BreakingError,
+                    "Could not start decompression",
+                    format!("Error: {e}"),
+                    context.clone(),
+                )]
+            })?
+        }
+        None => Box::new(file),
+    };
+    open_reader(
+        BufReader::new(reader),
+        FileFormat::from_filename(filename),
+        context,
+        level,
+    )
 }
 
-/// Open a compressed atomic data file, either PDB or mmCIF/PDBx. The correct type will be
-/// determined based on the file extension (.pdb.gz or .cif.gz).
+/// Open a gzip compressed atomic data file, the type of the file is determined in the same way as
+/// for [`open`], so for example `.pdb.gz`, `.ent.gz` or `.cif.gz`.
 ///
 /// # Errors
 /// Returns a `PDBError` if a `BreakingError` is found. Otherwise it returns the PDB with all errors/warnings found while parsing it.
@@ -54,34 +69,8 @@ pub fn open_gz(
     level: StrictnessLevel,
 ) -> Result<(PDB, Vec<PDBError>), Vec<PDBError>> {
     let filename = filename.as_ref();
-
-    if check_extension(filename, "gz") {
-        // open a decompression stream
-        let file = fs::File::open(filename).map_err(|_| {
-            vec![PDBError::new(
-                ErrorLevel::BreakingError,
-                "Could not open file",
-                "Could not open the given file, make sure it exists and you have the correct permissions",
-                Context::show(filename),
-            )]
-        })?;
-
-        let decompressor = GzDecoder::new(file);
-
-        let reader = std::io::BufReader::new(decompressor);
-
-        if check_extension(&filename[..filename.len() - 3], "pdb") {
-            open_pdb_raw(reader, Context::show(filename), level)
-        } else if check_extension(&filename[..filename.len() - 3], "cif") {
-            open_mmcif_bufread(reader, level)
-        } else {
-            Err(vec![PDBError::new(
-                ErrorLevel::BreakingError,
-                "Incorrect extension",
-                "Could not determine the type of the given file, make it .pdb.gz or .cif.gz",
-                Context::show(filename),
-            )])
-        }
+    if CompressionFormat::from_filename(filename) == Some(CompressionFormat::Gzip) {
+        open(filename, level)
     } else {
         Err(vec![PDBError::new(
             ErrorLevel::BreakingError,
@@ -92,62 +81,81 @@ pub fn open_gz(
     }
 }
 
-/// Open a stream with either PDB or mmCIF data. The distinction is made on the start of the first line.
-/// If it starts with `HEADER` it is a PDB file, if it starts with `data_` it is a mmCIF file.
+/// Open a stream with atomic data in any of the supported formats. The format is determined based on
+/// the start of the data, see [`FileFormat::detect`]. For example if it starts with `HEADER` it is a PDB
+/// file, and if it starts with `data_` it is a mmCIF file.
 ///
 /// # Errors
 /// Returns a `PDBError` if a `BreakingError` is found. Otherwise it returns the PDB with all errors/warnings found while parsing it.
-/// It returns a breaking error if the buffer could not be read, the file type could not be determined form the first line, or there was a breaking error in the file itself.
+/// It returns a breaking error if the buffer could not be read, the file type could not be determined form the start of the data, or there was a breaking error in the file itself.
 /// See the `PDBError` for more details.
 ///
 /// # Related
 /// If you want to open a file see [`open`]. There are also function to open a specified file type directly
 /// see [`crate::open_pdb_raw`] and [`crate::open_mmcif_raw`] respectively.
 pub fn open_raw<T: std::io::Read + std::io::Seek>(
-    mut input: std::io::BufReader<T>,
+    input: std::io::BufReader<T>,
     level: StrictnessLevel,
 ) -> Result<(PDB, Vec<PDBError>), Vec<PDBError>> {
-    let mut first_line = String::new();
-    if input.read_line(&mut first_line).is_err() {
-        return Err(vec![PDBError::new(
-            ErrorLevel::BreakingError,
-            "Buffer could not be read",
-            "The buffer provided to `open_raw` could not be read.",
-            Context::None,
-        )]);
-    }
-    if input.rewind().is_err() {
-        return Err(vec![PDBError::new(
-            ErrorLevel::BreakingError,
-            "Buffer could not be read",
-            "The buffer provided to `open_raw` could not be rewound to the start.",
-            Context::None,
-        )]);
-    }
-    if first_line.starts_with("HEADER") {
-        open_pdb_raw(input, Context::None, level)
-    } else if first_line.starts_with("data_") {
-        let mut contents = String::new();
-        if input.read_to_string(&mut contents).is_ok() {
-            open_mmcif_raw(&contents, level)
-        } else {
-            Err(vec![PDBError::new(
+    open_reader(input, None, Context::None, level)
+}
+
+/// Open the given stream in the given format, or in the format detected from the start of the stream
+fn open_reader(
+    mut input: BufReader<impl Read>,
+    format: Option<FileFormat>,
+    context: Context,
+    level: StrictnessLevel,
+) -> Result<(PDB, Vec<PDBError>), Vec<PDBError>> {
+    let format = if let Some(format) = format {
+        format
+    } else {
+        let start = input.fill_buf().map_err(|e| {
+            vec![PDBError::new(
                 ErrorLevel::BreakingError,
                 "Buffer could not be read",
-                "The buffer provided to `open_raw` could not be read to end.",
-                Context::show(&first_line),
-            )])
-        }
-    } else {
-        Err(vec![PDBError::new(
-            ErrorLevel::BreakingError,
-            "Could not determine file type",
-            "Could not determine the type of the given file, make it .pdb or .cif",
-            Context::show(&first_line),
-        )])
+                format!("Error: {e}"),
+                context.clone(),
+            )]
+        })?;
+        FileFormat::detect(start).ok_or_else(|| {
+            vec![PDBError::new(
+                ErrorLevel::BreakingError,
+                "Could not determine file type",
+                "Could not determine the type of the given file from its extension or contents, make it .pdb or .cif",
+                context.clone(),
+            )]
+        })?
+    };
+    match format {
+        FileFormat::Pdb => open_pdb_raw(input, context, level),
+        FileFormat::Mmcif => open_mmcif_bufread(input, level),
+        #[cfg(feature = "bcif")]
+        FileFormat::Bcif => open_bcif_raw(&read_bytes(input, context)?, level),
+        #[cfg(not(feature = "bcif"))]
+        FileFormat::Bcif => Err(vec![format.feature_error("bcif", context)]),
+        #[cfg(feature = "mmtf")]
+        FileFormat::Mmtf => open_mmtf_raw(&read_bytes(input, context)?, level),
+        #[cfg(not(feature = "mmtf"))]
+        FileFormat::Mmtf => Err(vec![format.feature_error("mmtf", context)]),
     }
 }
 
+/// Read the whole stream, for the binary formats
+#[cfg(any(feature = "bcif", feature = "mmtf"))]
+fn read_bytes(mut input: impl Read, context: Context) -> Result<Vec<u8>, Vec<PDBError>> {
+    let mut bytes = Vec::new();
+    input.read_to_end(&mut bytes).map_err(|e| {
+        vec![PDBError::new(
+            ErrorLevel::BreakingError,
+            "Error while reading file",
+            format!("Error: {e}"),
+            context,
+        )]
+    })?;
+    Ok(bytes)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,413 @@
+use super::parser::{get_f64, get_isize, get_text};
+use crate::cif::*;
+use crate::error::*;
+use crate::structs::*;
+use crate::StrictnessLevel;
+use std::fs::File;
+use std::io::prelude::*;
+
+/// Open a chemical component dictionary file (like `components.cif` from the wwPDB) and parse
+/// all component definitions into a [`ComponentLibrary`]. The components are described by the
+/// `_chem_comp`, `_chem_comp_atom` and `_chem_comp_bond` categories and identified by
+/// `_chem_comp.id`, the data block name is only used if that is missing. A data block can contain
+/// multiple components, their atoms and bonds are then matched on their `comp_id`.
+/// Returns a PDBError if a BreakingError is found. Otherwise it returns the library with all errors/warnings found while parsing it.
+///
+/// # Related
+/// If you want to open a dictionary from memory see [`open_components_raw`]. To apply the
+/// definitions to a structure see [`PDB::apply_components`].
+pub fn open_components(
+    filename: impl AsRef<str>,
+    level: StrictnessLevel,
+) -> Result<(ComponentLibrary, Vec<PDBError>), Vec<PDBError>> {
+    let filename = filename.as_ref();
+    let mut file = if let Ok(f) = File::open(filename) {
+        f
+    } else {
+        return Err(vec![PDBError::new(ErrorLevel::BreakingError, "Could not open file", "Could not open the specified file, make sure the path is correct, you have permission, and that it is not open in another program.", Context::show(filename))]);
+    };
+    let mut contents = String::new();
+    if let Err(e) = file.read_to_string(&mut contents) {
+        return Err(vec![PDBError::new(
+            ErrorLevel::BreakingError,
+            "Error while reading file",
+            format!("Error: {e}"),
+            Context::show(filename),
+        )]);
+    }
+    open_components_raw(&contents, level)
+}
+
+/// Parse the given chemical component dictionary `&str` into a [`ComponentLibrary`]. See [`open_components`] for details.
+pub fn open_components_raw(
+    input: &str,
+    level: StrictnessLevel,
+) -> Result<(ComponentLibrary, Vec<PDBError>), Vec<PDBError>> {
+    let blocks = match lex_cif_blocks(input) {
+        Ok(blocks) => blocks,
+        Err(e) => return Err(vec![e]),
+    };
+    let mut library = ComponentLibrary::new();
+    let mut errors = Vec::new();
+    for block in &blocks {
+        library.extend(parse_components(block, &mut errors));
+    }
+    if errors.iter().any(|e| e.fails(level)) {
+        Err(errors)
+    } else {
+        Ok((library, errors))
+    }
+}
+
+/// Parse all chemical components in a data block
+fn parse_components(block: &DataBlock, errors: &mut Vec<PDBError>) -> Vec<ChemicalComponent> {
+    let mut components = Vec::new();
+
+    /// Get the value of a column in a row of a category, parsed with the given function
+    macro_rules! parse_column {
+        ($type:tt, $table:expr, $row:expr, $column:expr, $context:expr) => {
+            match $table.0.iter().position(|c| *c == $column) {
+                Some(index) => match $type($row[index], $context, Some($column)) {
+                    Ok(value) => value,
+                    Err(e) => {
+                        errors.push(e);
+                        None
+                    }
+                },
+                None => None,
+            }
+        };
+    }
+
+    let chem_comp = get_category(block, "chem_comp");
+    for (index, row) in chem_comp.1.iter().enumerate() {
+        let context = Context::show(format!("Data block {}: chem_comp row {index}", block.name));
+        let mut component = ChemicalComponent::new(
+            parse_column!(get_text, chem_comp, row, "id", &context)
+                .unwrap_or_else(|| block.name.clone()),
+        );
+        if let Some(name) = parse_column!(get_text, chem_comp, row, "name", &context) {
+            component.set_name(name);
+        }
+        if let Some(t) = parse_column!(get_text, chem_comp, row, "type", &context) {
+            component.set_component_type(t);
+        }
+        if let Some(charge) =
+            parse_column!(get_isize, chem_comp, row, "pdbx_formal_charge", &context)
+        {
+            component.set_formal_charge(charge);
+        }
+        components.push(component);
+    }
+    if components.is_empty() {
+        components.push(ChemicalComponent::new(&block.name));
+    }
+
+    /// Get the index of the component with the `comp_id` of a row, rows without a `comp_id`
+    /// belong to the first component and unknown ids start a new component
+    macro_rules! component_index {
+        ($table:expr, $row:expr, $context:expr) => {
+            match parse_column!(get_text, $table, $row, "comp_id", $context) {
+                Some(id) => components
+                    .iter()
+                    .position(|c| c.id() == id)
+                    .unwrap_or_else(|| {
+                        components.push(ChemicalComponent::new(id));
+                        components.len() - 1
+                    }),
+                None => 0,
+            }
+        };
+    }
+
+    let atom_table = get_category(block, "chem_comp_atom");
+    for (index, row) in atom_table.1.iter().enumerate() {
+        let context = Context::show(format!(
+            "Data block {}: chem_comp_atom row {index}",
+            block.name
+        ));
+        let component = component_index!(atom_table, row, &context);
+        let name = if let Some(name) = parse_column!(get_text, atom_table, row, "atom_id", &context)
+        {
+            name
+        } else {
+            errors.push(PDBError::new(
+                ErrorLevel::LooseWarning,
+                "Component atom without name",
+                "Every atom in a chemical component should have a name (`_chem_comp_atom.atom_id`), this atom is skipped.",
+                context,
+            ));
+            continue;
+        };
+        let position = (
+            parse_column!(
+                get_f64,
+                atom_table,
+                row,
+                "pdbx_model_Cartn_x_ideal",
+                &context
+            ),
+            parse_column!(
+                get_f64,
+                atom_table,
+                row,
+                "pdbx_model_Cartn_y_ideal",
+                &context
+            ),
+            parse_column!(
+                get_f64,
+                atom_table,
+                row,
+                "pdbx_model_Cartn_z_ideal",
+                &context
+            ),
+        );
+        components[component].add_atom(ComponentAtom {
+            name,
+            element: parse_column!(get_text, atom_table, row, "type_symbol", &context)
+                .and_then(Element::from_symbol),
+            charge: parse_column!(get_isize, atom_table, row, "charge", &context).unwrap_or(0),
+            aromatic: parse_column!(get_text, atom_table, row, "pdbx_aromatic_flag", &context)
+                .map_or(false, |flag| flag == "Y"),
+            leaving: parse_column!(
+                get_text,
+                atom_table,
+                row,
+                "pdbx_leaving_atom_flag",
+                &context
+            )
+            .map_or(false, |flag| flag == "Y"),
+            ideal_position: match position {
+                (Some(x), Some(y), Some(z)) => Some((x, y, z)),
+                _ => None,
+            },
+        });
+    }
+
+    let bond_table = get_category(block, "chem_comp_bond");
+    for (index, row) in bond_table.1.iter().enumerate() {
+        let context = Context::show(format!(
+            "Data block {}: chem_comp_bond row {index}",
+            block.name
+        ));
+        let component = component_index!(bond_table, row, &context);
+        let atom1 = parse_column!(get_text, bond_table, row, "atom_id_1", &context);
+        let atom2 = parse_column!(get_text, bond_table, row, "atom_id_2", &context);
+        let value_order = parse_column!(get_text, bond_table, row, "value_order", &context)
+            .unwrap_or_else(|| "SING".to_string());
+        let aromatic = parse_column!(get_text, bond_table, row, "pdbx_aromatic_flag", &context)
+            .map_or(false, |flag| flag == "Y");
+        match (atom1, atom2, bond_order(&value_order, aromatic)) {
+            (Some(atom1), Some(atom2), Some(order)) => {
+                components[component].add_bond(atom1, atom2, order);
+            }
+            (Some(_), Some(_), None) => errors.push(PDBError::new(
+                ErrorLevel::LooseWarning,
+                "Unsupported bond order",
+                format!("The bond order '{value_order}' is not supported, this bond is skipped."),
+                context,
+            )),
+            _ => errors.push(PDBError::new(
+                ErrorLevel::LooseWarning,
+                "Incomplete component bond",
+                "Every bond in a chemical component should name both its atoms (`_chem_comp_bond.atom_id_1` and `_chem_comp_bond.atom_id_2`), this bond is skipped.",
+                context,
+            )),
+        }
+    }
+
+    components
+}
+
+/// Get the bond order from the `value_order` and `pdbx_aromatic_flag` of a `_chem_comp_bond`.
+/// Bonds flagged as aromatic are always given as [`BondOrder::Aromatic`].
+pub(super) fn bond_order(value_order: &str, aromatic: bool) -> Option<BondOrder> {
+    if aromatic {
+        return Some(BondOrder::Aromatic);
+    }
+    match value_order.to_ascii_uppercase().as_str() {
+        "SING" => Some(BondOrder::Single),
+        "DOUB" => Some(BondOrder::Double),
+        "TRIP" => Some(BondOrder::Triple),
+        "AROM" => Some(BondOrder::Aromatic),
+        "DELO" => Some(BondOrder::Delocalised),
+        _ => None,
+    }
+}
+
+/// Get a category from the data block as a table, with the column names (without the category
+/// prefix) and the rows. A category given as single data items is returned as a single row.
+pub(super) fn get_category<'a>(
+    block: &'a DataBlock,
+    category: &str,
+) -> (Vec<&'a str>, Vec<Vec<&'a Value>>) {
+    block
+        .category(category)
+        .map_or_else(|| (Vec::new(), Vec::new()), |c| (c.columns, c.rows))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    /// The acetate ion and water as given in the chemical component dictionary (shortened)
+    const COMPONENTS: &str = r#"data_ACT
+#
+_chem_comp.id                                    ACT
+_chem_comp.name                                  "ACETATE ION"
+_chem_comp.type                                  NON-POLYMER
+_chem_comp.pdbx_formal_charge                    -1
+#
+loop_
+_chem_comp_atom.comp_id
+_chem_comp_atom.atom_id
+_chem_comp_atom.type_symbol
+_chem_comp_atom.charge
+_chem_comp_atom.pdbx_aromatic_flag
+_chem_comp_atom.pdbx_leaving_atom_flag
+_chem_comp_atom.pdbx_model_Cartn_x_ideal
+_chem_comp_atom.pdbx_model_Cartn_y_ideal
+_chem_comp_atom.pdbx_model_Cartn_z_ideal
+ACT C   C 0  N N -0.006 0.000  0.000
+ACT O   O 0  N N -0.623 1.079  0.000
+ACT OXT O -1 N N -0.623 -1.079 0.000
+ACT CH3 C 0  N N 1.505  0.000  0.000
+#
+loop_
+_chem_comp_bond.comp_id
+_chem_comp_bond.atom_id_1
+_chem_comp_bond.atom_id_2
+_chem_comp_bond.value_order
+_chem_comp_bond.pdbx_aromatic_flag
+ACT C O   DOUB N
+ACT C OXT SING N
+ACT C CH3 SING N
+#
+data_HOH
+#
+_chem_comp.id                                    HOH
+_chem_comp.name                                  WATER
+_chem_comp.type                                  NON-POLYMER
+_chem_comp.pdbx_formal_charge                    0
+#
+_chem_comp_atom.comp_id                          HOH
+_chem_comp_atom.atom_id                          O
+_chem_comp_atom.type_symbol                      O
+_chem_comp_atom.charge                           0
+_chem_comp_atom.pdbx_leaving_atom_flag           N
+#
+"#;
+
+    #[test]
+    fn parse_components() {
+        let (library, errors) = open_components_raw(COMPONENTS, StrictnessLevel::Strict).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(library.len(), 2);
+        let act = library.get("ACT").unwrap();
+        assert_eq!(act.name(), "ACETATE ION");
+        assert_eq!(act.component_type(), "NON-POLYMER");
+        assert_eq!(act.formal_charge(), -1);
+        assert_eq!(act.atoms().count(), 4);
+        assert_eq!(act.atom("OXT").unwrap().charge, -1);
+        assert_eq!(act.atom("CH3").unwrap().element, Some(Element::C));
+        assert_eq!(
+            act.atom("O").unwrap().ideal_position,
+            Some((-0.623, 1.079, 0.0))
+        );
+        assert_eq!(
+            act.bonds().collect::<Vec<_>>(),
+            vec![
+                ("C", "O", BondOrder::Double),
+                ("C", "OXT", BondOrder::Single),
+                ("C", "CH3", BondOrder::Single)
+            ]
+        );
+        let hoh = library.get("HOH").unwrap();
+        assert_eq!(hoh.atoms().count(), 1);
+        assert_eq!(hoh.bonds().count(), 0);
+    }
+
+    #[test]
+    fn multiple_components_in_block() {
+        let input = r#"data_ligands
+loop_
+_chem_comp.id
+_chem_comp.name
+NH4 "AMMONIUM ION"
+CO  "CARBON MONOXIDE"
+loop_
+_chem_comp_atom.comp_id
+_chem_comp_atom.atom_id
+_chem_comp_atom.type_symbol
+NH4 N N
+CO  C C
+CO  O O
+_chem_comp_bond.comp_id     CO
+_chem_comp_bond.atom_id_1   C
+_chem_comp_bond.atom_id_2   O
+_chem_comp_bond.value_order TRIP
+"#;
+        let (library, errors) = open_components_raw(input, StrictnessLevel::Strict).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(library.len(), 2);
+        assert!(library.get("ligands").is_none());
+        let nh4 = library.get("NH4").unwrap();
+        assert_eq!(nh4.name(), "AMMONIUM ION");
+        assert_eq!(nh4.atoms().count(), 1);
+        assert_eq!(nh4.bonds().count(), 0);
+        let co = library.get("CO").unwrap();
+        assert_eq!(co.atoms().count(), 2);
+        assert_eq!(
+            co.bonds().collect::<Vec<_>>(),
+            vec![("C", "O", BondOrder::Triple)]
+        );
+    }
+
+    #[test]
+    fn block_name_fallback() {
+        let input = "data_XYZ\n_chem_comp_atom.atom_id C1\n_chem_comp_atom.type_symbol C\n";
+        let (library, _) = open_components_raw(input, StrictnessLevel::Loose).unwrap();
+        assert_eq!(library.get("XYZ").unwrap().atoms().count(), 1);
+    }
+
+    #[test]
+    fn apply_components() {
+        let (library, _) = open_components_raw(COMPONENTS, StrictnessLevel::Loose).unwrap();
+        let mut model = Model::new(1);
+        for (serial, (name, element, x)) in [
+            ("C", "C", 0.0),
+            ("O", "O", 1.2),
+            ("OXT", "O", -1.2),
+            ("CH3", "C", 2.4),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            model.add_atom(
+                Atom::new(true, serial + 1, name, x, 0.0, 0.0, 1.0, 0.0, element, 0).unwrap(),
+                "A",
+                (1, None),
+                ("ACT", None),
+            );
+        }
+        let mut pdb = PDB::new();
+        pdb.add_model(model);
+        assert_eq!(pdb.apply_components(&library), 3);
+        assert_eq!(pdb.apply_components(&library), 0);
+        assert_eq!(pdb.atom(2).unwrap().charge(), -1);
+        assert_eq!(pdb.atom(0).unwrap().charge(), 0);
+        let conformer = pdb.conformer(0).unwrap();
+        let act = conformer.component(&library).unwrap();
+        assert_eq!(act.bonds_in(conformer).count(), 3);
+        assert_eq!(act.leaving_atoms_in(conformer).count(), 0);
+    }
+
+    #[test]
+    fn bond_orders() {
+        assert_eq!(bond_order("sing", false), Some(BondOrder::Single));
+        assert_eq!(bond_order("DOUB", true), Some(BondOrder::Aromatic));
+        assert_eq!(bond_order("DELO", false), Some(BondOrder::Delocalised));
+        assert_eq!(bond_order("QUAD", false), None);
+    }
+}
@@ -1,8 +1,7 @@
-/// Parse CIF files into intermediate structure
-mod lexer;
-/// Save the CIF intermediate structure
-mod lexitem;
+/// Parse chemical component dictionaries
+mod components;
 /// Parse intermediate structure to PDB structure
 mod parser;
 
+pub use components::*;
 pub use parser::*;
@@ -1,9 +1,13 @@
-use super::lexitem::*;
+use super::components::{bond_order, get_category};
+use crate::cif::*;
 use crate::error::*;
 use crate::structs::*;
 use crate::validate::*;
 use crate::StrictnessLevel;
 use crate::TransformationMatrix;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 
@@ -56,6 +60,7 @@ pub(crate) fn open_mmcif_bufread(
 
 /// Parse the given mmCIF `&str` into a PDB struct. This allows opening mmCIF files directly from memory.
 /// Returns a PDBError if a BreakingError is found. Otherwise it returns the PDB with all errors/warnings found while parsing it.
+/// Only the first data block is read, see [`parse_mmcif`] to read files with multiple data blocks.
 ///
 /// # Related
 /// If you want to open a file see [`open_mmcif`]. There is also a function to open a PDB file directly
@@ -65,14 +70,128 @@ pub fn open_mmcif_raw(
     input: &str,
     level: StrictnessLevel,
 ) -> Result<(PDB, Vec<PDBError>), Vec<PDBError>> {
-    match super::lexer::lex_cif(input) {
-        Ok(data_block) => parse_mmcif(&data_block, level),
+    match lex_cif_blocks(input) {
+        Ok(blocks) => parse_first_block(&blocks, level),
         Err(e) => Err(vec![e]),
     }
 }
 
-/// Parse a CIF intermediate structure into a PDB
-fn parse_mmcif(
+/// Parse the given BinaryCIF file into a PDB struct.
+/// Returns a PDBError if a BreakingError is found. Otherwise it returns the PDB with all errors/warnings found while parsing it.
+///
+/// # Related
+/// If you want to open a file from memory see [`open_bcif_raw`]. To open a textual mmCIF file see [`open_mmcif`].
+#[cfg(feature = "bcif")]
+pub fn open_bcif(
+    filename: impl AsRef<str>,
+    level: StrictnessLevel,
+) -> Result<(PDB, Vec<PDBError>), Vec<PDBError>> {
+    let filename = filename.as_ref();
+    match std::fs::read(filename) {
+        Ok(bytes) => open_bcif_raw(&bytes, level),
+        Err(_) => Err(vec![PDBError::new(ErrorLevel::BreakingError, "Could not open file", "Could not open the specified file, make sure the path is correct, you have permission, and that it is not open in another program.", Context::show(filename))]),
+    }
+}
+
+/// Parse the given BinaryCIF bytes into a PDB struct, the categories are interpreted in the same
+/// way as for textual mmCIF files, see [`parse_mmcif`].
+/// Returns a PDBError if a BreakingError is found. Otherwise it returns the PDB with all errors/warnings found while parsing it.
+/// Only the first data block is read, see [`crate::cif::parse_binary`] to read files with multiple data blocks.
+#[cfg(feature = "bcif")]
+pub fn open_bcif_raw(
+    input: &[u8],
+    level: StrictnessLevel,
+) -> Result<(PDB, Vec<PDBError>), Vec<PDBError>> {
+    match parse_binary_blocks(input) {
+        Ok(blocks) => parse_first_block(&blocks, level),
+        Err(e) => Err(vec![e]),
+    }
+}
+
+/// Parse the first of the given data blocks into a PDB struct
+fn parse_first_block(
+    blocks: &[DataBlock],
+    level: StrictnessLevel,
+) -> Result<(PDB, Vec<PDBError>), Vec<PDBError>> {
+    let block = if let Some(block) = blocks.first() {
+        block
+    } else {
+        return Err(vec![PDBError::new(
+            ErrorLevel::BreakingError,
+            "Data Block not opened",
+            "The file does not contain any data block, these should be opened with \"data_\".",
+            Context::none(),
+        )]);
+    };
+    let (pdb, mut errors) = parse_mmcif(block, level)?;
+    if blocks.len() > 1 {
+        errors.push(PDBError::new(
+            ErrorLevel::GeneralWarning,
+            "Multiple data blocks",
+            format!("This file contains {} data blocks, only the first one is read. Use the `cif` module and `parse_mmcif` to read the other data blocks.", blocks.len()),
+            Context::none(),
+        ));
+    }
+    Ok((pdb, errors))
+}
+
+/// The categories of which all items are interpreted when parsing or generated when saving an
+/// mmCIF file, see [`INTERPRETED_ITEMS`] for the categories of which only some items are used
+const INTERPRETED_CATEGORIES: &[&str] = &[
+    "entry",
+    "audit_conform",
+    "database_PDB_matrix",
+    "struct_ncs_oper",
+    "structs_ncs_oper",
+    "atom_site",
+    "struct_conn",
+];
+
+/// The single data items that are interpreted when parsing or generated when saving an mmCIF
+/// file, all other items of these categories are kept as is in the PDB, see [`PDB::cif_items`].
+/// An item ending in `*` stands for all items starting with the text before it.
+const INTERPRETED_ITEMS: &[&str] = &[
+    "cell.entry_id",
+    "cell.length_a",
+    "cell.length_b",
+    "cell.length_c",
+    "cell.angle_alpha",
+    "cell.angle_beta",
+    "cell.angle_gamma",
+    "cell.Z_PDB",
+    "symmetry.entry_id",
+    "symmetry.space_group_name_H-M",
+    "symmetry.pdbx_full_space_group_name_H-M",
+    "symmetry.space_group_name_Hall",
+    "symmetry.Int_Tables_number",
+    "space_group.IT_number",
+    "space_group.name_H-M_alt",
+    "space_group.name_Hall",
+    "atom_sites.entry_id",
+    "atom_sites.Cartn_transf*",
+];
+
+/// Determine if the given data item is interpreted when parsing or generated when saving an
+/// mmCIF file. Loops are only interpreted as a whole, single data items of the categories in
+/// [`INTERPRETED_ITEMS`] are checked one by one.
+fn is_interpreted(item: &DataItem) -> bool {
+    match item {
+        DataItem::Single(single) if !INTERPRETED_CATEGORIES.contains(&item.category()) => {
+            INTERPRETED_ITEMS.iter().any(|tag| {
+                tag.strip_suffix('*').map_or(*tag == single.name, |prefix| {
+                    single.name.starts_with(prefix)
+                })
+            })
+        }
+        _ => INTERPRETED_CATEGORIES.contains(&item.category()),
+    }
+}
+
+/// Parse a data block of a CIF file, see [`crate::cif::parse`], into a PDB struct. This can be
+/// used to read any of the data blocks of files with multiple data blocks. All items which are
+/// not interpreted are kept in the PDB, see [`PDB::cif_items`].
+/// Returns a PDBError if a BreakingError is found. Otherwise it returns the PDB with all errors/warnings found while parsing it.
+pub fn parse_mmcif(
     input: &DataBlock,
     level: StrictnessLevel,
 ) -> Result<(PDB, Vec<PDBError>), Vec<PDBError>> {
@@ -84,6 +203,14 @@ fn parse_mmcif(
     pdb.identifier = Some(input.name.clone());
 
     for item in &input.items {
+        let interpreted = match item {
+            Item::DataItem(di) => is_interpreted(di),
+            Item::SaveFrame(_) => false,
+        };
+        if !interpreted {
+            pdb.add_cif_item(item.clone());
+            continue;
+        }
         let result = match item {
             Item::DataItem(di) => match di {
                 DataItem::Loop(multiple) => {
@@ -179,7 +306,7 @@ fn parse_mmcif(
                             } else {
                                 match mtrix_id {
                                     Some(id) => {
-                                        let mut mtrix = pdb.mtrix_mut().find(|m| m.serial_number == id).expect("Could not find the MtriX record with the previously found `_struct_ncs_oper.id`");
+                                        let mtrix = pdb.mtrix_mut().find(|m| m.serial_number == id).expect("Could not find the MtriX record with the previously found `_struct_ncs_oper.id`");
                                         if s.ends_with("code") {
                                             match get_text(&single.content, &context, None) {
                                                 Ok(Some(t)) if t == "given" => {mtrix.contained = true; None},
@@ -229,7 +356,8 @@ fn parse_mmcif(
     }
 
     reshuffle_conformers(&mut pdb);
-    errors.extend(validate(&pdb));
+    errors.extend(parse_connections(input, &mut pdb));
+    errors.extend(validate_invariants(&pdb));
     if errors.iter().any(|e| e.fails(level)) {
         Err(errors)
     } else {
@@ -305,6 +433,42 @@ fn flatten_result<T, E>(value: Result<Result<T, E>, E>) -> Result<T, E> {
     }
 }
 
+/// The number of rows of the atomic data loop parsed at once, when parsing in parallel
+#[cfg(feature = "rayon")]
+const ATOM_CHUNK_SIZE: usize = 1024;
+
+/// A row of the atomic data loop, parsed independently of the other rows
+struct AtomRow {
+    /// The serial number of the model the atom is part of
+    model_number: usize,
+    /// The atom, if it could be created
+    atom: Option<ParsedAtom>,
+    /// The errors found while parsing the row
+    errors: Vec<PDBError>,
+}
+
+/// An atom with the identifiers needed to place it in the hierarchy
+struct ParsedAtom {
+    /// The atom itself
+    atom: Atom,
+    /// The (author) chain name
+    chain_name: String,
+    /// The residue number, if not given it is numbered after the residues read so far
+    residue_number: Option<isize>,
+    /// The insertion code of the residue
+    insertion_code: Option<String>,
+    /// The residue name
+    residue_name: String,
+    /// The alternative location of the conformer
+    alt_loc: Option<String>,
+    /// The label residue number
+    label_seq_id: Option<isize>,
+    /// The label chain id
+    label_asym_id: Option<String>,
+    /// The label entity id
+    label_entity_id: Option<String>,
+}
+
 /// Parse a loop containing atomic data
 fn parse_atoms(input: &Loop, pdb: &mut PDB) -> Option<Vec<PDBError>> {
     #[derive(Eq, PartialEq)]
@@ -355,39 +519,16 @@ fn parse_atoms(input: &Loop, pdb: &mut PDB) -> Option<Vec<PDBError>> {
         24, ATOM_X, "atom_site.Cartn_x", Required;
         25, ATOM_Y, "atom_site.Cartn_y", Required;
         26, ATOM_Z, "atom_site.Cartn_z", Required;
+        27, ATOM_SEGMENT_ID, "atom_site.auth_seg_id", Optional;
+        28, ATOM_ENTITY_ID, "atom_site.label_entity_id", Optional;
     );
 
-    let positions_: Vec<Result<Option<usize>, PDBError>> = COLUMNS
-        .iter()
-        .map(|tag| (input.header.iter().position(|t| t == tag.1), tag))
-        .map(|(pos, tag)| match pos {
-            Some(p) => Ok(Some(p)),
-            None if tag.0 == Required => Err(PDBError::new(
-                ErrorLevel::InvalidatingError,
-                "Missing column in coordinate atoms data loop",
-                "The above column is missing",
-                Context::show(tag.1),
-            )),
-            None => Ok(None),
-        })
-        .collect();
-
-    let mut errors = positions_
-        .iter()
-        .filter_map(|i| i.clone().err())
-        .collect::<Vec<_>>();
-
-    if !errors.is_empty() {
-        return Some(errors);
-    }
-
-    // The previous lines make sure that there is no error in the vector.
-    #[allow(clippy::unwrap_used)]
-    let positions: Vec<Option<usize>> = positions_.iter().map(|i| *i.as_ref().unwrap()).collect();
-
-    for (index, row) in input.data.iter().enumerate() {
+    /// Parse a single row of the loop into an atom, this does not depend on the other rows so
+    /// rows can be parsed in parallel
+    fn parse_row(index: usize, row: &[Value], positions: &[Option<usize>]) -> AtomRow {
         let values: Vec<Option<&Value>> = positions.iter().map(|i| i.map(|x| &row[x])).collect();
         let context = Context::show(format!("Main atomic data loop row: {index}"));
+        let mut errors = Vec::new();
 
         /// Parse a column given the function to use and the column index
         macro_rules! parse_column {
@@ -413,14 +554,13 @@ fn parse_atoms(input: &Loop, pdb: &mut PDB) -> Option<Vec<PDBError>> {
         let element = parse_column!(get_text, ATOM_TYPE).expect("Atom element should be provided");
         let residue_name =
             parse_column!(get_text, ATOM_COMP_ID).expect("Residue name should be provided");
-        #[allow(clippy::cast_possible_wrap)]
-        let residue_number = parse_column!(get_isize, ATOM_AUTH_SEQ_ID).unwrap_or_else(|| {
-            parse_column!(get_isize, ATOM_SEQ_ID)
-                .unwrap_or_else(|| pdb.total_residue_count() as isize)
-        });
-        let chain_name = parse_column!(get_text, ATOM_AUTH_ASYM_ID).unwrap_or_else(|| {
-            parse_column!(get_text, ATOM_ASYM_ID).expect("Chain name should be provided")
-        });
+        let label_seq_id = parse_column!(get_isize, ATOM_SEQ_ID);
+        let label_asym_id = parse_column!(get_text, ATOM_ASYM_ID);
+        let label_entity_id = parse_column!(get_text, ATOM_ENTITY_ID);
+        let residue_number = parse_column!(get_isize, ATOM_AUTH_SEQ_ID).or(label_seq_id);
+        let chain_name = parse_column!(get_text, ATOM_AUTH_ASYM_ID)
+            .or_else(|| label_asym_id.clone())
+            .expect("Chain name should be provided");
         let pos_x = parse_column!(get_f64, ATOM_X).expect("Atom X position should be provided");
         let pos_y = parse_column!(get_f64, ATOM_Y).expect("Atom Y position should be provided");
         let pos_z = parse_column!(get_f64, ATOM_Z).expect("Atom Z position should be provided");
@@ -430,6 +570,7 @@ fn parse_atoms(input: &Loop, pdb: &mut PDB) -> Option<Vec<PDBError>> {
         let model_number = parse_column!(get_usize, ATOM_MODEL).unwrap_or(1);
         let alt_loc = parse_column!(get_text, ATOM_ALT_ID);
         let insertion_code = parse_column!(get_text, ATOM_INSERTION);
+        let segment_id = parse_column!(get_text, ATOM_SEGMENT_ID);
         let aniso_temp = [
             [
                 parse_column!(get_f64, ATOM_ANISOU_1_1),
@@ -487,23 +628,6 @@ fn parse_atoms(input: &Loop, pdb: &mut PDB) -> Option<Vec<PDBError>> {
             None
         };
 
-        let model = unsafe {
-            // I could not find a way to make the borrow checker happy, but if no item
-            // could be find the borrow should be ended and as such safe for mutating
-            // in the second branch.
-            let pdb_pointer: *mut PDB = pdb;
-            if let Some(m) = (*pdb_pointer)
-                .models_mut()
-                .find(|m| m.serial_number() == model_number)
-            {
-                m
-            } else {
-                (*pdb_pointer).add_model(Model::new(model_number));
-                #[allow(clippy::unwrap_used)]
-                (*pdb_pointer).models_mut().rev().next().unwrap()
-            }
-        };
-
         let mut hetero = false;
         if atom_type == "ATOM" {
             hetero = false;
@@ -532,20 +656,146 @@ fn parse_atoms(input: &Loop, pdb: &mut PDB) -> Option<Vec<PDBError>> {
             if let Some(matrix) = aniso {
                 atom.set_anisotropic_temperature_factors(matrix);
             }
+            if let Some(segment_id) = segment_id {
+                if atom.set_segment_id(segment_id).is_err() {
+                    errors.push(PDBError::new(
+                        ErrorLevel::InvalidatingError,
+                        "Atom segment id incorrect",
+                        "The segment id should only contain valid characters.",
+                        context,
+                    ));
+                }
+            }
 
-            model.add_atom(
-                atom,
-                chain_name,
-                (residue_number, insertion_code.as_deref()),
-                (residue_name, alt_loc.as_deref()),
-            );
+            AtomRow {
+                model_number,
+                atom: Some(ParsedAtom {
+                    atom,
+                    chain_name,
+                    residue_number,
+                    insertion_code,
+                    residue_name,
+                    alt_loc,
+                    label_seq_id,
+                    label_asym_id,
+                    label_entity_id,
+                }),
+                errors,
+            }
         } else {
             errors.push(PDBError::new(
                 ErrorLevel::InvalidatingError,
                 "Atom definition incorrect",
                 "The atom name and element should only contain valid characters.",
-                context.clone(),
-            ))
+                context,
+            ));
+            AtomRow {
+                model_number,
+                atom: None,
+                errors,
+            }
+        }
+    }
+
+    let positions_: Vec<Result<Option<usize>, PDBError>> = COLUMNS
+        .iter()
+        .map(|tag| (input.header.iter().position(|t| t == tag.1), tag))
+        .map(|(pos, tag)| match pos {
+            Some(p) => Ok(Some(p)),
+            None if tag.0 == Required => Err(PDBError::new(
+                ErrorLevel::InvalidatingError,
+                "Missing column in coordinate atoms data loop",
+                "The above column is missing",
+                Context::show(tag.1),
+            )),
+            None => Ok(None),
+        })
+        .collect();
+
+    let mut errors = positions_
+        .iter()
+        .filter_map(|i| i.clone().err())
+        .collect::<Vec<_>>();
+
+    if !errors.is_empty() {
+        return Some(errors);
+    }
+
+    // The previous lines make sure that there is no error in the vector.
+    #[allow(clippy::unwrap_used)]
+    let positions: Vec<Option<usize>> = positions_.iter().map(|i| *i.as_ref().unwrap()).collect();
+
+    #[cfg(feature = "rayon")]
+    let rows: Vec<AtomRow> = input
+        .data
+        .par_chunks(ATOM_CHUNK_SIZE)
+        .enumerate()
+        .flat_map_iter(|(chunk, rows)| {
+            let offset = chunk * ATOM_CHUNK_SIZE;
+            (offset..)
+                .zip(rows)
+                .map(|(index, row)| parse_row(index, row, &positions))
+        })
+        .collect();
+    #[cfg(not(feature = "rayon"))]
+    let rows: Vec<AtomRow> = input
+        .data
+        .iter()
+        .enumerate()
+        .map(|(index, row)| parse_row(index, row, &positions))
+        .collect();
+
+    // The hierarchy is built in the order of the rows, to keep the order of the atoms and errors
+    for row in rows {
+        errors.extend(row.errors);
+        let model = unsafe {
+            // I could not find a way to make the borrow checker happy, but if no item
+            // could be find the borrow should be ended and as such safe for mutating
+            // in the second branch.
+            let pdb_pointer: *mut PDB = pdb;
+            if let Some(m) = (*pdb_pointer)
+                .models_mut()
+                .find(|m| m.serial_number() == row.model_number)
+            {
+                m
+            } else {
+                (*pdb_pointer).add_model(Model::new(row.model_number));
+                #[allow(clippy::unwrap_used)]
+                (*pdb_pointer).models_mut().next_back().unwrap()
+            }
+        };
+
+        if let Some(atom) = row.atom {
+            #[allow(clippy::cast_possible_wrap)]
+            let residue_number = atom
+                .residue_number
+                .unwrap_or_else(|| pdb.total_residue_count() as isize);
+            model.add_atom(
+                atom.atom,
+                &atom.chain_name,
+                (residue_number, atom.insertion_code.as_deref()),
+                (atom.residue_name, atom.alt_loc.as_deref()),
+            );
+
+            // Keep the label identifiers next to the author identifiers used in the hierarchy
+            if let Some(residue) = model
+                .chains_mut()
+                .rev()
+                .find(|c| c.id() == atom.chain_name)
+                .and_then(|c| {
+                    c.residues_mut()
+                        .rev()
+                        .find(|r| r.id() == (residue_number, atom.insertion_code.as_deref()))
+                })
+            {
+                residue.set_label_seq_id(atom.label_seq_id);
+                if let Some(id) = atom.label_asym_id {
+                    residue.set_label_asym_id(id);
+                }
+                if let Some(id) = atom.label_entity_id {
+                    residue.set_label_entity_id(id);
+                }
+            }
         }
     }
     if !errors.is_empty() {
@@ -555,14 +805,197 @@ fn parse_atoms(input: &Loop, pdb: &mut PDB) -> Option<Vec<PDBError>> {
     }
 }
 
+/// Parse the `struct_conn` category and add the bonds to the PDB, has to be done after all
+/// atoms are already in place. The bonds are added in all models. Bonds to symmetry mates
+/// are skipped as these cannot be represented.
+fn parse_connections(input: &DataBlock, pdb: &mut PDB) -> Vec<PDBError> {
+    let mut errors = Vec::new();
+    let table = get_category(input, "struct_conn");
+
+    /// Parse a column given the function to use and the column name
+    macro_rules! parse_column {
+        ($type:tt, $row:expr, $column:expr, $context:expr) => {
+            match table.0.iter().position(|c| *c == $column) {
+                Some(index) => match $type($row[index], $context, Some($column)) {
+                    Ok(value) => value,
+                    Err(e) => {
+                        errors.push(e);
+                        None
+                    }
+                },
+                None => None,
+            }
+        };
+    }
+
+    let mut bonds = Vec::new();
+    for (index, row) in table.1.iter().enumerate() {
+        let context = Context::show(format!("Connection loop row: {index}"));
+        let kind = match parse_column!(get_text, row, "conn_type_id", &context)
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "covale" => Bond::Covalent,
+            "disulf" => Bond::Disulfide,
+            "hydrog" => Bond::Hydrogen,
+            "metalc" => Bond::MetalCoordination,
+            "mismat" => Bond::MisMatchedBasePairs,
+            "saltbr" => Bond::SaltBridge,
+            "modres" => Bond::CovalentModificationResidue,
+            "covale_base" => Bond::CovalentModificationNucleotideBase,
+            "covale_sugar" => Bond::CovalentModificationNucleotideSugar,
+            "covale_phosphate" => Bond::CovalentModificationNucleotidePhosphate,
+            _ => {
+                errors.push(PDBError::new(
+                    ErrorLevel::LooseWarning,
+                    "Unknown connection type",
+                    "The connection type is not one of the types defined in the mmCIF dictionary, this bond is skipped.",
+                    context.clone(),
+                ));
+                continue;
+            }
+        };
+        let order = parse_column!(get_text, row, "pdbx_value_order", &context)
+            .and_then(|order| bond_order(&order, false))
+            .or_else(|| (kind == Bond::Disulfide).then_some(BondOrder::Single));
+        let symmetry1 = parse_column!(get_text, row, "ptnr1_symmetry", &context);
+        let symmetry2 = parse_column!(get_text, row, "ptnr2_symmetry", &context);
+        if symmetry1 != symmetry2 {
+            continue;
+        }
+        let mut partners = Vec::with_capacity(2);
+        for (prefix, alt_prefix) in [("ptnr1", "pdbx_ptnr1"), ("ptnr2", "pdbx_ptnr2")] {
+            let chain = parse_column!(get_text, row, &format!("{prefix}_auth_asym_id"), &context)
+                .or_else(|| {
+                    parse_column!(get_text, row, &format!("{prefix}_label_asym_id"), &context)
+                });
+            let residue = parse_column!(get_isize, row, &format!("{prefix}_auth_seq_id"), &context)
+                .or_else(|| {
+                    parse_column!(get_isize, row, &format!("{prefix}_label_seq_id"), &context)
+                });
+            let insertion_code = parse_column!(
+                get_text,
+                row,
+                &format!("{alt_prefix}_PDB_ins_code"),
+                &context
+            );
+            let name = parse_column!(get_text, row, &format!("{prefix}_label_comp_id"), &context);
+            let alt_loc = parse_column!(
+                get_text,
+                row,
+                &format!("{alt_prefix}_label_alt_id"),
+                &context
+            );
+            let atom = parse_column!(get_text, row, &format!("{prefix}_label_atom_id"), &context);
+            if let (Some(chain), Some(residue), Some(atom)) = (chain, residue, atom) {
+                partners.push((chain, residue, insertion_code, name, alt_loc, atom));
+            }
+        }
+        if partners.len() == 2 {
+            bonds.push((context, partners.remove(0), partners.remove(0), kind, order));
+        } else {
+            errors.push(PDBError::new(
+                ErrorLevel::LooseWarning,
+                "Incomplete connection",
+                "The chain, residue number, and atom name should be given for both partners of a connection, this bond is skipped.",
+                context,
+            ));
+        }
+    }
+
+    let mut new_bonds = Vec::new();
+    for model in pdb.models() {
+        let mut lookup: HashMap<AtomKey<'_>, Vec<AtomEntry<'_>>> = HashMap::new();
+        for chain in model.chains() {
+            for residue in chain.residues() {
+                for conformer in residue.conformers() {
+                    for atom in conformer.atoms() {
+                        lookup
+                            .entry((
+                                chain.id(),
+                                residue.serial_number(),
+                                residue.insertion_code(),
+                                atom.name(),
+                            ))
+                            .or_default()
+                            .push((
+                                atom.counter(),
+                                conformer.name(),
+                                conformer.alternative_location(),
+                            ));
+                    }
+                }
+            }
+        }
+        for (context, partner1, partner2, kind, order) in &bonds {
+            let find = |(chain, residue, insertion_code, name, alt_loc, atom): &(
+                String,
+                isize,
+                Option<String>,
+                Option<String>,
+                Option<String>,
+                String,
+            )| {
+                lookup
+                    .get(&(
+                        chain.as_str(),
+                        *residue,
+                        insertion_code.as_deref(),
+                        atom.as_str(),
+                    ))
+                    .into_iter()
+                    .flatten()
+                    .filter(|(_, conformer, conformer_alt_loc)| {
+                        name.as_ref().map_or(true, |n| conformer == n)
+                            && (alt_loc.is_none() || *conformer_alt_loc == alt_loc.as_deref())
+                    })
+                    .map(|(counter, _, alt_loc)| (*counter, *alt_loc))
+                    .collect::<Vec<_>>()
+            };
+            let (atoms1, atoms2) = (find(partner1), find(partner2));
+            if atoms1.is_empty() || atoms2.is_empty() {
+                errors.push(PDBError::new(
+                    ErrorLevel::LooseWarning,
+                    "Could not find a bond partner",
+                    format!(
+                        "One of the atoms could not be found while parsing a connection in model {}.",
+                        model.serial_number()
+                    ),
+                    context.clone(),
+                ));
+                continue;
+            }
+            for (counter1, alt1) in &atoms1 {
+                for (counter2, alt2) in &atoms2 {
+                    if alt1 == alt2 || alt1.is_none() || alt2.is_none() {
+                        new_bonds.push((*counter1, *counter2, *kind, *order));
+                    }
+                }
+            }
+        }
+    }
+    for (atom1, atom2, kind, order) in new_bonds {
+        pdb.add_bond_counters(atom1, atom2, kind, order);
+    }
+    errors
+}
+
+/// The chain id, residue serial number, insertion code and atom name identifying the atoms
+/// partaking in a connection
+type AtomKey<'a> = (&'a str, isize, Option<&'a str>, &'a str);
+
+/// The counter, conformer name and alternative location of an atom partaking in a connection
+type AtomEntry<'a> = (usize, &'a str, Option<&'a str>);
+
 /// Get the Textual content of the value, if available
-fn get_text(
+pub(super) fn get_text(
     value: &Value,
     context: &Context,
     column: Option<&str>,
 ) -> Result<Option<String>, PDBError> {
     match value {
-        Value::Text(t) => Ok(Some(t.to_string())),
+        Value::Text(t) => Ok(Some(t.clone())),
         Value::Inapplicable => Ok(None),
         Value::Unknown => Ok(None),
         Value::Numeric(n) => Ok(Some(format!("{n}"))),
@@ -578,7 +1011,7 @@ fn get_text(
 }
 
 /// Get the Numeric content of the value, if available, it also fails on NumericWithUncertainty
-fn get_f64(
+pub(super) fn get_f64(
     value: &Value,
     context: &Context,
     column: Option<&str>,
@@ -599,7 +1032,7 @@ fn get_f64(
 }
 
 /// Get the Numeric content of the value, if available, as a usize
-fn get_usize(
+pub(super) fn get_usize(
     value: &Value,
     context: &Context,
     column: Option<&str>,
@@ -612,7 +1045,7 @@ fn get_usize(
                 clippy::cast_sign_loss,
                 clippy::float_cmp
             )]
-            if (0.0..std::usize::MAX as f64).contains(&num) && num.trunc() == num {
+            if (0.0..usize::MAX as f64).contains(&num) && num.trunc() == num {
                 Ok(Some(num as usize))
             } else {
                 Err(PDBError::new(
@@ -631,7 +1064,7 @@ fn get_usize(
 }
 
 /// Get the Numeric content of the value, if available, as an isize
-fn get_isize(
+pub(super) fn get_isize(
     value: &Value,
     context: &Context,
     column: Option<&str>,
@@ -643,8 +1076,7 @@ fn get_isize(
                 clippy::cast_possible_truncation,
                 clippy::float_cmp
             )]
-            if (std::isize::MIN as f64..std::isize::MAX as f64).contains(&num) && num.trunc() == num
-            {
+            if (isize::MIN as f64..isize::MAX as f64).contains(&num) && num.trunc() == num {
                 Ok(Some(num as isize))
             } else {
                 Err(PDBError::new(
@@ -0,0 +1,341 @@
+use crate::msgpack::*;
+use rmpv::Value as Message;
+
+/// The values of an MMTF field after decoding
+#[derive(Debug, PartialEq)]
+pub(super) enum Array {
+    /// Integer values
+    Integers(Vec<i64>),
+    /// Floating point values
+    Floats(Vec<f64>),
+    /// Textual values, characters which are not set are represented by an empty string
+    Strings(Vec<String>),
+}
+
+/// Decode an MMTF field, either a plain MessagePack array or binary data starting with the
+/// codec header, see <https://github.com/rcsb/mmtf/blob/master/spec.md#codecs>
+pub(super) fn decode(value: &Message) -> Result<Array, String> {
+    if let Some(items) = value.as_array() {
+        return if let Some(integers) = items.iter().map(Message::as_i64).collect() {
+            Ok(Array::Integers(integers))
+        } else if let Some(floats) = items.iter().map(Message::as_f64).collect() {
+            Ok(Array::Floats(floats))
+        } else {
+            items
+                .iter()
+                .map(|item| text(item).map(str::to_string))
+                .collect::<Result<_, _>>()
+                .map(Array::Strings)
+        };
+    }
+    let data = binary(value)?;
+    if data.len() < 12 {
+        return Err("The binary data is shorter than the codec header".to_string());
+    }
+    let header = |offset: usize| {
+        i32::from_be_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    };
+    let (codec, parameter) = (header(0), header(8));
+    let length = usize::try_from(header(4))
+        .map_err(|_| format!("Invalid length in codec header {}", header(4)))?;
+    let body = &data[12..];
+
+    let int8 = || read(body, |b| i64::from(i8::from_be_bytes(b)));
+    let int16 = || read(body, |b| i64::from(i16::from_be_bytes(b)));
+    let int32 = || read(body, |b| i64::from(i32::from_be_bytes(b)));
+    let divide = |values: Vec<i64>| -> Result<Array, String> {
+        if parameter == 0 {
+            return Err("Division by zero in codec header".to_string());
+        }
+        #[allow(clippy::cast_precision_loss)]
+        Ok(Array::Floats(
+            values
+                .into_iter()
+                .map(|value| value as f64 / f64::from(parameter))
+                .collect(),
+        ))
+    };
+
+    let decoded = match codec {
+        1 => Array::Floats(read(body, |b| {
+            let number = f32::from_be_bytes(b);
+            number
+                .to_string()
+                .parse()
+                .unwrap_or_else(|_| f64::from(number))
+        })?),
+        2 => Array::Integers(int8()?),
+        3 => Array::Integers(int16()?),
+        4 => Array::Integers(int32()?),
+        5 => {
+            let size = usize::try_from(parameter)
+                .ok()
+                .filter(|size| *size > 0)
+                .ok_or_else(|| format!("Invalid string length in codec header {parameter}"))?;
+            Array::Strings(
+                body.chunks(size)
+                    .map(|chunk| {
+                        String::from_utf8_lossy(chunk)
+                            .trim_end_matches('\0')
+                            .to_string()
+                    })
+                    .collect(),
+            )
+        }
+        6 => Array::Strings(
+            run_length(&int32()?, length)?
+                .into_iter()
+                .map(|value| match value {
+                    0 => Ok(String::new()),
+                    _ => u32::try_from(value)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .map(String::from)
+                        .ok_or_else(|| format!("Invalid character code {value}")),
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        7 => Array::Integers(run_length(&int32()?, length)?),
+        8 => Array::Integers(delta(run_length(&int32()?, length)?)?),
+        9 => divide(run_length(&int32()?, length)?)?,
+        10 => divide(delta(recursive_index(&int16()?, 0x7FFF, -0x8000))?)?,
+        11 => divide(int16()?)?,
+        12 => divide(recursive_index(&int16()?, 0x7FFF, -0x8000))?,
+        13 => divide(recursive_index(&int8()?, 0x7F, -0x80))?,
+        14 => Array::Integers(recursive_index(&int16()?, 0x7FFF, -0x8000)),
+        15 => Array::Integers(recursive_index(&int8()?, 0x7F, -0x80)),
+        _ => return Err(format!("Unknown codec {codec}")),
+    };
+    let found = match &decoded {
+        Array::Integers(values) => values.len(),
+        Array::Floats(values) => values.len(),
+        Array::Strings(values) => values.len(),
+    };
+    if found == length {
+        Ok(decoded)
+    } else {
+        Err(format!(
+            "The decoded data contains {found} values while the codec header defines {length}"
+        ))
+    }
+}
+
+/// Decode an MMTF field containing integers
+pub(super) fn integers(value: &Message) -> Result<Vec<i64>, String> {
+    match decode(value)? {
+        Array::Integers(values) => Ok(values),
+        _ => Err("Expected integer values".to_string()),
+    }
+}
+
+/// Decode an MMTF field containing numbers
+pub(super) fn floats(value: &Message) -> Result<Vec<f64>, String> {
+    match decode(value)? {
+        #[allow(clippy::cast_precision_loss)]
+        Array::Integers(values) => Ok(values.into_iter().map(|value| value as f64).collect()),
+        Array::Floats(values) => Ok(values),
+        Array::Strings(_) => Err("Expected numeric values".to_string()),
+    }
+}
+
+/// Decode an MMTF field containing text
+pub(super) fn strings(value: &Message) -> Result<Vec<String>, String> {
+    match decode(value)? {
+        Array::Strings(values) => Ok(values),
+        _ => Err("Expected textual values".to_string()),
+    }
+}
+
+/// Read all big endian values of the given size with the given conversion function
+fn read<const N: usize, T>(bytes: &[u8], convert: impl Fn([u8; N]) -> T) -> Result<Vec<T>, String> {
+    if bytes.len() % N != 0 {
+        return Err(format!(
+            "The length of the binary data is not a multiple of the size of its values ({N})"
+        ));
+    }
+    Ok(bytes
+        .chunks_exact(N)
+        .map(|chunk| {
+            let mut array = [0; N];
+            array.copy_from_slice(chunk);
+            convert(array)
+        })
+        .collect())
+}
+
+/// Expand pairs of values and repeat counts, fails if this would give more than `length` values
+fn run_length(values: &[i64], length: usize) -> Result<Vec<i64>, String> {
+    if values.len() % 2 != 0 {
+        return Err("Run length encoded data should consist of pairs of values".to_string());
+    }
+    let mut output = Vec::new();
+    for pair in values.chunks_exact(2) {
+        let count = usize::try_from(pair[1])
+            .ok()
+            .filter(|count| output.len() + count <= length)
+            .ok_or_else(|| format!("Invalid run length {}", pair[1]))?;
+        output.extend(std::iter::repeat(pair[0]).take(count));
+    }
+    Ok(output)
+}
+
+/// Turn differences into the original values by taking the cumulative sum, fails if the sum
+/// overflows
+fn delta(mut values: Vec<i64>) -> Result<Vec<i64>, String> {
+    let mut sum = 0_i64;
+    for value in &mut values {
+        sum = sum
+            .checked_add(*value)
+            .ok_or("Delta encoded data overflows")?;
+        *value = sum;
+    }
+    Ok(values)
+}
+
+/// Combine values that were split into multiple smaller values, every value equal to one of
+/// the limits is added to the next one
+fn recursive_index(values: &[i64], max: i64, min: i64) -> Vec<i64> {
+    let mut output = Vec::with_capacity(values.len());
+    let mut sum = 0;
+    for value in values {
+        sum += value;
+        if *value != max && *value != min {
+            output.push(sum);
+            sum = 0;
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    /// Build a binary field with the given header and body
+    fn encoded(codec: i32, length: i32, parameter: i32, body: &[u8]) -> Message {
+        let mut data = Vec::new();
+        data.extend_from_slice(&codec.to_be_bytes());
+        data.extend_from_slice(&length.to_be_bytes());
+        data.extend_from_slice(&parameter.to_be_bytes());
+        data.extend_from_slice(body);
+        Message::Binary(data)
+    }
+
+    /// The big endian bytes of the given 32 bit integers
+    fn int32(values: &[i32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    /// The big endian bytes of the given 16 bit integers
+    fn int16(values: &[i16]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn plain() {
+        let message = Message::Array(vec![Message::from(1), Message::from(-2)]);
+        assert_eq!(integers(&message).unwrap(), vec![1, -2]);
+        assert_eq!(floats(&message).unwrap(), vec![1.0, -2.0]);
+        let message = Message::Array(vec![Message::from(1.5), Message::from(2)]);
+        assert_eq!(floats(&message).unwrap(), vec![1.5, 2.0]);
+        let message = Message::Array(vec![Message::from("A"), Message::from("B")]);
+        assert_eq!(strings(&message).unwrap(), vec!["A", "B"]);
+        assert!(integers(&message).is_err());
+    }
+
+    #[test]
+    fn basic_codecs() {
+        let bytes: Vec<u8> = [1.1_f32, -2.5]
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect();
+        assert_eq!(floats(&encoded(1, 2, 0, &bytes)).unwrap(), vec![1.1, -2.5]);
+        assert_eq!(
+            integers(&encoded(2, 2, 0, &[1, 0xFF])).unwrap(),
+            vec![1, -1]
+        );
+        assert_eq!(
+            integers(&encoded(3, 2, 0, &int16(&[300, -300]))).unwrap(),
+            vec![300, -300]
+        );
+        assert_eq!(
+            integers(&encoded(4, 2, 0, &int32(&[70000, -1]))).unwrap(),
+            vec![70000, -1]
+        );
+        assert_eq!(
+            strings(&encoded(5, 3, 4, b"A\0\0\0BB\0\0CCCC")).unwrap(),
+            vec!["A", "BB", "CCCC"]
+        );
+    }
+
+    #[test]
+    fn run_length_codecs() {
+        assert_eq!(
+            strings(&encoded(6, 5, 0, &int32(&[0, 3, 65, 2]))).unwrap(),
+            vec!["", "", "", "A", "A"]
+        );
+        assert_eq!(
+            integers(&encoded(7, 5, 0, &int32(&[1, 4, 2, 1]))).unwrap(),
+            vec![1, 1, 1, 1, 2]
+        );
+        assert_eq!(
+            integers(&encoded(8, 7, 0, &int32(&[1, 7]))).unwrap(),
+            vec![1, 2, 3, 4, 5, 6, 7]
+        );
+        assert_eq!(
+            floats(&encoded(9, 3, 100, &int32(&[100, 3]))).unwrap(),
+            vec![1.0, 1.0, 1.0]
+        );
+        assert!(integers(&encoded(7, 2, 0, &int32(&[1, 4]))).is_err());
+        assert!(integers(&encoded(7, 2, 0, &int32(&[1, -4]))).is_err());
+        assert_eq!(
+            delta(vec![i64::MAX, 1]),
+            Err("Delta encoded data overflows".into())
+        );
+    }
+
+    #[test]
+    fn recursive_index_codecs() {
+        assert_eq!(
+            floats(&encoded(10, 3, 1000, &int16(&[100, 32767, 32767, 1, -200]))).unwrap(),
+            vec![0.1, 65.635, 65.435]
+        );
+        assert_eq!(
+            floats(&encoded(11, 2, 10, &int16(&[15, -5]))).unwrap(),
+            vec![1.5, -0.5]
+        );
+        assert_eq!(
+            floats(&encoded(12, 2, 100, &int16(&[32767, 10, -32768, -2]))).unwrap(),
+            vec![327.77, -327.7]
+        );
+        assert_eq!(
+            floats(&encoded(13, 2, 10, &[127, 3, 0x80, 0xFE])).unwrap(),
+            vec![13.0, -13.0]
+        );
+        assert_eq!(
+            integers(&encoded(14, 2, 0, &int16(&[32767, 1, 5]))).unwrap(),
+            vec![32768, 5]
+        );
+        assert_eq!(
+            integers(&encoded(15, 3, 0, &[127, 127, 2, 0x80, 0x80, 0xFF, 0])).unwrap(),
+            vec![256, -257, 0]
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(decode(&Message::from(1)).is_err());
+        assert!(decode(&Message::Binary(vec![0, 0, 0, 4])).is_err());
+        assert!(decode(&encoded(16, 0, 0, &[])).is_err());
+        assert!(decode(&encoded(4, 1, 0, &[0, 0, 0])).is_err());
+        assert!(decode(&encoded(4, 2, 0, &int32(&[1]))).is_err());
+        assert!(decode(&encoded(11, 1, 0, &int16(&[1]))).is_err());
+        assert!(decode(&encoded(5, 1, 0, b"A")).is_err());
+    }
+}
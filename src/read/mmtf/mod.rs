@@ -0,0 +1,6 @@
+/// Decode the binary codecs used in MMTF files
+mod codec;
+/// Parse MMTF files
+mod parser;
+
+pub use parser::*;
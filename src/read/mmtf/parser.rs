@@ -0,0 +1,588 @@
+use super::codec::*;
+use crate::error::*;
+use crate::msgpack::*;
+use crate::structs::*;
+use crate::validate::*;
+use crate::StrictnessLevel;
+use rmpv::Value as Message;
+
+/// Open the given MMTF file (Macromolecular Transmission Format, see <https://mmtf.rcsb.org>)
+/// into a PDB struct. The groups are read as residues, and the bonds within and between groups
+/// are added as covalent bonds.
+/// Returns a PDBError if a BreakingError is found. Otherwise it returns the PDB with all errors/warnings found while parsing it.
+pub fn open_mmtf(
+    filename: impl AsRef<str>,
+    level: StrictnessLevel,
+) -> Result<(PDB, Vec<PDBError>), Vec<PDBError>> {
+    let filename = filename.as_ref();
+    match std::fs::read(filename) {
+        Ok(bytes) => open_mmtf_raw(&bytes, level),
+        Err(_) => Err(vec![PDBError::new(ErrorLevel::BreakingError, "Could not open file", "Could not open the specified file, make sure the path is correct, you have permission, and that it is not open in another program.", Context::show(filename))]),
+    }
+}
+
+/// Parse the given MMTF bytes into a PDB struct, see [`open_mmtf`].
+/// Returns a PDBError if a BreakingError is found. Otherwise it returns the PDB with all errors/warnings found while parsing it.
+pub fn open_mmtf_raw(
+    input: &[u8],
+    level: StrictnessLevel,
+) -> Result<(PDB, Vec<PDBError>), Vec<PDBError>> {
+    let invalid = |message: String| {
+        vec![PDBError::new(
+            ErrorLevel::BreakingError,
+            "Invalid MMTF",
+            message,
+            Context::none(),
+        )]
+    };
+    let file = rmpv::decode::read_value(&mut &input[..])
+        .map_err(|e| invalid(format!("The file is not valid MessagePack. Error: {e}")))?;
+    let (mut pdb, mut errors) = parse_structure(&file).map_err(invalid)?;
+
+    reshuffle_conformers(&mut pdb);
+    errors.extend(validate_invariants(&pdb));
+    if errors.iter().any(|e| e.fails(level)) {
+        Err(errors)
+    } else {
+        Ok((pdb, errors))
+    }
+}
+
+/// A group type from the `groupList`, the atoms and bonds of all residues of this type
+struct Group {
+    /// The name of the residue
+    name: String,
+    /// The names of the atoms
+    atom_names: Vec<String>,
+    /// The elements of the atoms
+    elements: Vec<String>,
+    /// The formal charges of the atoms
+    charges: Vec<i64>,
+    /// Pairs of indices of bonded atoms within the group
+    bond_atoms: Vec<i64>,
+    /// The orders of the bonds
+    bond_orders: Vec<i64>,
+    /// The chemical component type, eg `L-PEPTIDE LINKING` or `NON-POLYMER`
+    chem_comp_type: String,
+}
+
+/// Parse a group type
+fn parse_group(group: &Message) -> Result<Group, String> {
+    let list =
+        |name: &str| -> Result<&[Message], String> { get(group, name).map_or(Ok(&[][..]), array) };
+    let integer_list =
+        |name: &str| -> Result<Vec<i64>, String> { list(name)?.iter().map(integer).collect() };
+    let text_list = |name: &str| -> Result<Vec<String>, String> {
+        list(name)?
+            .iter()
+            .map(|item| text(item).map(str::to_string))
+            .collect()
+    };
+    let output = Group {
+        name: text(field(group, "groupName")?)?.to_string(),
+        atom_names: text_list("atomNameList")?,
+        elements: text_list("elementList")?,
+        charges: integer_list("formalChargeList")?,
+        bond_atoms: integer_list("bondAtomList")?,
+        bond_orders: integer_list("bondOrderList")?,
+        chem_comp_type: get(group, "chemCompType")
+            .and_then(Message::as_str)
+            .unwrap_or_default()
+            .to_string(),
+    };
+    if output.elements.len() != output.atom_names.len()
+        || output.charges.len() != output.atom_names.len()
+    {
+        return Err(format!(
+            "The atom lists of group '{}' do not have the same length",
+            output.name
+        ));
+    }
+    if output.bond_atoms.len() % 2 != 0
+        || (!output.bond_orders.is_empty()
+            && output.bond_orders.len() * 2 != output.bond_atoms.len())
+    {
+        return Err(format!(
+            "The bond lists of group '{}' do not match",
+            output.name
+        ));
+    }
+    Ok(output)
+}
+
+/// Get the bond order as defined in MMTF
+const fn bond_order(order: Option<&i64>) -> Option<BondOrder> {
+    match order {
+        Some(1) => Some(BondOrder::Single),
+        Some(2) => Some(BondOrder::Double),
+        Some(3) => Some(BondOrder::Triple),
+        _ => None,
+    }
+}
+
+/// Convert a count or index from the file
+fn to_usize(value: i64) -> Result<usize, String> {
+    usize::try_from(value).map_err(|_| format!("Invalid count or index {value}"))
+}
+
+/// Parse the top level map of an MMTF file into a PDB struct, any error in the structure of
+/// the file is returned as a message which is turned into a breaking error
+fn parse_structure(file: &Message) -> Result<(PDB, Vec<PDBError>), String> {
+    let mut pdb = PDB::default();
+    let mut errors = Vec::new();
+
+    /// Decode an optional field with the given function
+    fn optional<T>(
+        file: &Message,
+        name: &str,
+        decode: fn(&Message) -> Result<Vec<T>, String>,
+    ) -> Result<Option<Vec<T>>, String> {
+        get(file, name)
+            .filter(|value| !value.is_nil())
+            .map(|value| decode(value).map_err(|e| format!("{e} in '{name}'")))
+            .transpose()
+    }
+    /// Decode a required field with the given function
+    fn required<T>(
+        file: &Message,
+        name: &str,
+        decode: fn(&Message) -> Result<Vec<T>, String>,
+    ) -> Result<Vec<T>, String> {
+        optional(file, name, decode)?.ok_or_else(|| format!("Missing field '{name}'"))
+    }
+    /// Check the length of a list
+    fn check<T>(list: &[T], name: &str, length: usize) -> Result<(), String> {
+        if list.len() == length {
+            Ok(())
+        } else {
+            Err(format!(
+                "The field '{name}' contains {} values while {length} were expected",
+                list.len()
+            ))
+        }
+    }
+
+    pdb.identifier = get(file, "structureId")
+        .and_then(Message::as_str)
+        .map(str::to_string);
+    if let Some(cell) = optional(file, "unitCell", floats)? {
+        check(&cell, "unitCell", 6)?;
+        pdb.unit_cell = Some(UnitCell::new(
+            cell[0], cell[1], cell[2], cell[3], cell[4], cell[5],
+        ));
+    }
+    if let Some(space_group) = get(file, "spaceGroup").and_then(Message::as_str) {
+        pdb.symmetry = Symmetry::new(space_group);
+        if pdb.symmetry.is_none() {
+            errors.push(PDBError::new(
+                ErrorLevel::StrictWarning,
+                "Unknown space group",
+                format!("The space group \"{space_group}\" could not be found."),
+                Context::none(),
+            ));
+        }
+    }
+
+    let groups = array(field(file, "groupList")?)?
+        .iter()
+        .map(parse_group)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let chains_per_model = required(file, "chainsPerModel", integers)?;
+    let chain_ids = required(file, "chainIdList", strings)?;
+    let chain_count = chain_ids.len();
+    let chain_names =
+        optional(file, "chainNameList", strings)?.unwrap_or_else(|| chain_ids.clone());
+    check(&chain_names, "chainNameList", chain_count)?;
+    let groups_per_chain = required(file, "groupsPerChain", integers)?;
+    check(&groups_per_chain, "groupsPerChain", chain_count)?;
+
+    let group_types = required(file, "groupTypeList", integers)?
+        .into_iter()
+        .map(|index| {
+            to_usize(index)
+                .ok()
+                .filter(|index| *index < groups.len())
+                .ok_or_else(|| format!("Invalid group type {index}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let group_count = group_types.len();
+    let group_ids = required(file, "groupIdList", integers)?;
+    check(&group_ids, "groupIdList", group_count)?;
+    let insertion_codes = optional(file, "insCodeList", strings)?;
+    let sequence_indices = optional(file, "sequenceIndexList", integers)?;
+    if let Some(list) = &insertion_codes {
+        check(list, "insCodeList", group_count)?;
+    }
+    if let Some(list) = &sequence_indices {
+        check(list, "sequenceIndexList", group_count)?;
+    }
+
+    let atom_count: usize = group_types
+        .iter()
+        .map(|t| groups[*t].atom_names.len())
+        .sum();
+    let x = required(file, "xCoordList", floats)?;
+    let y = required(file, "yCoordList", floats)?;
+    let z = required(file, "zCoordList", floats)?;
+    check(&x, "xCoordList", atom_count)?;
+    check(&y, "yCoordList", atom_count)?;
+    check(&z, "zCoordList", atom_count)?;
+    let atom_ids = optional(file, "atomIdList", integers)?;
+    let b_factors = optional(file, "bFactorList", floats)?;
+    let occupancies = optional(file, "occupancyList", floats)?;
+    let alt_locs = optional(file, "altLocList", strings)?;
+    if let Some(list) = &atom_ids {
+        check(list, "atomIdList", atom_count)?;
+    }
+    if let Some(list) = &b_factors {
+        check(list, "bFactorList", atom_count)?;
+    }
+    if let Some(list) = &occupancies {
+        check(list, "occupancyList", atom_count)?;
+    }
+    if let Some(list) = &alt_locs {
+        check(list, "altLocList", atom_count)?;
+    }
+
+    if chains_per_model
+        .iter()
+        .map(|c| to_usize(*c))
+        .sum::<Result<usize, _>>()?
+        != chain_count
+    {
+        return Err(
+            "The number of chains in 'chainsPerModel' does not match 'chainIdList'".to_string(),
+        );
+    }
+    if groups_per_chain
+        .iter()
+        .map(|c| to_usize(*c))
+        .sum::<Result<usize, _>>()?
+        != group_count
+    {
+        return Err(
+            "The number of groups in 'groupsPerChain' does not match 'groupTypeList'".to_string(),
+        );
+    }
+
+    // The entity of every chain, as the index in the entity list
+    let mut entities = vec![None; chain_count];
+    if let Some(list) = get(file, "entityList") {
+        for (index, entity) in array(list)?.iter().enumerate() {
+            for chain in integers(field(entity, "chainIndexList")?)? {
+                if let Some(slot) = entities.get_mut(to_usize(chain)?) {
+                    *slot = Some(index + 1);
+                }
+            }
+        }
+    }
+
+    // The unique counter of every atom by its index in the file, or None if the atom was invalid
+    let mut counters: Vec<Option<usize>> = Vec::with_capacity(atom_count);
+    let (mut chain_index, mut group_index) = (0, 0);
+    for (model_index, chains) in chains_per_model.iter().enumerate() {
+        let mut model = Model::new(model_index + 1);
+        for _ in 0..*chains {
+            let mut chain = Chain::new(&chain_names[chain_index])
+                .ok_or_else(|| format!("Invalid chain name '{}'", chain_names[chain_index]))?;
+            for _ in 0..groups_per_chain[chain_index] {
+                let group = &groups[group_types[group_index]];
+                let number = isize::try_from(group_ids[group_index])
+                    .map_err(|_| format!("Invalid group id {}", group_ids[group_index]))?;
+                let insertion_code = insertion_codes
+                    .as_ref()
+                    .map(|codes| codes[group_index].as_str())
+                    .filter(|code| !code.is_empty());
+                let mut residue = Residue::new(number, insertion_code, None)
+                    .ok_or_else(|| format!("Invalid insertion code for group {number}"))?;
+                let sequence_index = sequence_indices.as_ref().map(|list| list[group_index]);
+                residue.set_label_seq_id(
+                    sequence_index
+                        .filter(|index| *index >= 0)
+                        .and_then(|index| isize::try_from(index + 1).ok()),
+                );
+                residue.set_label_asym_id(&chain_ids[chain_index]);
+                if let Some(entity) = entities[chain_index] {
+                    residue.set_label_entity_id(entity.to_string());
+                }
+                let hetero = sequence_index.map_or_else(
+                    || group.chem_comp_type.eq_ignore_ascii_case("NON-POLYMER"),
+                    |index| index < 0,
+                );
+
+                let first_atom = counters.len();
+                for (index, name) in group.atom_names.iter().enumerate() {
+                    let atom_index = counters.len();
+                    let serial_number = match &atom_ids {
+                        Some(ids) => to_usize(ids[atom_index])?,
+                        None => atom_index + 1,
+                    };
+                    let charge = isize::try_from(group.charges[index])
+                        .map_err(|_| format!("Invalid charge {}", group.charges[index]))?;
+                    if let Some(atom) = Atom::new(
+                        hetero,
+                        serial_number,
+                        name,
+                        x[atom_index],
+                        y[atom_index],
+                        z[atom_index],
+                        occupancies.as_ref().map_or(1.0, |list| list[atom_index]),
+                        b_factors.as_ref().map_or(0.0, |list| list[atom_index]),
+                        &group.elements[index],
+                        charge,
+                    ) {
+                        counters.push(Some(atom.counter()));
+                        let alt_loc = alt_locs
+                            .as_ref()
+                            .map(|list| list[atom_index].as_str())
+                            .filter(|alt_loc| !alt_loc.is_empty());
+                        residue.add_atom(atom, (&group.name, alt_loc));
+                    } else {
+                        counters.push(None);
+                        errors.push(PDBError::new(
+                            ErrorLevel::InvalidatingError,
+                            "Atom definition incorrect",
+                            "The atom name and element should only contain valid characters.",
+                            Context::show(format!("MMTF atom {atom_index}")),
+                        ));
+                    }
+                }
+
+                for (index, pair) in group.bond_atoms.chunks_exact(2).enumerate() {
+                    let atom = |local: i64| {
+                        to_usize(local)
+                            .ok()
+                            .filter(|local| *local < group.atom_names.len())
+                            .map(|local| counters[first_atom + local])
+                            .ok_or_else(|| format!("Invalid bond in group '{}'", group.name))
+                    };
+                    if let (Some(a), Some(b)) = (atom(pair[0])?, atom(pair[1])?) {
+                        pdb.add_bond_counters(
+                            a,
+                            b,
+                            Bond::Covalent,
+                            bond_order(group.bond_orders.get(index)),
+                        );
+                    }
+                }
+                if residue.conformer_count() > 0 {
+                    chain.add_residue(residue);
+                }
+                group_index += 1;
+            }
+            // MMTF chains with the same name, like the polymer, ligands, and waters of an author
+            // chain, are merged into one Chain
+            let existing = model.chains().position(|c| c.id() == chain.id());
+            if let Some(existing) = existing.and_then(|index| model.chain_mut(index)) {
+                existing.join(chain);
+            } else {
+                model.add_chain(chain);
+            }
+            chain_index += 1;
+        }
+        pdb.add_model(model);
+    }
+
+    // The bonds between groups refer to the atoms by their index in the whole file
+    let bond_atoms = optional(file, "bondAtomList", integers)?.unwrap_or_default();
+    let bond_orders = optional(file, "bondOrderList", integers)?.unwrap_or_default();
+    if bond_atoms.len() % 2 != 0
+        || (!bond_orders.is_empty() && bond_orders.len() * 2 != bond_atoms.len())
+    {
+        return Err("The lists 'bondAtomList' and 'bondOrderList' do not match".to_string());
+    }
+    for (index, pair) in bond_atoms.chunks_exact(2).enumerate() {
+        let atom = |global: i64| {
+            to_usize(global)
+                .ok()
+                .and_then(|global| counters.get(global).copied())
+                .ok_or_else(|| format!("Invalid atom index {global} in 'bondAtomList'"))
+        };
+        if let (Some(a), Some(b)) = (atom(pair[0])?, atom(pair[1])?) {
+            pdb.add_bond_counters(a, b, Bond::Covalent, bond_order(bond_orders.get(index)));
+        }
+    }
+
+    Ok((pdb, errors))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    /// Build a MessagePack map
+    fn map(fields: Vec<(&str, Message)>) -> Message {
+        Message::Map(
+            fields
+                .into_iter()
+                .map(|(key, value)| (Message::from(key), value))
+                .collect(),
+        )
+    }
+
+    /// Build a plain MessagePack array
+    fn list<T: Into<Message> + Clone>(values: &[T]) -> Message {
+        Message::Array(values.iter().cloned().map(Into::into).collect())
+    }
+
+    /// Build a binary field with the given header and 32 bit integers as body
+    fn encoded(codec: i32, length: i32, parameter: i32, body: &[i32]) -> Message {
+        let mut data = Vec::new();
+        for value in [codec, length, parameter].iter().chain(body) {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        Message::Binary(data)
+    }
+
+    /// A small structure with two alanines in chain A and a water in chain B
+    fn structure() -> Message {
+        let group = |name: &str, atoms: &[&str], bonds: &[i64], kind: &str| {
+            map(vec![
+                ("groupName", Message::from(name)),
+                ("atomNameList", list(atoms)),
+                (
+                    "elementList",
+                    list(&atoms.iter().map(|a| &a[..1]).collect::<Vec<_>>()),
+                ),
+                ("formalChargeList", list(&vec![0; atoms.len()])),
+                ("bondAtomList", list(bonds)),
+                ("bondOrderList", list(&vec![1; bonds.len() / 2])),
+                ("chemCompType", Message::from(kind)),
+            ])
+        };
+        let mut chain_ids = b"A\0\0\0B\0\0\0".to_vec();
+        let mut chain_id_field = Vec::new();
+        for value in [5_i32, 2, 4] {
+            chain_id_field.extend_from_slice(&value.to_be_bytes());
+        }
+        chain_id_field.append(&mut chain_ids);
+        map(vec![
+            ("mmtfVersion", Message::from("1.0.0")),
+            ("structureId", Message::from("1ABC")),
+            ("unitCell", list(&[10.0, 20.0, 30.0, 90.0, 90.0, 90.0])),
+            ("spaceGroup", Message::from("P 21 21 21")),
+            ("numModels", Message::from(1)),
+            ("chainsPerModel", list(&[2])),
+            ("groupsPerChain", list(&[2, 1])),
+            ("chainIdList", Message::Binary(chain_id_field)),
+            ("chainNameList", list(&["A", "B"])),
+            (
+                "groupList",
+                Message::Array(vec![
+                    group("ALA", &["N", "CA"], &[0, 1], "L-PEPTIDE LINKING"),
+                    group("HOH", &["O"], &[], "NON-POLYMER"),
+                ]),
+            ),
+            ("groupTypeList", list(&[0, 0, 1])),
+            ("groupIdList", encoded(8, 3, 0, &[1, 2, 97, 1])),
+            ("insCodeList", encoded(6, 3, 0, &[0, 1, 65, 1, 0, 1])),
+            ("sequenceIndexList", list(&[0, 1, -1])),
+            ("atomIdList", encoded(8, 5, 0, &[1, 5])),
+            ("xCoordList", encoded(9, 5, 10, &[15, 5])),
+            ("yCoordList", list(&[0.0, 1.0, 2.0, 3.0, 4.0])),
+            ("zCoordList", list(&[0, 0, 0, 0, 0])),
+            ("bFactorList", encoded(9, 5, 100, &[1050, 5])),
+            ("occupancyList", encoded(9, 5, 100, &[100, 5])),
+            ("altLocList", encoded(6, 5, 0, &[0, 5])),
+            ("bondAtomList", list(&[1, 2])),
+            ("bondOrderList", list(&[1])),
+            (
+                "entityList",
+                Message::Array(vec![
+                    map(vec![("chainIndexList", list(&[0]))]),
+                    map(vec![("chainIndexList", list(&[1]))]),
+                ]),
+            ),
+        ])
+    }
+
+    /// Encode the given MessagePack value
+    fn bytes(message: &Message) -> Vec<u8> {
+        let mut output = Vec::new();
+        rmpv::encode::write_value(&mut output, message).unwrap();
+        output
+    }
+
+    #[test]
+    fn small_structure() {
+        let (pdb, errors) = open_mmtf_raw(&bytes(&structure()), StrictnessLevel::Strict).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(pdb.identifier.as_deref(), Some("1ABC"));
+        assert_eq!(
+            pdb.unit_cell,
+            Some(UnitCell::new(10.0, 20.0, 30.0, 90.0, 90.0, 90.0))
+        );
+        assert_eq!(pdb.symmetry, Symmetry::new("P 21 21 21"));
+        assert_eq!(pdb.model_count(), 1);
+        assert_eq!(pdb.chain_count(), 2);
+        assert_eq!(pdb.residue_count(), 3);
+        assert_eq!(pdb.atom_count(), 5);
+
+        let residues: Vec<_> = pdb.residues().collect();
+        assert_eq!(residues[0].id(), (1, None));
+        assert_eq!(residues[1].id(), (2, Some("A")));
+        assert_eq!(residues[2].id(), (99, None));
+        assert_eq!(residues[1].name(), Some("ALA"));
+        assert_eq!(residues[1].label_seq_id(), Some(2));
+        assert_eq!(residues[2].label_seq_id(), None);
+        assert_eq!(residues[2].label_asym_id(), Some("B"));
+        assert_eq!(residues[2].label_entity_id(), Some("2"));
+
+        let atom = pdb.atoms().nth(4).unwrap();
+        assert_eq!(atom.serial_number(), 5);
+        assert_eq!(atom.name(), "O");
+        assert_eq!(atom.pos(), (1.5, 4.0, 0.0));
+        assert_eq!(atom.b_factor(), 10.5);
+        assert_eq!(atom.occupancy(), 1.0);
+        assert!(atom.hetero());
+        assert!(!pdb.atom(0).unwrap().hetero());
+
+        // Two bonds within the alanines and one peptide bond between them
+        assert_eq!(pdb.bonds().count(), 3);
+        let peptide = pdb
+            .bonds()
+            .find(|(a, b, _, _)| a.serial_number() == 2 && b.serial_number() == 3)
+            .unwrap();
+        assert_eq!(peptide.2, Bond::Covalent);
+        assert_eq!(peptide.3, Some(BondOrder::Single));
+    }
+
+    #[test]
+    fn invalid_structure() {
+        let errors = open_mmtf_raw(b"\xc1", StrictnessLevel::Loose).unwrap_err();
+        assert_eq!(errors[0].short_description(), "Invalid MMTF");
+
+        let mut message = structure();
+        if let Message::Map(fields) = &mut message {
+            fields.retain(|(key, _)| key.as_str() != Some("xCoordList"));
+        }
+        let errors = open_mmtf_raw(&bytes(&message), StrictnessLevel::Loose).unwrap_err();
+        assert_eq!(errors[0].long_description(), "Missing field 'xCoordList'");
+
+        let mut message = structure();
+        if let Message::Map(fields) = &mut message {
+            for (key, value) in fields.iter_mut() {
+                if key.as_str() == Some("bondAtomList") {
+                    *value = list(&[1, 5]);
+                }
+            }
+        }
+        let errors = open_mmtf_raw(&bytes(&message), StrictnessLevel::Loose).unwrap_err();
+        assert_eq!(
+            errors[0].long_description(),
+            "Invalid atom index 5 in 'bondAtomList'"
+        );
+    }
+
+    #[test]
+    fn open_by_extension() {
+        let root = env!("CARGO_MANIFEST_DIR");
+        std::fs::create_dir_all(format!("{root}/dump")).unwrap();
+        std::fs::write(format!("{root}/dump/small.mmtf"), bytes(&structure())).unwrap();
+        let (pdb, _) =
+            crate::open(format!("{root}/dump/small.mmtf"), StrictnessLevel::Loose).unwrap();
+        assert_eq!(pdb.atom_count(), 5);
+    }
+}
@@ -2,10 +2,20 @@
 mod general;
 /// Parse mmCIF/PDBx files
 mod mmcif;
+/// Parse MMTF files
+#[cfg(feature = "mmtf")]
+mod mmtf;
 /// Parse PDB files
 mod pdb;
-use super::check_extension;
 
 pub use general::{open, open_gz, open_raw};
-pub use mmcif::{open_mmcif, open_mmcif_raw};
-pub use pdb::{open_pdb, open_pdb_raw};
+#[cfg(feature = "bcif")]
+pub use mmcif::{open_bcif, open_bcif_raw};
+pub use mmcif::{open_components, open_components_raw, open_mmcif, open_mmcif_raw, parse_mmcif};
+#[cfg(feature = "mmtf")]
+pub use mmtf::{open_mmtf, open_mmtf_raw};
+#[cfg(feature = "mmap")]
+pub use pdb::open_pdb_mmap;
+pub use pdb::{
+    open_pdb, open_pdb_bytes, open_pdb_models, open_pdb_models_raw, open_pdb_raw, ModelReader,
+};
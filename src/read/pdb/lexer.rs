@@ -3,7 +3,6 @@ use crate::error::*;
 use crate::reference_tables;
 
 use std::cmp;
-use std::convert::TryFrom;
 use std::ops::Range;
 use std::str::FromStr;
 
@@ -35,11 +34,14 @@ pub fn lex_line(line: &str, linenumber: usize) -> Result<(LexItem, Vec<PDBError>
             "SEQADV" => Ok(lex_seqadv(linenumber, line)),
             "MODRES" => Ok(lex_modres(linenumber, line)),
             "SSBOND" => Ok(lex_ssbond(linenumber, line)),
+            "CONECT" => Ok(lex_connect(linenumber, line)),
             "ENDMDL" => Ok((LexItem::EndModel(), Vec::new())),
             "TER   " => Ok((LexItem::TER(), Vec::new())),
             "END   " => Ok((LexItem::End(), Vec::new())),
             _ => Ok((LexItem::Empty(), Vec::new())),
         }
+    } else if line == "ENDMDL" {
+        Ok((LexItem::EndModel(), Vec::new()))
     } else if line.len() > 2 {
         match &line[..3] {
             "TER" => Ok((LexItem::TER(), Vec::new())),
@@ -101,12 +103,6 @@ fn lex_header(linenumber: usize, line: &str) -> Result<(LexItem, Vec<PDBError>),
     } else {
         Ok((
             LexItem::Header(
-                line.chars().collect::<Vec<char>>()[10..50]
-                    .iter()
-                    .collect::<String>(),
-                line.chars().collect::<Vec<char>>()[50..59]
-                    .iter()
-                    .collect::<String>(),
                 line.chars().collect::<Vec<char>>()[62..66]
                     .iter()
                     .collect::<String>(),
@@ -158,6 +154,18 @@ fn lex_atom(
     ) = lex_atom_basics(linenumber, line);
     errors.extend(basic_errors);
 
+    let segment_id = if crate::structs::valid_identifier(&segment_id) {
+        segment_id
+    } else {
+        errors.push(PDBError::new(
+            ErrorLevel::LooseWarning,
+            "Invalid segment id",
+            "The segment id contains characters that are not allowed (only ASCII graphic characters and spaces), so it is ignored.",
+            Context::line(linenumber, line, 72, 4),
+        ));
+        String::new()
+    };
+
     Ok((
         LexItem::Atom(
             hetero,
@@ -216,12 +224,12 @@ fn lex_anisou(linenumber: usize, line: &str) -> (LexItem, Vec<PDBError>) {
         (
             serial_number,
             atom_name,
-            alternate_location,
+            _,
             residue_name,
             chain_id,
             residue_serial_number,
-            insertion,
-            segment_id,
+            _,
+            _,
             element,
             charge,
         ),
@@ -233,13 +241,10 @@ fn lex_anisou(linenumber: usize, line: &str) -> (LexItem, Vec<PDBError>) {
         LexItem::Anisou(
             serial_number,
             atom_name,
-            alternate_location,
             residue_name,
             chain_id,
             residue_serial_number,
-            insertion,
             factors,
-            segment_id,
             element,
             charge,
         ),
@@ -268,7 +273,7 @@ fn lex_atom_basics(
     Vec<PDBError>,
 ) {
     let mut errors = Vec::new();
-    let chars: Vec<char> = line.chars().collect();
+    let chars = line.as_bytes();
 
     let serial_number = parse(linenumber, line, 6..11, &mut errors);
     let atom_name = parse(linenumber, line, 12..16, &mut errors);
@@ -281,8 +286,7 @@ fn lex_atom_basics(
     let element = parse(linenumber, line, 76..78, &mut errors);
 
     let mut charge = 0;
-    #[allow(clippy::unwrap_used)]
-    if chars.len() >= 80 && !(chars[78] == ' ' && chars[79] == ' ') {
+    if chars.len() >= 80 && !(chars[78] == b' ' && chars[79] == b' ') {
         if !chars[78].is_ascii_digit() {
             errors.push(PDBError::new(
                 ErrorLevel::InvalidatingError,
@@ -290,7 +294,7 @@ fn lex_atom_basics(
                 "The charge is not numeric, it is defined to be [0-9][+-], so two characters in total.",
                 Context::line(linenumber, line, 78, 1),
             ));
-        } else if chars[79] != '-' && chars[79] != '+' {
+        } else if chars[79] != b'-' && chars[79] != b'+' {
             errors.push(PDBError::new(
                 ErrorLevel::InvalidatingError,
                 "Atom charge is not correct",
@@ -298,8 +302,8 @@ fn lex_atom_basics(
                 Context::line(linenumber, line, 79, 1),
             ));
         } else {
-            charge = isize::try_from(chars[78].to_digit(10).unwrap()).unwrap();
-            if chars[79] == '-' {
+            charge = isize::from(chars[78] - b'0');
+            if chars[79] == b'-' {
                 charge *= -1;
             }
         }
@@ -603,7 +607,6 @@ fn lex_modres(linenumber: usize, line: &str) -> (LexItem, Vec<PDBError>) {
     let mut errors = Vec::new();
     let chars: Vec<char> = line.chars().collect();
 
-    let id = parse(linenumber, line, 7..11, &mut errors);
     let res_name = parse(linenumber, line, 12..15, &mut errors);
     let chain_id = parse_char(linenumber, line, 16, &mut errors);
     let seq_num = parse(linenumber, line, 18..22, &mut errors);
@@ -613,7 +616,6 @@ fn lex_modres(linenumber: usize, line: &str) -> (LexItem, Vec<PDBError>) {
 
     (
         LexItem::Modres(
-            id,
             res_name,
             String::from(chain_id),
             seq_num,
@@ -651,25 +653,32 @@ fn lex_ssbond(linenumber: usize, line: &str) -> (LexItem, Vec<PDBError>) {
         Some(String::from(parse_char(linenumber, line, 35, &mut errors)))
     };
 
-    let mut extra = None;
-
-    if chars.len() >= 78 {
-        let sym1 = parse(linenumber, line, 59..65, &mut errors);
-        let sym2 = parse(linenumber, line, 66..72, &mut errors);
-        let distance: f64 = parse(linenumber, line, 73..78, &mut errors);
-        extra = Some((sym1, sym2, distance));
-    }
-
     (
         LexItem::SSBond(
             (res_1, res_seq_1, icode_1, chain_1.to_string()),
             (res_2, res_seq_2, icode_2, chain_2.to_string()),
-            extra,
         ),
         errors,
     )
 }
 
+/// Parse a CONECT line into the corresponding LexItem
+fn lex_connect(linenumber: usize, line: &str) -> (LexItem, Vec<PDBError>) {
+    let mut errors = Vec::new();
+    let serial = parse(linenumber, line, 6..11, &mut errors);
+    // Only the four covalent bond fields are used, the obsolete hydrogen and salt bridge fields are ignored
+    let bonded = [11..16, 16..21, 21..26, 26..31]
+        .into_iter()
+        .filter(|range| {
+            line.get(range.clone())
+                .map_or(false, |t| !t.trim().is_empty())
+        })
+        .map(|range| parse(linenumber, line, range, &mut errors))
+        .collect();
+
+    (LexItem::Connect(serial, bonded), errors)
+}
+
 /// Parse a field from a line, with T::default() as fall back, leave errors in the given mutable vec.
 fn parse<T: FromStr + Default>(
     linenumber: usize,
@@ -688,7 +697,8 @@ fn parse_default<T: FromStr>(
     errors: &mut Vec<PDBError>,
     default: T,
 ) -> T {
-    let context = Context::line(linenumber, line, range.start, range.len());
+    // Only create the context when needed, to not copy the line for every field
+    let context = || Context::line(linenumber, line, range.start, range.len());
     if line.len() < range.end {
         errors.push(PDBError::new(
             ErrorLevel::InvalidatingError,
@@ -697,11 +707,11 @@ fn parse_default<T: FromStr>(
                 "This line was too short to parse the expected data field (at {} to {})",
                 range.start, range.end
             ),
-            context,
+            context(),
         ));
         return default;
     }
-    if let Ok(v) = line[range].trim().parse::<T>() {
+    if let Ok(v) = line[range.clone()].trim().parse::<T>() {
         v
     } else {
         errors.push(PDBError::new(
@@ -711,7 +721,7 @@ fn parse_default<T: FromStr>(
                 "The text presented is not of the right kind ({}).",
                 std::any::type_name::<T>()
             ),
-            context,
+            context(),
         ));
         default
     }
@@ -719,15 +729,16 @@ fn parse_default<T: FromStr>(
 
 /// Parse a character, needed because the trim in the generic `parse` could leave us with an empty character leading to errors
 fn parse_char(linenumber: usize, line: &str, position: usize, errors: &mut Vec<PDBError>) -> char {
-    let context = Context::line(linenumber, line, position, 1);
-    if let Some(s) = line.chars().nth(position) {
+    // Like the other fields the position is a byte offset, but the line is only indexed when it
+    // starts at a character boundary
+    if let Some(s) = line.get(position..).and_then(|rest| rest.chars().next()) {
         s
     } else {
         errors.push(PDBError::new(
             ErrorLevel::InvalidatingError,
             "Line too short",
             format!("This line was too short to parse the expected data field (at {position})"),
-            context,
+            Context::line(linenumber, line, position, 1),
         ));
         ' '
     }
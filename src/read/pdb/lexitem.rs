@@ -4,11 +4,8 @@
 /// See wwPDB v3.30 for detailed explanation of the meaning of all fields
 #[derive(Debug, Clone)]
 pub enum LexItem {
-    /// A HEADER in a PDB file
-    /// * classification
-    /// * date of deposition
-    /// * identification
-    Header(String, String, String),
+    /// A HEADER in a PDB file, saved as its identification
+    Header(String),
     /// A REMARK saved as the remark-type-number and the remark line itself
     Remark(usize, String),
     /// An Atom with all its information, including the deprecated and rarely used fields.
@@ -49,26 +46,20 @@ pub enum LexItem {
     /// An Anisou record with all its information, including the deprecated and rarely used fields.
     /// * serial number
     /// * name
-    /// * alternate location
     /// * residue name
     /// * chain id
     /// * residue serial number
-    /// * insertion
     /// * temperature factors
-    /// * segment id
     /// * element
     /// * charge
     Anisou(
         usize,
         String,
-        Option<String>,
         String,
         String,
         isize,
-        Option<String>,
         [[f64; 3]; 3],
         String,
-        String,
         isize,
     ),
     /// A SCALEn line, as the row (1/2/3) and data
@@ -155,22 +146,13 @@ pub enum LexItem {
         String,
     ),
     /// A MODRES record, having information about modifications of atoms
-    ///  * IDCode
     ///  * ResName
     ///  * ChainID
     ///  * SeqNum
     ///  * InsertionCode
     ///  * Standard residue name
     ///  * Comment
-    Modres(
-        String,
-        String,
-        String,
-        isize,
-        Option<String>,
-        String,
-        String,
-    ),
+    Modres(String, String, isize, Option<String>, String, String),
     /// A disulfide bond
     /// * Residue name 1 (CYS)
     /// * Residue serial number 1
@@ -180,14 +162,15 @@ pub enum LexItem {
     /// * Residue serial number 2
     /// * Insertion code 2
     /// * Chain id 2
-    /// * Symmetry operation residue 1
-    /// * Symmetry operation residue 2
-    /// * Bond length
     SSBond(
         (String, isize, Option<String>, String),
         (String, isize, Option<String>, String),
-        Option<(String, String, f64)>,
     ),
+    /// CONECT, bonds from one atom to others, a bonded atom given multiple times indicates a
+    /// double or triple bond
+    /// * Serial number of the atom
+    /// * Serial numbers of the bonded atoms
+    Connect(usize, Vec<usize>),
     /// ENDMODEL, end of the current model
     EndModel(),
     /// TER =, termination of ATOM lines to allow for HETATMs to be defined
@@ -2,6 +2,8 @@
 mod lexer;
 /// The items as generated by the lexer
 mod lexitem;
+/// Read the models of a PDB file one at a time
+mod models;
 /// The parser and lexer
 mod parser;
 /// Structs to help in building up the PDB struct
@@ -9,4 +11,5 @@ mod temporary_structs;
 /// Methods to validate the parsed structure
 mod validate;
 
+pub use models::*;
 pub use parser::*;
@@ -0,0 +1,331 @@
+use super::lexer::*;
+use super::lexitem::*;
+use super::parser::parse_pdb_lines;
+use super::temporary_structs::*;
+use crate::error::*;
+use crate::structs::*;
+use crate::validate::{reshuffle_residue_conformers, validate_model};
+use crate::StrictnessLevel;
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines};
+
+/// The record names which start the coordinate section of a PDB file
+const COORDINATE_RECORDS: &[&str] = &["MODEL", "ATOM", "HETATM", "ANISOU", "TER"];
+
+/// The record names in the header which refer to atoms, these cannot be read before the atoms are known
+const ATOM_REFERENCE_RECORDS: &[&str] = &["MODRES", "SSBOND"];
+
+/// Open the given PDB file to read its models one at a time, see [`ModelReader`].
+/// Returns a PDBError if a BreakingError is found in the header. Otherwise it returns the reader
+/// with all errors/warnings found while parsing the header.
+///
+/// # Related
+/// If you want to open a file from memory see [`open_pdb_models_raw`]. To read the whole file
+/// at once see [`crate::open_pdb`].
+pub fn open_pdb_models(
+    filename: impl AsRef<str>,
+    level: StrictnessLevel,
+) -> Result<(ModelReader<File>, Vec<PDBError>), Vec<PDBError>> {
+    let filename = filename.as_ref();
+    let file = if let Ok(f) = File::open(filename) {
+        f
+    } else {
+        return Err(vec![PDBError::new(ErrorLevel::BreakingError, "Could not open file", "Could not open the specified file, make sure the path is correct, you have permission, and that it is not open in another program.", Context::show(filename))]);
+    };
+    open_pdb_models_raw(BufReader::new(file), Context::show(filename), level)
+}
+
+/// Read the models of the input stream one at a time, see [`ModelReader`].
+/// Returns a PDBError if a BreakingError is found in the header. Otherwise it returns the reader
+/// with all errors/warnings found while parsing the header.
+///
+/// ## Arguments
+/// * `input` - the input stream
+/// * `context` - the context of the full stream, to place error messages correctly, for files this is `Context::show(filename)`.
+/// * `level` - the strictness level to operate in. If errors are generated which are breaking in the given level the parsing will fail.
+pub fn open_pdb_models_raw<T: std::io::Read>(
+    input: BufReader<T>,
+    context: Context,
+    level: StrictnessLevel,
+) -> Result<(ModelReader<T>, Vec<PDBError>), Vec<PDBError>> {
+    let mut lines = input.lines();
+    let mut header = Vec::new();
+    let mut pending = None;
+    for (linenumber, line) in (1..).zip(&mut lines) {
+        if let Ok(text) = &line {
+            let record = text.get(..6).unwrap_or(text).trim_end();
+            if COORDINATE_RECORDS.contains(&record) {
+                pending = Some((linenumber, line));
+                break;
+            } else if ATOM_REFERENCE_RECORDS.contains(&record) {
+                continue;
+            }
+        }
+        header.push((linenumber, line));
+    }
+    let linenumber = pending
+        .as_ref()
+        .map_or(header.len(), |(linenumber, _)| linenumber - 1);
+
+    let (header, errors) = parse_pdb_lines(header.into_iter(), &context)?;
+    if errors.iter().any(|e| e.fails(level)) {
+        return Err(errors);
+    }
+    Ok((
+        ModelReader {
+            header,
+            lines,
+            linenumber,
+            pending,
+            models: BuildUpModels::new(),
+            first: None,
+            trailing_errors: Vec::new(),
+            context,
+            level,
+        },
+        errors,
+    ))
+}
+
+/// An iterator over the models in a PDB file which reads a single model at a time, so the memory
+/// use stays constant for files with many models, like NMR ensembles or MD trajectories. The
+/// header of the file (everything before the first model) is read when opening the file and is
+/// available as a PDB without models, see [`ModelReader::header`].
+///
+/// Every model is given with the errors/warnings found while parsing it, including the
+/// validation against the first model (all models should contain corresponding atoms). If any
+/// of these are breaking in the strictness level of the reader the model is given as an error.
+/// The errors/warnings found after the last model are available with
+/// [`ModelReader::trailing_errors`] once all models are read, if any of these are breaking they
+/// are also given as a final error.
+///
+/// Records that refer to atoms (MODRES, SSBOND, and CONECT) are not read as these can only
+/// be handled when all models are known, use [`crate::open_pdb`] to read these.
+///
+/// ```rust
+/// use pdbtbx::*;
+/// let (reader, _errors) = open_pdb_models("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+/// assert!(reader.header().unit_cell.is_some());
+/// for result in reader {
+///     let (model, _errors) = result.unwrap();
+///     assert_eq!(model.atom_count(), 1271);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ModelReader<T> {
+    /// The header of the file
+    header: PDB,
+    /// The remaining lines of the file
+    lines: Lines<BufReader<T>>,
+    /// The line number of the last line read
+    linenumber: usize,
+    /// The line which ended the header, it still has to be handled
+    pending: Option<(usize, std::io::Result<String>)>,
+    /// The models being built up
+    models: BuildUpModels,
+    /// The first model, to validate the other models against
+    first: Option<Model>,
+    /// The errors/warnings found after the last model
+    trailing_errors: Vec<PDBError>,
+    /// The context of the file
+    context: Context,
+    /// The strictness level to read the models with
+    level: StrictnessLevel,
+}
+
+impl<T> ModelReader<T> {
+    /// Get the header of the file, this contains all information except the models.
+    pub const fn header(&self) -> &PDB {
+        &self.header
+    }
+
+    /// Get the header of the file, and stop reading the models
+    pub fn into_header(self) -> PDB {
+        self.header
+    }
+
+    /// Get the errors/warnings found after the last model, like in the CONECT or MASTER records.
+    /// These are only known once all models are read.
+    pub fn trailing_errors(&self) -> &[PDBError] {
+        &self.trailing_errors
+    }
+
+    /// Finish a model, by joining the atoms of blank alternative conformers into the other
+    /// conformers and validating it against the first model. Without a model the errors are the
+    /// trailing errors of the file, which are only given if any are breaking.
+    #[allow(clippy::type_complexity)]
+    fn finish(
+        &mut self,
+        model: Option<Model>,
+        mut errors: Vec<PDBError>,
+    ) -> Option<Result<(Model, Vec<PDBError>), Vec<PDBError>>> {
+        let mut model = if let Some(model) = model {
+            model
+        } else {
+            self.trailing_errors.extend(errors.iter().cloned());
+            return errors
+                .iter()
+                .any(|e| e.fails(self.level))
+                .then_some(Err(errors));
+        };
+        for residue in model.residues_mut() {
+            reshuffle_residue_conformers(residue);
+        }
+        if let Some(first) = &self.first {
+            errors.extend(validate_model(first, &model));
+        } else {
+            self.first = Some(model.clone());
+        }
+        if errors.iter().any(|e| e.fails(self.level)) {
+            Some(Err(errors))
+        } else {
+            Some(Ok((model, errors)))
+        }
+    }
+}
+
+impl<T: std::io::Read> Iterator for ModelReader<T> {
+    type Item = Result<(Model, Vec<PDBError>), Vec<PDBError>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut errors = Vec::new();
+        loop {
+            let (linenumber, read_line) = if let Some(pending) = self.pending.take() {
+                pending
+            } else if let Some(line) = self.lines.next() {
+                (self.linenumber + 1, line)
+            } else {
+                let model = self.models.take_model();
+                return self.finish(model, errors);
+            };
+            self.linenumber = linenumber;
+
+            let line = if let Ok(l) = read_line {
+                l
+            } else {
+                errors.push(PDBError::new(
+                    ErrorLevel::BreakingError,
+                    "Could read line",
+                    format!("Could not read line {linenumber} while parsing the input file."),
+                    self.context.clone(),
+                ));
+                return Some(Err(errors));
+            };
+
+            match lex_line(&line, linenumber) {
+                Ok((result, line_errors)) => {
+                    errors.extend(line_errors);
+                    match result {
+                        item @ LexItem::Atom(..) => self.models.add_atom(item),
+                        LexItem::Anisou(s, n, _r, _c, _rs, factors, _e, _ch) => {
+                            self.models.add_anisou(s, &n, factors)
+                        }
+                        LexItem::Model(number) => {
+                            let model = self.models.take_model();
+                            self.models.number = number;
+                            if model.is_some() {
+                                return self.finish(model, errors);
+                            }
+                        }
+                        LexItem::EndModel() => {
+                            let model = self.models.take_model();
+                            if model.is_some() {
+                                return self.finish(model, errors);
+                            }
+                        }
+                        LexItem::TER() => self.models.ter(),
+                        _ => (),
+                    }
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn models_match_full_read() {
+        for file in ["example-pdbs/models.pdb", "example-pdbs/1ubq.pdb"] {
+            let (pdb, _) = crate::open_pdb(file, StrictnessLevel::Loose).unwrap();
+            let (reader, _) = open_pdb_models(file, StrictnessLevel::Loose).unwrap();
+            assert_eq!(reader.header().model_count(), 0);
+            assert_eq!(reader.header().identifier, pdb.identifier);
+            assert_eq!(reader.header().unit_cell, pdb.unit_cell);
+            assert_eq!(reader.header().remark_count(), pdb.remark_count());
+            let models: Vec<Model> = reader.map(|result| result.unwrap().0).collect();
+            assert_eq!(models.len(), pdb.model_count());
+            for (model, expected) in models.iter().zip(pdb.models()) {
+                assert_eq!(model, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn trajectory() {
+        let text = "HEADER    TRAJECTORY                              01-JAN-00   TRAJ
+CRYST1   10.000   10.000   10.000  90.00  90.00  90.00 P 1           1
+SSBOND   1 CYS A    1    CYS A    2                          1555   1555  2.03
+MODEL        1
+ATOM      1  SG  CYS A   1       1.000   1.000   1.000  1.00  0.00           S
+ATOM      2  SG  CYS A   2       2.000   1.000   1.000  1.00  0.00           S
+ENDMDL
+MODEL        2
+ATOM      1  SG  CYS A   1       1.500   1.000   1.000  1.00  0.00           S
+ATOM      2  SG  CYS A   2       2.500   1.000   1.000  1.00  0.00           S
+ENDMDL
+CONECT    1    2
+END
+";
+        let (reader, errors) = open_pdb_models_raw(
+            BufReader::new(text.as_bytes()),
+            Context::none(),
+            StrictnessLevel::Medium,
+        )
+        .unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(reader.header().identifier.as_deref(), Some("TRAJ"));
+        let models: Vec<Model> = reader.map(|result| result.unwrap().0).collect();
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[1].serial_number(), 2);
+        assert_eq!(models[1].atom(0).unwrap().x(), 1.5);
+        assert!(models.iter().all(|model| model.atom_count() == 2));
+    }
+
+    #[test]
+    fn validation_and_trailing_errors() {
+        let text = "MODEL        1
+ATOM      1  SG  CYS A   1       1.000   1.000   1.000  1.00  0.00           S
+ATOM      2  SG  CYS A   2       2.000   1.000   1.000  1.00  0.00           S
+ENDMDL
+MODEL        2
+ATOM      1  SG  CYS A   1       1.500   1.000   1.000  1.00  0.00           S
+ENDMDL
+CONECT    1    X
+END
+";
+        let (mut reader, _) = open_pdb_models_raw(
+            BufReader::new(text.as_bytes()),
+            Context::none(),
+            StrictnessLevel::Loose,
+        )
+        .unwrap();
+        let (_, errors) = reader.next().unwrap().unwrap();
+        assert!(errors.is_empty());
+        let (_, errors) = reader.next().unwrap().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].short_description(), "Invalid Model");
+        // The invalid CONECT record is found after the last model
+        assert!(reader.trailing_errors().is_empty());
+        let errors = reader.next().unwrap().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].level(), ErrorLevel::InvalidatingError);
+        assert!(reader.next().is_none());
+        assert_eq!(reader.trailing_errors().len(), 1);
+    }
+}
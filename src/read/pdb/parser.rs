@@ -53,11 +53,90 @@ pub fn open_pdb_raw<T>(
 where
     T: std::io::Read,
 {
+    let (pdb, mut errors) = parse_pdb_lines((1..).zip(input.lines()), &context)?;
+    errors.extend(validate_invariants(&pdb));
+
+    if errors.iter().any(|e| e.fails(level)) {
+        Err(errors)
+    } else {
+        Ok((pdb, errors))
+    }
+}
+
+/// Parse the given bytes, the full contents of a PDB file, into a PDB struct. This is useful for
+/// data that is already in memory or memory mapped, see [`open_pdb_mmap`].
+/// Both `\n` and `\r\n` line endings are supported.
+/// Returns a PDBError if a BreakingError is found. Otherwise it returns the PDB with all errors/warnings found while parsing it.
+///
+/// ## Arguments
+/// * `bytes` - the contents of the file
+/// * `context` - the context of the full file, to place error messages correctly, for files this is `Context::show(filename)`.
+/// * `level` - the strictness level to operate in. If errors are generated which are breaking in the given level the parsing will fail.
+///
+/// # Related
+/// To memory map a file and parse it see [`open_pdb_mmap`] (requires the `mmap` feature).
+pub fn open_pdb_bytes(
+    bytes: &[u8],
+    context: Context,
+    level: StrictnessLevel,
+) -> Result<(PDB, Vec<PDBError>), Vec<PDBError>> {
+    let lines = bytes
+        .strip_suffix(b"\n")
+        .unwrap_or(bytes)
+        .split(|byte| *byte == b'\n')
+        .map(|line| {
+            std::str::from_utf8(line.strip_suffix(b"\r").unwrap_or(line))
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        });
+    let (pdb, mut errors) = parse_pdb_lines((1..).zip(lines), &context)?;
+    errors.extend(validate_invariants(&pdb));
+
+    if errors.iter().any(|e| e.fails(level)) {
+        Err(errors)
+    } else {
+        Ok((pdb, errors))
+    }
+}
+
+/// Memory map the given PDB file and parse its contents, see [`open_pdb_bytes`].
+/// Returns a PDBError if a BreakingError is found. Otherwise it returns the PDB with all errors/warnings found while parsing it.
+#[cfg(feature = "mmap")]
+pub fn open_pdb_mmap(
+    filename: impl AsRef<str>,
+    level: StrictnessLevel,
+) -> Result<(PDB, Vec<PDBError>), Vec<PDBError>> {
+    let filename = filename.as_ref();
+    let file = if let Ok(f) = File::open(filename) {
+        f
+    } else {
+        return Err(vec![PDBError::new(ErrorLevel::BreakingError, "Could not open file", "Could not open the specified file, make sure the path is correct, you have permission, and that it is not open in another program.", Context::show(filename))]);
+    };
+    // Safety: the file is only read, changing it while it is being parsed is undefined behaviour
+    // which is documented by memmap2, this is the same risk as any other memory mapped file
+    let map = match unsafe { memmap2::Mmap::map(&file) } {
+        Ok(map) => map,
+        Err(e) => {
+            return Err(vec![PDBError::new(
+                ErrorLevel::BreakingError,
+                "Could not memory map file",
+                format!("Error: {e}"),
+                Context::show(filename),
+            )])
+        }
+    };
+    open_pdb_bytes(&map, Context::show(filename), level)
+}
+
+/// Parse the given lines, with their (1 based) line numbers, into a PDB struct. It only fails if
+/// a line could not be read, the caller has to decide if any of the returned errors are fatal.
+/// The lines can be owned or borrowed.
+pub(super) fn parse_pdb_lines<L: AsRef<str>>(
+    lines: impl Iterator<Item = (usize, std::io::Result<L>)>,
+    context: &Context,
+) -> Result<(PDB, Vec<PDBError>), Vec<PDBError>> {
     let mut errors = Vec::new();
     let mut pdb = PDB::new();
-    let mut current_model_number = 0;
-    let mut current_model: IndexMap<String, IndexMap<(isize, Option<String>), Residue>> =
-        IndexMap::new();
+    let mut models = BuildUpModels::new();
     let mut sequence: HashMap<String, Vec<(usize, usize, Vec<String>)>> = HashMap::new();
     let mut seqres_lines = Vec::new();
     let mut seqres_start_linenumber = usize::MAX;
@@ -67,31 +146,23 @@ where
     let mut temp_scale = BuildUpMatrix::empty();
     let mut temp_origx = BuildUpMatrix::empty();
     let mut temp_mtrix: Vec<(usize, BuildUpMatrix, bool)> = Vec::new();
-    let mut last_residue_serial_number = 0;
-    let mut residue_serial_addition = 0;
-    let mut last_atom_serial_number = 0;
-    let mut atom_serial_addition = 0;
-    let mut chain_iter = ('A'..='Z').cycle();
-    // Initialize chain_id value
-    let mut chain_id_new = chain_iter.next();
 
-    for (mut linenumber, read_line) in input.lines().enumerate() {
-        linenumber += 1; // 1 based indexing in files
-
-        let line = if let Ok(l) = read_line {
-            l
+    for (linenumber, read_line) in lines {
+        let line = if let Ok(l) = &read_line {
+            l.as_ref()
         } else {
             return Err(vec![PDBError::new(
                 ErrorLevel::BreakingError,
                 "Could read line",
                 format!("Could not read line {linenumber} while parsing the input file."),
-                context,
+                context.clone(),
             )]);
         };
-        let line_result = lex_line(&line, linenumber);
-        let line_context = Context::FullLine {
+        let line_result = lex_line(line, linenumber);
+        // Only create the context when needed, to not allocate for every line
+        let line_context = || Context::FullLine {
             linenumber,
-            line: line.clone(),
+            line: line.to_string(),
         };
 
         // Then immediately add this lines information to the final PDB struct
@@ -99,123 +170,19 @@ where
             Ok((result, line_errors)) => {
                 errors.extend(line_errors);
                 match result {
-                    LexItem::Header(_, _, identifier) => pdb.identifier = Some(identifier),
+                    LexItem::Header(identifier) => pdb.identifier = Some(identifier),
                     LexItem::Remark(num, text) => {
-                        let _ = pdb.add_remark(num, text.to_string()); // Better error messages are created downstream
+                        let _ = pdb.add_remark(num, text.clone()); // Better error messages are created downstream
                     }
-                    LexItem::Atom(
-                        hetero,
-                        serial_number,
-                        name,
-                        alt_loc,
-                        residue_name,
-                        mut chain_id,
-                        residue_serial_number,
-                        insertion_code,
-                        x,
-                        y,
-                        z,
-                        occ,
-                        b,
-                        _,
-                        element,
-                        charge,
-                    ) => {
-                        if serial_number == 0 && last_atom_serial_number == 99_999 {
-                            atom_serial_addition += 100_000
-                        }
-
-                        if residue_serial_number == 0 && last_residue_serial_number == 9999 {
-                            residue_serial_addition += 10000;
-                        }
-
-                        if chain_id.trim().is_empty() {
-                            chain_id = chain_id_new
-                                .expect("Chain ID iterator is exhausted")
-                                .to_string();
-                        }
-
-                        let atom = Atom::new(
-                            hetero,
-                            serial_number + atom_serial_addition,
-                            name,
-                            x,
-                            y,
-                            z,
-                            occ,
-                            b,
-                            element,
-                            charge,
-                        )
-                        .expect("Invalid characters in atom creation");
-                        let conformer_id = (residue_name.as_str(), alt_loc.as_deref());
-
-                        let current_chain = if let Some(chain) = current_model.get_mut(&chain_id) {
-                            chain
-                        } else {
-                            current_model.insert(chain_id.clone(), IndexMap::new());
-                            current_model.get_mut(&chain_id).expect("Element that was just inserted into this IndexMap was not found in this IndexMap.")
-                        };
-
-                        if let Some(residue) = current_chain.get_mut(&(
-                            residue_serial_number + residue_serial_addition,
-                            insertion_code.clone(),
-                        )) {
-                            residue.add_atom(atom, conformer_id);
-                        } else {
-                            current_chain.insert(
-                                (
-                                    residue_serial_number + residue_serial_addition,
-                                    insertion_code.clone(),
-                                ),
-                                Residue::new(
-                                    residue_serial_number + residue_serial_addition,
-                                    insertion_code.as_deref(),
-                                    Some(
-                                        Conformer::new(
-                                            residue_name.as_str(),
-                                            alt_loc.as_deref(),
-                                            Some(atom),
-                                        )
-                                        .expect("Invalid characters in Conformer creation"),
-                                    ),
-                                )
-                                .expect("Invalid characters in Residue creation"),
-                            );
-                        }
-
-                        last_residue_serial_number = residue_serial_number;
-                        last_atom_serial_number = serial_number;
-                    }
-                    LexItem::Anisou(s, n, _, _r, _c, _rs, _, factors, _, _e, _ch) => {
-                        let mut found = false;
-                        for atom in current_model
-                            .values_mut()
-                            .rev()
-                            .flat_map(|residues| residues.values_mut().flat_map(Residue::atoms_mut))
-                        {
-                            if atom.serial_number() == s {
-                                atom.set_anisotropic_temperature_factors(factors);
-                                found = true;
-                                break;
-                            }
-                        }
-                        if !found {
-                            eprintln!("Could not find atom for temperature factors, coupled to atom {s} {n}")
-                        }
+                    item @ LexItem::Atom(..) => models.add_atom(item),
+                    LexItem::Anisou(s, n, _r, _c, _rs, factors, _e, _ch) => {
+                        models.add_anisou(s, &n, factors)
                     }
                     LexItem::Model(number) => {
-                        if !current_model.is_empty() {
-                            pdb.add_model(Model::from_iter(
-                                current_model_number,
-                                current_model.into_iter().map(|(id, residues)| {
-                                    Chain::from_iter(id, residues.into_values())
-                                        .expect("Invalid characters in Chain definition")
-                                }),
-                            ));
+                        if let Some(model) = models.take_model() {
+                            pdb.add_model(model);
                         }
-                        current_model_number = number;
-                        current_model = IndexMap::new();
+                        models.number = number;
                     }
                     LexItem::Scale(n, row) => {
                         temp_scale.set_row(n, row);
@@ -253,7 +220,7 @@ where
                         } else {
                             sequence.insert(chain_id, vec![(ser_num, num_res, values)]);
                         }
-                        seqres_lines.push(line);
+                        seqres_lines.push(line.to_string());
                     }
                     LexItem::Dbref(_pdb_id, chain_id, local_pos, db, db_acc, db_id, db_pos) => {
                         database_references.push((
@@ -290,7 +257,7 @@ where
                             }
                         }
                         if !found {
-                            errors.push(PDBError::new(ErrorLevel::BreakingError, "Solitary DBREF2", format!("Could not find the DBREF1 record fitting to this DBREF2 with chain id '{chain_id}'"), line_context.clone()))
+                            errors.push(PDBError::new(ErrorLevel::BreakingError, "Solitary DBREF2", format!("Could not find the DBREF1 record fitting to this DBREF2 with chain id '{chain_id}'"), line_context()))
                         }
                     }
                     LexItem::Seqadv(
@@ -317,12 +284,14 @@ where
                             ErrorLevel::StrictWarning,
                             "Sequence Difference Database not found",
                             format!("For this sequence difference (chain: {chain_id}) the corresponding database definition (DBREF) was not found, make sure the DBREF is located before the SEQADV"),
-                            line_context.clone()
+                            line_context()
                         ))
                         }
                     }
-                    item @ LexItem::Modres(..) => modifications.push((line_context.clone(), item)),
-                    item @ LexItem::SSBond(..) => bonds.push((line_context.clone(), item)),
+                    item @ LexItem::Modres(..) => modifications.push((line_context(), item)),
+                    item @ (LexItem::SSBond(..) | LexItem::Connect(..)) => {
+                        bonds.push((line_context(), item));
+                    }
                     LexItem::Master(
                         num_remark,
                         num_empty,
@@ -338,15 +307,8 @@ where
                         _num_seq,
                     ) => {
                         // The last atoms need to be added to make the MASTER checksum work out
-                        if !current_model.is_empty() {
-                            pdb.add_model(Model::from_iter(
-                                current_model_number,
-                                current_model.into_iter().map(|(id, residues)| {
-                                    Chain::from_iter(id, residues.into_values())
-                                        .expect("Invalid characters in Chain definition")
-                                }),
-                            ));
-                            current_model = IndexMap::new();
+                        if let Some(model) = models.take_model() {
+                            pdb.add_model(model);
                         }
                         // The for now forgotten numbers will have to be added when the appropriate records are added to the parser
                         if num_remark != pdb.remark_count() {
@@ -355,7 +317,7 @@ where
                                 ErrorLevel::StrictWarning,
                                 "MASTER checksum failed",
                                 format!("The number of REMARKS ({}) is different then posed in the MASTER Record ({})", pdb.remark_count(), num_remark),
-                                line_context.clone()
+                                line_context()
                             )
                         );
                         }
@@ -365,7 +327,7 @@ where
                                 ErrorLevel::LooseWarning,
                                 "MASTER checksum failed",
                                 format!("The empty checksum number is not empty (value: {num_empty}) while it is defined to be empty."),
-                                line_context.clone()
+                                line_context()
                             )
                         );
                         }
@@ -387,7 +349,7 @@ where
                                 ErrorLevel::StrictWarning,
                                 "MASTER checksum failed",
                                 format!("The number of coordinate transformation records ({xform}) is different then posed in the MASTER Record ({num_xform})"),
-                                line_context.clone()
+                                line_context()
                             )
                         );
                         }
@@ -397,26 +359,20 @@ where
                                 ErrorLevel::LooseWarning,
                                 "MASTER checksum failed",
                                 format!("The number of Atoms ({}) is different then posed in the MASTER Record ({})", pdb.total_atom_count(), num_coord),
-                                line_context.clone()
+                                line_context()
                             )
                         );
                         }
                     }
-                    LexItem::TER() => chain_id_new = chain_iter.next(),
+                    LexItem::TER() => models.ter(),
                     _ => (),
                 }
             }
             Err(e) => errors.push(e),
         }
     }
-    if !current_model.is_empty() {
-        pdb.add_model(Model::from_iter(
-            current_model_number,
-            current_model.into_iter().map(|(id, residues)| {
-                Chain::from_iter(id, residues.into_values())
-                    .expect("Invalid characters in Chain definition")
-            }),
-        ));
+    if let Some(model) = models.take_model() {
+        pdb.add_model(model);
     }
 
     for (chain_id, reference, complete) in database_references {
@@ -475,17 +431,12 @@ where
         sequence,
         seqres_lines,
         seqres_start_linenumber - 1, // Convert from 1 based to 0 based numbering
-        &context,
+        context,
     ));
     errors.extend(add_modifications(&mut pdb, modifications));
     errors.extend(add_bonds(&mut pdb, bonds));
-    errors.extend(validate(&pdb));
 
-    if errors.iter().any(|e| e.fails(level)) {
-        Err(errors)
-    } else {
-        Ok((pdb, errors))
-    }
+    Ok((pdb, errors))
 }
 
 /// Merge all warnings about long REMARK definitions into a single warning
@@ -563,7 +514,7 @@ fn add_modifications(pdb: &mut PDB, modifications: Vec<(Context, LexItem)>) -> V
     let mut errors = Vec::new();
     for (context, item) in modifications {
         match item {
-            LexItem::Modres(_, res_name, chain_id, seq_num, insertion_code, std_name, comment) => {
+            LexItem::Modres(res_name, chain_id, seq_num, insertion_code, std_name, comment) => {
                 if let Some(chain) = pdb.chains_mut().find(|c| c.id() == chain_id) {
                     if let Some(residue) = chain
                         .residues_mut()
@@ -602,9 +553,18 @@ fn add_modifications(pdb: &mut PDB, modifications: Vec<(Context, LexItem)>) -> V
 #[allow(clippy::unwrap_used)]
 fn add_bonds(pdb: &mut PDB, bonds: Vec<(Context, LexItem)>) -> Vec<PDBError> {
     let mut errors = Vec::new();
+    let mut connections: IndexMap<(usize, usize), (usize, Context)> = IndexMap::new();
     for (context, bond) in bonds {
         match bond {
-            LexItem::SSBond(atom1, atom2, ..) => {
+            LexItem::Connect(serial, bonded) => {
+                for partner in bonded {
+                    connections
+                        .entry((serial, partner))
+                        .or_insert((0, context.clone()))
+                        .0 += 1;
+                }
+            }
+            LexItem::SSBond(atom1, atom2) => {
                 let find = |atom: (String, isize, Option<String>, String)| {
                     pdb.chains()
                         .find(|c| c.id() == atom.3)
@@ -627,7 +587,12 @@ fn add_bonds(pdb: &mut PDB, bonds: Vec<(Context, LexItem)>) -> Vec<PDBError> {
                 let ref2 = find(atom2);
 
                 if let (Some(counter1), Some(counter2)) = (ref1, ref2) {
-                    pdb.add_bond_counters(counter1, counter2, Bond::Disulfide);
+                    pdb.add_bond_counters(
+                        counter1,
+                        counter2,
+                        Bond::Disulfide,
+                        Some(BondOrder::Single),
+                    );
                 } else {
                     errors.push(PDBError::new(
                         ErrorLevel::InvalidatingError,
@@ -644,5 +609,67 @@ fn add_bonds(pdb: &mut PDB, bonds: Vec<(Context, LexItem)>) -> Vec<PDBError> {
             }
         }
     }
+    errors.extend(add_connections(pdb, &connections));
+    errors
+}
+
+/// Adds the bonds from the CONECT records to the PDB. Following the convention a bond listed
+/// twice or three times is a double or triple bond. Each bond is only listed once per direction
+/// but both directions are taken into account. The bonds are added in all models, and
+/// alternative locations are only bonded to the same alternative location or shared atoms.
+fn add_connections(
+    pdb: &mut PDB,
+    connections: &IndexMap<(usize, usize), (usize, Context)>,
+) -> Vec<PDBError> {
+    let mut errors = Vec::new();
+    let mut new_bonds = Vec::new();
+    let mut missing = Vec::new();
+    for model in pdb.models() {
+        let mut lookup: HashMap<usize, Vec<(usize, Option<String>)>> = HashMap::new();
+        for hierarchy in model.atoms_with_hierarchy() {
+            lookup
+                .entry(hierarchy.atom().serial_number())
+                .or_default()
+                .push((
+                    hierarchy.atom().counter(),
+                    hierarchy
+                        .conformer()
+                        .alternative_location()
+                        .map(str::to_string),
+                ));
+        }
+        for (&(serial, partner), (count, context)) in connections {
+            if serial == partner || serial > partner && connections.contains_key(&(partner, serial))
+            {
+                continue; // Self bonds are ignored, and both directions are handled at once
+            }
+            let count = connections
+                .get(&(partner, serial))
+                .map_or(*count, |(other, _)| (*count).max(*other));
+            let order = match count {
+                1 => BondOrder::Single,
+                2 => BondOrder::Double,
+                _ => BondOrder::Triple,
+            };
+            if let (Some(atoms1), Some(atoms2)) = (lookup.get(&serial), lookup.get(&partner)) {
+                for (counter1, alt1) in atoms1 {
+                    for (counter2, alt2) in atoms2 {
+                        if alt1 == alt2 || alt1.is_none() || alt2.is_none() {
+                            new_bonds.push((*counter1, *counter2, order));
+                        }
+                    }
+                }
+            } else if !missing.contains(&(serial, partner)) {
+                missing.push((serial, partner));
+                errors.push(PDBError::new(
+                    ErrorLevel::LooseWarning,
+                    "Could not find a bond partner",
+                    format!("One of the atoms could not be found while parsing a CONECT bond between atoms {serial} and {partner}."),
+                    context.clone(),
+                ));
+            }
+        }
+    }
+    pdb.add_new_covalent_bonds(new_bonds);
     errors
 }
@@ -1,5 +1,11 @@
+use super::lexitem::LexItem;
+use crate::structs::*;
 use crate::TransformationMatrix;
 
+use indexmap::IndexMap;
+use std::iter::Cycle;
+use std::ops::RangeInclusive;
+
 /// To help build a a matrix from separate rows
 pub struct BuildUpMatrix {
     /// First row
@@ -55,3 +61,170 @@ impl BuildUpMatrix {
         }
     }
 }
+
+/// The chains of a model as built up from the atom records, with the residues by serial number
+/// and insertion code, both in order of appearance
+type BuildUpChains = IndexMap<String, IndexMap<(isize, Option<String>), Residue>>;
+
+/// To help build models from the atom records. It keeps track of the numbering of atoms and
+/// residues over all models, to handle serial numbers wrapping around in large files.
+#[derive(Debug)]
+pub struct BuildUpModels {
+    /// The serial number of the model currently being built
+    pub number: usize,
+    /// The chains of the model currently being built
+    chains: BuildUpChains,
+    /// The residue serial number of the last atom
+    last_residue_serial_number: isize,
+    /// The number to add to residue serial numbers, increased every time these wrap around
+    residue_serial_addition: isize,
+    /// The serial number of the last atom
+    last_atom_serial_number: usize,
+    /// The number to add to atom serial numbers, increased every time these wrap around
+    atom_serial_addition: usize,
+    /// The chain ids used for atoms without a chain id, the next one is used after every TER
+    chain_ids: Cycle<RangeInclusive<char>>,
+    /// The chain id for atoms without a chain id
+    chain_id: Option<char>,
+}
+
+impl BuildUpModels {
+    /// Create an empty struct
+    pub fn new() -> Self {
+        let mut chain_ids = ('A'..='Z').cycle();
+        let chain_id = chain_ids.next();
+        BuildUpModels {
+            number: 0,
+            chains: IndexMap::new(),
+            last_residue_serial_number: 0,
+            residue_serial_addition: 0,
+            last_atom_serial_number: 0,
+            atom_serial_addition: 0,
+            chain_ids,
+            chain_id,
+        }
+    }
+
+    /// Add an atom to the model currently being built, it panics if the item is not a `LexItem::Atom`
+    pub fn add_atom(&mut self, item: LexItem) {
+        if let LexItem::Atom(
+            hetero,
+            serial_number,
+            name,
+            alt_loc,
+            residue_name,
+            mut chain_id,
+            residue_serial_number,
+            insertion_code,
+            x,
+            y,
+            z,
+            occ,
+            b,
+            segment_id,
+            element,
+            charge,
+        ) = item
+        {
+            if serial_number == 0 && self.last_atom_serial_number == 99_999 {
+                self.atom_serial_addition += 100_000
+            }
+
+            if residue_serial_number == 0 && self.last_residue_serial_number == 9999 {
+                self.residue_serial_addition += 10000;
+            }
+
+            if chain_id.trim().is_empty() {
+                chain_id = self
+                    .chain_id
+                    .expect("Chain ID iterator is exhausted")
+                    .to_string();
+            }
+
+            let mut atom = Atom::new(
+                hetero,
+                serial_number + self.atom_serial_addition,
+                name,
+                x,
+                y,
+                z,
+                occ,
+                b,
+                element,
+                charge,
+            )
+            .expect("Invalid characters in atom creation");
+            // Invalid segment ids are reported and removed by the lexer
+            let _ = atom.set_segment_id(segment_id);
+            let conformer_id = (residue_name.as_str(), alt_loc.as_deref());
+            let residue_id = (
+                residue_serial_number + self.residue_serial_addition,
+                insertion_code,
+            );
+
+            let current_chain = self.chains.entry(chain_id).or_default();
+
+            if let Some(residue) = current_chain.get_mut(&residue_id) {
+                residue.add_atom(atom, conformer_id);
+            } else {
+                let residue = Residue::new(
+                    residue_id.0,
+                    residue_id.1.as_deref(),
+                    Some(
+                        Conformer::new(residue_name.as_str(), alt_loc.as_deref(), Some(atom))
+                            .expect("Invalid characters in Conformer creation"),
+                    ),
+                )
+                .expect("Invalid characters in Residue creation");
+                current_chain.insert(residue_id, residue);
+            }
+
+            self.last_residue_serial_number = residue_serial_number;
+            self.last_atom_serial_number = serial_number;
+        } else {
+            panic!("Found an invalid element while adding atoms, it is not a LexItem::Atom");
+        }
+    }
+
+    /// Set the anisotropic temperature factors of the last atom with the given serial number in
+    /// the model currently being built
+    pub fn add_anisou(&mut self, serial_number: usize, name: &str, factors: [[f64; 3]; 3]) {
+        for atom in self
+            .chains
+            .values_mut()
+            .rev()
+            .flat_map(|residues| residues.values_mut().flat_map(Residue::atoms_mut))
+        {
+            if atom.serial_number() == serial_number {
+                atom.set_anisotropic_temperature_factors(factors);
+                return;
+            }
+        }
+        eprintln!(
+            "Could not find atom for temperature factors, coupled to atom {serial_number} {name}"
+        )
+    }
+
+    /// Handle a TER record, atoms without a chain id after this record get the next chain id
+    pub fn ter(&mut self) {
+        self.chain_id = self.chain_ids.next();
+    }
+
+    /// Take the model currently being built, if it contains any atoms, and start a new model
+    /// with the same serial number
+    pub fn take_model(&mut self) -> Option<Model> {
+        if self.chains.is_empty() {
+            None
+        } else {
+            Some(Model::from_iter(
+                self.number,
+                std::mem::take(&mut self.chains)
+                    .into_iter()
+                    .map(|(id, residues)| {
+                        Chain::from_iter(id, residues.into_values())
+                            .expect("Invalid characters in Chain definition")
+                    }),
+            ))
+        }
+    }
+}
@@ -20,9 +20,9 @@ pub fn validate_seqres(
     for (chain_id, data) in sequence {
         if let Some(chain) = pdb.chains_mut().find(|c| c.id() == chain_id) {
             let mut chain_sequence = Vec::new();
-            let mut serial = 1;
             let mut residues = 0;
-            for (line, (ser_num, res_num, seq)) in data.into_iter().enumerate() {
+            for (serial, (line, (ser_num, res_num, seq))) in (1..).zip(data.into_iter().enumerate())
+            {
                 if serial != ser_num {
                     errors.push(PDBError::new(
                         ErrorLevel::StrictWarning,
@@ -31,7 +31,6 @@ pub fn validate_seqres(
                         context.clone()
                     ));
                 }
-                serial += 1;
                 if residues == 0 {
                     residues = res_num;
                 } else if residues != res_num {
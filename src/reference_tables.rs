@@ -1,3 +1,4 @@
+use crate::structs::*;
 #[cfg(feature = "rayon")]
 use rayon::prelude::*;
 
@@ -53,11 +54,38 @@ pub fn is_amino_acid(aa: impl AsRef<str>) -> bool {
     AMINO_ACIDS.contains(&aa.as_ref())
 }
 
+/// Returns if the given residue name is a common name for water
+pub fn is_water(name: impl AsRef<str>) -> bool {
+    WATER_NAMES.contains(&name.as_ref())
+}
+
+/// Returns if the given residue name is a common deoxyribonucleotide (DNA)
+pub fn is_deoxyribonucleotide(name: impl AsRef<str>) -> bool {
+    DEOXYRIBONUCLEOTIDES.contains(&name.as_ref())
+}
+
+/// Returns if the given residue name is a common ribonucleotide (RNA)
+pub fn is_ribonucleotide(name: impl AsRef<str>) -> bool {
+    RIBONUCLEOTIDES.contains(&name.as_ref())
+}
+
 /// Returns if the given atom name is a name for an atom in the backbone of a protein
 pub fn is_backbone(name: impl AsRef<str>) -> bool {
     BACKBONE_NAMES.contains(&name.as_ref())
 }
 
+/// Gets the topology template for the given residue name, see [`ResidueTemplate`] for details.
+/// Common alternative names, like the Amber protonation variants (HID, HIE, HIP, ASH, GLH, LYN, CYX)
+/// and WAT for water, resolve to the template of their standard residue.
+pub fn get_residue_template(name: impl AsRef<str>) -> Option<&'static ResidueTemplate> {
+    let name = name.as_ref();
+    let name = TEMPLATE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, standard)| standard);
+    RESIDUE_TEMPLATES.iter().find(|t| t.name == name)
+}
+
 /// Returns if the given number is a valid remark-type-number (according to wwPDB v 3.30)
 pub fn valid_remark_type_number(number: usize) -> bool {
     REMARK_TYPES.contains(&number)
@@ -78,11 +106,37 @@ const AMINO_ACIDS: &[&str] = &[
     "TRP", "TYR", "VAL", "SEC", "PYL",
 ];
 
+/// Common names for water, including the names used by molecular dynamics packages and heavy water
+const WATER_NAMES: &[&str] = &[
+    "HOH", "WAT", "H2O", "DOD", "D2O", "TIP", "TIP3", "TIP4", "SPC", "SOL", "T3P", "T4P",
+];
+
+/// The common deoxyribonucleotides
+const DEOXYRIBONUCLEOTIDES: &[&str] = &["DA", "DC", "DG", "DT", "DI", "DU"];
+
+/// The common ribonucleotides
+const RIBONUCLEOTIDES: &[&str] = &["A", "C", "G", "U", "I"];
+
 /// The names of atom in the backbone of proteins
 const BACKBONE_NAMES: &[&str] = &[
     "N", "CA", "C", "O", "H", "H1", "H2", "H3", "HA", "HA2", "HA3",
 ];
 
+/// Alternative residue names mapped to the name of their template
+const TEMPLATE_ALIASES: &[(&str, &str)] = &[
+    ("ASH", "ASP"),
+    ("CYX", "CYS"),
+    ("GLH", "GLU"),
+    ("HID", "HIS"),
+    ("HIE", "HIS"),
+    ("HIP", "HIS"),
+    ("LYN", "LYS"),
+    ("WAT", "HOH"),
+];
+
+/// The topology templates for the 20 standard amino acids, the DNA and RNA nucleotides, and water
+const RESIDUE_TEMPLATES: &[ResidueTemplate] = include!("reference/residue_templates.txt");
+
 /// The list of Hermann Mauguin symbols in the same order as in the handbook
 const HERMANN_MAUGUIN_SYMBOL: &[&str] = include!("reference/hermann_mauguin_symbols.txt");
 
@@ -92,3 +146,113 @@ const HALL_SYMBOL: &[&str] = include!("reference/hall_symbols.txt");
 /// Reworked from CCTBX output (Jan 2021)
 const SYMBOL_TRANSFORMATION: &[&[[[f64; 4]; 3]]] =
     include!("reference/crystal_transformations.txt");
+
+/// The topology of a standard residue: the atoms it contains and the covalent bonds between them.
+/// The atom names follow the wwPDB Chemical Component Dictionary (PDB v3 naming) and include the
+/// hydrogens and the atoms that leave when the residue is part of a polymer (like OXT).
+/// Use [`get_residue_template`] to get the template for a residue name.
+#[derive(Debug)]
+pub struct ResidueTemplate {
+    /// The residue name
+    pub name: &'static str,
+    /// The names and elements of all atoms
+    pub atoms: &'static [(&'static str, Element)],
+    /// The atoms that are not present when this residue is linked in a polymer chain
+    pub leaving_atoms: &'static [&'static str],
+    /// The bonds within this residue, as the names of both atoms and the bond order
+    pub bonds: &'static [(&'static str, &'static str, BondOrder)],
+}
+
+impl ResidueTemplate {
+    /// Get the element of the atom with the given name, if this atom is part of this template
+    pub fn element(&self, name: impl AsRef<str>) -> Option<Element> {
+        let name = name.as_ref();
+        self.atoms.iter().find(|(n, _)| *n == name).map(|(_, e)| *e)
+    }
+
+    /// Get the names of all heavy (non hydrogen) atoms in this template
+    pub fn heavy_atoms(&self) -> impl Iterator<Item = &'static str> {
+        self.atoms
+            .iter()
+            .filter(|(_, e)| *e != Element::H)
+            .map(|(n, _)| *n)
+    }
+
+    /// Get the names of all atoms bonded to the atom with the given name, with the bond order
+    pub fn bonded_atoms<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = (&'static str, BondOrder)> + 'a {
+        self.bonds.iter().filter_map(move |(a, b, order)| {
+            if *a == name {
+                Some((*b, *order))
+            } else if *b == name {
+                Some((*a, *order))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Get all bonds of this template between atoms present in the given conformer. Atoms are
+    /// matched on name, bonds to atoms missing from the conformer are skipped.
+    pub fn bonds_in<'a>(
+        &'a self,
+        conformer: &'a Conformer,
+    ) -> impl Iterator<Item = (&'a Atom, &'a Atom, BondOrder)> + 'a {
+        self.bonds.iter().filter_map(move |(a, b, order)| {
+            Some((
+                conformer.atoms().find(|atom| atom.name() == *a)?,
+                conformer.atoms().find(|atom| atom.name() == *b)?,
+                *order,
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn residue_templates_consistent() {
+        for template in RESIDUE_TEMPLATES {
+            for (a, b, _) in template.bonds {
+                assert!(template.element(a).is_some(), "{} {a}", template.name);
+                assert!(template.element(b).is_some(), "{} {b}", template.name);
+            }
+            for name in template.leaving_atoms {
+                assert!(template.element(name).is_some(), "{} {name}", template.name);
+            }
+            for (name, _) in template.atoms {
+                assert!(
+                    template.bonded_atoms(name).next().is_some(),
+                    "{} {name}",
+                    template.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn residue_template_lookup() {
+        let his = get_residue_template("HIS").unwrap();
+        assert_eq!(get_residue_template("HIE").unwrap().name, "HIS");
+        assert_eq!(his.element("NE2"), Some(Element::N));
+        assert_eq!(his.heavy_atoms().count(), 11);
+        assert_eq!(
+            get_residue_template("GLY").unwrap().heavy_atoms().count(),
+            5
+        );
+        assert_eq!(get_residue_template("WAT").unwrap().name, "HOH");
+        assert!(get_residue_template("HEM").is_none());
+        let mut ca = get_residue_template("ALA")
+            .unwrap()
+            .bonded_atoms("CA")
+            .map(|(n, _)| n)
+            .collect::<Vec<_>>();
+        ca.sort_unstable();
+        assert_eq!(ca, vec!["C", "CB", "HA", "N"]);
+    }
+}
@@ -1,39 +1,47 @@
+use flate2::write::GzEncoder;
 use flate2::Compression;
 
+use super::mmcif::save_mmcif_;
+use super::pdb::save_pdb_;
 use super::*;
 use crate::structs::PDB;
-use crate::StrictnessLevel;
 use crate::{check_extension, error::*};
+use crate::{CompressionFormat, FileFormat, StrictnessLevel};
+use std::fs::File;
+use std::io::{BufWriter, Write};
 
 /// Save the given PDB struct to the given file, validating it beforehand.
 /// If validation gives rise to problems, use the `save_raw` function. The correct file
-/// type (pdb or mmCIF/PDBx) will be determined based on the given file extension.
+/// type (pdb or mmCIF/PDBx, or BinaryCIF with the `bcif` feature) will be determined based on the given
+/// file extension, which allows for common aliases like `.ent` and `.mmcif`, see [`FileFormat::from_filename`].
+/// The file is compressed if the name ends in a compression extension, see [`CompressionFormat`] for the
+/// supported formats and the features needed.
 /// # Errors
-/// Fails if the validation fails with the given `level`.
+/// Fails if the validation fails with the given `level`, if the type of file could not be determined,
+/// or if the feature needed for the type or compression of the file is not enabled.
 pub fn save(
     pdb: &PDB,
     filename: impl AsRef<str>,
     level: StrictnessLevel,
 ) -> Result<(), Vec<PDBError>> {
-    if check_extension(&filename, "pdb") {
-        save_pdb(pdb, filename, level)
-    } else if check_extension(&filename, "cif") {
-        save_mmcif(pdb, filename, level)
-    } else {
-        Err(vec![PDBError::new(
-            ErrorLevel::BreakingError,
-            "Incorrect extension",
-            "Could not determine the type of the given file, make it .pdb or .cif",
-            Context::show(filename.as_ref()),
-        )])
-    }
+    let filename = filename.as_ref();
+    let compression = CompressionFormat::from_filename(filename);
+    let encoder = compression
+        .map(|compression| compression.encoder(Context::show(filename)))
+        .transpose()
+        .map_err(|e| vec![e])?;
+    save_format(pdb, filename, level, |file| match encoder {
+        Some(encoder) => encoder(file),
+        None => Box::new(file),
+    })
 }
 
 /// Save the given PDB struct to the given file and compressing to gz, validating it beforehand.
 /// If validation gives rise to problems, use the `save_raw` function. The correct file
-/// type (pdb or mmCIF/PDBx) will be determined based on the given file extension.
+/// type will be determined based on the given file extension like [`save`], see [`FileFormat::from_filename`].
 /// # Errors
-/// Fails if the validation fails with the given `level`.
+/// Fails if the validation fails with the given `level`, if the file name does not end in `.gz`,
+/// or if the type of file could not be determined.
 pub fn save_gz(
     pdb: &PDB,
     filename: impl AsRef<str>,
@@ -41,35 +49,62 @@ pub fn save_gz(
     compression_level: Option<Compression>,
 ) -> Result<(), Vec<PDBError>> {
     let filename = filename.as_ref();
-    if check_extension(filename, "gz") {
-        // safety check to prevent out of bounds indexing
-        if filename.len() < 3 {
+    if CompressionFormat::from_filename(filename) == Some(CompressionFormat::Gzip) {
+        save_format(pdb, filename, level, |file| {
+            Box::new(GzEncoder::new(file, compression_level.unwrap_or_default()))
+        })
+    } else {
+        Err(vec![PDBError::new(
+            ErrorLevel::BreakingError,
+            "Incorrect extension",
+            "Could not determine the type of the given file, make it .pdb.gz or .cif.gz",
+            Context::show(filename),
+        )])
+    }
+}
+
+/// Save the given PDB struct to the given file in the format determined by the file extension,
+/// the file is written through the given function, which can compress the file
+fn save_format(
+    pdb: &PDB,
+    filename: &str,
+    level: StrictnessLevel,
+    compress: impl FnOnce(File) -> Box<dyn Write>,
+) -> Result<(), Vec<PDBError>> {
+    let context = Context::show(filename);
+    let (name, _) = CompressionFormat::strip_extension(filename);
+    let format = match FileFormat::from_filename(filename) {
+        Some(FileFormat::Pdb) if check_extension(name, "pdbqt") => {
             return Err(vec![PDBError::new(
                 ErrorLevel::BreakingError,
-                "Filename too short",
-                "Could not determine the type of the given file, make it .pdb.gz or .cif.gz",
-                Context::show(filename),
+                "Format not supported",
+                "PDBQT files can only be opened, as the charges and atom types needed for these files are not known.",
+                context,
             )]);
         }
-
-        if check_extension(&filename[..filename.len() - 3], "pdb") {
-            save_pdb_gz(pdb, filename, level, compression_level)
-        } else if check_extension(&filename[..filename.len() - 3], "cif") {
-            save_mmcif_gz(pdb, filename, level, compression_level)
-        } else {
-            Err(vec![PDBError::new(
+        Some(format) => format,
+        None => {
+            return Err(vec![PDBError::new(
                 ErrorLevel::BreakingError,
                 "Incorrect extension",
-                "Could not determine the type of the given file, make it .pdb.gz or .cif.gz",
-                Context::show(filename),
-            )])
+                "Could not determine the type of the given file, make it .pdb or .cif",
+                context,
+            )]);
         }
-    } else {
-        Err(vec![PDBError::new(
+    };
+    let writer = |file: File| -> BufWriter<Box<dyn Write>> { BufWriter::new(compress(file)) };
+    match format {
+        FileFormat::Pdb => save_pdb_(pdb, filename, level, writer),
+        #[cfg(feature = "bcif")]
+        FileFormat::Bcif => save_mmcif_(pdb, filename, level, writer, save_bcif_raw),
+        #[cfg(not(feature = "bcif"))]
+        FileFormat::Bcif => Err(vec![format.feature_error("bcif", context)]),
+        FileFormat::Mmcif => save_mmcif_(pdb, filename, level, writer, save_mmcif_raw),
+        FileFormat::Mmtf => Err(vec![PDBError::new(
             ErrorLevel::BreakingError,
-            "Incorrect extension",
-            "Could not determine the type of the given file, make it .pdb.gz or .cif.gz",
-            Context::show(filename),
-        )])
+            "Format not supported",
+            "MMTF files can only be opened, save the structure as .pdb, .cif or .bcif instead.",
+            context,
+        )]),
     }
 }
@@ -1,11 +1,14 @@
 #[cfg(feature = "compression")]
 use flate2::{write::GzEncoder, Compression};
 
+use crate::cif;
 use crate::error::*;
 use crate::structs::*;
-use crate::validate;
+use crate::transformation::TransformationMatrix;
+use crate::validate::validate_invariants;
 use crate::StrictnessLevel;
 
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufWriter;
@@ -19,7 +22,7 @@ pub fn save_mmcif(
     filename: impl AsRef<str>,
     level: StrictnessLevel,
 ) -> Result<(), Vec<PDBError>> {
-    save_mmcif_(pdb, filename, level, BufWriter::new)
+    save_mmcif_(pdb, filename, level, BufWriter::new, save_mmcif_raw)
 }
 
 /// Save the given PDB struct to the given file as mmCIF or PDBx and compresses to .gz
@@ -33,21 +36,23 @@ pub fn save_mmcif_gz(
     level: StrictnessLevel,
     compression_level: Option<Compression>,
 ) -> Result<(), Vec<PDBError>> {
-    save_mmcif_(pdb, filename, level, |file| {
-        BufWriter::new(GzEncoder::new(
-            file,
-            compression_level.unwrap_or(Compression::default()),
-        ))
-    })
+    save_mmcif_(
+        pdb,
+        filename,
+        level,
+        |file| BufWriter::new(GzEncoder::new(file, compression_level.unwrap_or_default())),
+        save_mmcif_raw,
+    )
 }
 
-/// Generic function to save the given PDB struct to the given file as mmCIF or PDBx,
-/// to some writer function, e.g. a GzEncoder or BufWriter.
-fn save_mmcif_<T, W>(
+/// Generic function to save the given PDB struct to the given file as mmCIF, PDBx, or BinaryCIF
+/// (depending on the save function), to some writer function, e.g. a GzEncoder or BufWriter.
+pub(super) fn save_mmcif_<T, W>(
     pdb: &PDB,
     filename: impl AsRef<str>,
     level: StrictnessLevel,
     writer: W,
+    save: fn(&PDB, BufWriter<T>),
 ) -> Result<(), Vec<PDBError>>
 where
     T: Write,
@@ -56,7 +61,7 @@ where
     // Validates the PDB, and returns early if any errors are found
     let filename = filename.as_ref();
 
-    let mut errors = validate(pdb);
+    let mut errors = validate_invariants(pdb);
     for error in &errors {
         if error.fails(level) {
             return Err(errors);
@@ -80,7 +85,7 @@ where
     let writer = writer(file);
 
     // Now call the writer function
-    save_mmcif_raw(pdb, writer);
+    save(pdb, writer);
 
     Ok(())
 }
@@ -88,332 +93,449 @@ where
 /// Save the given PDB struct to the given BufWriter.
 /// It does not validate or renumber the PDB, so if that is needed that needs to be done in preparation.
 /// It does change the output format based on the StrictnessLevel given.
+/// The file is written with the generic CIF writer, see [`cif::write`], all CIF items which were
+/// not interpreted when reading the structure are written back as well, see [`PDB::cif_items`].
 #[allow(clippy::unwrap_used)]
-pub fn save_mmcif_raw<T: Write>(pdb: &PDB, mut sink: BufWriter<T>) {
-    /// Write a piece of text to the file, has the same structure as format!
-    macro_rules! write {
-        ($($arg:tt)*) => {
-            sink.write_fmt(format_args!($($arg)*)).unwrap();
-            sink.write_all(b"\n").unwrap();
-        }
-    }
+pub fn save_mmcif_raw<T: Write>(pdb: &PDB, sink: BufWriter<T>) {
+    cif::write(&[mmcif_block(pdb)], sink).unwrap();
+}
 
-    let empty = "?".to_string();
-    let name = pdb.identifier.as_ref().unwrap_or(&empty);
+/// Save the given PDB struct to the given file as BinaryCIF.
+/// # Errors
+/// It validates the PDB. It fails if the validation fails with the given `level`, or if the file could not be opened.
+/// If validation gives rise to problems, use the `save_raw` function.
+#[cfg(feature = "bcif")]
+pub fn save_bcif(
+    pdb: &PDB,
+    filename: impl AsRef<str>,
+    level: StrictnessLevel,
+) -> Result<(), Vec<PDBError>> {
+    save_mmcif_(pdb, filename, level, BufWriter::new, save_bcif_raw)
+}
+
+/// Save the given PDB struct to the given BufWriter as BinaryCIF, with the same categories as
+/// [`save_mmcif_raw`]. It does not validate or renumber the PDB, so if that is needed that needs
+/// to be done in preparation.
+#[cfg(feature = "bcif")]
+#[allow(clippy::unwrap_used)]
+pub fn save_bcif_raw<T: Write>(pdb: &PDB, sink: BufWriter<T>) {
+    cif::write_binary(&[mmcif_block(pdb)], sink).unwrap();
+}
+
+/// Generate the mmCIF data block for the given PDB struct
+fn mmcif_block(pdb: &PDB) -> cif::DataBlock {
+    let name = pdb.identifier.as_deref();
+    let mut block = cif::DataBlock::new(name.unwrap_or("?"));
 
     // Header
-    write!(
-        "data_{}
-#
-_entry.id   {}
-#
-_audit_conform.dict_name       mmcif_pdbx.dic
-_audit_conform.dict_version    5.338
-_audit_conform.dict_location   http://mmcif.pdb.org/dictionaries/ascii/mmcif_pdbx.dic",
-        name,
-        name
+    block.add_single("entry.id", name);
+    block.add_single("audit_conform.dict_name", "mmcif_pdbx.dic");
+    block.add_single("audit_conform.dict_version", 5.338);
+    block.add_single(
+        "audit_conform.dict_location",
+        "http://mmcif.pdb.org/dictionaries/ascii/mmcif_pdbx.dic",
     );
 
     // Cryst
     if let Some(unit_cell) = &pdb.unit_cell {
-        write!(
-            "# Unit cell definition
-_cell.entry_id           {}
-_cell.length_a           {}
-_cell.length_b           {}
-_cell.length_c           {}
-_cell.angle_alpha        {}
-_cell.angle_beta         {}
-_cell.angle_gamma        {}
-_cell.Z_PDB              {}",
-            name,
-            unit_cell.a(),
-            unit_cell.b(),
-            unit_cell.c(),
-            unit_cell.alpha(),
-            unit_cell.beta(),
-            unit_cell.gamma(),
-            if let Some(symmetry) = &pdb.symmetry {
-                symmetry.z().to_string()
-            } else {
-                "?".to_owned()
-            }
+        block.add_category(
+            "cell",
+            &[
+                "entry_id",
+                "length_a",
+                "length_b",
+                "length_c",
+                "angle_alpha",
+                "angle_beta",
+                "angle_gamma",
+                "Z_PDB",
+            ],
+            vec![vec![
+                name.into(),
+                unit_cell.a().into(),
+                unit_cell.b().into(),
+                unit_cell.c().into(),
+                unit_cell.alpha().into(),
+                unit_cell.beta().into(),
+                unit_cell.gamma().into(),
+                pdb.symmetry.as_ref().map(Symmetry::z).into(),
+            ]],
         );
     }
 
     // Scale
     if let Some(scale) = &pdb.scale {
-        let ma = scale.matrix();
-        write!(
-            "# Scale definition
-            _atom_sites.entry_id                   '{}'
-_atom_sites.Cartn_transf_matrix[1][1]  {}
-_atom_sites.Cartn_transf_matrix[1][2]  {}
-_atom_sites.Cartn_transf_matrix[1][3]  {}
-_atom_sites.Cartn_transf_matrix[2][1]  {}
-_atom_sites.Cartn_transf_matrix[2][2]  {}
-_atom_sites.Cartn_transf_matrix[2][3]  {}
-_atom_sites.Cartn_transf_matrix[3][1]  {}
-_atom_sites.Cartn_transf_matrix[3][2]  {}
-_atom_sites.Cartn_transf_matrix[3][3]  {}
-_atom_sites.Cartn_transf_vector[1]     {}
-_atom_sites.Cartn_transf_vector[2]     {}
-_atom_sites.Cartn_transf_vector[3]     {}",
-            name,
-            ma[0][0],
-            ma[0][1],
-            ma[0][2],
-            ma[1][0],
-            ma[1][1],
-            ma[1][2],
-            ma[2][0],
-            ma[2][1],
-            ma[2][2],
-            ma[0][3],
-            ma[1][3],
-            ma[2][3],
+        let mut row = vec![name.into()];
+        row.extend(matrix_values(scale));
+        block.add_category(
+            "atom_sites",
+            &[
+                "entry_id",
+                "Cartn_transf_matrix[1][1]",
+                "Cartn_transf_matrix[1][2]",
+                "Cartn_transf_matrix[1][3]",
+                "Cartn_transf_matrix[2][1]",
+                "Cartn_transf_matrix[2][2]",
+                "Cartn_transf_matrix[2][3]",
+                "Cartn_transf_matrix[3][1]",
+                "Cartn_transf_matrix[3][2]",
+                "Cartn_transf_matrix[3][3]",
+                "Cartn_transf_vector[1]",
+                "Cartn_transf_vector[2]",
+                "Cartn_transf_vector[3]",
+            ],
+            vec![row],
         );
     }
 
     // OrigX
     if let Some(origx) = &pdb.origx {
-        let ma = origx.matrix();
-        write!(
-            "# OrigX definition
-_database_PDB_matrix.entry_id                   '{}'
-_database_PDB_matrix.origx[1][1]  {}
-_database_PDB_matrix.origx[1][2]  {}
-_database_PDB_matrix.origx[1][3]  {}
-_database_PDB_matrix.origx[2][1]  {}
-_database_PDB_matrix.origx[2][2]  {}
-_database_PDB_matrix.origx[2][3]  {}
-_database_PDB_matrix.origx[3][1]  {}
-_database_PDB_matrix.origx[3][2]  {}
-_database_PDB_matrix.origx[3][3]  {}
-_database_PDB_matrix.origx_vector[1]     {}
-_database_PDB_matrix.origx_vector[2]     {}
-_database_PDB_matrix.origx_vector[3]     {}",
-            name,
-            ma[0][0],
-            ma[0][1],
-            ma[0][2],
-            ma[1][0],
-            ma[1][1],
-            ma[1][2],
-            ma[2][0],
-            ma[2][1],
-            ma[2][2],
-            ma[0][3],
-            ma[1][3],
-            ma[2][3],
+        let mut row = vec![name.into()];
+        row.extend(matrix_values(origx));
+        block.add_category(
+            "database_PDB_matrix",
+            &[
+                "entry_id",
+                "origx[1][1]",
+                "origx[1][2]",
+                "origx[1][3]",
+                "origx[2][1]",
+                "origx[2][2]",
+                "origx[2][3]",
+                "origx[3][1]",
+                "origx[3][2]",
+                "origx[3][3]",
+                "origx_vector[1]",
+                "origx_vector[2]",
+                "origx_vector[3]",
+            ],
+            vec![row],
         );
     }
 
     // MtriX
-    for mtrix in pdb.mtrix() {
-        let ma = mtrix.transformation.matrix();
-        write!(
-            r#"# OrigX definition
-_struct_ncs_oper.id            '{}'
-_struct_ncs_oper.code          {}
-_struct_ncs_oper.matrix[1][1]  {}
-_struct_ncs_oper.matrix[1][2]  {}
-_struct_ncs_oper.matrix[1][3]  {}
-_struct_ncs_oper.matrix[2][1]  {}
-_struct_ncs_oper.matrix[2][2]  {}
-_struct_ncs_oper.matrix[2][3]  {}
-_struct_ncs_oper.matrix[3][1]  {}
-_struct_ncs_oper.matrix[3][2]  {}
-_struct_ncs_oper.matrix[3][3]  {}
-_struct_ncs_oper.vector[1]     {}
-_struct_ncs_oper.vector[2]     {}
-_struct_ncs_oper.vector[3]     {}"#,
-            mtrix.serial_number,
-            if mtrix.contained {
-                "given"
-            } else {
-                "generated"
-            },
-            ma[0][0],
-            ma[0][1],
-            ma[0][2],
-            ma[1][0],
-            ma[1][1],
-            ma[1][2],
-            ma[2][0],
-            ma[2][1],
-            ma[2][2],
-            ma[0][3],
-            ma[1][3],
-            ma[2][3],
-        );
-    }
+    block.add_category(
+        "struct_ncs_oper",
+        &[
+            "id",
+            "code",
+            "matrix[1][1]",
+            "matrix[1][2]",
+            "matrix[1][3]",
+            "matrix[2][1]",
+            "matrix[2][2]",
+            "matrix[2][3]",
+            "matrix[3][1]",
+            "matrix[3][2]",
+            "matrix[3][3]",
+            "vector[1]",
+            "vector[2]",
+            "vector[3]",
+        ],
+        pdb.mtrix()
+            .map(|mtrix| {
+                let mut row = vec![
+                    mtrix.serial_number.into(),
+                    (if mtrix.contained {
+                        "given"
+                    } else {
+                        "generated"
+                    })
+                    .into(),
+                ];
+                row.extend(matrix_values(&mtrix.transformation));
+                row
+            })
+            .collect(),
+    );
 
     if let Some(symmetry) = &pdb.symmetry {
-        write!(
-            "# Space group definition
-_symmetry.entry_id                         {}
-_symmetry.space_group_name_H-M             '{}'
-_symmetry.pdbx_full_space_group_name_H-M   '{}'
-_symmetry.Int_Tables_number                {}",
-            name,
-            symmetry.herman_mauguin_symbol(),
-            symmetry.herman_mauguin_symbol(),
-            symmetry.index()
+        block.add_category(
+            "symmetry",
+            &[
+                "entry_id",
+                "space_group_name_H-M",
+                "pdbx_full_space_group_name_H-M",
+                "Int_Tables_number",
+            ],
+            vec![vec![
+                name.into(),
+                symmetry.herman_mauguin_symbol().into(),
+                symmetry.herman_mauguin_symbol().into(),
+                symmetry.index().into(),
+            ]],
         );
     }
 
     let anisou = pdb
         .atoms()
         .any(|a| a.anisotropic_temperature_factors().is_some());
-    write!(
-        "loop_
-_atom_site.group_PDB
-_atom_site.id
-_atom_site.type_symbol
-_atom_site.label_atom_id
-_atom_site.label_alt_id
-_atom_site.label_comp_id
-_atom_site.label_asym_id
-_atom_site.auth_asym_id
-_atom_site.label_entity_id
-_atom_site.label_seq_id
-_atom_site.auth_seq_id
-_atom_site.pdbx_PDB_ins_code
-_atom_site.Cartn_x
-_atom_site.Cartn_y
-_atom_site.Cartn_z
-_atom_site.occupancy
-_atom_site.B_iso_or_equiv
-_atom_site.pdbx_formal_charge
-_atom_site.pdbx_PDB_model_num{}",
-        if anisou {
-            "
-_atom_site.aniso_U[1][1]
-_atom_site.aniso_U[1][2]
-_atom_site.aniso_U[1][3]
-_atom_site.aniso_U[2][1]
-_atom_site.aniso_U[2][2]
-_atom_site.aniso_U[2][3]
-_atom_site.aniso_U[3][1]
-_atom_site.aniso_U[3][2]
-_atom_site.aniso_U[3][3]"
-        } else {
-            ""
-        }
-    );
-
-    let mut lines = Vec::new();
+    let segment_ids = pdb.atoms().any(|a| a.segment_id().is_some());
+    let mut header = vec![
+        "atom_site.group_PDB",
+        "atom_site.id",
+        "atom_site.type_symbol",
+        "atom_site.label_atom_id",
+        "atom_site.label_alt_id",
+        "atom_site.label_comp_id",
+        "atom_site.label_asym_id",
+        "atom_site.auth_asym_id",
+        "atom_site.label_entity_id",
+        "atom_site.label_seq_id",
+        "atom_site.auth_seq_id",
+        "atom_site.pdbx_PDB_ins_code",
+        "atom_site.Cartn_x",
+        "atom_site.Cartn_y",
+        "atom_site.Cartn_z",
+        "atom_site.occupancy",
+        "atom_site.B_iso_or_equiv",
+        "atom_site.pdbx_formal_charge",
+        "atom_site.pdbx_PDB_model_num",
+    ];
+    if anisou {
+        header.extend([
+            "atom_site.aniso_U[1][1]",
+            "atom_site.aniso_U[1][2]",
+            "atom_site.aniso_U[1][3]",
+            "atom_site.aniso_U[2][1]",
+            "atom_site.aniso_U[2][2]",
+            "atom_site.aniso_U[2][3]",
+            "atom_site.aniso_U[3][1]",
+            "atom_site.aniso_U[3][2]",
+            "atom_site.aniso_U[3][3]",
+        ]);
+    }
+    if segment_ids {
+        header.push("atom_site.auth_seg_id");
+    }
+    let mut atom_site = cif::Loop::new(header);
 
     for model in pdb.models() {
         let mut chain_index = 0;
         for chain in model.chains() {
             chain_index += 1;
             for (residue_index, residue) in chain.residues().enumerate() {
+                let [label_asym_id, label_entity_id, label_seq_id] =
+                    label_ids(chain_index, residue_index, residue);
                 for conformer in residue.conformers() {
                     for atom in conformer.atoms() {
                         let mut data = vec![
-                            (if atom.hetero() { "HETATM" } else { "ATOM" }).to_string(), // ATOM or HETATM
-                            atom.serial_number().to_string(), // Serial number
-                            atom.element()
-                                .map_or_else(|| "", Element::symbol)
-                                .to_string(), // Element
-                            atom.name().to_string(),          // Name
-                            conformer.alternative_location().unwrap_or(".").to_string(), // Alternative location
-                            conformer.name().to_string(), // Residue name
-                            number_to_base26(chain_index), // Label Chain name, defined to be without gaps
-                            chain.id().to_string(),        // Auth Chain name
-                            chain_index.to_string(),       // Entity ID, using chain serial number
-                            (residue_index + 1).to_string(), // `label_seq_id` defined to be [1-N] where N is the index
-                            residue.serial_number().to_string(), // Residue serial number
-                            residue.insertion_code().unwrap_or(".").to_string(), // Insertion code
-                            print_float(atom.x()),           // X
-                            print_float(atom.y()),           // Y
-                            print_float(atom.z()),           // Z
-                            print_float(atom.occupancy()),   // OCC/Q
-                            print_float(atom.b_factor()),    // B
-                            atom.charge().to_string(),       // Charge
-                            model.serial_number().to_string(), // Model serial number
+                            (if atom.hetero() { "HETATM" } else { "ATOM" }).into(), // ATOM or HETATM
+                            atom.serial_number().into(),                            // Serial number
+                            atom.element().map(Element::symbol).into(),             // Element
+                            identifier(atom.name()),                                // Name
+                            optional_identifier(conformer.alternative_location()), // Alternative location
+                            identifier(conformer.name()),                          // Residue name
+                            identifier(&label_asym_id), // Label Chain name
+                            identifier(chain.id()),     // Auth Chain name
+                            identifier(&label_entity_id), // Entity ID
+                            identifier(&label_seq_id),  // Label residue serial number
+                            residue.serial_number().into(), // Residue serial number
+                            optional_identifier(residue.insertion_code()), // Insertion code
+                            round_float(atom.x()).into(), // X
+                            round_float(atom.y()).into(), // Y
+                            round_float(atom.z()).into(), // Z
+                            round_float(atom.occupancy()).into(), // OCC/Q
+                            round_float(atom.b_factor()).into(), // B
+                            atom.charge().into(),       // Charge
+                            model.serial_number().into(), // Model serial number
                         ];
                         if anisou {
                             if let Some(matrix) = atom.anisotropic_temperature_factors() {
-                                data.extend(vec![
-                                    print_float(matrix[0][0]),
-                                    print_float(matrix[0][1]),
-                                    print_float(matrix[0][2]),
-                                    print_float(matrix[1][0]),
-                                    print_float(matrix[1][1]),
-                                    print_float(matrix[1][2]),
-                                    print_float(matrix[2][0]),
-                                    print_float(matrix[2][1]),
-                                    print_float(matrix[2][2]),
-                                ]);
+                                data.extend(
+                                    matrix
+                                        .iter()
+                                        .flatten()
+                                        .map(|value| round_float(*value).into()),
+                                );
                             } else {
-                                data.extend(vec![
-                                    ".".to_string(),
-                                    ".".to_string(),
-                                    ".".to_string(),
-                                    ".".to_string(),
-                                    ".".to_string(),
-                                    ".".to_string(),
-                                    ".".to_string(),
-                                    ".".to_string(),
-                                    ".".to_string(),
-                                ]);
+                                data.extend(vec![cif::Value::Inapplicable; 9]);
                             }
                         }
+                        if segment_ids {
+                            data.push(optional_identifier(atom.segment_id()));
+                        }
 
-                        lines.push(data);
+                        atom_site.add_row(data);
                     }
                 }
             }
         }
     }
-    if !lines.is_empty() {
-        // Now align the table
-        let mut sizes = vec![1; lines[0].len()];
-        for line in &lines {
-            for index in 0..line.len() {
-                sizes[index] = std::cmp::max(sizes[index], line[index].len());
-            }
-        }
+    block.add_loop(atom_site);
 
-        // Now write the table
-        for line in lines {
-            let mut output = String::new();
-            output.push_str(&line[0]);
-            output.push_str(&" ".repeat(sizes[0] - line[0].len()));
-            for index in 1..line.len() {
-                output.push(' ');
-                if line[index].trim() != "" {
-                    output.push_str(&line[index]);
-                    output.push_str(&" ".repeat(sizes[index] - line[index].len()));
-                } else {
-                    output.push('?');
-                    output.push_str(&" ".repeat(sizes[index] - 1));
+    // Connections
+    let mut hierarchy = HashMap::new();
+    if let Some(model) = pdb.models().next() {
+        for (chain_index, chain) in model.chains().enumerate() {
+            for (residue_index, residue) in chain.residues().enumerate() {
+                for conformer in residue.conformers() {
+                    for atom in conformer.atoms() {
+                        hierarchy.insert(
+                            atom.counter(),
+                            (
+                                chain_index + 1,
+                                chain,
+                                residue_index + 1,
+                                residue,
+                                conformer,
+                                atom,
+                            ),
+                        );
+                    }
                 }
             }
-            output.push('\n');
-            sink.write_all(output.as_bytes()).unwrap();
         }
     }
+    let mut struct_conn = cif::Loop::new([
+        "struct_conn.id",
+        "struct_conn.conn_type_id",
+        "struct_conn.ptnr1_label_asym_id",
+        "struct_conn.ptnr1_label_comp_id",
+        "struct_conn.ptnr1_label_seq_id",
+        "struct_conn.ptnr1_label_atom_id",
+        "struct_conn.pdbx_ptnr1_label_alt_id",
+        "struct_conn.pdbx_ptnr1_PDB_ins_code",
+        "struct_conn.ptnr1_auth_asym_id",
+        "struct_conn.ptnr1_auth_seq_id",
+        "struct_conn.ptnr2_label_asym_id",
+        "struct_conn.ptnr2_label_comp_id",
+        "struct_conn.ptnr2_label_seq_id",
+        "struct_conn.ptnr2_label_atom_id",
+        "struct_conn.pdbx_ptnr2_label_alt_id",
+        "struct_conn.pdbx_ptnr2_PDB_ins_code",
+        "struct_conn.ptnr2_auth_asym_id",
+        "struct_conn.ptnr2_auth_seq_id",
+        "struct_conn.pdbx_value_order",
+    ]);
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (atom1, atom2, bond, order) in pdb.bonds() {
+        if let (Some(partner1), Some(partner2)) = (
+            hierarchy.get(&atom1.counter()),
+            hierarchy.get(&atom2.counter()),
+        ) {
+            let kind = match bond {
+                Bond::Covalent => "covale",
+                Bond::Disulfide => "disulf",
+                Bond::Hydrogen => "hydrog",
+                Bond::MetalCoordination => "metalc",
+                Bond::MisMatchedBasePairs => "mismat",
+                Bond::SaltBridge => "saltbr",
+                Bond::CovalentModificationResidue => "modres",
+                Bond::CovalentModificationNucleotideBase => "covale_base",
+                Bond::CovalentModificationNucleotideSugar => "covale_sugar",
+                Bond::CovalentModificationNucleotidePhosphate => "covale_phosphate",
+            };
+            let count = counts.entry(kind).or_insert(0);
+            *count += 1;
+            let mut data = vec![format!("{kind}{count}").into(), kind.into()];
+            for (chain_index, chain, residue_index, residue, conformer, atom) in
+                [partner1, partner2]
+            {
+                let [label_asym_id, _, label_seq_id] =
+                    label_ids(*chain_index, *residue_index - 1, residue);
+                data.extend(vec![
+                    identifier(&label_asym_id),
+                    identifier(conformer.name()),
+                    identifier(&label_seq_id),
+                    identifier(atom.name()),
+                    optional_identifier(conformer.alternative_location()),
+                    optional_identifier(residue.insertion_code()),
+                    identifier(chain.id()),
+                    residue.serial_number().into(),
+                ]);
+            }
+            data.push(
+                match order {
+                    Some(BondOrder::Single) => Some("sing"),
+                    Some(BondOrder::Double) => Some("doub"),
+                    Some(BondOrder::Triple) => Some("trip"),
+                    Some(BondOrder::Aromatic) => Some("arom"),
+                    Some(BondOrder::Delocalised) => Some("delo"),
+                    None => None,
+                }
+                .into(),
+            );
+            struct_conn.add_row(data);
+        }
+    }
+    if !struct_conn.data.is_empty() {
+        block.add_loop(struct_conn);
+    }
 
-    write!("#");
+    // Write back all items that were not interpreted while reading, single data items are placed
+    // directly after the generated items of the same category to keep the category together
+    for item in pdb.cif_items() {
+        let position = match item {
+            cif::Item::DataItem(single @ cif::DataItem::Single(_)) => block
+                .items
+                .iter()
+                .rposition(|i| i.category() == Some(single.category())),
+            _ => None,
+        };
+        match position {
+            Some(index) => block.items.insert(index + 1, item.clone()),
+            None => block.items.push(item.clone()),
+        }
+    }
+    block
+}
 
-    sink.flush().unwrap();
+/// Get the label chain id, entity id, and residue number (`label_asym_id`, `label_entity_id`,
+/// and `label_seq_id`) of a residue. Residues without known label ids (for example read from a
+/// PDB file) get ids based on the (1 based) index of their chain and the index of the residue.
+fn label_ids(chain_index: usize, residue_index: usize, residue: &Residue) -> [String; 3] {
+    let seq_id = match (residue.label_seq_id(), residue.label_asym_id()) {
+        (Some(id), _) => id.to_string(),
+        // Non polymer residues from mmCIF files do not have a label_seq_id
+        (None, Some(_)) => ".".to_string(),
+        // `label_seq_id` defined to be [1-N] where N is the index
+        (None, None) => (residue_index + 1).to_string(),
+    };
+    [
+        residue
+            .label_asym_id()
+            .map_or_else(|| number_to_base26(chain_index), str::to_string),
+        residue
+            .label_entity_id()
+            .map_or_else(|| chain_index.to_string(), str::to_string),
+        seq_id,
+    ]
 }
 
-/// Print a floating point with at least 1 decimal place and at max 5 decimals
-#[allow(clippy::cast_possible_truncation)]
-fn print_float(num: f64) -> String {
-    let rounded = (num * 100000.).round() / 100000.;
-    if (rounded.round() - rounded).abs() < std::f64::EPSILON {
-        format!("{}.0", rounded.trunc() as isize)
-    } else {
-        format!("{rounded}")
+/// Get the values of a transformation matrix in the order used in mmCIF files, first the rows
+/// of the rotation matrix then the translation vector
+fn matrix_values(transformation: &TransformationMatrix) -> Vec<cif::Value> {
+    let ma = transformation.matrix();
+    (0..3)
+        .flat_map(|row| (0..3).map(move |column| ma[row][column]))
+        .chain((0..3).map(|row| ma[row][3]))
+        .map(cif::Value::from)
+        .collect()
+}
+
+/// Get the value for an identifier. Identifiers that look like numbers (like entity ids) are
+/// written as numbers if these are read back as the same identifier, all others are written
+/// as text. Empty identifiers are unknown (`?`) and `.` is kept as inapplicable.
+fn identifier(text: &str) -> cif::Value {
+    match text.parse::<f64>() {
+        Ok(number) if format!("{number}") == text => number.into(),
+        _ if text.is_empty() => cif::Value::Unknown,
+        _ if text == "." => cif::Value::Inapplicable,
+        _ => text.into(),
     }
 }
 
+/// Get the value for an optional identifier, a missing identifier is written as inapplicable (`.`)
+fn optional_identifier(text: Option<&str>) -> cif::Value {
+    text.map_or(cif::Value::Inapplicable, identifier)
+}
+
+/// Round a floating point to at max 5 decimals
+fn round_float(num: f64) -> f64 {
+    (num * 100000.).round() / 100000.
+}
+
 #[cfg(test)]
 #[allow(clippy::print_stdout)]
 mod tests {
@@ -422,8 +544,9 @@ mod tests {
     #[test]
     #[allow(clippy::excessive_precision, clippy::print_literal)]
     fn test_print_float() {
-        assert_eq!(print_float(1.), "1.0".to_string());
-        assert_eq!(print_float(128734.), "128734.0".to_string());
+        let print_float = |num| cif::Value::from(round_float(num)).to_string();
+        assert_eq!(print_float(1.), "1".to_string());
+        assert_eq!(print_float(128734.), "128734".to_string());
         assert_eq!(print_float(0.1), "0.1".to_string());
         assert_eq!(print_float(1.015), "1.015".to_string());
         assert_eq!(print_float(2.015), "2.015".to_string());
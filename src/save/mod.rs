@@ -6,5 +6,7 @@ mod mmcif;
 mod pdb;
 
 pub use general::{save, save_gz};
+#[cfg(feature = "bcif")]
+pub use mmcif::{save_bcif, save_bcif_raw};
 pub use mmcif::{save_mmcif, save_mmcif_gz, save_mmcif_raw};
 pub use pdb::{save_pdb, save_pdb_gz, save_pdb_raw};
@@ -3,6 +3,7 @@ use crate::StrictnessLevel;
 use crate::TransformationMatrix;
 
 use std::cmp;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
 use std::io::BufWriter;
 use std::iter;
@@ -10,7 +11,10 @@ use std::iter;
 use std::io::Write;
 
 use crate::PDB;
-use crate::{validate, validate_pdb, Context, ErrorLevel, PDBError};
+use crate::{validate_pdb, Context, ErrorLevel, PDBError};
+
+/// The links between the residues of standard polymers, these are not written as CONECT records
+const POLYMER_LINKS: &[(&str, &str)] = &[("C", "N"), ("O3'", "P")];
 
 #[cfg(feature = "compression")]
 use flate2::{write::GzEncoder, Compression};
@@ -59,7 +63,7 @@ pub fn save_pdb_gz(
 }
 
 /// Generic function to save the given PDB struct to the given file, validating it beforehand.
-fn save_pdb_<T, W>(
+pub(super) fn save_pdb_<T, W>(
     pdb: &PDB,
     filename: impl AsRef<str>,
     level: StrictnessLevel,
@@ -72,8 +76,7 @@ where
     // Validates the PDB, and returns early if any errors are found
     let filename = filename.as_ref();
 
-    let mut errors = validate(pdb);
-    errors.extend(validate_pdb(pdb));
+    let mut errors = validate_pdb(pdb);
     for error in &errors {
         if error.fails(level) {
             return Err(errors);
@@ -532,6 +535,7 @@ pub fn save_pdb_raw<T: Write>(pdb: &PDB, mut sink: BufWriter<T>, level: Strictne
                 for conformer in residue.conformers() {
                     for atom in conformer.atoms() {
                         let element = atom.element().map_or_else(|| "", Element::symbol);
+                        let segment_id = format!("{:<4.4}", atom.segment_id().unwrap_or(""));
                         print_line(vec![
                             (6, if atom.hetero() { "HETATM" } else { "ATOM  " }),
                             (0, &atom_line(atom, conformer, residue, chain)),
@@ -541,7 +545,8 @@ pub fn save_pdb_raw<T: Write>(pdb: &PDB, mut sink: BufWriter<T>, level: Strictne
                             (8, &format!("{:8.3}", atom.pos().2)),
                             (6, &format!("{:6.2}", atom.occupancy())),
                             (6, &format!("{:6.2}", atom.b_factor())),
-                            (0, "          "),
+                            (0, "      "),
+                            (0, &segment_id),
                             (2, element),
                             (0, &atom.pdb_charge()),
                         ]);
@@ -558,7 +563,8 @@ pub fn save_pdb_raw<T: Write>(pdb: &PDB, mut sink: BufWriter<T>, level: Strictne
                                 (7, &format!("{:8.3}", (f[0][1] * 10000.0) as isize)),
                                 (7, &format!("{:8.3}", (f[0][2] * 10000.0) as isize)),
                                 (7, &format!("{:8.3}", (f[1][2] * 10000.0) as isize)),
-                                (0, "      "),
+                                (0, "  "),
+                                (0, &segment_id),
                                 (2, element),
                                 (0, &atom.pdb_charge()),
                             ]);
@@ -583,6 +589,59 @@ pub fn save_pdb_raw<T: Write>(pdb: &PDB, mut sink: BufWriter<T>, level: Strictne
             print_line(vec![(0, "ENDMDL")]);
         }
     }
+
+    // Connections, double and triple bonds are indicated by listing the bonded atom multiple times.
+    // Like in the wwPDB files only the bonds involving hetero atoms and the links between residues,
+    // except the standard polymer links, are written. Atoms with serial numbers that do not fit
+    // in the five columns are skipped, as the wrapped serial numbers are not unique.
+    let residues: HashMap<usize, (&str, (isize, Option<&str>))> = pdb
+        .chains()
+        .flat_map(|c| {
+            c.residues()
+                .flat_map(move |r| r.atoms().map(move |a| (a.counter(), (c.id(), r.id()))))
+        })
+        .collect();
+    let mut connections: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    let mut seen = HashSet::new();
+    for (a, b, bond, order) in pdb.bonds() {
+        if matches!(
+            bond,
+            Bond::Hydrogen | Bond::SaltBridge | Bond::MisMatchedBasePairs
+        ) {
+            continue;
+        }
+        if !a.hetero()
+            && !b.hetero()
+            && (residues.get(&a.counter()) == residues.get(&b.counter())
+                || POLYMER_LINKS.contains(&(a.name(), b.name()))
+                || POLYMER_LINKS.contains(&(b.name(), a.name())))
+        {
+            continue;
+        }
+        let (a, b) = (a.serial_number(), b.serial_number());
+        if a == b || a > 99_999 || b > 99_999 || !seen.insert((a.min(b), a.max(b))) {
+            continue;
+        }
+        let repeat = match order {
+            Some(BondOrder::Double) => 2,
+            Some(BondOrder::Triple) => 3,
+            _ => 1,
+        };
+        for _ in 0..repeat {
+            connections.entry(a).or_default().push(b);
+            connections.entry(b).or_default().push(a);
+        }
+    }
+    let mut num_connect = 0;
+    for (serial, bonded) in &connections {
+        for chunk in bonded.chunks(4) {
+            let mut fields = vec![(0, "CONECT".to_string()), (5, serial.to_string())];
+            fields.extend(chunk.iter().map(|partner| (5, partner.to_string())));
+            print_line(fields.iter().map(|(l, t)| (*l, t.as_str())).collect());
+            num_connect += 1;
+        }
+    }
+
     if level != StrictnessLevel::Loose {
         let mut xform = 0;
         if pdb.origx.is_some() || level == StrictnessLevel::Strict {
@@ -606,7 +665,7 @@ pub fn save_pdb_raw<T: Write>(pdb: &PDB, mut sink: BufWriter<T>, level: Strictne
             (5, xform.to_string().as_str()),
             (5, pdb.total_atom_count().to_string().as_str()),
             (5, pdb.model_count().to_string().as_str()),
-            (5, "0"), //numConnect
+            (5, num_connect.to_string().as_str()),
             (5, "0"), //numSeq
         ]);
     }
@@ -35,6 +35,8 @@ pub struct Atom {
     element: Option<Element>,
     /// The charge of the Atom
     charge: isize,
+    /// The segment identifier of the Atom, if given
+    segment_id: Option<String>,
     /// The anisotropic temperature factors, if applicable
     atf: Option<[[f64; 3]; 3]>,
 }
@@ -92,6 +94,7 @@ impl Atom {
                 b_factor,
                 element,
                 charge,
+                segment_id: None,
                 atf: None,
             })
         } else {
@@ -324,6 +327,32 @@ impl Atom {
         self.charge = new_charge;
     }
 
+    /// Get the segment identifier of the atom, if given. This is used by software like CHARMM
+    /// and NAMD to identify molecules. In PDB files the segment identifier can at most contain
+    /// 4 characters.
+    pub fn segment_id(&self) -> Option<&str> {
+        self.segment_id.as_deref()
+    }
+
+    /// Set the segment identifier of the atom. The identifier will be trimmed (whitespace
+    /// removed), setting an empty identifier removes it.
+    /// For PDB files the identifier can at most contain 4 characters, enforced when saving the file.
+    /// # Errors
+    /// The identifier can only contain valid characters, the ASCII graphic characters (`char.is_ascii_graphic() || char == ' '`).
+    /// If the identifier is invalid an error message is provided.
+    pub fn set_segment_id(&mut self, new_segment_id: impl Into<String>) -> Result<(), String> {
+        let new_segment_id = new_segment_id.into();
+        if valid_identifier(&new_segment_id) {
+            self.segment_id = prepare_identifier(new_segment_id);
+            Ok(())
+        } else {
+            Err(format!(
+                "New segment id has invalid characters for atom {} segment id {}",
+                self.serial_number, new_segment_id
+            ))
+        }
+    }
+
     /// Get the charge in the PDB format `[0-9][-+]`. If the charge is 0 or outside bounds (below -9 or above 9) it returns an empty string.
     #[allow(clippy::cast_possible_truncation)]
     pub fn pdb_charge(&self) -> String {
@@ -612,6 +641,7 @@ impl Clone for Atom {
         )
         .expect("Invalid Atom properties in a clone");
         atom.atf = self.atf;
+        atom.segment_id = self.segment_id.clone();
         atom
     }
 }
@@ -622,6 +652,7 @@ impl PartialEq for Atom {
             && self.name() == other.name()
             && self.element() == other.element()
             && self.charge() == other.charge()
+            && self.segment_id == other.segment_id
             && self.atf == other.atf
             && self.pos() == other.pos()
             && self.occupancy == other.occupancy
@@ -635,7 +666,7 @@ impl Eq for Atom {}
 
 impl PartialOrd for Atom {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.serial_number.cmp(&other.serial_number))
+        Some(self.cmp(other))
     }
 }
 
@@ -775,6 +806,14 @@ mod tests {
         a.set_charge(-1);
         assert_eq!(a.charge(), -1);
         assert_eq!(a.pdb_charge(), "1-".to_string());
+        assert_eq!(a.segment_id(), None);
+        assert!(a.set_segment_id(" PROA ").is_ok());
+        assert_eq!(a.segment_id(), Some("PROA"));
+        assert_eq!(a.clone().segment_id(), Some("PROA"));
+        assert!(a.set_segment_id("Å").is_err());
+        assert_eq!(a.segment_id(), Some("PROA"));
+        assert!(a.set_segment_id("").is_ok());
+        assert_eq!(a.segment_id(), None);
     }
 
     #[test]
@@ -810,7 +849,7 @@ mod tests {
     #[test]
     fn check_display() {
         let a = Atom::new(false, 0, "C", 1.0, 1.0, 1.0, 0.0, 0.0, "", 0).unwrap();
-        format!("{a:?}");
-        format!("{a}");
+        let _ = format!("{a:?}");
+        let _ = format!("{a}");
     }
 }
@@ -23,3 +23,19 @@ pub enum Bond {
     /// ?
     CovalentModificationNucleotidePhosphate,
 }
+
+/// The order of a covalent bond between two atoms
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum BondOrder {
+    /// A single bond
+    Single,
+    /// A double bond
+    Double,
+    /// A triple bond
+    Triple,
+    /// A bond in an aromatic ring
+    Aromatic,
+    /// A bond with delocalised electrons, like the oxygens in a carboxylate group
+    Delocalised,
+}
@@ -0,0 +1,564 @@
+use crate::structs::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// A graph view of the bonds in a PDB, with the atoms as nodes and the bonds as edges.
+/// It allows for queries on the connectivity, like the bonded neighbours of an atom, the
+/// connected fragments (molecules), shortest bond paths, and ring perception.
+/// Create one with [`PDB::bond_graph`] or [`PDB::bond_graph_with`]. The graph is a snapshot,
+/// bonds added to the PDB afterwards are not reflected in it.
+#[derive(Debug, Clone)]
+pub struct BondGraph<'a> {
+    /// The atoms, the index in this list is the node index
+    atoms: Vec<&'a Atom>,
+    /// The node index for each atom counter
+    index: HashMap<usize, usize>,
+    /// The bonded nodes for each node, with the bond type and order
+    adjacency: Vec<Vec<(usize, Bond, Option<BondOrder>)>>,
+    /// The bridges, bonds that are not part of any ring, as (low, high) node pairs
+    bridges: HashSet<(usize, usize)>,
+}
+
+impl<'a> BondGraph<'a> {
+    /// Create a new graph from the given atoms and bonds. Bonds to atoms that are not given
+    /// and bonds of an atom to itself are ignored, as are duplicate bonds.
+    pub(crate) fn new(
+        atoms: impl Iterator<Item = &'a Atom>,
+        bonds: impl Iterator<Item = (&'a Atom, &'a Atom, Bond, Option<BondOrder>)>,
+    ) -> Self {
+        let atoms: Vec<&Atom> = atoms.collect();
+        let index: HashMap<usize, usize> = atoms
+            .iter()
+            .enumerate()
+            .map(|(i, a)| (a.counter(), i))
+            .collect();
+        let mut adjacency: Vec<Vec<(usize, Bond, Option<BondOrder>)>> =
+            vec![Vec::new(); atoms.len()];
+        for (a, b, bond, order) in bonds {
+            if let (Some(&a), Some(&b)) = (index.get(&a.counter()), index.get(&b.counter())) {
+                if a != b && !adjacency[a].iter().any(|(n, ..)| *n == b) {
+                    adjacency[a].push((b, bond, order));
+                    adjacency[b].push((a, bond, order));
+                }
+            }
+        }
+        let bridges = bridges(&adjacency);
+        BondGraph {
+            atoms,
+            index,
+            adjacency,
+            bridges,
+        }
+    }
+
+    /// Get the number of atoms in this graph
+    pub fn atom_count(&self) -> usize {
+        self.atoms.len()
+    }
+
+    /// Get the number of bonds in this graph
+    pub fn bond_count(&self) -> usize {
+        self.adjacency.iter().map(Vec::len).sum::<usize>() / 2
+    }
+
+    /// Get the atoms bonded to the given atom, with the type and order of the bond.
+    /// If the atom is not part of this graph no atoms are returned.
+    pub fn neighbours(
+        &self,
+        atom: &Atom,
+    ) -> impl Iterator<Item = (&'a Atom, Bond, Option<BondOrder>)> + '_ {
+        self.index
+            .get(&atom.counter())
+            .into_iter()
+            .flat_map(move |i| {
+                self.adjacency[*i]
+                    .iter()
+                    .map(move |(n, bond, order)| (self.atoms[*n], *bond, *order))
+            })
+    }
+
+    /// Get the number of bonds of the given atom, or `None` if the atom is not part of this graph
+    pub fn degree(&self, atom: &Atom) -> Option<usize> {
+        self.index
+            .get(&atom.counter())
+            .map(|i| self.adjacency[*i].len())
+    }
+
+    /// Get the bond between the given atoms, with its type and order, if the atoms are bonded
+    pub fn bond(&self, atom1: &Atom, atom2: &Atom) -> Option<(Bond, Option<BondOrder>)> {
+        let b = *self.index.get(&atom2.counter())?;
+        self.adjacency[*self.index.get(&atom1.counter())?]
+            .iter()
+            .find(|(n, ..)| *n == b)
+            .map(|(_, bond, order)| (*bond, *order))
+    }
+
+    /// Get the connected fragments in this graph, these are the sets of atoms that are connected
+    /// through bonds, like separate molecules. Atoms without any bonds are a fragment on their
+    /// own. The fragments are given in the order of their first atom in the PDB.
+    pub fn fragments(&self) -> Vec<Vec<&'a Atom>> {
+        self.components(|_, _| true)
+            .into_iter()
+            .map(|component| component.into_iter().map(|i| self.atoms[i]).collect())
+            .collect()
+    }
+
+    /// Get the fragment containing the given atom, see [`BondGraph::fragments`].
+    /// If the atom is not part of this graph it returns `None`.
+    pub fn fragment(&self, atom: &Atom) -> Option<Vec<&'a Atom>> {
+        let start = *self.index.get(&atom.counter())?;
+        let mut seen = vec![false; self.atoms.len()];
+        Some(
+            self.flood(start, &mut seen, |_, _| true)
+                .into_iter()
+                .map(|i| self.atoms[i])
+                .collect(),
+        )
+    }
+
+    /// Get the shortest path of bonds between the given atoms, as the list of atoms on the path
+    /// including both given atoms. It returns `None` if the atoms are not connected.
+    pub fn shortest_path(&self, from: &Atom, to: &Atom) -> Option<Vec<&'a Atom>> {
+        let from = *self.index.get(&from.counter())?;
+        let to = *self.index.get(&to.counter())?;
+        self.path(from, to, None)
+            .map(|path| path.into_iter().map(|i| self.atoms[i]).collect())
+    }
+
+    /// Get the number of bonds on the shortest path between the given atoms, see
+    /// [`BondGraph::shortest_path`]. It returns `None` if the atoms are not connected.
+    pub fn distance(&self, from: &Atom, to: &Atom) -> Option<usize> {
+        self.shortest_path(from, to).map(|path| path.len() - 1)
+    }
+
+    /// Get the smallest set of smallest rings (SSSR) in this graph. Each ring is given as the
+    /// list of atoms in the order they are bonded, the last atom is bonded to the first. The
+    /// rings are sorted on size. The number of rings is the cycle rank of the graph
+    /// (`bonds - atoms + fragments`). Note that the SSSR is not always unique, for symmetric
+    /// ring systems one of the valid sets is returned.
+    pub fn rings(&self) -> Vec<Vec<&'a Atom>> {
+        let mut rings = Vec::new();
+        for component in self.components(|a, b| !self.bridges.contains(&(a.min(b), a.max(b)))) {
+            if component.len() > 2 {
+                rings.extend(self.component_rings(&component));
+            }
+        }
+        rings.sort_by_key(Vec::len);
+        rings
+            .into_iter()
+            .map(|ring| ring.into_iter().map(|i| self.atoms[i]).collect())
+            .collect()
+    }
+
+    /// Get the rings in the SSSR (see [`BondGraph::rings`]) where all bonds are aromatic.
+    pub fn aromatic_rings(&self) -> Vec<Vec<&'a Atom>> {
+        self.rings()
+            .into_iter()
+            .filter(|ring| {
+                (0..ring.len()).all(|i| {
+                    self.bond(ring[i], ring[(i + 1) % ring.len()])
+                        .map_or(false, |(_, order)| order == Some(BondOrder::Aromatic))
+                })
+            })
+            .collect()
+    }
+
+    /// Returns if the given atom is part of any ring
+    pub fn in_ring(&self, atom: &Atom) -> bool {
+        self.index.get(&atom.counter()).map_or(false, |i| {
+            self.adjacency[*i]
+                .iter()
+                .any(|(n, ..)| !self.bridges.contains(&((*i).min(*n), (*i).max(*n))))
+        })
+    }
+
+    /// Get the connected components using only the edges that pass the given filter
+    fn components(&self, use_edge: impl Fn(usize, usize) -> bool + Copy) -> Vec<Vec<usize>> {
+        let mut seen = vec![false; self.atoms.len()];
+        let mut components = Vec::new();
+        for start in 0..self.atoms.len() {
+            if !seen[start] {
+                components.push(self.flood(start, &mut seen, use_edge));
+            }
+        }
+        components
+    }
+
+    /// Find all nodes reachable from the start node using only the edges that pass the given
+    /// filter, marking them as seen. The nodes are returned in sorted order.
+    fn flood(
+        &self,
+        start: usize,
+        seen: &mut [bool],
+        use_edge: impl Fn(usize, usize) -> bool,
+    ) -> Vec<usize> {
+        let mut component = vec![start];
+        let mut stack = vec![start];
+        seen[start] = true;
+        while let Some(node) = stack.pop() {
+            for (next, ..) in &self.adjacency[node] {
+                if !seen[*next] && use_edge(node, *next) {
+                    seen[*next] = true;
+                    component.push(*next);
+                    stack.push(*next);
+                }
+            }
+        }
+        component.sort_unstable();
+        component
+    }
+
+    /// Breadth first search for the shortest path between two nodes, optionally ignoring the
+    /// direct edge between them.
+    fn path(
+        &self,
+        from: usize,
+        to: usize,
+        skip_edge: Option<(usize, usize)>,
+    ) -> Option<Vec<usize>> {
+        let mut previous: HashMap<usize, usize> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        previous.insert(from, from);
+        while let Some(node) = queue.pop_front() {
+            if node == to {
+                let mut path = vec![to];
+                let mut current = to;
+                while current != from {
+                    current = previous[&current];
+                    path.push(current);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for (next, ..) in &self.adjacency[node] {
+                if skip_edge == Some((node, *next)) || skip_edge == Some((*next, node)) {
+                    continue;
+                }
+                if let std::collections::hash_map::Entry::Vacant(e) = previous.entry(*next) {
+                    e.insert(node);
+                    queue.push_back(*next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Find the smallest set of smallest rings in a two edge connected component. The candidate
+    /// rings are the smallest ring through each edge, these are added in order of size if they
+    /// are linearly independent (over GF(2) on the edges) of the rings already selected. If this
+    /// does not result in enough rings all candidates as defined by Horton are used.
+    fn component_rings(&self, component: &[usize]) -> Vec<Vec<usize>> {
+        let nodes: HashSet<usize> = component.iter().copied().collect();
+        let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+        for &a in component {
+            for (b, ..) in &self.adjacency[a] {
+                let key = (a.min(*b), a.max(*b));
+                if a < *b && nodes.contains(b) && !self.bridges.contains(&key) {
+                    let next = edges.len();
+                    edges.insert(key, next);
+                }
+            }
+        }
+        let rank = (edges.len() + 1).saturating_sub(component.len());
+        if rank == 0 {
+            return Vec::new();
+        }
+
+        let mut candidates: Vec<Vec<usize>> = edges
+            .keys()
+            .filter_map(|(a, b)| self.path(*a, *b, Some((*a, *b))))
+            .collect();
+        let mut rings = select_independent(&mut candidates, &edges, rank);
+        if rings.len() < rank {
+            let mut candidates = self.horton_candidates(component, &edges);
+            rings = select_independent(&mut candidates, &edges, rank);
+        }
+        rings
+    }
+
+    /// Generate the Horton candidate rings for a component: for each node and edge the ring
+    /// formed by the shortest paths from the node to both ends of the edge, if these paths
+    /// only share the starting node.
+    fn horton_candidates(
+        &self,
+        component: &[usize],
+        edges: &HashMap<(usize, usize), usize>,
+    ) -> Vec<Vec<usize>> {
+        let mut candidates = Vec::new();
+        for &root in component {
+            let mut previous: HashMap<usize, usize> = HashMap::new();
+            let mut queue = VecDeque::from([root]);
+            previous.insert(root, root);
+            while let Some(node) = queue.pop_front() {
+                for (next, ..) in &self.adjacency[node] {
+                    if edges.contains_key(&(node.min(*next), node.max(*next))) {
+                        if let std::collections::hash_map::Entry::Vacant(e) = previous.entry(*next)
+                        {
+                            e.insert(node);
+                            queue.push_back(*next);
+                        }
+                    }
+                }
+            }
+            let trace = |mut node: usize| {
+                let mut path = vec![node];
+                while node != root {
+                    node = previous[&node];
+                    path.push(node);
+                }
+                path
+            };
+            for &(a, b) in edges.keys() {
+                let (path_a, mut path_b) = (trace(a), trace(b));
+                let shared: HashSet<&usize> = path_a.iter().collect();
+                if path_b.iter().filter(|n| shared.contains(n)).count() == 1 {
+                    // Root to a, followed by b back to (but not including) root
+                    path_b.pop();
+                    let mut ring = path_a;
+                    ring.reverse();
+                    ring.extend(path_b);
+                    candidates.push(ring);
+                }
+            }
+        }
+        candidates
+    }
+}
+
+/// Find all bridges, edges whose removal disconnects the graph, given as (low, high) node
+/// pairs. Uses an iterative version of Tarjan's algorithm.
+fn bridges(adjacency: &[Vec<(usize, Bond, Option<BondOrder>)>]) -> HashSet<(usize, usize)> {
+    let n = adjacency.len();
+    let mut order = vec![usize::MAX; n];
+    let mut low = vec![0; n];
+    let mut bridges = HashSet::new();
+    let mut counter = 0;
+    for root in 0..n {
+        if order[root] != usize::MAX {
+            continue;
+        }
+        // Stack of (node, parent, next neighbour index)
+        let mut stack = vec![(root, usize::MAX, 0)];
+        order[root] = counter;
+        low[root] = counter;
+        counter += 1;
+        while let Some(&mut (node, parent, ref mut next)) = stack.last_mut() {
+            if let Some((child, ..)) = adjacency[node].get(*next) {
+                let child = *child;
+                *next += 1;
+                if child == parent {
+                    continue;
+                }
+                if order[child] == usize::MAX {
+                    order[child] = counter;
+                    low[child] = counter;
+                    counter += 1;
+                    stack.push((child, node, 0));
+                } else {
+                    low[node] = low[node].min(order[child]);
+                }
+            } else {
+                stack.pop();
+                if parent != usize::MAX {
+                    low[parent] = low[parent].min(low[node]);
+                    if low[node] > order[parent] {
+                        bridges.insert((node.min(parent), node.max(parent)));
+                    }
+                }
+            }
+        }
+    }
+    bridges
+}
+
+/// Select linearly independent rings from the candidates, in order of size, until the given
+/// number of rings is found. Independence is tested with Gaussian elimination over GF(2) on
+/// the edge incidence vectors.
+fn select_independent(
+    candidates: &mut [Vec<usize>],
+    edges: &HashMap<(usize, usize), usize>,
+    rank: usize,
+) -> Vec<Vec<usize>> {
+    candidates.sort_by_key(Vec::len);
+    let words = (edges.len() + 63) / 64;
+    // The reduced basis as (pivot bit, vector)
+    let mut basis: Vec<(usize, Vec<u64>)> = Vec::new();
+    let mut rings = Vec::new();
+    let mut seen = HashSet::new();
+    for ring in candidates.iter() {
+        let mut vector = vec![0_u64; words];
+        for i in 0..ring.len() {
+            let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+            let bit = edges[&(a.min(b), a.max(b))];
+            vector[bit / 64] ^= 1 << (bit % 64);
+        }
+        if !seen.insert(vector.clone()) {
+            continue;
+        }
+        for (pivot, row) in &basis {
+            if vector[pivot / 64] & (1 << (pivot % 64)) != 0 {
+                for (v, r) in vector.iter_mut().zip(row) {
+                    *v ^= r;
+                }
+            }
+        }
+        if let Some(word) = vector.iter().position(|w| *w != 0) {
+            let pivot = word * 64 + vector[word].trailing_zeros() as usize;
+            basis.push((pivot, vector));
+            rings.push(ring.clone());
+            if rings.len() == rank {
+                break;
+            }
+        }
+    }
+    rings
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::open_pdb;
+    use std::path::Path;
+
+    /// Build a PDB with a single residue containing the given atoms and bonds
+    fn molecule(atoms: &[&str], bonds: &[(usize, usize, BondOrder)]) -> PDB {
+        let mut model = Model::new(1);
+        for (i, name) in atoms.iter().enumerate() {
+            let atom = Atom::new(true, i + 1, *name, 0.0, 0.0, 0.0, 1.0, 0.0, "C", 0).unwrap();
+            model.add_atom(atom, "A", (1, None), ("LIG", None));
+        }
+        let mut pdb = PDB::new();
+        pdb.add_model(model);
+        for (a, b, order) in bonds {
+            pdb.add_bond_with_order((*a, None), (*b, None), Bond::Covalent, *order)
+                .unwrap();
+        }
+        pdb
+    }
+
+    #[test]
+    fn naphthalene() {
+        let bonds: Vec<_> = [
+            (1, 2),
+            (2, 3),
+            (3, 4),
+            (4, 5),
+            (5, 6),
+            (6, 1),
+            (5, 7),
+            (7, 8),
+            (8, 9),
+            (9, 10),
+            (10, 6),
+        ]
+        .into_iter()
+        .map(|(a, b)| (a, b, BondOrder::Aromatic))
+        .chain([(1, 11, BondOrder::Single), (12, 13, BondOrder::Double)])
+        .collect();
+        let names: Vec<String> = (1..=13).map(|i| format!("C{i}")).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let pdb = molecule(&names, &bonds);
+        let graph = pdb.bond_graph();
+        let atom = |serial| pdb.atoms().find(|a| a.serial_number() == serial).unwrap();
+
+        assert_eq!(graph.atom_count(), 13);
+        assert_eq!(graph.bond_count(), 13);
+        assert_eq!(graph.degree(atom(6)), Some(3));
+        assert_eq!(
+            graph.bond(atom(13), atom(12)),
+            Some((Bond::Covalent, Some(BondOrder::Double)))
+        );
+        let mut neighbours: Vec<usize> = graph
+            .neighbours(atom(5))
+            .map(|(a, ..)| a.serial_number())
+            .collect();
+        neighbours.sort_unstable();
+        assert_eq!(neighbours, vec![4, 6, 7]);
+
+        let fragments = graph.fragments();
+        assert_eq!(fragments.len(), 2);
+        assert_eq!(fragments[0].len(), 11);
+        assert_eq!(graph.fragment(atom(13)).unwrap().len(), 2);
+
+        assert_eq!(graph.distance(atom(11), atom(9)), Some(4));
+        assert_eq!(graph.shortest_path(atom(11), atom(12)), None);
+        let path: Vec<usize> = graph
+            .shortest_path(atom(2), atom(4))
+            .unwrap()
+            .iter()
+            .map(|a| a.serial_number())
+            .collect();
+        assert_eq!(path, vec![2, 3, 4]);
+
+        let rings = graph.rings();
+        assert_eq!(rings.len(), 2);
+        assert!(rings.iter().all(|r| r.len() == 6));
+        assert_eq!(graph.aromatic_rings().len(), 2);
+        assert!(graph.in_ring(atom(6)));
+        assert!(!graph.in_ring(atom(11)));
+        assert!(!graph.in_ring(atom(12)));
+    }
+
+    #[test]
+    fn cubane() {
+        let bonds: Vec<_> = [
+            (1, 2),
+            (2, 3),
+            (3, 4),
+            (4, 1),
+            (5, 6),
+            (6, 7),
+            (7, 8),
+            (8, 5),
+            (1, 5),
+            (2, 6),
+            (3, 7),
+            (4, 8),
+        ]
+        .into_iter()
+        .map(|(a, b)| (a, b, BondOrder::Single))
+        .collect();
+        let pdb = molecule(&["C1", "C2", "C3", "C4", "C5", "C6", "C7", "C8"], &bonds);
+        let graph = pdb.bond_graph();
+        let rings = graph.rings();
+        assert_eq!(rings.len(), 5);
+        assert!(rings.iter().all(|r| r.len() == 4));
+
+        // The fallback candidates should give the same result
+        let component: Vec<usize> = (0..8).collect();
+        let edges: HashMap<(usize, usize), usize> = bonds
+            .iter()
+            .enumerate()
+            .map(|(i, (a, b, _))| (((a - 1).min(b - 1), (a - 1).max(b - 1)), i))
+            .collect();
+        let mut candidates = graph.horton_candidates(&component, &edges);
+        let rings = select_independent(&mut candidates, &edges, 5);
+        assert_eq!(rings.len(), 5);
+        assert!(rings.iter().all(|r| r.len() == 4));
+    }
+
+    #[test]
+    fn ubiquitin_rings() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("example-pdbs")
+            .join("1ubq.pdb");
+        let (mut pdb, _) = open_pdb(path.to_str().unwrap(), crate::StrictnessLevel::Loose).unwrap();
+        pdb.add_template_bonds();
+        let graph = pdb.bond_graph();
+        // 3 PRO, 2 PHE, 1 TYR, 1 HIS
+        let rings = graph.rings();
+        assert_eq!(rings.len(), 7);
+        assert_eq!(graph.aromatic_rings().len(), 4);
+        // The peptide bonds link the protein into a single fragment, next to the waters
+        let fragments = graph.fragments();
+        let waters = pdb.residues().filter(|r| r.name() == Some("HOH")).count();
+        assert_eq!(fragments.len(), waters + 1);
+        assert_eq!(fragments[0].len(), pdb.atom_count() - waters);
+        for residue in pdb.residues() {
+            for atom in residue.atoms().filter(|a| a.name() == "CA") {
+                assert_eq!(graph.in_ring(atom), residue.name() == Some("PRO"));
+            }
+        }
+        assert!(rings.iter().all(|r| r.len() == 5 || r.len() == 6));
+    }
+}
@@ -1,10 +1,13 @@
 #![allow(dead_code)]
+use crate::structs::segment;
+use crate::structs::torsion;
 use crate::structs::*;
 use crate::transformation::TransformationMatrix;
 use doc_cfg::doc_cfg;
 #[cfg(feature = "rayon")]
 use rayon::prelude::*;
 use std::cmp::Ordering;
+use std::ops::Range;
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -254,22 +257,48 @@ impl<'a> Chain {
         }
     }
 
+    /// Resolve the terms of the search that need the whole Chain, see [`Search::resolve`]
+    fn resolve_search(&self, search: &mut Search) {
+        search.resolve(
+            &|inner| self.find(inner).map(|h| h.atom().counter()).collect(),
+            &|| {
+                self.residues()
+                    .flat_map(|r| r.atoms().map(move |a| (Some(self), Some(r), a)))
+                    .collect()
+            },
+        );
+    }
+
     /// Find all hierarchies matching the given information
     pub fn find(
         &'a self,
-        search: Search,
-    ) -> impl DoubleEndedIterator<Item = AtomConformerResidue<'a>> + '_ {
+        mut search: Search,
+    ) -> impl DoubleEndedIterator<Item = AtomConformerResidue<'a>> + 'a {
+        self.resolve_search(&mut search);
         self.residues()
             .map(move |r| (r, search.clone().add_residue_info(r)))
             .filter(|(_r, search)| !matches!(search, Search::Known(false)))
             .flat_map(move |(r, search)| r.find(search).map(move |h| h.extend(r)))
     }
 
+    /// Find all Residues with any atom matching the given search. For more details see [Search].
+    pub fn find_residues(
+        &'a self,
+        mut search: Search,
+    ) -> impl DoubleEndedIterator<Item = &'a Residue> + 'a {
+        self.resolve_search(&mut search);
+        self.residues().filter(move |r| {
+            let search = search.add_residue_info(r);
+            !matches!(search, Search::Known(false)) && r.find(search).next().is_some()
+        })
+    }
+
     /// Find all hierarchies matching the given information
     pub fn find_mut(
         &'a mut self,
-        search: Search,
-    ) -> impl DoubleEndedIterator<Item = AtomConformerResidueMut<'a>> + '_ {
+        mut search: Search,
+    ) -> impl DoubleEndedIterator<Item = AtomConformerResidueMut<'a>> + 'a {
+        self.resolve_search(&mut search);
         self.residues_mut()
             .map(move |r| {
                 let search = search.clone().add_residue_info(r);
@@ -358,7 +387,7 @@ impl<'a> Chain {
     /// Get an iterator of references to a struct containing all atoms with their hierarchy making up this Chain.
     pub fn atoms_with_hierarchy(
         &'a self,
-    ) -> impl DoubleEndedIterator<Item = hierarchy::AtomConformerResidue<'a>> + '_ {
+    ) -> impl DoubleEndedIterator<Item = hierarchy::AtomConformerResidue<'a>> + 'a {
         self.residues()
             .flat_map(|r| r.atoms_with_hierarchy().map(move |h| h.extend(r)))
     }
@@ -366,7 +395,7 @@ impl<'a> Chain {
     /// Get an iterator of mutable references to a struct containing all atoms with their hierarchy making up this Chain.
     pub fn atoms_with_hierarchy_mut(
         &'a mut self,
-    ) -> impl DoubleEndedIterator<Item = hierarchy::AtomConformerResidueMut<'a>> + '_ {
+    ) -> impl DoubleEndedIterator<Item = hierarchy::AtomConformerResidueMut<'a>> + 'a {
         self.residues_mut().flat_map(|r| {
             let residue: *mut Residue = r;
             r.atoms_with_hierarchy_mut().map(move |h| h.extend(residue))
@@ -520,6 +549,110 @@ impl<'a> Chain {
         self.residues.extend(other.residues);
     }
 
+    /// Get the discontinuities between consecutive Residues in this Chain. These are the
+    /// physical breaks in the backbone, where the linking atoms (C-N for amino acids, O3'-P for
+    /// nucleotides) are too far apart to be bonded, and the places where the numbering is not
+    /// consecutive: jumps, insertion codes, and decreasing numbers. A break with consecutive
+    /// numbering shows missing residues that were not numbered as such, while a jump without a
+    /// break shows a renumbered chain. Residues without the linking atoms, like ligands and
+    /// waters, are never considered broken from their neighbours.
+    pub fn gaps(&self) -> Vec<ChainGap> {
+        segment::gaps(self)
+    }
+
+    /// Get the index ranges of the Residues of the segments of this Chain with a continuous
+    /// backbone, split at every physical break found by [`Chain::gaps`]. An empty Chain has
+    /// no segments.
+    pub fn segments(&self) -> Vec<Range<usize>> {
+        segment::segments(self)
+    }
+
+    /// Split this Chain into one Chain per segment, see [`Chain::segments`]. All Chains keep
+    /// the id and database reference of this Chain, see [`Model::split_chains_at_breaks`] to
+    /// give every segment a unique id.
+    pub fn split_segments(mut self) -> Vec<Chain> {
+        let segments = self.segments();
+        let mut output = Vec::with_capacity(segments.len());
+        for range in segments.into_iter().rev() {
+            output.push(Chain {
+                id: self.id.clone(),
+                residues: self.residues.split_off(range.start),
+                database_reference: self.database_reference.clone(),
+            });
+        }
+        output.reverse();
+        output
+    }
+
+    /// Get the torsion angles of all Conformers of the Residue at the given index. The backbone
+    /// torsions use the neighbouring Residues with the same alternative location (or their first
+    /// Conformer) and are only given if the peptide bond with that neighbour is present, so not
+    /// at chain breaks.
+    ///
+    /// ## Arguments
+    /// * `index` - the index of the Residue
+    ///
+    /// ## Fails
+    /// It returns an empty list if the index is out of bounds.
+    pub fn torsions(&self, index: usize) -> Vec<Torsions> {
+        self.residue(index).map_or_else(Vec::new, |residue| {
+            residue
+                .conformers()
+                .map(|conformer| torsion::torsions(self, index, conformer))
+                .collect()
+        })
+    }
+
+    /// Get a torsion angle of the first Conformer of the Residue at the given index, in degrees in
+    /// the range `-180..=180`. See [`Chain::torsions`] for the torsions of all Conformers.
+    ///
+    /// ## Arguments
+    /// * `index` - the index of the Residue
+    /// * `torsion` - the torsion angle to calculate
+    ///
+    /// ## Fails
+    /// It returns `None` if the index is out of bounds, if any of the atoms defining the torsion
+    /// is missing, or if the Residue does not have this torsion.
+    pub fn torsion(&self, index: usize, torsion: Torsion) -> Option<f64> {
+        let conformer = self.residue(index)?.conformer(0)?;
+        torsion::torsion(self, index, conformer, torsion)
+    }
+
+    /// Get the phi backbone torsion (C(i-1)-N-CA-C) of the Residue at the given index, see [`Chain::torsion`].
+    pub fn phi(&self, index: usize) -> Option<f64> {
+        self.torsion(index, Torsion::Phi)
+    }
+
+    /// Get the psi backbone torsion (N-CA-C-N(i+1)) of the Residue at the given index, see [`Chain::torsion`].
+    pub fn psi(&self, index: usize) -> Option<f64> {
+        self.torsion(index, Torsion::Psi)
+    }
+
+    /// Get the omega torsion of the peptide bond preceding the Residue at the given index
+    /// (CA(i-1)-C(i-1)-N-CA), see [`Chain::torsion`].
+    pub fn omega(&self, index: usize) -> Option<f64> {
+        self.torsion(index, Torsion::Omega)
+    }
+
+    /// Set a torsion angle of the Residue at the given index by rotating the downstream atoms
+    /// around the central bond. For backbone torsions all atoms in the C terminal direction (up
+    /// to the next chain break) are rotated, the torsion is measured on the first Conformer and
+    /// the same rotation is applied to all Conformers. Side chain torsions are set for every
+    /// Conformer separately and only rotate the atoms further along the side chain.
+    ///
+    /// ## Arguments
+    /// * `index` - the index of the Residue
+    /// * `torsion` - the torsion angle to set
+    /// * `value` - the new value in degrees
+    ///
+    /// ## Fails
+    /// It returns `None` if the torsion cannot be determined (see [`Chain::torsion`]) or if the
+    /// torsion is part of a ring (like phi and the side chain torsions of proline). Otherwise it
+    /// returns the previous value of the torsion.
+    pub fn set_torsion(&mut self, index: usize, torsion: Torsion, value: f64) -> Option<f64> {
+        torsion::set_torsion(self, index, torsion, value)
+    }
+
     /// Sort the residues of this chain
     pub fn sort(&mut self) {
         self.residues.sort();
@@ -546,7 +679,7 @@ impl fmt::Display for Chain {
 
 impl PartialOrd for Chain {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.id().cmp(other.id()))
+        Some(self.cmp(other))
     }
 }
 
@@ -616,7 +749,7 @@ mod tests {
     #[test]
     fn check_display() {
         let a = Chain::new("A").unwrap();
-        format!("{a:?}");
-        format!("{a}");
+        let _ = format!("{a:?}");
+        let _ = format!("{a}");
     }
 }
@@ -0,0 +1,368 @@
+use crate::error::*;
+use crate::structs::*;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// The minimal overlap in Å of the van der Waals radii of a serious clash, as used for the
+/// MolProbity clashscore
+const SERIOUS_OVERLAP: f64 = 0.4;
+
+/// The tolerance in Å on the sum of the covalent radii to consider two atoms bonded
+const BOND_TOLERANCE: f64 = 0.4;
+
+/// The reduction in Å of the overlap between hydrogen bond donors and acceptors (nitrogen,
+/// oxygen, and hydrogens bonded to these), as a hydrogen bond brings them closer than their van
+/// der Waals radii
+const HYDROGEN_BOND_ALLOWANCE: f64 = 0.6;
+
+/// The van der Waals radius in Å of hydrogens bonded to nitrogen or oxygen
+const POLAR_HYDROGEN_RADIUS: f64 = 1.0;
+
+/// Get the van der Waals radius in Å used for clashes for the given element, the radii for the
+/// common elements in biomolecules are those used by MolProbity (Word et al. 1999), the radii for
+/// other elements are the van der Waals radii from [`AtomicRadius`]
+fn clash_radius(element: &Element) -> Option<f64> {
+    match element {
+        Element::H => Some(1.17),
+        Element::C => Some(1.70),
+        Element::N => Some(1.55),
+        Element::O => Some(1.40),
+        Element::P | Element::S => Some(1.80),
+        _ => element.atomic_radius().van_der_waals,
+    }
+}
+
+/// Atom pairs separated by this number of bonds or less, so bonded atoms, 1-3 pairs, and 1-4
+/// pairs, are never considered a clash by default, as in MolProbity
+pub(crate) const EXCLUDED_BONDS: usize = 3;
+
+/// The atom names of the bonds linking residues in a polymer: peptide bonds, phosphodiester
+/// bonds, and disulfide bridges
+const POLYMER_LINKS: &[(&str, &str)] = &[("C", "N"), ("O3'", "P"), ("SG", "SG")];
+
+/// A steric clash between two atoms, see [`PDB::clashes`]
+#[derive(Debug, Clone)]
+pub struct Clash<'a> {
+    /// The two clashing atoms with their hierarchy
+    pub atoms: [AtomConformerResidueChainModel<'a>; 2],
+    /// The distance between the atoms in Å
+    pub distance: f64,
+    /// The overlap of the van der Waals radii in Å
+    pub overlap: f64,
+}
+
+/// The steric clashes in a structure, see [`PDB::clashes`]
+#[derive(Debug, Clone, Default)]
+pub struct ClashReport<'a> {
+    /// The clashes, sorted from the largest to the smallest overlap
+    pub clashes: Vec<Clash<'a>>,
+    /// The number of atoms in the structure
+    pub atom_count: usize,
+}
+
+impl<'a> ClashReport<'a> {
+    /// Get the clashscore: the number of serious clashes per 1000 atoms, 0 if there are no atoms.
+    /// As MolProbity calculates this score after adding all hydrogens, the score is only
+    /// comparable to MolProbity scores for structures with hydrogens, see
+    /// [`PDB::add_hydrogens`].
+    #[allow(clippy::cast_precision_loss)]
+    pub fn clashscore(&self) -> f64 {
+        if self.atom_count == 0 {
+            0.0
+        } else {
+            self.clashes.len() as f64 * 1000.0 / self.atom_count as f64
+        }
+    }
+
+    /// Get a warning for every clash, so these can be reported together with the other errors
+    /// and warnings
+    pub fn errors(&self) -> Vec<PDBError> {
+        let describe = |h: &AtomConformerResidueChainModel<'a>| {
+            format!(
+                "atom {} of residue {}{} {}{} in chain {} of model {}",
+                h.atom().name(),
+                h.residue().serial_number(),
+                h.residue().insertion_code().unwrap_or(""),
+                h.conformer().name(),
+                h.conformer()
+                    .alternative_location()
+                    .map_or_else(String::new, |alt| format!(" (alternative location {alt})")),
+                h.chain().id(),
+                h.model().serial_number(),
+            )
+        };
+        self.clashes
+            .iter()
+            .map(|clash| {
+                let mut first = describe(&clash.atoms[0]);
+                first[..1].make_ascii_uppercase();
+                PDBError::new(
+                    ErrorLevel::GeneralWarning,
+                    "Steric clash",
+                    format!(
+                        "{} overlaps {:.2} Å with {} at a distance of {:.2} Å.",
+                        first,
+                        clash.overlap,
+                        describe(&clash.atoms[1]),
+                        clash.distance
+                    ),
+                    Context::None,
+                )
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for ClashReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Clashscore {:.2}: {} serious clashes in {} atoms",
+            self.clashscore(),
+            self.clashes.len(),
+            self.atom_count
+        )
+    }
+}
+
+/// Find all serious clashes in the given PDB, skipping atom pairs separated by at most the given
+/// number of bonds
+pub(crate) fn find_clashes(pdb: &PDB, excluded_bonds: usize) -> ClashReport<'_> {
+    let atoms: Vec<AtomConformerResidueChainModel<'_>> = pdb.atoms_with_hierarchy().collect();
+    let radii: Vec<Option<&AtomicRadius>> = atoms
+        .iter()
+        .map(|h| h.atom().element().map(Element::atomic_radius))
+        .collect();
+    let search = atoms
+        .iter()
+        .filter_map(|h| h.atom().element())
+        .map(|e| {
+            clash_radius(e)
+                .unwrap_or(0.0)
+                .max(e.atomic_radius().covalent_single + BOND_TOLERANCE)
+        })
+        .fold(0.0, f64::max)
+        * 2.0;
+    let index: HashMap<usize, usize> = atoms
+        .iter()
+        .enumerate()
+        .map(|(i, h)| (h.atom().counter(), i))
+        .collect();
+    let tree = rstar::RTree::bulk_load(atoms.clone());
+    let explicit = pdb.bond_graph();
+
+    // Find all atoms within the search distance and determine which of these are bonded
+    let mut contacts: Vec<Vec<(usize, f64)>> = vec![Vec::new(); atoms.len()];
+    let mut bonded: Vec<Vec<usize>> = vec![Vec::new(); atoms.len()];
+    for (i, a) in atoms.iter().enumerate() {
+        for b in tree.locate_within_distance(a.atom().pos(), search * search) {
+            let j = index[&b.atom().counter()];
+            if j <= i
+                || !std::ptr::eq(a.model(), b.model())
+                || matches!(
+                    (a.conformer().alternative_location(), b.conformer().alternative_location()),
+                    (Some(x), Some(y)) if x != y
+                )
+            {
+                continue;
+            }
+            let distance = a.atom().distance(b.atom());
+            let covalent = radii[i].zip(radii[j]).map_or(false, |(r, s)| {
+                distance <= r.covalent_single + s.covalent_single + BOND_TOLERANCE
+            });
+            let linked = std::ptr::eq(a.residue(), b.residue())
+                || explicit.bond(a.atom(), b.atom()).is_some()
+                || POLYMER_LINKS.iter().any(|(x, y)| {
+                    (a.atom().name() == *x && b.atom().name() == *y)
+                        || (a.atom().name() == *y && b.atom().name() == *x)
+                });
+            if covalent && linked {
+                bonded[i].push(j);
+                bonded[j].push(i);
+            } else {
+                contacts[i].push((j, distance));
+            }
+        }
+    }
+
+    let polar = |i: usize| match atoms[i].atom().element() {
+        Some(Element::N | Element::O) => true,
+        Some(Element::H) => bonded[i]
+            .iter()
+            .any(|j| matches!(atoms[*j].atom().element(), Some(Element::N | Element::O))),
+        _ => false,
+    };
+    let radius = |i: usize| match atoms[i].atom().element()? {
+        Element::H if polar(i) => Some(POLAR_HYDROGEN_RADIUS),
+        element => clash_radius(element),
+    };
+    let mut clashes = Vec::new();
+    for (i, contacts) in contacts.into_iter().enumerate() {
+        if contacts.is_empty() {
+            continue;
+        }
+        let nearby = bonded_within(&bonded, i, excluded_bonds);
+        for (j, distance) in contacts {
+            if nearby.contains(&j) {
+                continue;
+            }
+            let (r, s) = match (radius(i), radius(j)) {
+                (Some(r), Some(s)) => (r, s),
+                _ => continue,
+            };
+            let mut overlap = r + s - distance;
+            // Two hydrogens or two heavy atoms can be the donor and acceptor, but not one of each
+            let hydrogens = [i, j]
+                .iter()
+                .filter(|k| atoms[**k].atom().element() == Some(&Element::H))
+                .count();
+            if polar(i) && polar(j) && hydrogens != 2 {
+                overlap -= HYDROGEN_BOND_ALLOWANCE;
+            }
+            if overlap >= SERIOUS_OVERLAP {
+                clashes.push(Clash {
+                    atoms: [atoms[i].clone(), atoms[j].clone()],
+                    distance,
+                    overlap,
+                });
+            }
+        }
+    }
+    clashes.sort_by(|a, b| b.overlap.total_cmp(&a.overlap));
+    ClashReport {
+        clashes,
+        atom_count: atoms.len(),
+    }
+}
+
+/// Get all atoms within the given number of bonds of the given atom
+fn bonded_within(bonded: &[Vec<usize>], atom: usize, bonds: usize) -> HashSet<usize> {
+    let mut found = HashSet::from([atom]);
+    let mut shell = vec![atom];
+    for _ in 0..bonds {
+        shell = shell
+            .iter()
+            .flat_map(|a| bonded[*a].iter().copied())
+            .filter(|a| found.insert(*a))
+            .collect();
+    }
+    found
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{open_pdb, StrictnessLevel};
+
+    fn ubiquitin() -> PDB {
+        open_pdb("example-pdbs/1ubq.pdb", StrictnessLevel::Loose)
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn ubiquitin_clashes() {
+        let pdb = ubiquitin();
+        // With 1-4 pairs skipped by default only the four serious clashes in ubiquitin remain
+        let report = pdb.clashes();
+        assert_eq!(report.atom_count, 1271);
+        assert_eq!(report.clashes.len(), 4);
+        assert!((report.clashscore() - 4000.0 / 1271.0).abs() < 1e-9);
+        assert!(report
+            .clashes
+            .windows(2)
+            .all(|w| w[0].overlap >= w[1].overlap));
+        let worst = &report.clashes[0];
+        assert_eq!(worst.atoms[0].atom().name(), "HZ2");
+        assert_eq!(worst.atoms[1].atom().name(), "CD");
+        assert!((worst.distance - 2.15).abs() < 0.01);
+        assert!((worst.overlap - 0.55).abs() < 0.01);
+        let errors = report.errors();
+        assert_eq!(errors.len(), 4);
+        assert_eq!(errors[0].level(), ErrorLevel::GeneralWarning);
+        assert_eq!(errors[0].short_description(), "Steric clash");
+        assert!(errors[0]
+            .long_description()
+            .starts_with("Atom HZ2 of residue 11 LYS in chain A of model 0 overlaps 0.55 Å"));
+        assert_eq!(
+            report.to_string(),
+            "Clashscore 3.15: 4 serious clashes in 1271 atoms"
+        );
+    }
+
+    #[test]
+    fn excluded_bonds() {
+        let pdb = ubiquitin();
+        let is_pair = |clash: &Clash<'_>, residue: isize, names: [&str; 2]| {
+            clash
+                .atoms
+                .iter()
+                .all(|h| h.residue().serial_number() == residue)
+                && clash.atoms.iter().any(|h| h.atom().name() == names[0])
+                && clash.atoms.iter().any(|h| h.atom().name() == names[1])
+        };
+        // The para positions of a phenylalanine ring are three bonds apart
+        let report = pdb.clashes_with_exclusion(2);
+        assert_eq!(report.clashes.len(), 72);
+        assert!(report
+            .clashes
+            .iter()
+            .any(|clash| is_pair(clash, 4, ["CG", "CZ"])));
+        // CG and CE1 are a 1-3 pair, only a clash if these are not excluded
+        assert!(!report
+            .clashes
+            .iter()
+            .any(|clash| is_pair(clash, 4, ["CG", "CE1"])));
+        assert!(pdb
+            .clashes_with_exclusion(1)
+            .clashes
+            .iter()
+            .any(|clash| is_pair(clash, 4, ["CG", "CE1"])));
+        assert!(!pdb
+            .clashes()
+            .clashes
+            .iter()
+            .any(|clash| is_pair(clash, 4, ["CG", "CZ"])));
+    }
+
+    #[test]
+    fn moved_atom() {
+        let mut pdb = ubiquitin();
+        let ca = pdb.atoms().find(|a| a.name() == "CA").unwrap().pos();
+        let water = pdb
+            .residues_mut()
+            .find(|r| r.name() == Some("HOH"))
+            .unwrap()
+            .atom_mut(0)
+            .unwrap();
+        water.set_pos((ca.0 + 1.5, ca.1, ca.2)).unwrap();
+        let report = pdb.clashes();
+        assert!(report.clashes.iter().any(|clash| {
+            clash.atoms.iter().any(|h| h.conformer().name() == "HOH")
+                && clash.atoms.iter().any(|h| h.atom().name() == "CA")
+                && (clash.distance - 1.5).abs() < 1e-6
+                && (clash.overlap - (1.70 + 1.40 - 1.5)).abs() < 1e-6
+        }));
+    }
+
+    #[test]
+    fn models_are_separate() {
+        let mut pdb = ubiquitin();
+        let mut model = pdb.model(0).unwrap().clone();
+        model.set_serial_number(2);
+        pdb.add_model(model);
+        let report = pdb.clashes();
+        assert_eq!(report.atom_count, 2 * 1271);
+        assert_eq!(report.clashes.len(), 8);
+        assert!((report.clashscore() - 4000.0 / 1271.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn empty() {
+        let pdb = PDB::new();
+        let report = pdb.clashes();
+        assert!(report.clashes.is_empty());
+        assert_eq!(report.clashscore(), 0.0);
+    }
+}
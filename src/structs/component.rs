@@ -0,0 +1,211 @@
+#![allow(dead_code)]
+use crate::structs::*;
+use std::collections::HashMap;
+
+/// An atom in a chemical component definition
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentAtom {
+    /// The name of the atom
+    pub name: String,
+    /// The element of the atom, if known
+    pub element: Option<Element>,
+    /// The formal charge of the atom
+    pub charge: isize,
+    /// If this atom is part of an aromatic system
+    pub aromatic: bool,
+    /// If this atom leaves when the component is linked to another component (eg OXT in amino acids)
+    pub leaving: bool,
+    /// The ideal position of this atom in the component model coordinates, if given
+    pub ideal_position: Option<(f64, f64, f64)>,
+}
+
+/// A chemical component definition, as given in the wwPDB Chemical Component Dictionary
+/// (`components.cif`). It describes the atoms and bonds for a single residue or ligand.
+/// Use [`crate::open_components`] to load the definitions from a file.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChemicalComponent {
+    /// The identifier, the same as the name of the residues of this type
+    id: String,
+    /// The full chemical name
+    name: String,
+    /// The type, eg `L-PEPTIDE LINKING` or `NON-POLYMER`
+    component_type: String,
+    /// The formal charge of the whole component
+    formal_charge: isize,
+    /// The atoms making up this component
+    atoms: Vec<ComponentAtom>,
+    /// The bonds, as the names of both atoms and the bond order
+    bonds: Vec<(String, String, BondOrder)>,
+}
+
+impl ChemicalComponent {
+    /// Create a new empty ChemicalComponent with the given identifier
+    pub fn new(id: impl Into<String>) -> Self {
+        ChemicalComponent {
+            id: id.into(),
+            name: String::new(),
+            component_type: String::new(),
+            formal_charge: 0,
+            atoms: Vec::new(),
+            bonds: Vec::new(),
+        }
+    }
+
+    /// Get the identifier of this component
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Get the full chemical name of this component
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Set the full chemical name of this component
+    pub fn set_name(&mut self, new_name: impl Into<String>) {
+        self.name = new_name.into();
+    }
+
+    /// Get the type of this component, eg `L-PEPTIDE LINKING` or `NON-POLYMER`
+    pub fn component_type(&self) -> &str {
+        &self.component_type
+    }
+
+    /// Set the type of this component
+    pub fn set_component_type(&mut self, new_type: impl Into<String>) {
+        self.component_type = new_type.into();
+    }
+
+    /// Get the formal charge of the whole component
+    pub const fn formal_charge(&self) -> isize {
+        self.formal_charge
+    }
+
+    /// Set the formal charge of the whole component
+    pub fn set_formal_charge(&mut self, new_charge: isize) {
+        self.formal_charge = new_charge;
+    }
+
+    /// Get the atoms of this component
+    pub fn atoms(&self) -> impl DoubleEndedIterator<Item = &ComponentAtom> + '_ {
+        self.atoms.iter()
+    }
+
+    /// Get the atom with the given name, if it is part of this component
+    pub fn atom(&self, name: impl AsRef<str>) -> Option<&ComponentAtom> {
+        let name = name.as_ref();
+        self.atoms.iter().find(|a| a.name == name)
+    }
+
+    /// Add an atom to this component
+    pub fn add_atom(&mut self, atom: ComponentAtom) {
+        self.atoms.push(atom);
+    }
+
+    /// Get the bonds of this component, as the names of both atoms and the bond order
+    pub fn bonds(&self) -> impl DoubleEndedIterator<Item = (&str, &str, BondOrder)> + '_ {
+        self.bonds
+            .iter()
+            .map(|(a, b, order)| (a.as_str(), b.as_str(), *order))
+    }
+
+    /// Add a bond between the atoms with the given names to this component
+    pub fn add_bond(
+        &mut self,
+        atom1: impl Into<String>,
+        atom2: impl Into<String>,
+        order: BondOrder,
+    ) {
+        self.bonds.push((atom1.into(), atom2.into(), order));
+    }
+
+    /// Returns if the atom with the given name is a leaving atom in this component
+    pub fn is_leaving_atom(&self, name: impl AsRef<str>) -> bool {
+        self.atom(name).map_or(false, |a| a.leaving)
+    }
+
+    /// Get all bonds of this component between atoms present in the given conformer. Atoms are
+    /// matched on name, bonds to atoms missing from the conformer are skipped.
+    pub fn bonds_in<'a>(
+        &'a self,
+        conformer: &'a Conformer,
+    ) -> impl Iterator<Item = (&'a Atom, &'a Atom, BondOrder)> + 'a {
+        self.bonds.iter().filter_map(move |(a, b, order)| {
+            Some((
+                conformer.atoms().find(|atom| atom.name() == a)?,
+                conformer.atoms().find(|atom| atom.name() == b)?,
+                *order,
+            ))
+        })
+    }
+
+    /// Get all atoms in the given conformer that are leaving atoms in this component.
+    pub fn leaving_atoms_in<'a>(
+        &'a self,
+        conformer: &'a Conformer,
+    ) -> impl Iterator<Item = &'a Atom> + 'a {
+        conformer
+            .atoms()
+            .filter(move |atom| self.is_leaving_atom(atom.name()))
+    }
+}
+
+/// A set of chemical component definitions, indexed by their identifier.
+/// Use [`crate::open_components`] to load a library from a `components.cif` file.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComponentLibrary {
+    /// The components
+    components: HashMap<String, ChemicalComponent>,
+}
+
+impl ComponentLibrary {
+    /// Create a new empty ComponentLibrary
+    pub fn new() -> Self {
+        ComponentLibrary::default()
+    }
+
+    /// Get the component with the given identifier
+    pub fn get(&self, id: impl AsRef<str>) -> Option<&ChemicalComponent> {
+        self.components.get(id.as_ref())
+    }
+
+    /// Add a component to this library, it returns the previous component with the same
+    /// identifier if that was present.
+    pub fn add(&mut self, component: ChemicalComponent) -> Option<ChemicalComponent> {
+        self.components.insert(component.id.clone(), component)
+    }
+
+    /// Get the number of components in this library
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    /// Returns if this library has no components
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// Get all components in this library, in no particular order
+    pub fn components(&self) -> impl Iterator<Item = &ChemicalComponent> + '_ {
+        self.components.values()
+    }
+}
+
+impl Extend<ChemicalComponent> for ComponentLibrary {
+    fn extend<T: IntoIterator<Item = ChemicalComponent>>(&mut self, iter: T) {
+        for component in iter {
+            self.add(component);
+        }
+    }
+}
+
+impl FromIterator<ChemicalComponent> for ComponentLibrary {
+    fn from_iter<T: IntoIterator<Item = ChemicalComponent>>(iter: T) -> Self {
+        let mut library = ComponentLibrary::new();
+        library.extend(iter);
+        library
+    }
+}
@@ -8,6 +8,18 @@ use rayon::prelude::*;
 use std::cmp::Ordering;
 use std::fmt;
 
+/// The type of a polymer, see [`Conformer::polymer_type`]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PolymerType {
+    /// A protein, made of amino acids
+    Protein,
+    /// DNA, made of deoxyribonucleotides
+    Dna,
+    /// RNA, made of ribonucleotides
+    Rna,
+}
+
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// A Conformer containing multiple atoms, analogous to `atom_group` in cctbx
@@ -181,14 +193,27 @@ impl Conformer {
         }
     }
 
+    /// Resolve the terms of the search that need the whole Conformer, see [`Search::resolve`]
+    fn resolve_search(&self, search: &mut Search) {
+        search.resolve(
+            &|inner| self.find(inner).map(Atom::counter).collect(),
+            &|| self.atoms().map(|a| (None, None, a)).collect(),
+        );
+    }
+
     /// Find all atoms matching the given information
-    pub fn find(&self, search: Search) -> impl DoubleEndedIterator<Item = &Atom> + '_ {
+    pub fn find(&self, mut search: Search) -> impl DoubleEndedIterator<Item = &Atom> + '_ {
+        self.resolve_search(&mut search);
         self.atoms()
             .filter(move |a| search.add_atom_info(a).complete().unwrap_or(true))
     }
 
     /// Find all atoms matching the given information
-    pub fn find_mut(&mut self, search: Search) -> impl DoubleEndedIterator<Item = &mut Atom> + '_ {
+    pub fn find_mut(
+        &mut self,
+        mut search: Search,
+    ) -> impl DoubleEndedIterator<Item = &mut Atom> + '_ {
+        self.resolve_search(&mut search);
         self.atoms_mut()
             .filter(move |a| search.add_atom_info(a).complete().unwrap_or(true))
     }
@@ -224,11 +249,76 @@ impl Conformer {
         self.atoms.push(new_atom);
     }
 
+    /// Returns whether this Conformer is water, based on its name.
+    pub fn is_water(&self) -> bool {
+        reference_tables::is_water(self.name())
+    }
+
+    /// Get the type of polymer this Conformer can be part of based on its name, or `None` if it
+    /// is not a common amino acid or nucleotide.
+    pub fn polymer_type(&self) -> Option<PolymerType> {
+        if self.is_amino_acid() {
+            Some(PolymerType::Protein)
+        } else if reference_tables::is_deoxyribonucleotide(self.name()) {
+            Some(PolymerType::Dna)
+        } else if reference_tables::is_ribonucleotide(self.name()) {
+            Some(PolymerType::Rna)
+        } else {
+            None
+        }
+    }
+
     /// Returns whether this Conformer is an amino acid.
     pub fn is_amino_acid(&self) -> bool {
         reference_tables::is_amino_acid(self.name())
     }
 
+    /// Get the standard residue template for this Conformer based on its name, if there is one.
+    /// See [`ResidueTemplate`](crate::ResidueTemplate).
+    pub fn template(&self) -> Option<&'static reference_tables::ResidueTemplate> {
+        reference_tables::get_residue_template(self.name())
+    }
+
+    /// Get the bonds within this Conformer as defined by its residue template. Only bonds
+    /// between atoms that are present are given. If there is no template for this Conformer
+    /// no bonds are returned.
+    pub fn template_bonds(&self) -> impl Iterator<Item = (&Atom, &Atom, BondOrder)> + '_ {
+        self.template()
+            .into_iter()
+            .flat_map(move |template| template.bonds_in(self))
+    }
+
+    /// Get the names of the heavy (non hydrogen) atoms of the residue template of this Conformer
+    /// that are not present. Atoms that leave when the residue is part of a polymer (like OXT)
+    /// are not included. If there is no template for this Conformer nothing is returned. Use
+    /// [`PDB::rebuild_missing_atoms`] to add the missing atoms for amino acids.
+    pub fn missing_atoms(&self) -> Vec<&'static str> {
+        self.template().map_or_else(Vec::new, |template| {
+            template
+                .heavy_atoms()
+                .filter(|name| {
+                    !template.leaving_atoms.contains(name)
+                        && !self.atoms().any(|a| a.name() == *name)
+                })
+                .collect()
+        })
+    }
+
+    /// Get the chemical component definition for this Conformer from the given library based on its name.
+    pub fn component<'a>(&self, library: &'a ComponentLibrary) -> Option<&'a ChemicalComponent> {
+        library.get(self.name())
+    }
+
+    /// Set the formal charges of the atoms in this Conformer to the charges given in the component
+    /// definition. Atoms are matched on name, atoms not present in the component are left unchanged.
+    pub fn apply_component_charges(&mut self, component: &ChemicalComponent) {
+        for atom in self.atoms_mut() {
+            if let Some(definition) = component.atom(atom.name()) {
+                atom.set_charge(definition.charge);
+            }
+        }
+    }
+
     /// Remove all Atoms matching the given predicate. As this is done in place this is the fastest way to remove Atoms from this Conformer.
     pub fn remove_atoms_by<F>(&mut self, predicate: F)
     where
@@ -380,7 +470,7 @@ impl fmt::Display for Conformer {
 
 impl PartialOrd for Conformer {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.id().cmp(&other.id()))
+        Some(self.cmp(other))
     }
 }
 
@@ -402,6 +492,23 @@ impl Extend<Atom> for Conformer {
 mod tests {
     use super::*;
 
+    #[test]
+    fn classification() {
+        let names = [
+            ("HOH", true, None),
+            ("WAT", true, None),
+            ("ALA", false, Some(PolymerType::Protein)),
+            ("DG", false, Some(PolymerType::Dna)),
+            ("G", false, Some(PolymerType::Rna)),
+            ("ZN", false, None),
+        ];
+        for (name, water, polymer) in names {
+            let a = Conformer::new(name, None, None).unwrap();
+            assert_eq!(a.is_water(), water);
+            assert_eq!(a.polymer_type(), polymer);
+        }
+    }
+
     #[test]
     fn test_text_validation() {
         let mut a = Conformer::new("A", None, None).unwrap();
@@ -463,7 +570,7 @@ mod tests {
     #[test]
     fn check_display() {
         let a = Conformer::new("A", None, None).unwrap();
-        format!("{a:?}");
-        format!("{a}");
+        let _ = format!("{a:?}");
+        let _ = format!("{a}");
     }
 }
@@ -0,0 +1,139 @@
+//! Small vector helpers for building and measuring atom positions, all positions are
+//! `(x, y, z)` tuples in Å and all angles are in degrees as in [`Atom::angle`](crate::Atom::angle).
+
+/// A position or direction in space
+pub(crate) type Vector = (f64, f64, f64);
+
+/// Add two vectors
+pub(crate) fn add(a: Vector, b: Vector) -> Vector {
+    (a.0 + b.0, a.1 + b.1, a.2 + b.2)
+}
+
+/// Subtract vector `b` from vector `a`
+pub(crate) fn sub(a: Vector, b: Vector) -> Vector {
+    (a.0 - b.0, a.1 - b.1, a.2 - b.2)
+}
+
+/// Scale a vector by the given factor
+pub(crate) fn scale(a: Vector, factor: f64) -> Vector {
+    (a.0 * factor, a.1 * factor, a.2 * factor)
+}
+
+/// The dot product of two vectors
+pub(crate) fn dot(a: Vector, b: Vector) -> f64 {
+    a.2.mul_add(b.2, a.0.mul_add(b.0, a.1 * b.1))
+}
+
+/// The cross product of two vectors
+pub(crate) fn cross(a: Vector, b: Vector) -> Vector {
+    (
+        a.1.mul_add(b.2, -a.2 * b.1),
+        a.2.mul_add(b.0, -a.0 * b.2),
+        a.0.mul_add(b.1, -a.1 * b.0),
+    )
+}
+
+/// The length of a vector
+pub(crate) fn length(a: Vector) -> f64 {
+    dot(a, a).sqrt()
+}
+
+/// The vector with the same direction with length one, a zero vector stays zero
+pub(crate) fn normalize(a: Vector) -> Vector {
+    let l = length(a);
+    if l > 0.0 {
+        scale(a, 1.0 / l)
+    } else {
+        a
+    }
+}
+
+/// The signed dihedral between the four given positions, in degrees in the range `-180..=180`.
+/// Following the IUPAC convention a positive dihedral is a clockwise rotation of `d` when
+/// looking from `b` to `c`.
+pub(crate) fn dihedral(a: Vector, b: Vector, c: Vector, d: Vector) -> f64 {
+    let b0 = sub(a, b);
+    let b1 = normalize(sub(c, b));
+    let b2 = sub(d, c);
+    let v = sub(b0, scale(b1, dot(b0, b1)));
+    let w = sub(b2, scale(b1, dot(b2, b1)));
+    dot(cross(b1, v), w).atan2(dot(v, w)).to_degrees()
+}
+
+/// Place a new atom `d` given three atoms `a`, `b` and `c`, with the bond length `c-d`, the angle
+/// `b-c-d`, and the dihedral `a-b-c-d` (natural extension reference frame).
+pub(crate) fn place(
+    a: Vector,
+    b: Vector,
+    c: Vector,
+    bond: f64,
+    angle: f64,
+    dihedral: f64,
+) -> Vector {
+    let (angle, dihedral) = (angle.to_radians(), dihedral.to_radians());
+    let bc = normalize(sub(c, b));
+    let n = normalize(cross(sub(b, a), bc));
+    let m = cross(n, bc);
+    let local = (
+        -bond * angle.cos(),
+        bond * angle.sin() * dihedral.cos(),
+        bond * angle.sin() * dihedral.sin(),
+    );
+    add(
+        c,
+        add(
+            scale(bc, local.0),
+            add(scale(m, local.1), scale(n, local.2)),
+        ),
+    )
+}
+
+/// Rotate a point around the axis through `origin` in the direction of `axis` by the given angle.
+/// Looking along the axis a positive angle is a clockwise rotation, so rotating the atom `d`
+/// around the axis from `b` to `c` increases the dihedral `a-b-c-d` by the angle.
+pub(crate) fn rotate(point: Vector, origin: Vector, axis: Vector, angle: f64) -> Vector {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let k = normalize(axis);
+    let v = sub(point, origin);
+    // Rodrigues' rotation formula
+    let rotated = add(
+        add(scale(v, cos), scale(cross(k, v), sin)),
+        scale(k, dot(k, v) * (1.0 - cos)),
+    );
+    add(origin, rotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn place_round_trip() {
+        let (a, b, c) = ((1.0, 2.0, 0.5), (2.2, 1.9, 0.1), (2.7, 3.1, -0.4));
+        for angle in [60.0, 109.5, 120.0, 150.0] {
+            for torsion in [-170.0, -60.0, 0.0, 45.0, 120.0, 180.0] {
+                let d = place(a, b, c, 1.5, angle, torsion);
+                assert!((length(sub(d, c)) - 1.5).abs() < 1e-9);
+                let measured = dot(normalize(sub(b, c)), normalize(sub(d, c)))
+                    .acos()
+                    .to_degrees();
+                assert!((measured - angle).abs() < 1e-6);
+                let difference = (dihedral(a, b, c, d) - torsion + 540.0) % 360.0 - 180.0;
+                assert!(
+                    difference.abs() < 1e-6,
+                    "{torsion} {}",
+                    dihedral(a, b, c, d)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rotate_dihedral() {
+        let (a, b, c) = ((1.0, 2.0, 0.5), (2.2, 1.9, 0.1), (2.7, 3.1, -0.4));
+        let d = place(a, b, c, 1.5, 110.0, -60.0);
+        let rotated = rotate(d, b, sub(c, b), 100.0);
+        assert!((length(sub(rotated, c)) - 1.5).abs() < 1e-9);
+        assert!((dihedral(a, b, c, rotated) - 40.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,368 @@
+use super::geometry::*;
+use crate::reference_tables::{self, ResidueTemplate};
+use crate::structs::*;
+
+/// The tetrahedral angle in degrees
+const TETRAHEDRAL: f64 = 109.47;
+
+/// The protonation state used for histidines, see [`HydrogenOptions`]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum HistidineProtonation {
+    /// Protonated on ND1 (HID)
+    Delta,
+    /// Protonated on NE2 (HIE)
+    #[default]
+    Epsilon,
+    /// Protonated on both ND1 and NE2, positively charged (HIP)
+    Both,
+}
+
+impl HistidineProtonation {
+    /// Get the residue name used for this protonation state (HID, HIE, or HIP)
+    pub const fn residue_name(self) -> &'static str {
+        match self {
+            Self::Delta => "HID",
+            Self::Epsilon => "HIE",
+            Self::Both => "HIP",
+        }
+    }
+}
+
+/// The options used for adding hydrogens with [`PDB::add_hydrogens_with`]. Residues already
+/// named after a protonation variant (HID, HIE, HIP, ASH, GLH, LYN, CYX) always keep that
+/// variant, these options determine the variant used for the standard residue names.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HydrogenOptions {
+    /// The protonation state for residues named HIS, HIE by default
+    pub histidine: HistidineProtonation,
+    /// Protonate ASP and GLU residues (as ASH and GLH), false by default
+    pub neutral_acids: bool,
+    /// Deprotonate LYS residues (as LYN), false by default
+    pub neutral_lysine: bool,
+    /// Treat CYS residues in a disulfide bond as CYX, so without HG. A cysteine is in a
+    /// disulfide bond if its SG has a disulfide [`Bond`] or is within 2.5 Å of the SG of another
+    /// cysteine. True by default.
+    pub detect_disulfides: bool,
+    /// Rename the residues to the name of the protonation variant used (eg HIS to HIE),
+    /// false by default
+    pub rename_variants: bool,
+}
+
+impl Default for HydrogenOptions {
+    fn default() -> Self {
+        HydrogenOptions {
+            histidine: HistidineProtonation::default(),
+            neutral_acids: false,
+            neutral_lysine: false,
+            detect_disulfides: true,
+            rename_variants: false,
+        }
+    }
+}
+
+/// Get the name of the protonation variant to use for a residue with the given name
+pub(crate) fn protonation_variant<'a>(
+    name: &'a str,
+    options: &HydrogenOptions,
+    disulfide: bool,
+) -> &'a str {
+    match name {
+        "HIS" => options.histidine.residue_name(),
+        "ASP" if options.neutral_acids => "ASH",
+        "GLU" if options.neutral_acids => "GLH",
+        "LYS" if options.neutral_lysine => "LYN",
+        "CYS" if disulfide && options.detect_disulfides => "CYX",
+        _ => name,
+    }
+}
+
+/// Get the hydrogens in the template that are not present in the given protonation variant
+/// or position in the chain. The naming of the variants follows the Amber force fields.
+fn excluded_hydrogens(variant: &str, n_terminal: bool) -> Vec<&'static str> {
+    let mut excluded = vec!["HXT"];
+    if n_terminal {
+        excluded.push("H");
+    } else {
+        excluded.extend(["H1", "H2", "H3"]);
+        if variant == "PRO" {
+            excluded.push("H");
+        }
+    }
+    excluded.extend(match variant {
+        "ASP" => &["HD2"][..],
+        "GLU" => &["HE2"],
+        "HIS" | "HIE" => &["HD1"],
+        "HID" => &["HE2"],
+        "LYN" => &["HZ1"],
+        "CYX" => &["HG"],
+        _ => &[],
+    });
+    excluded
+}
+
+/// Calculate the positions of the hydrogens missing from the given conformer of a standard
+/// amino acid, using ideal geometry based on the heavy atoms present. The variant is the
+/// residue name of the protonation state to use, see [`protonation_variant`]. The position
+/// of the C of the previous residue should be given if it is bonded to the N of this residue,
+/// otherwise this residue is treated as an N terminus. It returns the name, position, and
+/// parent atom of each hydrogen.
+pub(crate) fn missing_hydrogens<'a>(
+    conformer: &'a Conformer,
+    variant: &str,
+    previous_carbon: Option<Vector>,
+) -> Vec<(&'static str, Vector, &'a Atom)> {
+    let template = match reference_tables::get_residue_template(variant) {
+        Some(template) => template,
+        None => return Vec::new(),
+    };
+    if !reference_tables::is_amino_acid(template.name) {
+        return Vec::new();
+    }
+    let excluded = excluded_hydrogens(variant, previous_carbon.is_none());
+    let find = |name: &str| conformer.atoms().find(|a| a.name() == name);
+    let is_hydrogen = |name: &str| template.element(name) == Some(Element::H);
+
+    let mut output = Vec::new();
+    for (parent_name, element) in template.atoms.iter().filter(|(n, _)| !is_hydrogen(n)) {
+        let parent = match find(parent_name) {
+            Some(parent) => parent,
+            None => continue,
+        };
+        let missing: Vec<&'static str> = template
+            .bonded_atoms(parent_name)
+            .map(|(name, _)| name)
+            .filter(|name| is_hydrogen(name) && !excluded.contains(name) && find(name).is_none())
+            .collect();
+        if missing.is_empty() {
+            continue;
+        }
+        // The bonded atoms that are present, heavy atoms first as these are used as reference
+        let mut neighbours: Vec<(&str, Vector)> = template
+            .bonded_atoms(parent_name)
+            .filter(|(name, _)| !is_hydrogen(name))
+            .filter_map(|(name, _)| find(name).map(|a| (name, a.pos())))
+            .collect();
+        let mut planar = is_planar(template, parent_name, *element);
+        if *parent_name == "N" {
+            if let Some(carbon) = previous_carbon {
+                neighbours.push(("-C", carbon));
+                planar = true;
+            }
+        }
+        let heavy = neighbours.len();
+        neighbours.extend(
+            template
+                .bonded_atoms(parent_name)
+                .filter_map(|(name, _)| find(name).filter(|_| is_hydrogen(name)))
+                .map(|a| ("", a.pos())),
+        );
+        if heavy == 0 {
+            continue;
+        }
+        let length = match element {
+            Element::C if planar => 1.08,
+            Element::C => 1.09,
+            Element::N => 1.01,
+            Element::O => 0.96,
+            Element::S => 1.34,
+            _ => 1.0,
+        };
+
+        let positions: Vec<Vector> = if neighbours.len() == 1 {
+            // Rotatable, place relative to a reference atom bonded to the neighbour
+            let (neighbour_name, neighbour) = neighbours[0];
+            let candidates: Vec<(&str, Vector, BondOrder)> = template
+                .bonded_atoms(neighbour_name)
+                .filter(|(name, _)| name != parent_name && !is_hydrogen(name))
+                .filter_map(|(name, order)| find(name).map(|a| (name, a.pos(), order)))
+                .collect();
+            let double = candidates.iter().find(|c| c.2 == BondOrder::Double);
+            let reference = if planar && missing.len() == 1 && double.is_some() {
+                double
+            } else {
+                let names: Vec<&str> = candidates.iter().map(|c| c.0).collect();
+                predecessor(&names, neighbour_name).map(|i| &candidates[i])
+            };
+            let reference = match reference {
+                Some((_, reference, _)) => reference,
+                None => continue,
+            };
+            let (angle, dihedrals) = if planar {
+                (120.0, &[0.0, 180.0][..])
+            } else {
+                (TETRAHEDRAL, &[180.0, -60.0, 60.0][..])
+            };
+            dihedrals
+                .iter()
+                .map(|d| place(*reference, neighbour, parent.pos(), length, angle, *d))
+                .collect()
+        } else {
+            let p = parent.pos();
+            let units: Vec<Vector> = neighbours
+                .iter()
+                .map(|(_, n)| normalize(sub(*n, p)))
+                .collect();
+            let bisector = normalize(scale(
+                units.iter().fold((0.0, 0.0, 0.0), |acc, u| add(acc, *u)),
+                -1.0,
+            ));
+            if neighbours.len() == 2 && !planar {
+                // Two positions out of the plane of the neighbours, the first is rotated +120
+                // degrees from the downstream neighbour when looking along the upstream neighbour
+                let half = (TETRAHEDRAL / 2.0).to_radians();
+                let normal = normalize(cross(units[0], units[1]));
+                let first = add(scale(bisector, half.cos()), scale(normal, half.sin()));
+                let second = add(scale(bisector, half.cos()), scale(normal, -half.sin()));
+                let names: Vec<&str> = neighbours[..heavy].iter().map(|n| n.0).collect();
+                let up = predecessor(&names, parent_name).unwrap_or(0);
+                let down = neighbours[1 - up.min(1)].1;
+                if dot(cross(sub(p, neighbours[up].1), sub(down, p)), first) > 0.0 {
+                    vec![first, second]
+                } else {
+                    vec![second, first]
+                }
+            } else {
+                vec![bisector]
+            }
+            .into_iter()
+            .map(|direction| add(p, scale(direction, length)))
+            .collect()
+        };
+        output.extend(
+            missing
+                .into_iter()
+                .zip(positions)
+                .map(|(name, position)| (name, position, parent)),
+        );
+    }
+    output
+}
+
+/// Determine if the given atom has a planar (sp2) geometry, this is the case when it has a
+/// double or aromatic bond, or when it is an N or O bonded to such an atom (as in amides,
+/// guanidinium groups, and carboxylic acids).
+fn is_planar(template: &ResidueTemplate, name: &str, element: Element) -> bool {
+    let conjugated = |name: &str| {
+        template
+            .bonded_atoms(name)
+            .any(|(_, order)| order != BondOrder::Single)
+    };
+    conjugated(name)
+        || matches!(element, Element::N | Element::O)
+            && template
+                .bonded_atoms(name)
+                .any(|(other, _)| template.element(other) != Some(Element::H) && conjugated(other))
+}
+
+/// Get the index of the atom that precedes the given atom, based on the remoteness indicator
+/// in the atom names (A, B, G, D, E, Z, H) so CA precedes CB, and CB precedes CG. If there
+/// is no such atom the first atom is used. Returns `None` if no atoms are given.
+fn predecessor(names: &[&str], name: &str) -> Option<usize> {
+    let remoteness = |name: &str| {
+        name.chars()
+            .nth(1)
+            .and_then(|c| "ABGDEZH".find(c))
+            .map_or(0, |i| i + 1)
+    };
+    remoteness(name)
+        .checked_sub(1)
+        .and_then(|target| names.iter().position(|n| remoteness(n) == target))
+        .or_else(|| (!names.is_empty()).then_some(0))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{open_pdb, StrictnessLevel};
+    use std::collections::HashMap;
+
+    #[test]
+    fn rebuild_ubiquitin() {
+        let (mut pdb, _) = open_pdb("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+        let original: HashMap<(isize, String), (f64, f64, f64)> = pdb
+            .atoms_with_hierarchy()
+            .filter(|h| h.atom().element() == Some(&Element::H))
+            .map(|h| {
+                (
+                    (h.residue().serial_number(), h.atom().name().to_string()),
+                    h.atom().pos(),
+                )
+            })
+            .collect();
+        pdb.remove_atoms_by(|a| a.element() == Some(&Element::H));
+        // HIS 68 is protonated on ND1 in this structure
+        let options = HydrogenOptions {
+            histidine: HistidineProtonation::Delta,
+            ..Default::default()
+        };
+        assert_eq!(pdb.add_hydrogens_with(&options), original.len());
+        assert_eq!(pdb.add_hydrogens_with(&options), 0);
+
+        // Rotatable groups (hydroxyls, NH3+) and the flipped amide of ASN 60 can differ
+        let rotatable = ["H1", "H2", "H3", "HG", "HG1", "HD21", "HD22"];
+        for h in pdb.atoms_with_hierarchy() {
+            if h.atom().element() != Some(&Element::H) {
+                continue;
+            }
+            let key = (h.residue().serial_number(), h.atom().name().to_string());
+            let position = original[&key];
+            let deviation = length(sub(position, h.atom().pos()));
+            assert!(
+                rotatable.contains(&key.1.as_str()) || deviation < 0.45,
+                "{key:?} {deviation}"
+            );
+        }
+    }
+
+    #[test]
+    fn protonation_variants() {
+        let options = HydrogenOptions::default();
+        assert_eq!(protonation_variant("HIS", &options, false), "HIE");
+        assert_eq!(protonation_variant("HID", &options, false), "HID");
+        assert_eq!(protonation_variant("ASP", &options, false), "ASP");
+        assert_eq!(protonation_variant("CYS", &options, true), "CYX");
+        let options = HydrogenOptions {
+            histidine: HistidineProtonation::Both,
+            neutral_acids: true,
+            neutral_lysine: true,
+            detect_disulfides: false,
+            rename_variants: false,
+        };
+        assert_eq!(protonation_variant("HIS", &options, false), "HIP");
+        assert_eq!(protonation_variant("GLU", &options, false), "GLH");
+        assert_eq!(protonation_variant("LYS", &options, false), "LYN");
+        assert_eq!(protonation_variant("CYS", &options, true), "CYS");
+
+        assert!(excluded_hydrogens("HIE", false).contains(&"HD1"));
+        assert!(excluded_hydrogens("PRO", false).contains(&"H"));
+        assert!(!excluded_hydrogens("PRO", true).contains(&"H2"));
+        assert!(!excluded_hydrogens("ASH", false).contains(&"HD2"));
+        assert!(excluded_hydrogens("CYX", true).contains(&"HG"));
+    }
+
+    #[test]
+    fn variants_in_structure() {
+        let (mut pdb, _) = open_pdb("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+        pdb.remove_atoms_by(|a| a.element() == Some(&Element::H));
+        let options = HydrogenOptions {
+            histidine: HistidineProtonation::Both,
+            neutral_acids: true,
+            rename_variants: true,
+            ..Default::default()
+        };
+        pdb.add_hydrogens_with(&options);
+        let his = pdb.residues().find(|r| r.serial_number() == 68).unwrap();
+        assert_eq!(his.name(), Some("HIP"));
+        assert!(his.atoms().any(|a| a.name() == "HD1"));
+        assert!(his.atoms().any(|a| a.name() == "HE2"));
+        let asp = pdb.residues().find(|r| r.name() == Some("ASH")).unwrap();
+        let hd2 = asp.atoms().find(|a| a.name() == "HD2").unwrap();
+        let od2 = asp.atoms().find(|a| a.name() == "OD2").unwrap();
+        assert!((hd2.distance(od2) - 0.96).abs() < 1e-6);
+        // The C terminal carboxylate stays deprotonated
+        assert!(!pdb.atoms().any(|a| a.name() == "HXT"));
+    }
+}
@@ -1,32 +1,55 @@
 #![allow(clippy::missing_docs_in_private_items)]
 mod atom;
 mod bond;
+mod bond_graph;
 mod chain;
+#[cfg(feature = "rstar")]
+mod clash;
+mod component;
 mod conformer;
 mod database_reference;
 mod elements;
+mod geometry;
 mod helper;
 mod hierarchy;
+mod hydrogens;
 mod model;
 mod mtrix;
 mod pdb;
+mod ramachandran;
+mod rebuild;
 mod residue;
 mod search;
+mod segment;
+mod selection;
 mod symmetry;
+mod torsion;
 mod unit_cell;
 
 pub use atom::Atom;
-pub use bond::Bond;
+pub use bond::{Bond, BondOrder};
+pub use bond_graph::BondGraph;
 pub use chain::Chain;
-pub use conformer::Conformer;
+#[cfg(feature = "rstar")]
+pub use clash::{Clash, ClashReport};
+pub use component::{ChemicalComponent, ComponentAtom, ComponentLibrary};
+pub use conformer::{Conformer, PolymerType};
 pub use database_reference::*;
 pub use elements::{AtomicRadius, Element};
 pub use helper::*;
 pub use hierarchy::*;
+pub use hydrogens::{HistidineProtonation, HydrogenOptions};
 pub use model::Model;
 pub use mtrix::MtriX;
 pub use pdb::PDB;
+pub use ramachandran::{
+    RamachandranCategory, RamachandranRegion, RamachandranReport, RamachandranResidue,
+};
+#[cfg(feature = "rstar")]
+pub use rebuild::RebuiltResidue;
 pub use residue::Residue;
 pub use search::*;
+pub use segment::{ChainGap, NumberingStep};
 pub use symmetry::Symmetry;
+pub use torsion::{ResidueTorsions, Torsion, Torsions};
 pub use unit_cell::UnitCell;
@@ -6,6 +6,7 @@ use doc_cfg::doc_cfg;
 #[cfg(feature = "rayon")]
 use rayon::prelude::*;
 use std::cmp::Ordering;
+use std::collections::HashSet;
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -266,22 +267,52 @@ impl<'a> Model {
         }
     }
 
+    /// Resolve the terms of the search that need the whole Model, see [`Search::resolve`]
+    fn resolve_search(&self, search: &mut Search) {
+        search.resolve(
+            &|inner| self.find(inner).map(|h| h.atom().counter()).collect(),
+            &|| {
+                self.chains()
+                    .flat_map(|c| {
+                        c.residues()
+                            .flat_map(move |r| r.atoms().map(move |a| (Some(c), Some(r), a)))
+                    })
+                    .collect()
+            },
+        );
+    }
+
     /// Find all hierarchies matching the given search. For more details see [Search].
     pub fn find(
         &'a self,
-        search: Search,
-    ) -> impl DoubleEndedIterator<Item = AtomConformerResidueChain<'a>> + '_ {
+        mut search: Search,
+    ) -> impl DoubleEndedIterator<Item = AtomConformerResidueChain<'a>> + 'a {
+        self.resolve_search(&mut search);
         self.chains()
             .map(move |c| (c, search.clone().add_chain_info(c)))
             .filter(|(_c, search)| !matches!(search, Search::Known(false)))
             .flat_map(move |(c, search)| c.find(search).map(move |h| h.extend(c)))
     }
 
+    /// Find all Residues with any atom matching the given search, with the Chain they are in. For
+    /// more details see [Search].
+    pub fn find_residues(
+        &'a self,
+        mut search: Search,
+    ) -> impl DoubleEndedIterator<Item = (&'a Chain, &'a Residue)> + 'a {
+        self.resolve_search(&mut search);
+        self.chains()
+            .map(move |c| (c, search.clone().add_chain_info(c)))
+            .filter(|(_c, search)| !matches!(search, Search::Known(false)))
+            .flat_map(move |(c, search)| c.find_residues(search).map(move |r| (c, r)))
+    }
+
     /// Find all hierarchies matching the given search. For more details see [Search].
     pub fn find_mut(
         &'a mut self,
-        search: Search,
-    ) -> impl DoubleEndedIterator<Item = AtomConformerResidueChainMut<'a>> + '_ {
+        mut search: Search,
+    ) -> impl DoubleEndedIterator<Item = AtomConformerResidueChainMut<'a>> + 'a {
+        self.resolve_search(&mut search);
         self.chains_mut()
             .map(move |c| {
                 let search = search.clone().add_chain_info(c);
@@ -393,7 +424,7 @@ impl<'a> Model {
     /// Get an iterator of references to a struct containing all atoms with their hierarchy making up this Model.
     pub fn atoms_with_hierarchy(
         &'a self,
-    ) -> impl DoubleEndedIterator<Item = hierarchy::AtomConformerResidueChain<'a>> + '_ {
+    ) -> impl DoubleEndedIterator<Item = hierarchy::AtomConformerResidueChain<'a>> + 'a {
         self.chains()
             .flat_map(|c| c.atoms_with_hierarchy().map(move |h| h.extend(c)))
     }
@@ -401,7 +432,7 @@ impl<'a> Model {
     /// Get an iterator of mutable references to a struct containing all atoms with their hierarchy making up this Model.
     pub fn atoms_with_hierarchy_mut(
         &'a mut self,
-    ) -> impl DoubleEndedIterator<Item = hierarchy::AtomConformerResidueChainMut<'a>> + '_ {
+    ) -> impl DoubleEndedIterator<Item = hierarchy::AtomConformerResidueChainMut<'a>> + 'a {
         self.chains_mut().flat_map(|c| {
             let chain: *mut Chain = c;
             c.atoms_with_hierarchy_mut().map(move |h| h.extend(chain))
@@ -575,6 +606,35 @@ impl<'a> Model {
         self.chains.extend(other.chains);
     }
 
+    /// Split every Chain in this Model at the physical breaks in its backbone, see
+    /// [`Chain::segments`]. The first segment keeps the id of the Chain, the other segments
+    /// become new Chains directly after it with the first unused ids (A-Z, then AA, AB, etc.).
+    /// It returns the number of new Chains.
+    pub fn split_chains_at_breaks(&mut self) -> usize {
+        let mut used: HashSet<String> = self.chains().map(|c| c.id().to_string()).collect();
+        let mut next = 0;
+        let mut added = 0;
+        let mut chains = Vec::with_capacity(self.chains.len());
+        for chain in std::mem::take(&mut self.chains) {
+            for (index, mut segment) in chain.split_segments().into_iter().enumerate() {
+                if index > 0 {
+                    let id = loop {
+                        let id = number_to_base26(next);
+                        next += 1;
+                        if used.insert(id.clone()) {
+                            break id;
+                        }
+                    };
+                    segment.set_id(id);
+                    added += 1;
+                }
+                chains.push(segment);
+            }
+        }
+        self.chains = chains;
+        added
+    }
+
     /// Sort the Chains of this Model.
     pub fn sort(&mut self) {
         self.chains.sort();
@@ -601,7 +661,7 @@ impl fmt::Display for Model {
 
 impl PartialOrd for Model {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.serial_number.cmp(&other.serial_number))
+        Some(self.cmp(other))
     }
 }
 
@@ -661,8 +721,8 @@ mod tests {
     #[test]
     fn test_display() {
         let a = Model::new(0);
-        format!("{a:?}");
-        format!("{a}");
+        let _ = format!("{a:?}");
+        let _ = format!("{a}");
     }
 
     #[test]
@@ -688,7 +748,7 @@ mod tests {
             hierarchy.atom_mut().set_serial_number(123);
         }
         // Test that casting it to a 'normal' hierarchy works (needs some 'magic' to get an owned variant)
-        let hierarchy = a.atoms_with_hierarchy_mut().into_iter().next().unwrap();
+        let hierarchy = a.atoms_with_hierarchy_mut().next().unwrap();
         assert_eq!(hierarchy.without_mut().chain().id(), "C");
 
         // Test that all changes were properly executed
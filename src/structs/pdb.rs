@@ -1,4 +1,5 @@
 #![allow(dead_code)]
+use crate::cif;
 use crate::structs::hierarchy::*;
 use crate::transformation::TransformationMatrix;
 use crate::{reference_tables, PDBError};
@@ -8,7 +9,7 @@ use doc_cfg::doc_cfg;
 use rayon::prelude::*;
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, PartialEq)]
 /// A PDB struct is generated by opening a PDB or mmCIF file. It contains
 /// all information present in this file, like its atoms, bonds, hierarchy
 /// , and metadata. The struct can be used to access, interact with, and
@@ -50,7 +51,9 @@ pub struct PDB {
     /// The Models making up this PDB, containing all chain, residues, conformers, and atoms.
     models: Vec<Model>,
     /// Bonds in this PDB.
-    bonds: Vec<(usize, usize, Bond)>,
+    bonds: Vec<(usize, usize, Bond, Option<BondOrder>)>,
+    /// The CIF items (categories and save frames) of an mmCIF file which are not interpreted.
+    cif_items: Vec<cif::Item>,
 }
 
 /// # Creators
@@ -68,6 +71,7 @@ impl PDB {
             symmetry: None,
             models: Vec::new(),
             bonds: Vec::new(),
+            cif_items: Vec::new(),
         }
     }
 }
@@ -152,6 +156,52 @@ impl PDB {
     }
 }
 
+/// # CIF items
+/// Functionality for working with the CIF items of an mmCIF file which are not interpreted by
+/// this library, like `_struct_conf` or `_refine`. These items are kept as is and written back
+/// when saving as mmCIF, so make sure these still apply after editing the structure.
+impl PDB {
+    /// Get the number of uninterpreted CIF items.
+    pub fn cif_item_count(&self) -> usize {
+        self.cif_items.len()
+    }
+
+    /// Get an iterator of references to the uninterpreted CIF items.
+    pub fn cif_items(&self) -> impl DoubleEndedIterator<Item = &cif::Item> + '_ {
+        self.cif_items.iter()
+    }
+
+    /// Get an iterator of mutable references to the uninterpreted CIF items.
+    pub fn cif_items_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut cif::Item> + '_ {
+        self.cif_items.iter_mut()
+    }
+
+    /// Get a category of the uninterpreted CIF items by its name (eg `struct_conf`, without
+    /// leading underscore) as a table.
+    pub fn cif_category(&self, name: &str) -> Option<cif::Category<'_>> {
+        cif::category(
+            self.cif_items.iter().filter_map(|item| match item {
+                cif::Item::DataItem(data_item) => Some(data_item),
+                cif::Item::SaveFrame(_) => None,
+            }),
+            name,
+        )
+    }
+
+    /// Add a CIF item, this will be written when saving as mmCIF.
+    pub fn add_cif_item(&mut self, item: cif::Item) {
+        self.cif_items.push(item);
+    }
+
+    /// Delete the CIF items matching the given predicate.
+    pub fn delete_cif_items_by<F>(&mut self, predicate: F)
+    where
+        F: Fn(&cif::Item) -> bool,
+    {
+        self.cif_items.retain(|item| !predicate(item));
+    }
+}
+
 /// # MtriX
 /// Functionality for working with the MtriX records form the PDB. The MtriX are needed
 /// to transform the Models to the full asymmetric subunit, if needed to contain the
@@ -479,18 +529,39 @@ impl<'a> PDB {
     pub fn find(
         &'a self,
         search: Search,
-    ) -> impl DoubleEndedIterator<Item = AtomConformerResidueChainModel<'a>> + '_ {
+    ) -> impl DoubleEndedIterator<Item = AtomConformerResidueChainModel<'a>> + 'a {
         self.models()
             .map(move |m| (m, search.clone().add_model_info(m)))
             .filter(|(_m, search)| !matches!(search, Search::Known(false)))
             .flat_map(move |(m, search)| m.find(search).map(move |h| h.extend(m)))
     }
 
+    /// Find all Residues with any atom matching the given search, with the Model and Chain they
+    /// are in. For more details see [Search].
+    ///
+    /// ```
+    /// use pdbtbx::*;
+    /// let (pdb, _errors) = open("example-pdbs/1ubq.pdb", StrictnessLevel::Medium).unwrap();
+    /// let search = "resn HOH and within 3 of (resi 1-10 and not hetatm)".parse().unwrap();
+    /// for (_model, chain, residue) in pdb.find_residues(search) {
+    ///     println!("Water {} in chain {} is close", residue.serial_number(), chain.id());
+    /// }
+    /// ```
+    pub fn find_residues(
+        &'a self,
+        search: Search,
+    ) -> impl DoubleEndedIterator<Item = (&'a Model, &'a Chain, &'a Residue)> + 'a {
+        self.models()
+            .map(move |m| (m, search.clone().add_model_info(m)))
+            .filter(|(_m, search)| !matches!(search, Search::Known(false)))
+            .flat_map(move |(m, search)| m.find_residues(search).map(move |(c, r)| (m, c, r)))
+    }
+
     /// Find all hierarchies matching the given search. For more details see [Search].
     pub fn find_mut(
         &'a mut self,
         search: Search,
-    ) -> impl DoubleEndedIterator<Item = AtomConformerResidueChainModelMut<'a>> + '_ {
+    ) -> impl DoubleEndedIterator<Item = AtomConformerResidueChainModelMut<'a>> + 'a {
         self.models_mut()
             .map(move |m| {
                 let search = search.clone().add_model_info(m);
@@ -626,7 +697,7 @@ impl<'a> PDB {
     /// Get an iterator of references to a struct containing all atoms with their hierarchy making up this PDB.
     pub fn atoms_with_hierarchy(
         &'a self,
-    ) -> impl DoubleEndedIterator<Item = hierarchy::AtomConformerResidueChainModel<'a>> + '_ {
+    ) -> impl DoubleEndedIterator<Item = hierarchy::AtomConformerResidueChainModel<'a>> + 'a {
         self.models()
             .flat_map(|m| m.atoms_with_hierarchy().map(move |h| h.extend(m)))
     }
@@ -634,7 +705,7 @@ impl<'a> PDB {
     /// Get an iterator of mutable references to a struct containing all atoms with their hierarchy making up this PDB.
     pub fn atoms_with_hierarchy_mut(
         &'a mut self,
-    ) -> impl DoubleEndedIterator<Item = hierarchy::AtomConformerResidueChainModelMut<'a>> + '_
+    ) -> impl DoubleEndedIterator<Item = hierarchy::AtomConformerResidueChainModelMut<'a>> + 'a
     {
         self.models_mut().flat_map(|m| {
             let model: *mut Model = m;
@@ -651,6 +722,7 @@ impl<'a> PDB {
         for residue in self.residues_mut() {
             residue.remove_atoms_by(&predicate);
         }
+        self.remove_dangling_bonds();
     }
 
     /// Remove all Conformers matching the given predicate. The predicate will be run on all Conformers.
@@ -662,6 +734,7 @@ impl<'a> PDB {
         for chain in self.chains_mut() {
             chain.remove_conformers_by(&predicate);
         }
+        self.remove_dangling_bonds();
     }
 
     /// Remove all Residues matching the given predicate. The predicate will be run on all Residues.
@@ -673,6 +746,7 @@ impl<'a> PDB {
         for chain in self.chains_mut() {
             chain.remove_residues_by(&predicate);
         }
+        self.remove_dangling_bonds();
     }
 
     /// Remove all Residues matching the given predicate. The predicate will be run on all Residues.
@@ -684,6 +758,7 @@ impl<'a> PDB {
         for model in self.models_mut() {
             model.remove_chains_by(&predicate);
         }
+        self.remove_dangling_bonds();
     }
 
     /// Remove all Chains matching the given predicate. The predicate will be run on all Chains.
@@ -693,6 +768,7 @@ impl<'a> PDB {
         F: Fn(&Model) -> bool,
     {
         self.models.retain(|model| !predicate(model));
+        self.remove_dangling_bonds();
     }
 
     /// Remove the Model specified.
@@ -708,6 +784,7 @@ impl<'a> PDB {
     /// Panics if the index is out of bounds.
     pub fn remove_model(&mut self, index: usize) {
         self.models.remove(index);
+        self.remove_dangling_bonds();
     }
 
     /// Remove all Models except for models
@@ -748,6 +825,7 @@ impl<'a> PDB {
         let removed = start_count - retained.len();
         self.models.clear();
         self.models.extend(retained);
+        self.remove_dangling_bonds();
 
         Some(removed)
     }
@@ -798,6 +876,16 @@ impl<'a> PDB {
         }
     }
 
+    /// Remove the bonds to atoms that are no longer part of this PDB.
+    fn remove_dangling_bonds(&mut self) {
+        if self.bonds.is_empty() {
+            return;
+        }
+        let counters: HashSet<usize> = self.atoms().map(Atom::counter).collect();
+        self.bonds
+            .retain(|(a, b, ..)| counters.contains(a) && counters.contains(b));
+    }
+
     /// Remove all empty Models from this PDB, and all empty Chains from the Model, and all empty Residues from the Chains.
     pub fn remove_empty(&mut self) {
         self.models.iter_mut().for_each(Model::remove_empty);
@@ -812,39 +900,37 @@ impl<'a> PDB {
         self.models.retain(|m| m.chain_count() > 0);
     }
 
+    /// Split every Chain in every Model at the physical breaks in its backbone, see
+    /// [`Model::split_chains_at_breaks`]. As the new ids are assigned in the same order in
+    /// every Model, the same segments get the same ids in all Models of an ensemble. It returns
+    /// the total number of new Chains.
+    pub fn split_chains_at_breaks(&mut self) -> usize {
+        self.models_mut().map(Model::split_chains_at_breaks).sum()
+    }
+
     /// This renumbers all numbered structs in the PDB.
     /// So it renumbers models, atoms, residues, chains and [`MtriX`]s.
     pub fn renumber(&mut self) {
-        let mut model_counter = 1;
-        for model in self.models_mut() {
+        for (model_counter, model) in (1..).zip(self.models_mut()) {
             model.set_serial_number(model_counter);
-            model_counter += 1;
 
-            let mut counter = 1;
-            for atom in model.atoms_mut() {
+            for (counter, atom) in (1..).zip(model.atoms_mut()) {
                 atom.set_serial_number(counter);
-                counter += 1;
             }
-            let mut counter_i = 1;
-            for residue in model.residues_mut() {
-                residue.set_serial_number(counter_i);
+            for (counter, residue) in (1..).zip(model.residues_mut()) {
+                residue.set_serial_number(counter);
                 residue.remove_insertion_code();
-                counter_i += 1;
 
                 if residue.conformer_count() > 1 {
-                    counter = 0;
-                    for conformer in residue.conformers_mut() {
+                    for (counter, conformer) in residue.conformers_mut().enumerate() {
                         conformer.set_alternative_location(&number_to_base26(counter));
-                        counter += 1;
                     }
                 } else if let Some(conformer) = residue.conformer_mut(0) {
                     conformer.remove_alternative_location();
                 }
             }
-            counter = 0;
-            for chain in model.chains_mut() {
-                chain.set_id(&number_to_base26(counter));
-                counter += 1;
+            for (counter, chain) in model.chains_mut().enumerate() {
+                chain.set_id(number_to_base26(counter));
             }
         }
     }
@@ -977,27 +1063,49 @@ impl<'a> PDB {
         ((min[0], min[1], min[2]), (max[0], max[1], max[2]))
     }
 
-    /// Get the bonds in this PDB file. Runtime is `O(bonds_count * 2 * atom_count)` because it
-    /// has to iterate over all atoms to prevent borrowing problems.
-    pub fn bonds(&self) -> impl DoubleEndedIterator<Item = (&Atom, &Atom, Bond)> + '_ {
-        self.bonds.iter().map(move |(a, b, bond)| {
-            (
-                self.atoms()
-                    .find(|atom| atom.counter() == *a)
-                    .expect("Could not find an atom in the bonds list"),
-                self.atoms()
-                    .find(|atom| atom.counter() == *b)
-                    .expect("Could not find an atom in the bonds list"),
-                *bond,
+    /// Get the bonds in this PDB file, with the order of the bond if known. Runtime is
+    /// `O(atom_count + bonds_count)` as it first builds a lookup table of all atoms. Bonds to
+    /// atoms that are no longer part of this PDB, for example because they were removed through
+    /// a mutable reference to a Model, are skipped.
+    pub fn bonds(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (&Atom, &Atom, Bond, Option<BondOrder>)> + '_ {
+        let lookup: HashMap<usize, &Atom> = self.atoms().map(|a| (a.counter(), a)).collect();
+        self.bonds.iter().filter_map(move |(a, b, bond, order)| {
+            Some((*lookup.get(a)?, *lookup.get(b)?, *bond, *order))
+        })
+    }
+
+    /// Get a graph view of the covalent bonds in this PDB, to query bonded neighbours,
+    /// fragments, paths, and rings. Hydrogen bonds, salt bridges, mismatched base pairs, and metal
+    /// coordination are not included, use [`PDB::bond_graph_with`] to select the bonds yourself.
+    pub fn bond_graph(&self) -> BondGraph<'_> {
+        self.bond_graph_with(|bond| {
+            !matches!(
+                bond,
+                Bond::Hydrogen
+                    | Bond::SaltBridge
+                    | Bond::MisMatchedBasePairs
+                    | Bond::MetalCoordination
             )
         })
     }
 
+    /// Get a graph view of the bonds in this PDB, only including the bonds of the types
+    /// selected by the given function, see [`BondGraph`].
+    pub fn bond_graph_with(&self, use_bond: impl Fn(Bond) -> bool) -> BondGraph<'_> {
+        BondGraph::new(
+            self.atoms(),
+            self.bonds().filter(|(_, _, bond, _)| use_bond(*bond)),
+        )
+    }
+
     /// Add a bond of the given type to the list of bonds in this PDB.
     /// The atoms are selected by serial number and alternative location.
     /// It uses `binary_find_atom` in the background so the PDB should be sorted.
     /// If one of the atoms could not be found it returns `None` otherwise it
-    /// will return `Some(())`.
+    /// will return `Some(())`. The bond order is left unknown, use
+    /// [`PDB::add_bond_with_order`] to specify it.
     pub fn add_bond(
         &mut self,
         atom1: (usize, Option<&str>),
@@ -1008,14 +1116,397 @@ impl<'a> PDB {
             self.binary_find_atom(atom1.0, atom1.1)?.atom().counter(),
             self.binary_find_atom(atom2.0, atom2.1)?.atom().counter(),
             bond,
+            None,
+        ));
+        Some(())
+    }
+
+    /// Add a bond of the given type and order to the list of bonds in this PDB.
+    /// The atoms are selected by serial number and alternative location, see [`PDB::add_bond`].
+    /// If one of the atoms could not be found it returns `None` otherwise it
+    /// will return `Some(())`.
+    pub fn add_bond_with_order(
+        &mut self,
+        atom1: (usize, Option<&str>),
+        atom2: (usize, Option<&str>),
+        bond: Bond,
+        order: BondOrder,
+    ) -> Option<()> {
+        self.bonds.push((
+            self.binary_find_atom(atom1.0, atom1.1)?.atom().counter(),
+            self.binary_find_atom(atom2.0, atom2.1)?.atom().counter(),
+            bond,
+            Some(order),
         ));
         Some(())
     }
 
     /// Add a bond of the given type to the list of bonds in this PDB.
     /// The raw counters of the atoms are given.
-    pub(crate) fn add_bond_counters(&mut self, atom1: usize, atom2: usize, bond: Bond) {
-        self.bonds.push((atom1, atom2, bond));
+    pub(crate) fn add_bond_counters(
+        &mut self,
+        atom1: usize,
+        atom2: usize,
+        bond: Bond,
+        order: Option<BondOrder>,
+    ) {
+        self.bonds.push((atom1, atom2, bond, order));
+    }
+
+    /// Add the covalent bonds within all residues based on the standard residue templates,
+    /// see [`ResidueTemplate`](crate::ResidueTemplate), and the peptide and phosphodiester
+    /// bonds between consecutive residues in a chain whose linking atoms (C-N or O3'-P) are at
+    /// most 2.0 Å apart. Residues without a template are skipped, as are bonds to atoms that
+    /// are not present. Bonds that are already known are not added again. It returns the
+    /// number of bonds added.
+    pub fn add_template_bonds(&mut self) -> usize {
+        let links = self.chains().flat_map(|chain| {
+            let residues: Vec<&Residue> = chain.residues().collect();
+            residues
+                .windows(2)
+                .flat_map(|pair| segment::link_bonds(pair[0], pair[1]))
+                .map(|(a, b)| (a, b, BondOrder::Single))
+                .collect::<Vec<_>>()
+        });
+        let new_bonds: Vec<(usize, usize, BondOrder)> = self
+            .residues()
+            .flat_map(Residue::template_bonds)
+            .chain(links)
+            .map(|(a, b, order)| (a.counter(), b.counter(), order))
+            .collect();
+        self.add_new_covalent_bonds(new_bonds)
+    }
+
+    /// Apply the chemical component definitions from the given library to all conformers with a
+    /// matching name. This adds the covalent bonds within these conformers (bonds that are
+    /// already known are not added again) and sets the formal charges of their atoms, see
+    /// [`Conformer::apply_component_charges`]. Leaving atoms can be found with
+    /// [`ChemicalComponent::leaving_atoms_in`]. It returns the number of bonds added.
+    pub fn apply_components(&mut self, library: &ComponentLibrary) -> usize {
+        let new_bonds: Vec<(usize, usize, BondOrder)> = self
+            .conformers()
+            .filter_map(|c| c.component(library).map(|component| component.bonds_in(c)))
+            .flatten()
+            .map(|(a, b, order)| (a.counter(), b.counter(), order))
+            .collect();
+        for conformer in self.conformers_mut() {
+            if let Some(component) = library.get(conformer.name()) {
+                conformer.apply_component_charges(component);
+            }
+        }
+        self.add_new_covalent_bonds(new_bonds)
+    }
+
+    /// Add covalent bonds of the given order between the given pairs of atom counters,
+    /// skipping pairs that are already bonded. It returns the number of bonds added.
+    pub(crate) fn add_new_covalent_bonds(
+        &mut self,
+        bonds: Vec<(usize, usize, BondOrder)>,
+    ) -> usize {
+        let mut known: HashSet<(usize, usize)> = self
+            .bonds
+            .iter()
+            .map(|(a, b, ..)| (*a.min(b), *a.max(b)))
+            .collect();
+        let mut added = 0;
+        for (a, b, order) in bonds {
+            if known.insert((a.min(b), a.max(b))) {
+                self.add_bond_counters(a, b, Bond::Covalent, Some(order));
+                added += 1;
+            }
+        }
+        added
+    }
+
+    /// Add the missing hydrogens to all standard amino acids, using the default
+    /// [`HydrogenOptions`]. See [`PDB::add_hydrogens_with`] for details.
+    pub fn add_hydrogens(&mut self) -> usize {
+        self.add_hydrogens_with(&HydrogenOptions::default())
+    }
+
+    /// Add the missing hydrogens to all standard amino acids. The hydrogens are placed with ideal
+    /// geometry based on the heavy atoms present and are named following the PDB v3 conventions.
+    /// Residues that are not bonded to a previous residue get the N terminal hydrogens (H1, H2,
+    /// H3), C terminal carboxylates are kept deprotonated. The protonation state of titratable
+    /// residues is determined by their name (eg HID, ASH, or LYN) and the given options.
+    /// The new atoms get the occupancy and B factor of the atom they are bonded to, and the
+    /// serial numbers following the highest serial number in this PDB, use [`PDB::renumber`] to
+    /// clean up the numbering afterwards. It returns the number of hydrogens added.
+    pub fn add_hydrogens_with(&mut self, options: &HydrogenOptions) -> usize {
+        let disulfides: HashSet<usize> = self
+            .bonds
+            .iter()
+            .filter(|(_, _, bond, _)| *bond == Bond::Disulfide)
+            .flat_map(|(a, b, ..)| [*a, *b])
+            .collect();
+        let mut additions = Vec::new();
+        for model in self.models() {
+            let sulfurs: Vec<(&Residue, &Atom)> = model
+                .residues()
+                .flat_map(|r| {
+                    r.conformers()
+                        .filter(|c| c.name() == "CYS" || c.name() == "CYX")
+                        .filter_map(|c| c.atoms().find(|a| a.name() == "SG"))
+                        .map(move |a| (r, a))
+                })
+                .collect();
+            for chain in model.chains() {
+                let mut previous: Option<&Residue> = None;
+                for residue in chain.residues() {
+                    for conformer in residue.conformers() {
+                        let previous_carbon = previous
+                            .and_then(|p| {
+                                p.conformers()
+                                    .find(|c| {
+                                        c.alternative_location() == conformer.alternative_location()
+                                    })
+                                    .or_else(|| p.conformers().next())
+                            })
+                            .and_then(|c| c.atoms().find(|a| a.name() == "C"))
+                            .filter(|carbon| {
+                                conformer
+                                    .atoms()
+                                    .find(|a| a.name() == "N")
+                                    .map_or(false, |n| n.distance(carbon) < 2.0)
+                            })
+                            .map(Atom::pos);
+                        let disulfide =
+                            conformer
+                                .atoms()
+                                .find(|a| a.name() == "SG")
+                                .map_or(false, |sg| {
+                                    disulfides.contains(&sg.counter())
+                                        || sulfurs.iter().any(|(r, other)| {
+                                            !std::ptr::eq(*r, residue) && sg.distance(other) < 2.5
+                                        })
+                                });
+                        let variant =
+                            hydrogens::protonation_variant(conformer.name(), options, disulfide);
+                        let new_atoms: Vec<(&str, (f64, f64, f64), &Atom)> =
+                            hydrogens::missing_hydrogens(conformer, variant, previous_carbon);
+                        let name = (options.rename_variants && variant != conformer.name())
+                            .then(|| variant.to_string());
+                        additions.push((
+                            name,
+                            new_atoms
+                                .into_iter()
+                                .map(|(name, position, parent)| {
+                                    (
+                                        name,
+                                        position,
+                                        parent.hetero(),
+                                        parent.occupancy(),
+                                        parent.b_factor(),
+                                    )
+                                })
+                                .collect::<Vec<_>>(),
+                        ));
+                    }
+                    previous = Some(residue);
+                }
+            }
+        }
+
+        let mut serial = self.atoms().map(Atom::serial_number).max().unwrap_or(0);
+        let mut added = 0;
+        for (conformer, (name, new_atoms)) in self.conformers_mut().zip(additions) {
+            if let Some(name) = name {
+                conformer.set_name(name);
+            }
+            for (name, (x, y, z), hetero, occupancy, b_factor) in new_atoms {
+                serial += 1;
+                if let Some(atom) =
+                    Atom::new(hetero, serial, name, x, y, z, occupancy, b_factor, "H", 0)
+                {
+                    conformer.add_atom(atom);
+                    added += 1;
+                }
+            }
+        }
+        added
+    }
+
+    /// Rebuild the heavy atoms missing from standard amino acids, see [`Conformer::missing_atoms`]
+    /// to find these atoms. The side chains are built with ideal geometry from the backbone
+    /// (residues without N, CA, or C are left untouched). Side chain dihedrals that can be
+    /// measured from the atoms present are kept, the others are taken from the most common
+    /// rotamer that does not clash with the surrounding atoms. A missing O is placed in the plane
+    /// of the peptide bond. The new atoms get the occupancy and B factor of the CA, and the serial
+    /// numbers following the highest serial number in this PDB, use [`PDB::renumber`] to clean up
+    /// the numbering afterwards. Hydrogens are not added, use [`PDB::add_hydrogens`] for that.
+    /// It returns the atoms added for each conformer.
+    #[doc_cfg(feature = "rstar")]
+    pub fn rebuild_missing_atoms(&mut self) -> Vec<RebuiltResidue> {
+        let mut additions = Vec::new();
+        let tree = self.create_hierarchy_rtree();
+        for model in self.models() {
+            for chain in model.chains() {
+                let residues: Vec<&Residue> = chain.residues().collect();
+                for (index, residue) in residues.iter().enumerate() {
+                    for conformer in residue.conformers() {
+                        let ca = if let Some(ca) = conformer.atoms().find(|a| a.name() == "CA") {
+                            ca
+                        } else {
+                            additions.push(None);
+                            continue;
+                        };
+                        let next_nitrogen = residues
+                            .get(index + 1)
+                            .and_then(|n| {
+                                n.conformers()
+                                    .find(|c| {
+                                        c.alternative_location() == conformer.alternative_location()
+                                    })
+                                    .or_else(|| n.conformers().next())
+                            })
+                            .and_then(|c| c.atoms().find(|a| a.name() == "N"))
+                            .filter(|nitrogen| {
+                                conformer
+                                    .atoms()
+                                    .find(|a| a.name() == "C")
+                                    .map_or(false, |c| c.distance(nitrogen) < 2.0)
+                            })
+                            .map(Atom::pos);
+                        // The heavy atoms of the other residues in this model within 15 Å
+                        let surroundings: Vec<(f64, f64, f64)> = tree
+                            .locate_within_distance(ca.pos(), 15.0 * 15.0)
+                            .filter(|h| {
+                                std::ptr::eq(h.model(), model)
+                                    && !std::ptr::eq(h.residue(), *residue)
+                                    && h.residue().name() != Some("HOH")
+                                    && h.atom().element() != Some(&Element::H)
+                            })
+                            .map(|h| h.atom().pos())
+                            .collect();
+                        let new_atoms =
+                            rebuild::missing_heavy_atoms(conformer, next_nitrogen, &surroundings);
+                        additions.push((!new_atoms.is_empty()).then(|| {
+                            (
+                                RebuiltResidue {
+                                    model: model.serial_number(),
+                                    chain: chain.id().to_string(),
+                                    residue: (
+                                        residue.serial_number(),
+                                        residue.insertion_code().map(ToString::to_string),
+                                    ),
+                                    conformer: (
+                                        conformer.name().to_string(),
+                                        conformer.alternative_location().map(ToString::to_string),
+                                    ),
+                                    atoms: new_atoms.iter().map(|(name, ..)| *name).collect(),
+                                },
+                                new_atoms,
+                                (ca.hetero(), ca.occupancy(), ca.b_factor()),
+                            )
+                        }));
+                    }
+                }
+            }
+        }
+
+        let mut serial = self.atoms().map(Atom::serial_number).max().unwrap_or(0);
+        let mut report = Vec::new();
+        for (conformer, addition) in self.conformers_mut().zip(additions) {
+            let (rebuilt, new_atoms, (hetero, occupancy, b_factor)) = match addition {
+                Some(addition) => addition,
+                None => continue,
+            };
+            for (name, element, (x, y, z)) in new_atoms {
+                serial += 1;
+                if let Some(atom) = Atom::new(
+                    hetero,
+                    serial,
+                    name,
+                    x,
+                    y,
+                    z,
+                    occupancy,
+                    b_factor,
+                    element.symbol(),
+                    0,
+                ) {
+                    conformer.add_atom(atom);
+                }
+            }
+            report.push(rebuilt);
+        }
+        report
+    }
+
+    /// Get the torsion angles of all amino acid Conformers in this PDB, in the order of the
+    /// models, chains, residues, and conformers. See [`Chain::torsions`] for details.
+    pub fn torsion_table(&self) -> Vec<ResidueTorsions> {
+        let mut table = Vec::new();
+        for model in self.models() {
+            for chain in model.chains() {
+                for (index, residue) in chain.residues().enumerate() {
+                    for (conformer, torsions) in residue.conformers().zip(chain.torsions(index)) {
+                        if !conformer.is_amino_acid() {
+                            continue;
+                        }
+                        table.push(ResidueTorsions {
+                            model: model.serial_number(),
+                            chain: chain.id().to_string(),
+                            residue: (
+                                residue.serial_number(),
+                                residue.insertion_code().map(ToString::to_string),
+                            ),
+                            conformer: (
+                                conformer.name().to_string(),
+                                conformer.alternative_location().map(ToString::to_string),
+                            ),
+                            torsions,
+                        });
+                    }
+                }
+            }
+        }
+        table
+    }
+
+    /// Classify the backbone torsions of all amino acid Conformers into the favoured, allowed, and
+    /// outlier regions of the Ramachandran plot for their residue category (general, Gly, Pro,
+    /// pre-Pro, and Ile/Val). Residues without a phi or psi (at the termini and chain breaks) are
+    /// skipped. Use [`RamachandranReport::errors`] to get the outliers as warnings.
+    ///
+    /// The regions are an approximation: polygons digitised by hand from the favoured (98%) and
+    /// allowed (99.95%) contours of Lovell et al. (2003), with a precision of about 5 degrees.
+    /// They are not the Top8000 density tables MolProbity uses, so residues within about 5
+    /// degrees of a contour can be classified differently than by MolProbity.
+    pub fn ramachandran(&self) -> RamachandranReport {
+        RamachandranReport {
+            residues: self
+                .models()
+                .flat_map(|model| {
+                    model
+                        .chains()
+                        .flat_map(move |chain| ramachandran::classify_chain(model, chain))
+                })
+                .collect(),
+        }
+    }
+
+    /// Find all serious steric clashes: pairs of atoms with an overlap of their van der Waals
+    /// radii of at least 0.4 Å. Atoms separated by at most three covalent bonds (so 1-2, 1-3, and
+    /// 1-4 pairs), atoms in different models, and atoms in different alternative locations are not
+    /// considered. Like MolProbity, 1-4 pairs are skipped because their distance is mostly
+    /// determined by the torsion around the central bond. The covalent bonds are determined from the distance between the
+    /// atoms, only allowing bonds within a residue, bonds linking residues in a polymer, and the
+    /// bonds of this PDB (see [`PDB::bonds`]). Nitrogen and oxygen pairs are allowed to overlap
+    /// 0.6 Å more, as these can form hydrogen bonds. Use [`ClashReport::clashscore`] to get the
+    /// clashscore and [`ClashReport::errors`] to get the clashes as warnings. See
+    /// [`PDB::clashes_with_exclusion`] to change the number of bonds separating skipped pairs.
+    #[doc_cfg(feature = "rstar")]
+    pub fn clashes(&self) -> ClashReport<'_> {
+        clash::find_clashes(self, clash::EXCLUDED_BONDS)
+    }
+
+    /// Find all serious steric clashes like [`PDB::clashes`], but skip atom pairs separated by at
+    /// most the given number of covalent bonds instead of three. Use 2 to also report 1-4 pairs,
+    /// for example the para positions of an aromatic ring.
+    #[doc_cfg(feature = "rstar")]
+    pub fn clashes_with_exclusion(&self, excluded_bonds: usize) -> ClashReport<'_> {
+        clash::find_clashes(self, excluded_bonds)
     }
 
     /// Returns a HashMap with the chains in contact within a given distance.
@@ -1073,7 +1564,7 @@ impl<'a> PDB {
     }
 }
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 impl fmt::Display for PDB {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -1081,6 +1572,37 @@ impl fmt::Display for PDB {
     }
 }
 
+impl Clone for PDB {
+    /// The cloned atoms get new counters (see [`Atom::clone`]), so the bonds are remapped to these
+    fn clone(&self) -> Self {
+        let mut pdb = PDB {
+            identifier: self.identifier.clone(),
+            remarks: self.remarks.clone(),
+            scale: self.scale.clone(),
+            origx: self.origx.clone(),
+            mtrix: self.mtrix.clone(),
+            unit_cell: self.unit_cell.clone(),
+            symmetry: self.symmetry.clone(),
+            models: self.models.clone(),
+            bonds: Vec::new(),
+            cif_items: self.cif_items.clone(),
+        };
+        let counters: HashMap<usize, usize> = self
+            .atoms()
+            .zip(pdb.atoms())
+            .map(|(original, clone)| (original.counter(), clone.counter()))
+            .collect();
+        pdb.bonds = self
+            .bonds
+            .iter()
+            .filter_map(|(a, b, bond, order)| {
+                Some((*counters.get(a)?, *counters.get(b)?, *bond, *order))
+            })
+            .collect();
+        pdb
+    }
+}
+
 impl Default for PDB {
     fn default() -> Self {
         Self::new()
@@ -1183,6 +1705,75 @@ mod tests {
         assert!(test_pdb.models().any(|m| m == pdb.model(5).unwrap()));
     }
 
+    #[test]
+    fn template_bonds() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("example-pdbs")
+            .join("1ubq.pdb");
+        let (mut pdb, _) = open_pdb(path.to_str().unwrap(), crate::StrictnessLevel::Loose).unwrap();
+        let ssbonds = pdb.bonds().count();
+        let added = pdb.add_template_bonds();
+        assert!(added > 500);
+        assert_eq!(pdb.bonds().count(), ssbonds + added);
+        assert_eq!(pdb.add_template_bonds(), 0);
+        let met = pdb.residue(0).unwrap();
+        assert_eq!(met.name(), Some("MET"));
+        assert_eq!(met.template_bonds().count(), 18);
+        assert!(pdb
+            .bonds()
+            .any(|(a, b, ..)| a.serial_number() == 5 && b.serial_number() == 6));
+        // PHE 4 has an aromatic ring and a carbonyl double bond
+        let orders: Vec<_> = pdb
+            .bonds()
+            .filter(|(a, ..)| (56..=75).contains(&a.serial_number()))
+            .map(|(.., order)| order)
+            .collect();
+        assert_eq!(
+            orders
+                .iter()
+                .filter(|o| **o == Some(BondOrder::Aromatic))
+                .count(),
+            6
+        );
+        assert!(orders.contains(&Some(BondOrder::Double)));
+    }
+
+    #[test]
+    fn bonds_after_removal_and_clone() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("example-pdbs")
+            .join("3nig.pdb");
+        let (mut pdb, _) = open_pdb(path.to_str().unwrap(), crate::StrictnessLevel::Loose).unwrap();
+        let serials = |pdb: &PDB| -> Vec<(usize, usize)> {
+            pdb.bonds()
+                .map(|(a, b, ..)| (a.serial_number(), b.serial_number()))
+                .collect()
+        };
+        let original = serials(&pdb);
+        assert!(original.iter().any(|(a, _)| *a > 2000));
+        assert_eq!(serials(&pdb.clone()), original);
+
+        pdb.remove_atoms_by(Atom::hetero);
+        assert!(pdb.bonds.len() < original.len());
+        assert_eq!(pdb.bonds().count(), pdb.bonds.len());
+        assert!(pdb.bonds().all(|(a, b, ..)| !a.hetero() && !b.hetero()));
+        let mut buffer = Vec::new();
+        crate::save_pdb_raw(
+            &pdb,
+            std::io::BufWriter::new(&mut buffer),
+            crate::StrictnessLevel::Loose,
+        );
+        crate::save_mmcif_raw(&pdb, std::io::BufWriter::new(&mut buffer));
+
+        // Bonds to atoms removed without going through the PDB are skipped
+        let bonds = pdb.bonds.len();
+        pdb.model_mut(0).unwrap().remove_chains_by(|_| true);
+        assert_eq!(pdb.bonds.len(), bonds);
+        assert_eq!(pdb.bonds().count(), 0);
+        pdb.remove_models_by(|_| false);
+        assert!(pdb.bonds.is_empty());
+    }
+
     #[test]
     fn sort_atoms() {
         let a = Atom::new(false, 0, "", 0.0, 0.0, 0.0, 0.0, 0.0, "", 0).unwrap();
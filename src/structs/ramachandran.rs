@@ -0,0 +1,761 @@
+use super::torsion;
+use crate::error::*;
+use crate::reference_tables;
+use crate::structs::*;
+use std::fmt;
+
+/// The residue categories used for Ramachandran classification, these have distinct
+/// distributions of backbone torsions
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RamachandranCategory {
+    /// All residues not in any of the other categories
+    General,
+    /// Glycine
+    Glycine,
+    /// Proline
+    Proline,
+    /// Residues preceding a proline, except glycine and proline
+    PreProline,
+    /// Isoleucine and valine (beta branched residues)
+    IleVal,
+}
+
+impl RamachandranCategory {
+    /// Get the category for a residue with the given name, followed by a residue with the
+    /// given name (if bonded to the next residue)
+    pub fn new(name: &str, next: Option<&str>) -> Self {
+        fn standard(name: &str) -> &str {
+            reference_tables::get_residue_template(name).map_or(name, |template| template.name)
+        }
+        match standard(name) {
+            "GLY" => Self::Glycine,
+            "PRO" => Self::Proline,
+            _ if next.map(standard) == Some("PRO") => Self::PreProline,
+            "ILE" | "VAL" => Self::IleVal,
+            _ => Self::General,
+        }
+    }
+
+    /// Get the regions, as polygons of `(phi, psi)` vertices in degrees, of the favoured and
+    /// allowed backbone conformations for this category
+    const fn regions(self) -> (&'static [Region], &'static [Region]) {
+        match self {
+            Self::General => (GENERAL_FAVOURED, GENERAL_ALLOWED),
+            Self::Glycine => (GLYCINE_FAVOURED, GLYCINE_ALLOWED),
+            Self::Proline => (PROLINE_FAVOURED, PROLINE_ALLOWED),
+            Self::PreProline => (PRE_PROLINE_FAVOURED, PRE_PROLINE_ALLOWED),
+            Self::IleVal => (ILE_VAL_FAVOURED, ILE_VAL_ALLOWED),
+        }
+    }
+
+    /// Classify the given backbone torsions (in degrees) for a residue of this category. The
+    /// regions approximate the contours of Lovell et al. (2003) to about 5 degrees, see
+    /// [`PDB::ramachandran`].
+    pub fn classify(self, phi: f64, psi: f64) -> RamachandranRegion {
+        // Keep points on the edge of the plot inside the regions touching that edge
+        let (phi, psi) = (phi.clamp(-179.9, 179.9), psi.clamp(-179.9, 179.9));
+        let inside = |regions: &[Region]| regions.iter().any(|r| contains(r, phi, psi));
+        let (favoured, allowed) = self.regions();
+        if inside(favoured) {
+            RamachandranRegion::Favoured
+        } else if inside(allowed) {
+            RamachandranRegion::Allowed
+        } else {
+            RamachandranRegion::Outlier
+        }
+    }
+}
+
+impl fmt::Display for RamachandranCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::General => "General",
+                Self::Glycine => "Gly",
+                Self::Proline => "Pro",
+                Self::PreProline => "pre-Pro",
+                Self::IleVal => "Ile/Val",
+            }
+        )
+    }
+}
+
+/// The Ramachandran region a residue is in
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RamachandranRegion {
+    /// In the most common conformations
+    Favoured,
+    /// In an uncommon but possible conformation
+    Allowed,
+    /// Outside of the allowed conformations, often indicating an error in the model
+    Outlier,
+}
+
+impl fmt::Display for RamachandranRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Favoured => "favoured",
+                Self::Allowed => "allowed",
+                Self::Outlier => "outlier",
+            }
+        )
+    }
+}
+
+/// The Ramachandran classification of a single conformer, see [`PDB::ramachandran`]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RamachandranResidue {
+    /// The conformer and its torsions
+    pub torsions: ResidueTorsions,
+    /// The category of the residue
+    pub category: RamachandranCategory,
+    /// The region the backbone torsions are in
+    pub region: RamachandranRegion,
+}
+
+/// The Ramachandran classification of all residues in a structure, see [`PDB::ramachandran`]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RamachandranReport {
+    /// The classified residues, only residues with both a phi and psi are included
+    pub residues: Vec<RamachandranResidue>,
+}
+
+impl RamachandranReport {
+    /// Get the number of residues in the given region
+    pub fn count(&self, region: RamachandranRegion) -> usize {
+        self.residues.iter().filter(|r| r.region == region).count()
+    }
+
+    /// Get the percentage of residues in the given region, 0 if there are no residues
+    #[allow(clippy::cast_precision_loss)]
+    pub fn percentage(&self, region: RamachandranRegion) -> f64 {
+        if self.residues.is_empty() {
+            0.0
+        } else {
+            self.count(region) as f64 / self.residues.len() as f64 * 100.0
+        }
+    }
+
+    /// Get the Ramachandran outliers
+    pub fn outliers(&self) -> impl DoubleEndedIterator<Item = &RamachandranResidue> + '_ {
+        self.residues
+            .iter()
+            .filter(|r| r.region == RamachandranRegion::Outlier)
+    }
+
+    /// Get a warning for every Ramachandran outlier, so these can be reported together with
+    /// the other errors and warnings
+    pub fn errors(&self) -> Vec<PDBError> {
+        self.outliers()
+            .map(|outlier| {
+                let t = &outlier.torsions;
+                PDBError::new(
+                    ErrorLevel::GeneralWarning,
+                    "Ramachandran outlier",
+                    format!(
+                        "Residue {}{} {}{} in chain {} of model {} ({}) is a Ramachandran outlier with phi {:.1} and psi {:.1}.",
+                        t.residue.0,
+                        t.residue.1.as_deref().unwrap_or(""),
+                        t.conformer.0,
+                        t.conformer.1.as_ref().map_or_else(String::new, |alt| format!(" (alternative location {alt})")),
+                        t.chain,
+                        t.model,
+                        outlier.category,
+                        t.torsions.phi.unwrap_or(f64::NAN),
+                        t.torsions.psi.unwrap_or(f64::NAN),
+                    ),
+                    Context::None,
+                )
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for RamachandranReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Ramachandran analysis of {} residues:",
+            self.residues.len()
+        )?;
+        for region in [
+            RamachandranRegion::Favoured,
+            RamachandranRegion::Allowed,
+            RamachandranRegion::Outlier,
+        ] {
+            write!(
+                f,
+                " {} {} ({:.1}%)",
+                region,
+                self.count(region),
+                self.percentage(region)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Classify all amino acid conformers in the given chain
+pub(crate) fn classify_chain(model: &Model, chain: &Chain) -> Vec<RamachandranResidue> {
+    let mut output = Vec::new();
+    for (index, residue) in chain.residues().enumerate() {
+        for (conformer, torsions) in residue.conformers().zip(chain.torsions(index)) {
+            let (phi, psi) = match (torsions.phi, torsions.psi) {
+                (Some(phi), Some(psi)) => (phi, psi),
+                _ => continue,
+            };
+            if !conformer.is_amino_acid() {
+                continue;
+            }
+            let next = torsion::next(chain, index, conformer).map(Conformer::name);
+            let category = RamachandranCategory::new(conformer.name(), next);
+            output.push(RamachandranResidue {
+                torsions: ResidueTorsions {
+                    model: model.serial_number(),
+                    chain: chain.id().to_string(),
+                    residue: (
+                        residue.serial_number(),
+                        residue.insertion_code().map(ToString::to_string),
+                    ),
+                    conformer: (
+                        conformer.name().to_string(),
+                        conformer.alternative_location().map(ToString::to_string),
+                    ),
+                    torsions,
+                },
+                category,
+                region: category.classify(phi, psi),
+            });
+        }
+    }
+    output
+}
+
+/// A region in the Ramachandran plot as a polygon of `(phi, psi)` vertices in degrees
+type Region = &'static [(f64, f64)];
+
+/// Test if the given point is inside the polygon, using the even-odd rule
+fn contains(region: Region, phi: f64, psi: f64) -> bool {
+    let mut inside = false;
+    let mut previous = region[region.len() - 1];
+    for &(x, y) in region {
+        if (y > psi) != (previous.1 > psi)
+            && phi < (previous.0 - x) * (psi - y) / (previous.1 - y) + x
+        {
+            inside = !inside;
+        }
+        previous = (x, y);
+    }
+    inside
+}
+
+// The regions trace the favoured (98%) and allowed (99.95%) contours of Lovell et al. (2003)
+// "Structure validation by Calpha geometry: phi, psi and Cbeta deviation", digitised to
+// polygons with a precision of about 5 degrees. Regions crossing psi = 180 are split in two.
+
+/// Favoured regions for general residues: beta sheet and polyproline II connected through
+/// the bridge region to the right handed helix, and the left handed helix
+const GENERAL_FAVOURED: &[Region] = &[
+    &[
+        (-180.0, 180.0),
+        (-42.0, 180.0),
+        (-42.0, 165.0),
+        (-50.0, 125.0),
+        (-60.0, 105.0),
+        (-65.0, 85.0),
+        (-70.0, 55.0),
+        (-55.0, 15.0),
+        (-38.0, -25.0),
+        (-35.0, -45.0),
+        (-42.0, -62.0),
+        (-55.0, -70.0),
+        (-75.0, -70.0),
+        (-100.0, -62.0),
+        (-120.0, -50.0),
+        (-135.0, -25.0),
+        (-130.0, 20.0),
+        (-115.0, 50.0),
+        (-130.0, 80.0),
+        (-160.0, 90.0),
+        (-180.0, 95.0),
+    ],
+    &[
+        (-180.0, -180.0),
+        (-45.0, -180.0),
+        (-60.0, -170.0),
+        (-180.0, -165.0),
+    ],
+    &[
+        (48.0, 10.0),
+        (68.0, 12.0),
+        (78.0, 40.0),
+        (72.0, 75.0),
+        (57.0, 80.0),
+        (45.0, 50.0),
+    ],
+];
+
+/// Allowed regions for general residues
+const GENERAL_ALLOWED: &[Region] = &[
+    &[
+        (-180.0, 180.0),
+        (-35.0, 180.0),
+        (-35.0, 150.0),
+        (-45.0, 110.0),
+        (-50.0, 80.0),
+        (-45.0, 45.0),
+        (-30.0, -5.0),
+        (-25.0, -40.0),
+        (-30.0, -60.0),
+        (-45.0, -75.0),
+        (-60.0, -85.0),
+        (-90.0, -82.0),
+        (-120.0, -72.0),
+        (-150.0, -55.0),
+        (-162.0, -35.0),
+        (-162.0, 0.0),
+        (-155.0, 40.0),
+        (-170.0, 70.0),
+        (-180.0, 75.0),
+    ],
+    &[
+        (-180.0, -180.0),
+        (-35.0, -180.0),
+        (-45.0, -160.0),
+        (-80.0, -150.0),
+        (-180.0, -140.0),
+    ],
+    &[
+        (40.0, 0.0),
+        (45.0, -20.0),
+        (65.0, -35.0),
+        (90.0, -15.0),
+        (100.0, 30.0),
+        (92.0, 80.0),
+        (75.0, 100.0),
+        (52.0, 95.0),
+        (40.0, 60.0),
+    ],
+    &[(55.0, 160.0), (80.0, 160.0), (80.0, 180.0), (55.0, 180.0)],
+    &[
+        (55.0, -180.0),
+        (80.0, -180.0),
+        (80.0, -165.0),
+        (55.0, -165.0),
+    ],
+];
+
+/// Favoured regions for glycine, these are symmetric through the origin
+const GLYCINE_FAVOURED: &[Region] = &[
+    &[
+        (-95.0, -60.0),
+        (-60.0, -50.0),
+        (-50.0, -30.0),
+        (-50.0, 10.0),
+        (-65.0, 25.0),
+        (-90.0, 30.0),
+        (-110.0, 10.0),
+        (-110.0, -30.0),
+    ],
+    &[
+        (-180.0, 180.0),
+        (-60.0, 180.0),
+        (-60.0, 150.0),
+        (-80.0, 130.0),
+        (-120.0, 140.0),
+        (-150.0, 130.0),
+        (-180.0, 120.0),
+    ],
+    &[
+        (-180.0, -180.0),
+        (-60.0, -180.0),
+        (-70.0, -150.0),
+        (-120.0, -145.0),
+        (-180.0, -150.0),
+    ],
+    &[
+        (95.0, 60.0),
+        (60.0, 50.0),
+        (50.0, 30.0),
+        (50.0, -10.0),
+        (65.0, -25.0),
+        (90.0, -30.0),
+        (110.0, -10.0),
+        (110.0, 30.0),
+    ],
+    &[
+        (180.0, -180.0),
+        (60.0, -180.0),
+        (60.0, -150.0),
+        (80.0, -130.0),
+        (120.0, -140.0),
+        (150.0, -130.0),
+        (180.0, -120.0),
+    ],
+    &[
+        (180.0, 180.0),
+        (60.0, 180.0),
+        (70.0, 150.0),
+        (120.0, 145.0),
+        (180.0, 150.0),
+    ],
+];
+
+/// Allowed regions for glycine, these are symmetric through the origin
+const GLYCINE_ALLOWED: &[Region] = &[
+    &[
+        (-110.0, -90.0),
+        (-70.0, -80.0),
+        (-45.0, -55.0),
+        (-40.0, -20.0),
+        (-45.0, 30.0),
+        (-70.0, 60.0),
+        (-100.0, 60.0),
+        (-140.0, 40.0),
+        (-150.0, -10.0),
+        (-140.0, -60.0),
+    ],
+    &[
+        (-180.0, 180.0),
+        (-45.0, 180.0),
+        (-45.0, 150.0),
+        (-60.0, 110.0),
+        (-100.0, 95.0),
+        (-150.0, 100.0),
+        (-180.0, 110.0),
+    ],
+    &[
+        (-180.0, -180.0),
+        (-45.0, -180.0),
+        (-50.0, -150.0),
+        (-80.0, -115.0),
+        (-140.0, -120.0),
+        (-180.0, -130.0),
+    ],
+    &[
+        (110.0, 90.0),
+        (70.0, 80.0),
+        (45.0, 55.0),
+        (40.0, 20.0),
+        (45.0, -30.0),
+        (70.0, -60.0),
+        (100.0, -60.0),
+        (140.0, -40.0),
+        (150.0, 10.0),
+        (140.0, 60.0),
+    ],
+    &[
+        (180.0, -180.0),
+        (45.0, -180.0),
+        (45.0, -150.0),
+        (60.0, -110.0),
+        (100.0, -95.0),
+        (150.0, -100.0),
+        (180.0, -110.0),
+    ],
+    &[
+        (180.0, 180.0),
+        (45.0, 180.0),
+        (50.0, 150.0),
+        (80.0, 115.0),
+        (140.0, 120.0),
+        (180.0, 130.0),
+    ],
+];
+
+/// Favoured regions for proline: right handed helix and polyproline II
+const PROLINE_FAVOURED: &[Region] = &[
+    &[
+        (-90.0, -55.0),
+        (-65.0, -55.0),
+        (-50.0, -45.0),
+        (-45.0, -20.0),
+        (-55.0, 0.0),
+        (-75.0, 5.0),
+        (-95.0, -10.0),
+    ],
+    &[
+        (-95.0, 180.0),
+        (-50.0, 180.0),
+        (-50.0, 150.0),
+        (-60.0, 120.0),
+        (-75.0, 110.0),
+        (-95.0, 125.0),
+    ],
+    &[
+        (-95.0, -180.0),
+        (-50.0, -180.0),
+        (-55.0, -165.0),
+        (-95.0, -165.0),
+    ],
+];
+
+/// Allowed regions for proline, restricted to phi values around -65 by the ring
+const PROLINE_ALLOWED: &[Region] = &[
+    &[
+        (-100.0, -65.0),
+        (-65.0, -65.0),
+        (-45.0, -55.0),
+        (-35.0, -30.0),
+        (-40.0, 10.0),
+        (-50.0, 60.0),
+        (-45.0, 110.0),
+        (-40.0, 150.0),
+        (-40.0, 180.0),
+        (-105.0, 180.0),
+        (-105.0, 120.0),
+        (-100.0, 60.0),
+        (-110.0, 0.0),
+        (-105.0, -40.0),
+    ],
+    &[
+        (-105.0, -180.0),
+        (-40.0, -180.0),
+        (-45.0, -150.0),
+        (-105.0, -150.0),
+    ],
+];
+
+/// Favoured regions for residues preceding a proline
+const PRE_PROLINE_FAVOURED: &[Region] = &[
+    &[
+        (-180.0, 180.0),
+        (-45.0, 180.0),
+        (-45.0, 160.0),
+        (-55.0, 120.0),
+        (-75.0, 105.0),
+        (-120.0, 105.0),
+        (-150.0, 110.0),
+        (-180.0, 120.0),
+    ],
+    &[
+        (-180.0, -180.0),
+        (-50.0, -180.0),
+        (-60.0, -170.0),
+        (-180.0, -165.0),
+    ],
+    &[
+        (-95.0, -60.0),
+        (-70.0, -60.0),
+        (-50.0, -45.0),
+        (-45.0, -25.0),
+        (-60.0, -15.0),
+        (-85.0, -20.0),
+        (-100.0, -35.0),
+    ],
+];
+
+/// Allowed regions for residues preceding a proline
+const PRE_PROLINE_ALLOWED: &[Region] = &[
+    &[
+        (-180.0, 180.0),
+        (-40.0, 180.0),
+        (-40.0, 150.0),
+        (-50.0, 110.0),
+        (-60.0, 70.0),
+        (-75.0, 60.0),
+        (-110.0, 60.0),
+        (-150.0, 75.0),
+        (-180.0, 80.0),
+    ],
+    &[
+        (-180.0, -180.0),
+        (-40.0, -180.0),
+        (-50.0, -160.0),
+        (-100.0, -155.0),
+        (-180.0, -150.0),
+    ],
+    &[
+        (-110.0, -70.0),
+        (-70.0, -70.0),
+        (-45.0, -55.0),
+        (-38.0, -30.0),
+        (-45.0, 5.0),
+        (-70.0, 15.0),
+        (-110.0, 10.0),
+        (-130.0, -25.0),
+        (-130.0, -50.0),
+    ],
+    &[
+        (50.0, 20.0),
+        (70.0, 20.0),
+        (80.0, 45.0),
+        (70.0, 75.0),
+        (55.0, 70.0),
+        (48.0, 45.0),
+    ],
+];
+
+/// Favoured regions for isoleucine and valine
+const ILE_VAL_FAVOURED: &[Region] = &[
+    &[
+        (-170.0, 180.0),
+        (-60.0, 180.0),
+        (-60.0, 150.0),
+        (-75.0, 110.0),
+        (-110.0, 100.0),
+        (-150.0, 105.0),
+        (-170.0, 120.0),
+    ],
+    &[
+        (-180.0, -180.0),
+        (-95.0, -180.0),
+        (-110.0, -170.0),
+        (-180.0, -168.0),
+    ],
+    &[
+        (-120.0, -60.0),
+        (-90.0, -62.0),
+        (-65.0, -55.0),
+        (-50.0, -40.0),
+        (-55.0, -20.0),
+        (-80.0, -10.0),
+        (-110.0, -15.0),
+        (-125.0, -35.0),
+    ],
+];
+
+/// Allowed regions for isoleucine and valine
+const ILE_VAL_ALLOWED: &[Region] = &[
+    &[
+        (-180.0, 180.0),
+        (-45.0, 180.0),
+        (-45.0, 150.0),
+        (-55.0, 115.0),
+        (-70.0, 90.0),
+        (-100.0, 75.0),
+        (-140.0, 80.0),
+        (-180.0, 95.0),
+    ],
+    &[
+        (-180.0, -180.0),
+        (-55.0, -180.0),
+        (-70.0, -165.0),
+        (-120.0, -160.0),
+        (-180.0, -150.0),
+    ],
+    &[
+        (-140.0, -70.0),
+        (-95.0, -75.0),
+        (-65.0, -70.0),
+        (-42.0, -55.0),
+        (-38.0, -30.0),
+        (-50.0, 0.0),
+        (-80.0, 15.0),
+        (-110.0, 20.0),
+        (-130.0, 5.0),
+        (-150.0, -30.0),
+    ],
+    &[
+        (50.0, 20.0),
+        (70.0, 20.0),
+        (78.0, 45.0),
+        (68.0, 70.0),
+        (52.0, 65.0),
+        (48.0, 40.0),
+    ],
+];
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{open_pdb, StrictnessLevel};
+
+    #[test]
+    fn categories() {
+        use RamachandranCategory::*;
+        assert_eq!(RamachandranCategory::new("GLY", Some("PRO")), Glycine);
+        assert_eq!(RamachandranCategory::new("PRO", Some("PRO")), Proline);
+        assert_eq!(RamachandranCategory::new("ILE", Some("PRO")), PreProline);
+        assert_eq!(RamachandranCategory::new("VAL", None), IleVal);
+        assert_eq!(RamachandranCategory::new("HIE", Some("ALA")), General);
+        assert_eq!(RamachandranCategory::new("XYZ", None), General);
+    }
+
+    #[test]
+    fn classify() {
+        use RamachandranRegion::*;
+        let general = RamachandranCategory::General;
+        assert_eq!(general.classify(-60.0, -45.0), Favoured);
+        assert_eq!(general.classify(-120.0, 130.0), Favoured);
+        assert_eq!(general.classify(-90.0, 0.0), Favoured);
+        assert_eq!(general.classify(-180.0, 180.0), Favoured);
+        assert_eq!(general.classify(60.0, 40.0), Favoured);
+        assert_eq!(general.classify(-150.0, -20.0), Allowed);
+        assert_eq!(general.classify(85.0, 0.0), Allowed);
+        assert_eq!(general.classify(-60.0, -120.0), Outlier);
+        assert_eq!(general.classify(-170.0, 0.0), Outlier);
+        assert_eq!(general.classify(60.0, -120.0), Outlier);
+        assert_eq!(general.classify(0.0, 0.0), Outlier);
+        // Glycine is symmetric
+        let glycine = RamachandranCategory::Glycine;
+        assert_eq!(glycine.classify(80.0, 0.0), Favoured);
+        assert_eq!(glycine.classify(-80.0, 0.0), Favoured);
+        assert_eq!(glycine.classify(80.0, -170.0), Favoured);
+        assert_eq!(glycine.classify(-60.0, -120.0), Outlier);
+        assert_eq!(glycine.classify(60.0, 120.0), Outlier);
+        assert_eq!(glycine.classify(0.0, 90.0), Outlier);
+        // Proline has a restricted phi
+        let proline = RamachandranCategory::Proline;
+        assert_eq!(proline.classify(-65.0, 145.0), Favoured);
+        assert_eq!(proline.classify(-140.0, 145.0), Outlier);
+        assert_eq!(proline.classify(-60.0, -120.0), Outlier);
+        // Beta branched and pre-proline residues lack most of the bridge and left handed regions
+        for category in [
+            RamachandranCategory::IleVal,
+            RamachandranCategory::PreProline,
+        ] {
+            assert_eq!(category.classify(-120.0, 130.0), Favoured);
+            assert_eq!(category.classify(-70.0, -40.0), Favoured);
+            assert_eq!(category.classify(-60.0, -120.0), Outlier);
+            assert_eq!(category.classify(-170.0, 0.0), Outlier);
+            assert_eq!(category.classify(-100.0, 40.0), Outlier);
+            assert_eq!(category.classify(60.0, -60.0), Outlier);
+        }
+    }
+
+    #[test]
+    fn ubiquitin() {
+        let mut pdb = open_pdb("example-pdbs/1ubq.pdb", StrictnessLevel::Loose)
+            .unwrap()
+            .0;
+        let report = pdb.ramachandran();
+        assert_eq!(report.residues.len(), 74);
+        assert_eq!(report.count(RamachandranRegion::Favoured), 74);
+        assert_eq!(report.outliers().count(), 0);
+        assert!(report.errors().is_empty());
+        assert_eq!(
+            report.to_string(),
+            "Ramachandran analysis of 74 residues: favoured 74 (100.0%) allowed 0 (0.0%) outlier 0 (0.0%)"
+        );
+        // ILE 36 precedes PRO 37
+        let ile = &report.residues[34];
+        assert_eq!(ile.torsions.residue.0, 36);
+        assert_eq!(ile.category, RamachandranCategory::PreProline);
+
+        // Force LEU 8 into a disallowed conformation
+        let chain = pdb.chain_mut(0).unwrap();
+        chain.set_torsion(7, Torsion::Phi, 60.0).unwrap();
+        chain.set_torsion(7, Torsion::Psi, -120.0).unwrap();
+        let report = pdb.ramachandran();
+        let outliers = report.outliers().collect::<Vec<_>>();
+        assert_eq!(outliers.len(), 1);
+        assert_eq!(outliers[0].torsions.residue.0, 8);
+        assert_eq!(outliers[0].category, RamachandranCategory::General);
+        let errors = report.errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].level(), ErrorLevel::GeneralWarning);
+        assert_eq!(errors[0].short_description(), "Ramachandran outlier");
+        assert!((report.percentage(RamachandranRegion::Outlier) - 100.0 / 74.0).abs() < 1e-9);
+    }
+}
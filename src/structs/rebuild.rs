@@ -0,0 +1,415 @@
+#[cfg(feature = "rstar")]
+use super::geometry::*;
+use crate::reference_tables;
+#[cfg(feature = "rstar")]
+use crate::structs::*;
+
+/// The position of an atom defined relative to three atoms placed before it, as in a Z-matrix
+#[cfg_attr(not(feature = "rstar"), allow(dead_code))]
+struct InternalCoordinate {
+    /// The name of the atom
+    atom: &'static str,
+    /// The atoms `a`, `b` and `c` used to place this atom `d`
+    references: [&'static str; 3],
+    /// The bond length `c-d` in Å
+    bond: f64,
+    /// The angle `b-c-d` in degrees
+    angle: f64,
+    /// The dihedral `a-b-c-d` in degrees, relative to the side chain dihedral if there is one
+    dihedral: f64,
+    /// The index of the side chain dihedral (chi) this dihedral is relative to, the first atom
+    /// with a chi index defines that chi
+    chi: Option<usize>,
+}
+
+/// Create an internal coordinate
+const fn ic(
+    atom: &'static str,
+    references: [&'static str; 3],
+    bond: f64,
+    angle: f64,
+    dihedral: f64,
+    chi: Option<usize>,
+) -> InternalCoordinate {
+    InternalCoordinate {
+        atom,
+        references,
+        bond,
+        angle,
+        dihedral,
+        chi,
+    }
+}
+
+/// The internal coordinate of the CB of all amino acids except glycine
+#[cfg(feature = "rstar")]
+const BETA_CARBON: InternalCoordinate = ic("CB", ["C", "N", "CA"], 1.53, 110.5, -122.0, None);
+
+/// The internal coordinates of the side chain atoms beyond CB of the standard amino acids.
+/// The bond lengths, angles, and dihedral offsets are the median values in a set of high
+/// resolution crystal structures.
+#[rustfmt::skip]
+const SIDE_CHAINS: &[(&str, &[InternalCoordinate])] = &[
+    ("ARG", &[
+        ic("CG", ["N", "CA", "CB"], 1.52, 114.4, 0.0, Some(0)),
+        ic("CD", ["CA", "CB", "CG"], 1.52, 111.6, 0.0, Some(1)),
+        ic("NE", ["CB", "CG", "CD"], 1.46, 112.2, 0.0, Some(2)),
+        ic("CZ", ["CG", "CD", "NE"], 1.33, 124.7, 0.0, Some(3)),
+        ic("NH1", ["CD", "NE", "CZ"], 1.33, 120.6, 0.0, None),
+        ic("NH2", ["CD", "NE", "CZ"], 1.33, 119.9, 180.0, None),
+    ]),
+    ("ASN", &[
+        ic("CG", ["N", "CA", "CB"], 1.52, 112.6, 0.0, Some(0)),
+        ic("OD1", ["CA", "CB", "CG"], 1.23, 120.8, 0.0, Some(1)),
+        ic("ND2", ["CA", "CB", "CG"], 1.33, 116.5, 180.0, Some(1)),
+    ]),
+    ("ASP", &[
+        ic("CG", ["N", "CA", "CB"], 1.52, 112.7, 0.0, Some(0)),
+        ic("OD1", ["CA", "CB", "CG"], 1.25, 118.9, 0.0, Some(1)),
+        ic("OD2", ["CA", "CB", "CG"], 1.25, 118.3, 180.0, Some(1)),
+    ]),
+    ("CYS", &[
+        ic("SG", ["N", "CA", "CB"], 1.81, 114.2, 0.0, Some(0)),
+    ]),
+    ("GLN", &[
+        ic("CG", ["N", "CA", "CB"], 1.52, 114.1, 0.0, Some(0)),
+        ic("CD", ["CA", "CB", "CG"], 1.52, 112.7, 0.0, Some(1)),
+        ic("OE1", ["CB", "CG", "CD"], 1.23, 120.9, 0.0, Some(2)),
+        ic("NE2", ["CB", "CG", "CD"], 1.33, 116.5, 180.0, Some(2)),
+    ]),
+    ("GLU", &[
+        ic("CG", ["N", "CA", "CB"], 1.52, 114.4, 0.0, Some(0)),
+        ic("CD", ["CA", "CB", "CG"], 1.52, 113.1, 0.0, Some(1)),
+        ic("OE1", ["CB", "CG", "CD"], 1.25, 118.8, 0.0, Some(2)),
+        ic("OE2", ["CB", "CG", "CD"], 1.25, 118.3, 180.0, Some(2)),
+    ]),
+    ("HIS", &[
+        ic("CG", ["N", "CA", "CB"], 1.50, 113.7, 0.0, Some(0)),
+        ic("ND1", ["CA", "CB", "CG"], 1.38, 122.6, 0.0, Some(1)),
+        ic("CD2", ["CA", "CB", "CG"], 1.35, 131.2, 180.0, Some(1)),
+        ic("CE1", ["CB", "CG", "ND1"], 1.32, 109.4, 180.0, None),
+        ic("NE2", ["CB", "CG", "CD2"], 1.38, 107.1, 180.0, None),
+    ]),
+    ("ILE", &[
+        ic("CG1", ["N", "CA", "CB"], 1.53, 110.4, 0.0, Some(0)),
+        ic("CG2", ["N", "CA", "CB"], 1.52, 110.5, -122.2, Some(0)),
+        ic("CD1", ["CA", "CB", "CG1"], 1.51, 113.8, 0.0, Some(1)),
+    ]),
+    ("LEU", &[
+        ic("CG", ["N", "CA", "CB"], 1.53, 116.6, 0.0, Some(0)),
+        ic("CD1", ["CA", "CB", "CG"], 1.52, 110.4, 0.0, Some(1)),
+        ic("CD2", ["CA", "CB", "CG"], 1.52, 110.2, 122.7, Some(1)),
+    ]),
+    ("LYS", &[
+        ic("CG", ["N", "CA", "CB"], 1.53, 114.5, 0.0, Some(0)),
+        ic("CD", ["CA", "CB", "CG"], 1.53, 111.9, 0.0, Some(1)),
+        ic("CE", ["CB", "CG", "CD"], 1.53, 112.0, 0.0, Some(2)),
+        ic("NZ", ["CG", "CD", "CE"], 1.49, 112.3, 0.0, Some(3)),
+    ]),
+    ("MET", &[
+        ic("CG", ["N", "CA", "CB"], 1.52, 114.1, 0.0, Some(0)),
+        ic("SD", ["CA", "CB", "CG"], 1.80, 112.2, 0.0, Some(1)),
+        ic("CE", ["CB", "CG", "SD"], 1.80, 101.1, 0.0, Some(2)),
+    ]),
+    ("PHE", &[
+        ic("CG", ["N", "CA", "CB"], 1.50, 113.8, 0.0, Some(0)),
+        ic("CD1", ["CA", "CB", "CG"], 1.39, 120.7, 0.0, Some(1)),
+        ic("CD2", ["CA", "CB", "CG"], 1.39, 120.6, 180.0, Some(1)),
+        ic("CE1", ["CB", "CG", "CD1"], 1.39, 120.9, 180.0, None),
+        ic("CE2", ["CB", "CG", "CD2"], 1.39, 120.8, 180.0, None),
+        ic("CZ", ["CG", "CD1", "CE1"], 1.39, 119.9, 0.0, None),
+    ]),
+    ("PRO", &[
+        ic("CG", ["N", "CA", "CB"], 1.50, 103.5, 0.0, Some(0)),
+        ic("CD", ["CA", "CB", "CG"], 1.50, 102.6, 0.0, Some(1)),
+    ]),
+    ("SER", &[
+        ic("OG", ["N", "CA", "CB"], 1.42, 111.1, 0.0, Some(0)),
+    ]),
+    ("THR", &[
+        ic("OG1", ["N", "CA", "CB"], 1.43, 109.6, 0.0, Some(0)),
+        ic("CG2", ["N", "CA", "CB"], 1.52, 110.7, -120.4, Some(0)),
+    ]),
+    ("TRP", &[
+        ic("CG", ["N", "CA", "CB"], 1.50, 113.8, 0.0, Some(0)),
+        ic("CD1", ["CA", "CB", "CG"], 1.37, 126.9, 0.0, Some(1)),
+        ic("CD2", ["CA", "CB", "CG"], 1.43, 126.8, 180.0, Some(1)),
+        ic("NE1", ["CB", "CG", "CD1"], 1.37, 110.2, 180.0, None),
+        ic("CE2", ["CB", "CG", "CD2"], 1.42, 107.2, 180.0, None),
+        ic("CE3", ["CB", "CG", "CD2"], 1.40, 133.9, 0.0, None),
+        ic("CZ2", ["CG", "CD2", "CE2"], 1.40, 122.5, 180.0, None),
+        ic("CZ3", ["CG", "CD2", "CE3"], 1.39, 118.7, 180.0, None),
+        ic("CH2", ["CD2", "CE2", "CZ2"], 1.37, 117.4, 0.0, None),
+    ]),
+    ("TYR", &[
+        ic("CG", ["N", "CA", "CB"], 1.51, 113.7, 0.0, Some(0)),
+        ic("CD1", ["CA", "CB", "CG"], 1.40, 120.9, 0.0, Some(1)),
+        ic("CD2", ["CA", "CB", "CG"], 1.40, 120.9, 180.0, Some(1)),
+        ic("CE1", ["CB", "CG", "CD1"], 1.39, 121.2, 180.0, None),
+        ic("CE2", ["CB", "CG", "CD2"], 1.39, 121.1, 180.0, None),
+        ic("CZ", ["CG", "CD1", "CE1"], 1.39, 119.7, 0.0, None),
+        ic("OH", ["CD1", "CE1", "CZ"], 1.38, 120.0, 180.0, None),
+    ]),
+    ("VAL", &[
+        ic("CG1", ["N", "CA", "CB"], 1.52, 110.5, 0.0, Some(0)),
+        ic("CG2", ["N", "CA", "CB"], 1.52, 110.4, 122.7, Some(0)),
+    ]),
+];
+
+/// Get the four atoms defining the side chain dihedral with the given index (0 for chi1) of the
+/// standard amino acid with the given name. Returns `None` if this amino acid does not have this
+/// side chain dihedral.
+pub(crate) fn chi_atoms(name: &str, chi: usize) -> Option<[&'static str; 4]> {
+    let name = reference_tables::get_residue_template(name)?.name;
+    let (_, side_chain) = SIDE_CHAINS.iter().find(|(n, _)| *n == name)?;
+    side_chain.iter().find(|ic| ic.chi == Some(chi)).map(|ic| {
+        [
+            ic.references[0],
+            ic.references[1],
+            ic.references[2],
+            ic.atom,
+        ]
+    })
+}
+
+/// The most common rotamers of the standard amino acids, as the values of the side chain
+/// dihedrals (chi1, chi2, ...) in degrees, ordered from most to least common. Based on the
+/// modal values of the penultimate rotamer library (Lovell et al. 2000).
+#[cfg(feature = "rstar")]
+#[rustfmt::skip]
+const ROTAMERS: &[(&str, &[&[f64]])] = &[
+    ("ARG", &[&[-67.0, 180.0, 180.0, 180.0], &[-67.0, 180.0, 180.0, 85.0], &[-67.0, 180.0, 65.0, 85.0], &[-177.0, 180.0, 180.0, 180.0], &[-62.0, -68.0, 180.0, -85.0]]),
+    ("ASN", &[&[-65.0, -20.0], &[-168.0, 25.0], &[62.0, -10.0], &[-65.0, -75.0], &[-65.0, 120.0]]),
+    ("ASP", &[&[-70.0, -15.0], &[-177.0, 0.0], &[-177.0, 65.0], &[62.0, -10.0]]),
+    ("CYS", &[&[-65.0], &[-177.0], &[62.0]]),
+    ("GLN", &[&[-67.0, 180.0, -25.0], &[-177.0, 180.0, 20.0], &[-65.0, -65.0, -40.0], &[-177.0, 65.0, -100.0]]),
+    ("GLU", &[&[-67.0, 180.0, -10.0], &[-177.0, 180.0, 0.0], &[-65.0, -65.0, -40.0], &[-177.0, 65.0, 10.0], &[-65.0, 85.0, 0.0]]),
+    ("HIS", &[&[-65.0, -70.0], &[-177.0, -80.0], &[-177.0, 60.0], &[-65.0, 165.0], &[62.0, -75.0]]),
+    ("ILE", &[&[-65.0, 170.0], &[-57.0, -60.0], &[62.0, 170.0], &[-177.0, 170.0], &[-177.0, 66.0]]),
+    ("LEU", &[&[-65.0, 175.0], &[-177.0, 65.0], &[-172.0, 145.0], &[-85.0, 65.0]]),
+    ("LYS", &[&[-68.0, 180.0, 180.0, 180.0], &[-177.0, 180.0, 180.0, 180.0], &[-68.0, 180.0, 180.0, -65.0], &[-62.0, -68.0, 180.0, 180.0], &[-68.0, 180.0, 180.0, 65.0]]),
+    ("MET", &[&[-68.0, 180.0, 75.0], &[-65.0, -65.0, -70.0], &[-68.0, 180.0, -75.0], &[-177.0, 180.0, -75.0], &[-177.0, 65.0, 75.0]]),
+    ("PHE", &[&[-65.0, -85.0], &[-177.0, 80.0], &[62.0, 90.0], &[-65.0, -30.0]]),
+    ("PRO", &[&[30.0, -35.0], &[-30.0, 40.0]]),
+    ("SER", &[&[64.0], &[-65.0], &[178.0]]),
+    ("THR", &[&[59.0], &[-60.0], &[-172.0]]),
+    ("TRP", &[&[-65.0, 95.0], &[-65.0, -10.0], &[-177.0, -105.0], &[62.0, -90.0], &[-177.0, 90.0]]),
+    ("TYR", &[&[-65.0, -85.0], &[-177.0, 80.0], &[62.0, 90.0], &[-65.0, -30.0]]),
+    ("VAL", &[&[175.0], &[-64.0], &[63.0]]),
+];
+
+/// The atoms added to a single conformer by [`PDB::rebuild_missing_atoms`]
+#[cfg(feature = "rstar")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebuiltResidue {
+    /// The serial number of the model
+    pub model: usize,
+    /// The id of the chain
+    pub chain: String,
+    /// The serial number and insertion code of the residue
+    pub residue: (isize, Option<String>),
+    /// The name and alternative location of the conformer
+    pub conformer: (String, Option<String>),
+    /// The names of the atoms that were added
+    pub atoms: Vec<&'static str>,
+}
+
+/// The rotamers used for residues without side chain dihedrals, a single empty rotamer
+#[cfg(feature = "rstar")]
+const NO_ROTAMERS: &[&[f64]] = &[&[]];
+
+/// The name, element, and position of an atom to add
+#[cfg(feature = "rstar")]
+type NewAtom = (&'static str, Element, Vector);
+
+/// Two heavy atoms closer than this distance (in Å) are considered to clash when picking a rotamer
+#[cfg(feature = "rstar")]
+const CLASH_DISTANCE: f64 = 3.0;
+
+/// Calculate the positions of the heavy atoms missing from the given conformer of a standard
+/// amino acid. The side chain is built from the backbone (N, CA, and C are needed) with ideal
+/// internal coordinates. Side chain dihedrals that can be measured from the atoms present are
+/// kept, the others are taken from the most common rotamer that does not clash with the given
+/// environment (the positions of the surrounding heavy atoms). The O is placed in the plane of
+/// the peptide bond with the N of the next residue, if given. It returns the name, element, and
+/// position of each added atom.
+#[cfg(feature = "rstar")]
+pub(crate) fn missing_heavy_atoms(
+    conformer: &Conformer,
+    next_nitrogen: Option<Vector>,
+    environment: &[Vector],
+) -> Vec<NewAtom> {
+    let template = match conformer.template() {
+        Some(template) => template,
+        None => return Vec::new(),
+    };
+    let find = |name: &str| conformer.atoms().find(|a| a.name() == name).map(Atom::pos);
+    let (n, ca, c) = match (find("N"), find("CA"), find("C")) {
+        (Some(n), Some(ca), Some(c)) => (n, ca, c),
+        _ => return Vec::new(),
+    };
+    if !conformer.is_amino_acid() {
+        return Vec::new();
+    }
+    let mut output = Vec::new();
+    if find("O").is_none() {
+        let position = match next_nitrogen.or_else(|| find("OXT")) {
+            Some(reference) => place(reference, ca, c, 1.23, 120.8, 180.0),
+            // Without a next residue assume a psi of 135 degrees, as in a beta strand
+            None => place(n, ca, c, 1.23, 120.8, -45.0),
+        };
+        output.push(("O", Element::O, position));
+    }
+
+    let side_chain = SIDE_CHAINS
+        .iter()
+        .find(|(name, _)| *name == template.name)
+        .map_or(&[][..], |(_, side_chain)| *side_chain);
+    let coordinates = (template.name != "GLY")
+        .then_some(&BETA_CARBON)
+        .into_iter()
+        .chain(side_chain);
+    if coordinates.clone().all(|ic| find(ic.atom).is_some()) {
+        return output;
+    }
+
+    let rotamers = ROTAMERS
+        .iter()
+        .find(|(name, _)| *name == template.name)
+        .map_or(NO_ROTAMERS, |(_, rotamers)| *rotamers);
+
+    let mut best: Option<(f64, Vec<NewAtom>)> = None;
+    for rotamer in rotamers {
+        let mut placed: Vec<(&str, Vector)> =
+            conformer.atoms().map(|a| (a.name(), a.pos())).collect();
+        let mut atoms = Vec::new();
+        for ic in coordinates.clone() {
+            if placed.iter().any(|(name, _)| *name == ic.atom) {
+                continue;
+            }
+            let position = |name: &str| placed.iter().find(|(n, _)| *n == name).map(|p| p.1);
+            let (a, b, c) = match ic.references.map(position) {
+                [Some(a), Some(b), Some(c)] => (a, b, c),
+                _ => continue,
+            };
+            // Measure the side chain dihedral if the atom defining it is present
+            let chi = ic.chi.map_or(0.0, |i| {
+                side_chain
+                    .iter()
+                    .find(|defining| defining.chi == Some(i))
+                    .and_then(|defining| {
+                        let [a, b, c] = defining.references.map(position);
+                        Some(dihedral(a?, b?, c?, position(defining.atom)?))
+                    })
+                    .unwrap_or_else(|| rotamer.get(i).copied().unwrap_or(180.0))
+            });
+            let d = place(a, b, c, ic.bond, ic.angle, ic.dihedral + chi);
+            placed.push((ic.atom, d));
+            if let Some(element) = template.element(ic.atom) {
+                atoms.push((ic.atom, element, d));
+            }
+        }
+        let overlap: f64 = atoms
+            .iter()
+            .flat_map(|(_, _, a)| {
+                environment
+                    .iter()
+                    .map(move |b| (CLASH_DISTANCE - length(sub(*a, *b))).max(0.0))
+            })
+            .sum();
+        if best.as_ref().map_or(true, |(score, _)| overlap < *score) {
+            best = Some((overlap, atoms));
+        }
+    }
+    output.extend(best.map(|(_, atoms)| atoms).unwrap_or_default());
+    output
+}
+
+#[cfg(all(test, feature = "rstar"))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{open_pdb, StrictnessLevel};
+    use std::collections::HashMap;
+
+    fn positions(pdb: &PDB) -> HashMap<(isize, String), Vector> {
+        pdb.atoms_with_hierarchy()
+            .map(|h| {
+                (
+                    (h.residue().serial_number(), h.atom().name().to_string()),
+                    h.atom().pos(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rebuild_partial() {
+        let (mut pdb, _) = open_pdb("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+        pdb.remove_atoms_by(|a| a.element() == Some(&Element::H));
+        let original = positions(&pdb);
+        let removed = [
+            "O", "CB", "OD2", "ND2", "OE2", "NE2", "CG2", "CD2", "NH2", "CE1", "OH",
+        ];
+        pdb.remove_atoms_by(|a| !a.hetero() && removed.contains(&a.name()));
+        assert_eq!(
+            pdb.residues()
+                .find(|r| r.name() == Some("THR"))
+                .unwrap()
+                .conformer(0)
+                .unwrap()
+                .missing_atoms(),
+            vec!["O", "CB", "CG2"]
+        );
+        let remaining = pdb.atom_count();
+        let rebuilt = pdb.rebuild_missing_atoms();
+        assert_eq!(rebuilt.len(), 76);
+        assert_eq!(
+            rebuilt.iter().map(|r| r.atoms.len()).sum::<usize>(),
+            original.len() - remaining
+        );
+        assert!(pdb
+            .residues()
+            .all(|r| r.conformer(0).unwrap().missing_atoms().is_empty()));
+        // Only the C terminal O is placed without a reference, and the guanidinium group of
+        // ARG 72 is not planar in the deposited structure
+        for ((serial, name), position) in positions(&pdb) {
+            let deviation = length(sub(position, original[&(serial, name.clone())]));
+            assert!(
+                deviation < 0.3 || serial == 76 || (serial, name.as_str()) == (72, "NH2"),
+                "{serial} {name} {deviation}"
+            );
+        }
+    }
+
+    #[test]
+    fn rebuild_side_chains() {
+        let (mut pdb, _) = open_pdb("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+        pdb.remove_atoms_by(|a| a.element() == Some(&Element::H));
+        let original = positions(&pdb);
+        pdb.remove_atoms_by(|a| !a.hetero() && !["N", "CA", "C", "O"].contains(&a.name()));
+        assert!(crate::validate(&pdb)
+            .iter()
+            .any(|e| e.short_description() == "Missing atoms"));
+        let rebuilt = pdb.rebuild_missing_atoms();
+        assert_eq!(rebuilt.len(), 70);
+        let arg = rebuilt.iter().find(|r| r.residue.0 == 42).unwrap();
+        assert_eq!(arg.conformer, ("ARG".to_string(), None));
+        assert_eq!(arg.atoms, ["CB", "CG", "CD", "NE", "CZ", "NH1", "NH2"]);
+        assert!(!crate::validate(&pdb)
+            .iter()
+            .any(|e| e.short_description() == "Missing atoms"));
+        // The leaving OXT is not rebuilt
+        assert_eq!(positions(&pdb).len(), original.len() - 1);
+        // The rotamers are picked without knowledge of the original, but most should be right
+        let correct = pdb
+            .atoms_with_hierarchy()
+            .filter(|h| ["CG", "CG1", "OG", "OG1", "SG"].contains(&h.atom().name()))
+            .filter(|h| {
+                let key = (h.residue().serial_number(), h.atom().name().to_string());
+                length(sub(h.atom().pos(), original[&key])) < 1.0
+            })
+            .count();
+        assert!(correct > 45, "{correct}");
+    }
+}
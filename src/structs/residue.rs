@@ -15,6 +15,12 @@ pub struct Residue {
     serial_number: isize,
     /// The insertion code of this Residue, used in conjunction with the serial number to uniquely identify Residues.
     insertion_code: Option<String>,
+    /// The mmCIF `label_asym_id` of this Residue, if known
+    label_asym_id: Option<String>,
+    /// The mmCIF `label_seq_id` of this Residue, if known
+    label_seq_id: Option<isize>,
+    /// The mmCIF `label_entity_id` of this Residue, if known
+    label_entity_id: Option<String>,
     /// The list of conformers making up this Residue
     conformers: Vec<Conformer>,
 }
@@ -38,6 +44,9 @@ impl<'a> Residue {
         let mut res = Self {
             serial_number: number,
             insertion_code: None,
+            label_asym_id: None,
+            label_seq_id: None,
+            label_entity_id: None,
             conformers: Vec::new(),
         };
         if let Some(ic) = insertion_code {
@@ -83,6 +92,60 @@ impl<'a> Residue {
         self.insertion_code = None;
     }
 
+    /// Get the mmCIF label chain id (`label_asym_id`) of the Residue, if known. The chain id of
+    /// the containing [`Chain`] is the author chain id (`auth_asym_id`). Residues in a single
+    /// author chain can have different label chain ids, as mmCIF files give each polymer,
+    /// ligand, and group of waters its own label chain id.
+    #[must_use]
+    pub fn label_asym_id(&self) -> Option<&str> {
+        self.label_asym_id.as_deref()
+    }
+
+    /// Set the mmCIF label chain id (`label_asym_id`) of the Residue.
+    /// Fails and returns false if the `new_id` contains invalid characters
+    pub fn set_label_asym_id(&mut self, new_id: impl AsRef<str>) -> bool {
+        prepare_identifier(new_id)
+            .map(|id| self.label_asym_id = Some(id))
+            .is_some()
+    }
+
+    /// Set the mmCIF label chain id (`label_asym_id`) of the Residue to None.
+    pub fn remove_label_asym_id(&mut self) {
+        self.label_asym_id = None;
+    }
+
+    /// Get the mmCIF label residue number (`label_seq_id`) of the Residue, if known. This is
+    /// the index (starting at 1) in the sequence of the entity and is only defined for polymer
+    /// residues. The serial number of the Residue is the author residue number (`auth_seq_id`).
+    #[must_use]
+    pub const fn label_seq_id(&self) -> Option<isize> {
+        self.label_seq_id
+    }
+
+    /// Set the mmCIF label residue number (`label_seq_id`) of the Residue.
+    pub fn set_label_seq_id(&mut self, new_number: Option<isize>) {
+        self.label_seq_id = new_number;
+    }
+
+    /// Get the mmCIF entity id (`label_entity_id`) of the Residue, if known.
+    #[must_use]
+    pub fn label_entity_id(&self) -> Option<&str> {
+        self.label_entity_id.as_deref()
+    }
+
+    /// Set the mmCIF entity id (`label_entity_id`) of the Residue.
+    /// Fails and returns false if the `new_id` contains invalid characters
+    pub fn set_label_entity_id(&mut self, new_id: impl AsRef<str>) -> bool {
+        prepare_identifier(new_id)
+            .map(|id| self.label_entity_id = Some(id))
+            .is_some()
+    }
+
+    /// Set the mmCIF entity id (`label_entity_id`) of the Residue to None.
+    pub fn remove_label_entity_id(&mut self) {
+        self.label_entity_id = None;
+    }
+
     /// Returns the uniquely identifying construct for this Residue,
     /// consisting of the serial number and the insertion code.
     #[must_use]
@@ -239,12 +302,21 @@ impl<'a> Residue {
         }
     }
 
+    /// Resolve the terms of the search that need the whole Residue, see [`Search::resolve`]
+    fn resolve_search(&self, search: &mut Search) {
+        search.resolve(
+            &|inner| self.find(inner).map(|h| h.atom().counter()).collect(),
+            &|| self.atoms().map(|a| (None, Some(self), a)).collect(),
+        );
+    }
+
     /// Find all hierarchies matching the given search. For more details see [Search].
     #[must_use]
     pub fn find(
         &'a self,
-        search: Search,
-    ) -> impl DoubleEndedIterator<Item = AtomConformer<'a>> + '_ {
+        mut search: Search,
+    ) -> impl DoubleEndedIterator<Item = AtomConformer<'a>> + 'a {
+        self.resolve_search(&mut search);
         self.conformers()
             .map(move |c| (c, search.clone().add_conformer_info(c)))
             .filter(|(_c, search)| !matches!(search, Search::Known(false)))
@@ -258,8 +330,9 @@ impl<'a> Residue {
     #[must_use]
     pub fn find_mut(
         &'a mut self,
-        search: Search,
-    ) -> impl DoubleEndedIterator<Item = AtomConformerMut<'a>> + '_ {
+        mut search: Search,
+    ) -> impl DoubleEndedIterator<Item = AtomConformerMut<'a>> + 'a {
+        self.resolve_search(&mut search);
         self.conformers_mut()
             .map(move |c| {
                 let search = search.clone().add_conformer_info(c);
@@ -308,6 +381,12 @@ impl<'a> Residue {
         self.conformers().flat_map(Conformer::atoms)
     }
 
+    /// Get the bonds within all Conformers of this Residue as defined by the residue templates,
+    /// see [`Conformer::template_bonds`]. Add them to a PDB using [`PDB::add_template_bonds`].
+    pub fn template_bonds(&self) -> impl Iterator<Item = (&Atom, &Atom, BondOrder)> + '_ {
+        self.conformers().flat_map(Conformer::template_bonds)
+    }
+
     /// Get a parallel iterator of references to Atoms making up this Model.
     #[doc_cfg(feature = "rayon")]
     #[must_use]
@@ -333,7 +412,7 @@ impl<'a> Residue {
     #[must_use]
     pub fn atoms_with_hierarchy(
         &'a self,
-    ) -> impl DoubleEndedIterator<Item = hierarchy::AtomConformer<'a>> + '_ {
+    ) -> impl DoubleEndedIterator<Item = hierarchy::AtomConformer<'a>> + 'a {
         self.conformers()
             .flat_map(|c| c.atoms().map(move |a| (a, c)))
             .map(hierarchy::AtomConformer::from_tuple)
@@ -344,7 +423,7 @@ impl<'a> Residue {
     #[must_use]
     pub fn atoms_with_hierarchy_mut(
         &'a mut self,
-    ) -> impl DoubleEndedIterator<Item = hierarchy::AtomConformerMut<'a>> + '_ {
+    ) -> impl DoubleEndedIterator<Item = hierarchy::AtomConformerMut<'a>> + 'a {
         self.conformers_mut()
             .flat_map(|c| {
                 let conformer: *mut Conformer = c;
@@ -514,7 +593,7 @@ impl fmt::Display for Residue {
 
 impl PartialOrd for Residue {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.id().cmp(&other.id()))
+        Some(self.cmp(other))
     }
 }
 
@@ -592,7 +671,7 @@ mod tests {
     #[test]
     fn check_display() {
         let a = Residue::new(1, None, None).unwrap();
-        format!("{a:?}");
-        format!("{a}");
+        let _ = format!("{a:?}");
+        let _ = format!("{a}");
     }
 }
@@ -1,11 +1,13 @@
 use super::*;
+use std::collections::{HashMap, HashSet};
 use std::ops;
 
 /// Any parameter to use in a [Search] for atom(s) in a PDB.
-/// For position related searches look into the [rstar] crate which can be combined
-/// with this crate using the `rstar` feature, see [`PDB::create_atom_rtree`] and
-/// [`PDB::create_hierarchy_rtree`]. The rstar crate makes spatial lookup and queries
-/// way faster and feasible to use in high performance environments.
+/// Position related searches can be done with the spatial terms ([`Term::WithinPoint`],
+/// [`Term::WithinPoints`], [`Term::WithinSearch`], and [`Term::InsideBox`]), which use an
+/// R*-tree from the [rstar] crate internally when the `rstar` feature is enabled. For other
+/// spatial queries the rstar crate can be used directly, see [`PDB::create_atom_rtree`] and
+/// [`PDB::create_hierarchy_rtree`].
 #[allow(unused)]
 #[derive(Debug, Clone)]
 #[non_exhaustive]
@@ -14,11 +16,11 @@ pub enum Term {
     ModelSerialNumber(usize),
     /// Search for a range of model serial numbers, starting at the first number and ending with the last number inclusive.
     ModelSerialNumberRange(usize, usize),
-    /// The chain id eg `A`, see [Chain::id].
+    /// The (author) chain id eg `A`, see [Chain::id].
     ChainId(String),
     /// Search for a range of chain ids, using the Ord implementation of std::str <https://doc.rust-lang.org/std/primitive.str.html#impl-Ord>, starting at the first number and ending with the last number inclusive.
     ChainIdRange(String, String),
-    /// The residue serial number, see [Residue::serial_number].
+    /// The (author) residue serial number, see [Residue::serial_number].
     ResidueSerialNumber(isize),
     /// Search for a range of residue serial numbers, starting at the first number and ending with the last number inclusive.
     ResidueSerialNumberRange(isize, isize),
@@ -26,6 +28,14 @@ pub enum Term {
     ResidueInsertionCode(Option<String>),
     /// The residue serial number and insertion code combined, see [Residue::id].
     ResidueId(isize, Option<String>),
+    /// The mmCIF label chain id eg `A`, see [Residue::label_asym_id].
+    LabelChainId(String),
+    /// The mmCIF label residue number, see [Residue::label_seq_id].
+    LabelResidueNumber(isize),
+    /// Search for a range of mmCIF label residue numbers, starting at the first number and ending with the last number inclusive.
+    LabelResidueNumberRange(isize, isize),
+    /// The mmCIF entity id eg `1`, see [Residue::label_entity_id].
+    EntityId(String),
     /// The conformer name eg `ALA`, see [Conformer::name].
     ConformerName(String),
     /// The conformer alternative location eg `Some("A")`, see [Conformer::alternative_location].
@@ -54,9 +64,274 @@ pub enum Term {
     SideChain,
     /// Search for hetero atoms, this means that [Atom::hetero] is `true`.
     Hetero,
+    /// Search for a range of residue insertion codes, using the Ord implementation of std::str, starting at the first code and ending with the last code inclusive. Residues without insertion code do not match.
+    ResidueInsertionCodeRange(String, String),
+    /// Any of the conformer names eg `["ALA", "GLY"]`, see [Conformer::name].
+    ConformerNames(Vec<String>),
+    /// Any of the conformer alternative locations eg `[None, Some("A")]`, see [Conformer::alternative_location].
+    ConformerAlternativeLocations(Vec<Option<String>>),
+    /// The atom charge, see [Atom::charge].
+    Charge(isize),
+    /// Atom charge range starting at the first number and ending with the last number inclusive.
+    ChargeRange(isize, isize),
+    /// Any of the elements, see [Atom::element].
+    Elements(Vec<Element>),
+    /// Atom segment id, `None` matches atoms without segment id, see [Atom::segment_id].
+    SegmentId(Option<String>),
+    /// Atom names matching the regular expression, see [Atom::name].
+    #[cfg(feature = "regex")]
+    AtomNameRegex(regex::Regex),
+    /// Search for water, this means that [Conformer::is_water] is `true`.
+    Water,
+    /// Search for ions, this means conformers that are not water, are not part of a polymer (see
+    /// [Conformer::polymer_type]), and consist of a single atom.
+    Ion,
+    /// Search for ligands, this means hetero atoms ([Atom::hetero]) that are not water. This
+    /// includes ions, use `Term::Ligand & !Term::Ion` to exclude them.
+    Ligand,
+    /// Search for residues of the given polymer type, see [Conformer::polymer_type].
+    PolymerType(PolymerType),
+    /// Atoms within the given distance (inclusive) of the given point.
+    WithinPoint((f64, f64, f64), f64),
+    /// Atoms within the given distance (inclusive) of any of the given points.
+    WithinPoints(Points, f64),
+    /// Atoms within the given distance (inclusive) of any atom matching the given search, this
+    /// includes the matching atoms themselves. The search is evaluated on the struct the find is
+    /// started from, so [`PDB::find`] looks for atoms in the same model.
+    WithinSearch(Box<Search>, f64),
+    /// Atoms inside the axis aligned box (inclusive) given by its minimal and maximal corners.
+    InsideBox((f64, f64, f64), (f64, f64, f64)),
+    /// Atoms in the given set, see [`AtomSet`].
+    InSet(AtomSet),
+    /// All atoms of the residues with any atom matching the given search. Like
+    /// [`Term::WithinSearch`] the search is evaluated on the struct the find is started from.
+    ByResidue(Box<Search>),
+    /// All atoms of the chains with any atom matching the given search. Like
+    /// [`Term::WithinSearch`] the search is evaluated on the struct the find is started from.
+    ByChain(Box<Search>),
+    /// All atoms of the entities with any atom matching the given search. Like in mmCIF files
+    /// all chains with the same sequence of polymer residues form one entity, and all other
+    /// residues with the same name (like a ligand or water) form one entity. Polymer residues
+    /// are residues with any atom that is not [Atom::hetero]. Like [`Term::WithinSearch`] the
+    /// search is evaluated on the struct the find is started from.
+    SameEntity(Box<Search>),
+}
+
+/// The atoms of the struct a search is evaluated on, with the chain and residue they are in if
+/// these are part of that struct
+pub(crate) type ScopedAtom<'a> = (Option<&'a Chain>, Option<&'a Residue>, &'a Atom);
+
+/// A set of atoms to use in a [`Term::InSet`] search, for example to reuse the result of an
+/// earlier search. Atoms are identified by an internal unique id, and as a cloned atom gets a
+/// new id the set does not match clones of its atoms. The set is shared between clones of it.
+#[derive(Clone, Default)]
+pub struct AtomSet(std::sync::Arc<HashSet<usize>>);
+
+impl AtomSet {
+    /// Get the number of atoms
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Check if there are no atoms
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Check if the given atom is in this set
+    pub fn contains(&self, atom: &Atom) -> bool {
+        self.0.contains(&atom.counter())
+    }
+}
+
+impl<'a> FromIterator<&'a Atom> for AtomSet {
+    fn from_iter<T: IntoIterator<Item = &'a Atom>>(iter: T) -> Self {
+        AtomSet(std::sync::Arc::new(
+            iter.into_iter().map(Atom::counter).collect(),
+        ))
+    }
+}
+
+impl std::fmt::Debug for AtomSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AtomSet").field("len", &self.len()).finish()
+    }
+}
+
+/// An entity as used by [`Term::SameEntity`]
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum Entity<'a> {
+    /// A polymer, with the names of its residues
+    Polymer(&'a [&'a str]),
+    /// Another residue, with its name
+    Other(&'a str),
+}
+
+/// Get the address of the given value, to identify structs in the hierarchy
+const fn address<T>(value: &T) -> *const T {
+    value
+}
+
+/// Get all atoms in the scope sharing the key of any of the matched atoms
+fn expand<'a, K: std::hash::Hash + Eq>(
+    matched: &HashSet<usize>,
+    scope: &[ScopedAtom<'a>],
+    key: impl Fn(&ScopedAtom<'a>) -> K,
+) -> AtomSet {
+    let keys: HashSet<K> = scope
+        .iter()
+        .filter(|s| matched.contains(&s.2.counter()))
+        .map(&key)
+        .collect();
+    scope
+        .iter()
+        .filter(|s| keys.contains(&key(s)))
+        .map(|s| s.2)
+        .collect()
+}
+
+/// A set of positions to use in a [`Term::WithinPoints`] search. When the `rstar` feature is
+/// enabled the positions are stored in an R*-tree to make the lookup fast for large sets. The
+/// positions are shared between clones.
+#[derive(Clone)]
+pub struct Points {
+    /// The positions
+    #[cfg(feature = "rstar")]
+    tree: std::sync::Arc<rstar::RTree<(f64, f64, f64)>>,
+    /// The positions
+    #[cfg(not(feature = "rstar"))]
+    points: std::sync::Arc<Vec<(f64, f64, f64)>>,
+}
+
+impl Points {
+    /// Get the number of positions
+    pub fn len(&self) -> usize {
+        #[cfg(feature = "rstar")]
+        return self.tree.size();
+        #[cfg(not(feature = "rstar"))]
+        return self.points.len();
+    }
+
+    /// Check if there are no positions
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check if any of the positions is within the given distance (inclusive) of the given point
+    pub fn within(&self, point: (f64, f64, f64), distance: f64) -> bool {
+        #[cfg(feature = "rstar")]
+        return self
+            .tree
+            .locate_within_distance(point, distance * distance)
+            .next()
+            .is_some();
+        #[cfg(not(feature = "rstar"))]
+        return self
+            .points
+            .iter()
+            .any(|p| distance_squared(*p, point) <= distance * distance);
+    }
+}
+
+impl FromIterator<(f64, f64, f64)> for Points {
+    fn from_iter<T: IntoIterator<Item = (f64, f64, f64)>>(iter: T) -> Self {
+        Points {
+            #[cfg(feature = "rstar")]
+            tree: std::sync::Arc::new(rstar::RTree::bulk_load(iter.into_iter().collect())),
+            #[cfg(not(feature = "rstar"))]
+            points: std::sync::Arc::new(iter.into_iter().collect()),
+        }
+    }
+}
+
+impl std::fmt::Debug for Points {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Points").field("len", &self.len()).finish()
+    }
+}
+
+/// Get the squared distance between two points
+fn distance_squared(a: (f64, f64, f64), b: (f64, f64, f64)) -> f64 {
+    (a.2 - b.2).mul_add(
+        a.2 - b.2,
+        (a.1 - b.1).mul_add(a.1 - b.1, (a.0 - b.0).powi(2)),
+    )
 }
 
 impl Term {
+    /// Get the search nested in this term, for the terms that need the whole struct
+    const fn nested(&self) -> Option<&Search> {
+        match self {
+            Self::WithinSearch(search, _)
+            | Self::ByResidue(search)
+            | Self::ByChain(search)
+            | Self::SameEntity(search) => Some(search),
+            _ => None,
+        }
+    }
+
+    /// Create the same term with the given nested search, see [`Term::nested`]
+    fn with_nested(&self, search: Search) -> Self {
+        let search = Box::new(search);
+        match self {
+            Self::WithinSearch(_, distance) => Self::WithinSearch(search, *distance),
+            Self::ByResidue(_) => Self::ByResidue(search),
+            Self::ByChain(_) => Self::ByChain(search),
+            Self::SameEntity(_) => Self::SameEntity(search),
+            _ => self.clone(),
+        }
+    }
+
+    /// Replace a term that needs the whole struct by a term that can be evaluated per atom, given
+    /// the ids of the atoms matching its nested search and all atoms in the struct
+    fn resolve<'a>(
+        &self,
+        matched: &HashSet<usize>,
+        scope: impl FnOnce() -> Vec<ScopedAtom<'a>>,
+    ) -> Self {
+        match self {
+            Self::WithinSearch(_, distance) => Self::WithinPoints(
+                scope()
+                    .iter()
+                    .filter(|s| matched.contains(&s.2.counter()))
+                    .map(|s| s.2.pos())
+                    .collect(),
+                *distance,
+            ),
+            Self::ByResidue(_) => Self::InSet(expand(matched, &scope(), |s| s.1.map(address))),
+            Self::ByChain(_) => Self::InSet(expand(matched, &scope(), |s| s.0.map(address))),
+            Self::SameEntity(_) => {
+                let scope = scope();
+                let polymer: HashSet<*const Residue> = scope
+                    .iter()
+                    .filter_map(|s| s.1)
+                    .filter(|r| r.atoms().any(|a| !a.hetero()))
+                    .map(address)
+                    .collect();
+                let sequences: HashMap<*const Chain, Vec<&str>> = scope
+                    .iter()
+                    .filter_map(|s| s.0)
+                    .map(|c| {
+                        let sequence = c
+                            .residues()
+                            .filter(|r| polymer.contains(&address(*r)))
+                            .map(|r| r.name().unwrap_or_default())
+                            .collect();
+                        (address(c), sequence)
+                    })
+                    .collect();
+                Self::InSet(expand(matched, &scope, |s| match (s.0, s.1) {
+                    (Some(c), Some(r)) if polymer.contains(&address(r)) => {
+                        Entity::Polymer(&sequences[&address(c)])
+                    }
+                    (_, Some(r)) => Entity::Other(r.name().unwrap_or_default()),
+                    _ => Entity::Other(""),
+                }))
+            }
+            _ => self.clone(),
+        }
+    }
+
     const fn optional_matches_model(&self, model: &Model) -> Option<bool> {
         match self {
             Self::ModelSerialNumber(s) => Some(*s == model.serial_number()),
@@ -83,6 +358,19 @@ impl Term {
             }
             Self::ResidueInsertionCode(ic) => Some(ic.as_deref() == residue.insertion_code()),
             Self::ResidueId(s, ic) => Some((*s, ic.as_deref()) == residue.id()),
+            Self::LabelChainId(s) => Some(Some(s.as_str()) == residue.label_asym_id()),
+            Self::LabelResidueNumber(s) => Some(Some(*s) == residue.label_seq_id()),
+            Self::LabelResidueNumberRange(low, high) => Some(
+                residue
+                    .label_seq_id()
+                    .map_or(false, |s| *low <= s && *high >= s),
+            ),
+            Self::EntityId(s) => Some(Some(s.as_str()) == residue.label_entity_id()),
+            Self::ResidueInsertionCodeRange(low, high) => Some(
+                residue
+                    .insertion_code()
+                    .map_or(false, |ic| low.as_str() <= ic && high.as_str() >= ic),
+            ),
             _ => None,
         }
     }
@@ -95,6 +383,20 @@ impl Term {
             Self::ConformerId(n, al) => Some((n.as_str(), al.as_deref()) == conformer.id()),
             Self::Backbone if !conformer.is_amino_acid() => Some(false),
             Self::SideChain if !conformer.is_amino_acid() => Some(false),
+            Self::ConformerNames(names) => Some(names.iter().any(|n| n == conformer.name())),
+            Self::ConformerAlternativeLocations(locations) => Some(
+                locations
+                    .iter()
+                    .any(|al| al.as_deref() == conformer.alternative_location()),
+            ),
+            Self::Water => Some(conformer.is_water()),
+            Self::Ion => Some(
+                !conformer.is_water()
+                    && conformer.polymer_type().is_none()
+                    && conformer.atom_count() == 1,
+            ),
+            Self::Ligand if conformer.is_water() => Some(false),
+            Self::PolymerType(t) => Some(conformer.polymer_type() == Some(*t)),
             _ => None,
         }
     }
@@ -116,7 +418,26 @@ impl Term {
             }
             Self::Backbone => Some(atom.is_backbone()),
             Self::SideChain => Some(!atom.is_backbone()),
-            Self::Hetero => Some(atom.hetero()),
+            Self::Hetero | Self::Ligand => Some(atom.hetero()),
+            Self::Charge(c) => Some(atom.charge() == *c),
+            Self::ChargeRange(low, high) => Some(atom.charge() >= *low && atom.charge() <= *high),
+            Self::Elements(elements) => atom.element().map(|e| elements.contains(e)),
+            Self::SegmentId(s) => Some(s.as_deref() == atom.segment_id()),
+            #[cfg(feature = "regex")]
+            Self::AtomNameRegex(regex) => Some(regex.is_match(atom.name())),
+            Self::WithinPoint(point, distance) => {
+                Some(distance_squared(atom.pos(), *point) <= distance * distance)
+            }
+            Self::WithinPoints(points, distance) => Some(points.within(atom.pos(), *distance)),
+            Self::InSet(set) => Some(set.contains(atom)),
+            Self::InsideBox(min, max) => {
+                let (x, y, z) = atom.pos();
+                Some(
+                    (min.0..=max.0).contains(&x)
+                        && (min.1..=max.1).contains(&y)
+                        && (min.2..=max.2).contains(&z),
+                )
+            }
             _ => None,
         }
     }
@@ -125,6 +446,8 @@ impl Term {
 /// A collection of multiple search [Term]s in the search for (an) atom(s) in a PDB.
 /// You can use bitwise and (`&`), or (`|`), and xor (`^`) to chain a search.
 /// In the same way you can use not `!` to negate a search term.
+/// A search can also be parsed from a selection string like `"chain A and resi 10-20"`, see
+/// the [`FromStr`](#impl-FromStr-for-Search) implementation for the syntax.
 ///
 /// ```
 /// use pdbtbx::*;
@@ -181,6 +504,31 @@ impl Search {
         }
     }
 
+    /// Replace every term that needs the whole struct (like [`Term::WithinSearch`] and
+    /// [`Term::ByResidue`]) by a term that can be evaluated per atom, given functions to find the
+    /// ids ([`Atom::counter`]) of the atoms matching a search in the struct and to list all atoms
+    /// in the struct. This has to be done before the search is evaluated.
+    pub(crate) fn resolve<'a>(
+        &mut self,
+        find: &impl Fn(Search) -> HashSet<usize>,
+        scope: &impl Fn() -> Vec<ScopedAtom<'a>>,
+    ) {
+        match self {
+            Self::Ops(_, a, b) => {
+                a.resolve(find, scope);
+                b.resolve(find, scope);
+            }
+            Self::Not(a) => a.resolve(find, scope),
+            Self::Single(term) => {
+                if let Some(nested) = term.nested() {
+                    let matched = find(nested.clone());
+                    *term = term.resolve(&matched, scope);
+                }
+            }
+            Self::Known(_) => (),
+        }
+    }
+
     /// Check if the search is done.
     #[must_use]
     pub const fn complete(&self) -> Option<bool> {
@@ -200,10 +548,12 @@ impl Search {
                 Box::new(b.add_model_info(model)),
             ),
             Self::Not(a) => Self::Not(Box::new(a.add_model_info(model))),
-            Self::Single(a) => match a.optional_matches_model(model) {
-                Some(true) => Self::Known(true),
-                Some(false) => Self::Known(false),
-                None => self.clone(),
+            Self::Single(a) => match (a.optional_matches_model(model), a.nested()) {
+                (Some(true), _) => Self::Known(true),
+                (Some(false), _) => Self::Known(false),
+                // The nested searches are evaluated in the same model
+                (None, Some(nested)) => Self::Single(a.with_nested(nested.add_model_info(model))),
+                (None, None) => self.clone(),
             },
             Self::Known(_) => self.clone(),
         }
@@ -525,4 +875,246 @@ mod tests {
             Some(true)
         );
     }
+
+    #[test]
+    fn spatial() {
+        let (mut pdb, _) =
+            crate::open_pdb("example-pdbs/1ubq.pdb", crate::StrictnessLevel::Loose).unwrap();
+        let center = pdb.atom(100).unwrap().pos();
+        let near = |a: &Atom, p: (f64, f64, f64), d: f64| distance_squared(a.pos(), p) <= d * d;
+        assert_eq!(
+            pdb.find(Search::Single(Term::WithinPoint(center, 6.0)))
+                .count(),
+            pdb.atoms().filter(|a| near(a, center, 6.0)).count()
+        );
+        let (min, max) = ((20.0, 20.0, 0.0), (30.0, 30.0, 10.0));
+        assert_eq!(
+            pdb.find(Search::Single(Term::InsideBox(min, max))).count(),
+            pdb.atoms()
+                .filter(|a| {
+                    let (x, y, z) = a.pos();
+                    x >= min.0 && x <= max.0 && y >= min.1 && y <= max.1 && z >= min.2 && z <= max.2
+                })
+                .count()
+        );
+        let points: Points = [center, (0.0, 0.0, 0.0)].into_iter().collect();
+        assert_eq!(points.len(), 2);
+        assert_eq!(
+            pdb.find(Search::Single(Term::WithinPoints(points, 4.0)))
+                .count(),
+            pdb.atoms()
+                .filter(|a| near(a, center, 4.0) || near(a, (0.0, 0.0, 0.0), 4.0))
+                .count()
+        );
+
+        // Waters close to the first ten residues
+        let protein: Vec<_> = pdb
+            .atoms_with_hierarchy()
+            .filter(|h| h.residue().serial_number() <= 10 && !h.atom().hetero())
+            .map(|h| h.atom().pos())
+            .collect();
+        let waters = pdb
+            .atoms_with_hierarchy()
+            .filter(|h| h.conformer().name() == "HOH")
+            .filter(|h| protein.iter().any(|p| near(h.atom(), *p, 4.0)))
+            .count();
+        assert!(waters > 0);
+        let search = Term::ConformerName("HOH".to_string())
+            & Term::WithinSearch(
+                Box::new(Term::ResidueSerialNumberRange(1, 10) & !Term::Hetero),
+                4.0,
+            );
+        assert_eq!(pdb.find(search.clone()).count(), waters);
+        assert_eq!(pdb.chain(0).unwrap().find(search.clone()).count(), waters);
+        for mut h in pdb.find_mut(search.clone()) {
+            h.atom_mut().set_b_factor(99.0).unwrap();
+        }
+        assert_eq!(pdb.atoms().filter(|a| a.b_factor() == 99.0).count(), waters);
+
+        // Every model only looks at its own atoms
+        let mut model = pdb.model(0).unwrap().clone();
+        model.set_serial_number(2);
+        model.apply_transformation(&crate::TransformationMatrix::translation(100.0, 0.0, 0.0));
+        pdb.add_model(model);
+        assert_eq!(pdb.find(search).count(), 2 * waters);
+        let only_first =
+            Term::WithinSearch(Box::new(Search::Single(Term::ModelSerialNumber(0))), 1.0);
+        assert_eq!(
+            pdb.find(Search::Single(only_first)).count(),
+            pdb.model(0).unwrap().atom_count()
+        );
+    }
+
+    #[test]
+    fn expansion() {
+        let (mut pdb, _) =
+            crate::open_pdb("example-pdbs/1ubq.pdb", crate::StrictnessLevel::Loose).unwrap();
+        let first_three: usize = (0..3).map(|i| pdb.residue(i).unwrap().atom_count()).sum();
+        let search = |s: &str| s.parse::<Search>().unwrap();
+        assert_eq!(
+            pdb.find(Search::Single(Term::ByResidue(Box::new(
+                Term::AtomName("CA".to_string()) & Term::ResidueSerialNumberRange(1, 3)
+            ))))
+            .count(),
+            first_three
+        );
+        assert_eq!(
+            pdb.find(search("byres (name CA and resi 1-3)")).count(),
+            first_three
+        );
+        assert_eq!(pdb.find(search("byres none")).count(), 0);
+
+        // A second copy of the chain, with the same sequence
+        let mut copy = pdb.chain(0).unwrap().clone();
+        copy.set_id("B");
+        copy.apply_transformation(&crate::TransformationMatrix::translation(50.0, 0.0, 0.0));
+        pdb.model_mut(0).unwrap().add_chain(copy);
+        let chain = pdb.chain(0).unwrap().atom_count();
+        assert_eq!(
+            pdb.find(search("bychain (chain B and resi 1)")).count(),
+            chain
+        );
+        assert_eq!(pdb.find(search("bychain resi 1")).count(), 2 * chain);
+        assert_eq!(
+            pdb.find(search("same entity as (chain A and resi 1)"))
+                .count(),
+            2 * 1231
+        );
+        assert_eq!(
+            pdb.find(search("same entity as (resn HOH and resi 80)"))
+                .count(),
+            2 * 40
+        );
+        pdb.chain_mut(1).unwrap().remove_residue(0);
+        assert_eq!(
+            pdb.find(search("same entity as (chain A and resi 1)"))
+                .count(),
+            1231
+        );
+        assert_eq!(
+            pdb.chain(0)
+                .unwrap()
+                .find(search("same entity as resi 1"))
+                .count(),
+            1231
+        );
+
+        let set: AtomSet = pdb.chain(0).unwrap().atoms().take(10).collect();
+        assert_eq!(set.len(), 10);
+        assert_eq!(pdb.find(Search::Single(Term::InSet(set))).count(), 10);
+
+        // Residues
+        let residues: Vec<_> = pdb.find_residues(search("name CA and resi 1-10")).collect();
+        assert_eq!(residues.len(), 19);
+        assert!(residues[..10].iter().all(|(_, c, _)| c.id() == "A"));
+        assert_eq!(residues[10].2.serial_number(), 2);
+        assert_eq!(
+            pdb.model(0)
+                .unwrap()
+                .find_residues(search("chain B and not backbone"))
+                .count(),
+            75 - 5 + 40 // All but the glycines, and the waters
+        );
+        assert_eq!(
+            pdb.chain(0)
+                .unwrap()
+                .find_residues(search("byres within 3 of (resn HOH and resi 80)"))
+                .count(),
+            pdb.chain(0)
+                .unwrap()
+                .find_residues(search("within 3 of (resn HOH and resi 80)"))
+                .count()
+        );
+    }
+
+    #[test]
+    fn classification() {
+        let (mut pdb, _) =
+            crate::open_pdb("example-pdbs/1ubq.pdb", crate::StrictnessLevel::Loose).unwrap();
+        let count = |pdb: &PDB, term: Term| pdb.find(Search::Single(term)).count();
+        let zinc = Atom::new(true, 2000, "ZN", 0.0, 0.0, 0.0, 1.0, 0.0, "ZN", 2).unwrap();
+        let ion = Residue::new(200, None, Conformer::new("ZN", None, Some(zinc))).unwrap();
+        // A residue with only its CA atom, as in CA-only models, is not an ion
+        let alpha = Atom::new(false, 2001, "CA", 0.0, 0.0, 0.0, 1.0, 0.0, "C", 0).unwrap();
+        let ca_only = Residue::new(201, None, Conformer::new("ALA", None, Some(alpha))).unwrap();
+        let chain = pdb.chain_mut(0).unwrap();
+        chain.add_residue(ion);
+        chain.add_residue(ca_only);
+        chain.residue_mut(1).unwrap().set_insertion_code("A");
+        chain.residue_mut(2).unwrap().set_insertion_code("C");
+        chain.residue_mut(3).unwrap().set_insertion_code("E");
+        pdb.atom_mut(0).unwrap().set_charge(1);
+        pdb.atom_mut(1).unwrap().set_charge(-1);
+
+        assert_eq!(count(&pdb, Term::Water), 40);
+        assert_eq!(count(&pdb, Term::Ion), 1);
+        assert_eq!(count(&pdb, Term::Ligand), 1);
+        assert_eq!(count(&pdb, Term::PolymerType(PolymerType::Protein)), 1232);
+        assert_eq!(count(&pdb, Term::PolymerType(PolymerType::Dna)), 0);
+        assert_eq!(count(&pdb, Term::Charge(2)), 1);
+        assert_eq!(count(&pdb, Term::ChargeRange(-1, 1)), 1272);
+        assert_eq!(count(&pdb, Term::ChargeRange(1, 5)), 2);
+        assert_eq!(
+            count(&pdb, Term::Elements(vec![Element::S, Element::Zn])),
+            2
+        );
+        assert_eq!(
+            count(
+                &pdb,
+                Term::ConformerNames(vec!["GLY".to_string(), "ALA".to_string()])
+            ),
+            pdb.atoms_with_hierarchy()
+                .filter(|h| ["GLY", "ALA"].contains(&h.conformer().name()))
+                .count()
+        );
+        assert_eq!(
+            count(&pdb, Term::ConformerAlternativeLocations(vec![None])),
+            1273
+        );
+        assert_eq!(
+            count(
+                &pdb,
+                Term::ConformerAlternativeLocations(vec![Some("A".to_string())])
+            ),
+            0
+        );
+        let with_code = |codes: &[&str]| {
+            pdb.residues()
+                .filter(|r| r.insertion_code().map_or(false, |ic| codes.contains(&ic)))
+                .map(Residue::atom_count)
+                .sum::<usize>()
+        };
+        assert_eq!(
+            count(
+                &pdb,
+                Term::ResidueInsertionCodeRange("A".to_string(), "D".to_string())
+            ),
+            with_code(&["A", "C"])
+        );
+
+        let search = |s: &str| pdb.find(s.parse::<Search>().unwrap()).count();
+        assert_eq!(search("water"), 40);
+        assert_eq!(search("ion"), 1);
+        assert_eq!(search("ligand"), 0);
+        assert_eq!(search("protein and not water"), 1232);
+        assert_eq!(search("nucleic"), 0);
+        assert_eq!(search("charge > 0"), 2);
+        assert_eq!(search("charge -1:1"), 1272);
+        assert_eq!(search("charge -1"), 1);
+        assert_eq!(search("icode B to E"), with_code(&["C", "E"]));
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn regex() {
+        let (pdb, _) =
+            crate::open_pdb("example-pdbs/1ubq.pdb", crate::StrictnessLevel::Loose).unwrap();
+        let regex = regex::Regex::new("^H[AB]").unwrap();
+        assert_eq!(
+            pdb.find(Search::Single(Term::AtomNameRegex(regex.clone())))
+                .count(),
+            pdb.atoms().filter(|a| regex.is_match(a.name())).count()
+        );
+        assert!(regex.is_match("HA") && !regex.is_match("CA"));
+    }
 }
@@ -0,0 +1,264 @@
+use crate::structs::*;
+use std::ops::Range;
+
+/// The maximal distance in Å between the linking atoms of two bonded residues
+const LINK_DISTANCE: f64 = 2.0;
+
+/// The atoms linking consecutive residues in a polymer: the C and N of the peptide bond and the
+/// O3' and P of the phosphodiester bond. Each link is only used if the first residue has the
+/// first atom and the second residue the other atoms.
+const LINKS: &[(&str, &[&str])] = &[("C", &["N", "CA"]), ("O3'", &["P"])];
+
+/// The step in the numbering between two consecutive residues in a chain
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NumberingStep {
+    /// The serial number increases by one and the second residue has no insertion code
+    Consecutive,
+    /// The second residue has an insertion code and the same or the next serial number, as
+    /// used by numbering schemes following a reference sequence (like Kabat for antibodies)
+    Insertion,
+    /// The serial number increases by more than one, because residues are missing or the
+    /// chain is renumbered
+    Jump,
+    /// The serial number decreases, or stays the same without an insertion code
+    Decrease,
+}
+
+impl NumberingStep {
+    /// Determine the step between the residues with the given serial numbers and insertion
+    /// codes
+    pub fn new(first: (isize, Option<&str>), second: (isize, Option<&str>)) -> Self {
+        match (second.0 - first.0, second.1) {
+            (1, None) => Self::Consecutive,
+            (0 | 1, Some(_)) => Self::Insertion,
+            (step, _) if step > 1 => Self::Jump,
+            _ => Self::Decrease,
+        }
+    }
+}
+
+/// A discontinuity between two consecutive residues in a chain, see [`Chain::gaps`]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainGap {
+    /// The index of the residue before the gap, the gap is between this residue and the next
+    pub index: usize,
+    /// The serial numbers and insertion codes of the residues before and after the gap
+    pub residues: [(isize, Option<String>); 2],
+    /// The shortest distance in Å between the linking backbone atoms (C-N for proteins, O3'-P
+    /// for nucleic acids) of the conformers of both residues, if both residues have these atoms
+    pub distance: Option<f64>,
+    /// If the backbone is physically broken: the linking atoms are too far apart to be bonded
+    pub broken: bool,
+    /// The step in the numbering between both residues
+    pub numbering: NumberingStep,
+}
+
+/// Get the shortest distance between the linking backbone atoms of the given residues, if
+/// these are polymer residues of the same kind
+fn link_distance(first: &Residue, second: &Residue) -> Option<f64> {
+    LINKS.iter().find_map(|(from, to)| {
+        first
+            .conformers()
+            .filter_map(|a| find(a, from))
+            .flat_map(|atom| {
+                second
+                    .conformers()
+                    .filter(|b| to.iter().all(|name| find(b, name).is_some()))
+                    .filter_map(|b| find(b, to[0]))
+                    .map(move |other| atom.distance(other))
+            })
+            .min_by(f64::total_cmp)
+    })
+}
+
+/// Get the bonds linking the given consecutive residues in a polymer, between the linking
+/// backbone atoms of all pairs of conformers with compatible alternative locations that are at
+/// most [`LINK_DISTANCE`] apart
+pub(crate) fn link_bonds<'a>(first: &'a Residue, second: &'a Residue) -> Vec<(&'a Atom, &'a Atom)> {
+    let compatible =
+        |a: &Conformer, b: &Conformer| match (a.alternative_location(), b.alternative_location()) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        };
+    let mut bonds = Vec::new();
+    for (from, to) in LINKS {
+        for a in first.conformers() {
+            if let Some(atom) = find(a, from) {
+                for b in second.conformers().filter(|b| compatible(a, b)) {
+                    if to.iter().all(|name| find(b, name).is_some()) {
+                        let other = find(b, to[0]).filter(|o| atom.distance(o) <= LINK_DISTANCE);
+                        bonds.extend(other.map(|other| (atom, other)));
+                    }
+                }
+            }
+        }
+    }
+    bonds
+}
+
+/// Find the atom with the given name in the given conformer
+fn find<'a>(conformer: &'a Conformer, name: &str) -> Option<&'a Atom> {
+    conformer.atoms().find(|a| a.name() == name)
+}
+
+/// Find the gaps in the given chain, see [`Chain::gaps`]
+pub(crate) fn gaps(chain: &Chain) -> Vec<ChainGap> {
+    let residues: Vec<&Residue> = chain.residues().collect();
+    residues
+        .windows(2)
+        .enumerate()
+        .filter_map(|(index, pair)| {
+            let distance = link_distance(pair[0], pair[1]);
+            let broken = distance.map_or(false, |d| d > LINK_DISTANCE);
+            let numbering = NumberingStep::new(pair[0].id(), pair[1].id());
+            (broken || numbering != NumberingStep::Consecutive).then(|| ChainGap {
+                index,
+                residues: [pair[0], pair[1]].map(|r| {
+                    (
+                        r.serial_number(),
+                        r.insertion_code().map(ToString::to_string),
+                    )
+                }),
+                distance,
+                broken,
+                numbering,
+            })
+        })
+        .collect()
+}
+
+/// Find the segments in the given chain, see [`Chain::segments`]
+pub(crate) fn segments(chain: &Chain) -> Vec<Range<usize>> {
+    let mut output = Vec::new();
+    let mut start = 0;
+    for gap in gaps(chain).into_iter().filter(|gap| gap.broken) {
+        output.push(start..gap.index + 1);
+        start = gap.index + 1;
+    }
+    if start < chain.residue_count() {
+        output.push(start..chain.residue_count());
+    }
+    output
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{open_pdb, StrictnessLevel};
+
+    fn ubiquitin() -> PDB {
+        let (mut pdb, _) = open_pdb("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+        pdb.remove_residues_by(|r| r.name() == Some("HOH"));
+        pdb
+    }
+
+    #[test]
+    fn numbering() {
+        use NumberingStep::*;
+        assert_eq!(NumberingStep::new((1, None), (2, None)), Consecutive);
+        assert_eq!(NumberingStep::new((52, None), (52, Some("A"))), Insertion);
+        assert_eq!(
+            NumberingStep::new((52, Some("A")), (52, Some("B"))),
+            Insertion
+        );
+        assert_eq!(NumberingStep::new((52, Some("B")), (53, None)), Consecutive);
+        assert_eq!(NumberingStep::new((5, None), (9, None)), Jump);
+        assert_eq!(NumberingStep::new((5, None), (5, None)), Decrease);
+        assert_eq!(NumberingStep::new((5, None), (-1, None)), Decrease);
+    }
+
+    #[test]
+    fn continuous() {
+        let pdb = ubiquitin();
+        let chain = pdb.chain(0).unwrap();
+        assert!(chain.gaps().is_empty());
+        assert_eq!(chain.segments(), vec![0..76]);
+        assert!(Chain::new("A").unwrap().segments().is_empty());
+    }
+
+    #[test]
+    fn missing_residue() {
+        let mut pdb = ubiquitin();
+        pdb.chain_mut(0).unwrap().remove_residue(10);
+        let chain = pdb.chain(0).unwrap();
+        let gaps = chain.gaps();
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].index, 9);
+        assert_eq!(gaps[0].residues, [(10, None), (12, None)]);
+        assert!(gaps[0].broken);
+        assert!(gaps[0].distance.unwrap() > 3.0);
+        assert_eq!(gaps[0].numbering, NumberingStep::Jump);
+        assert_eq!(chain.segments(), vec![0..10, 10..75]);
+    }
+
+    #[test]
+    fn renumbered_and_unnumbered() {
+        let mut pdb = ubiquitin();
+        let chain = pdb.chain_mut(0).unwrap();
+        for residue in chain.residues_mut().skip(20) {
+            residue.set_serial_number(residue.serial_number() + 100);
+        }
+        chain.residue_mut(40).unwrap().set_insertion_code("A");
+        chain.residue_mut(40).unwrap().set_serial_number(140);
+        chain.remove_residue(60);
+        for residue in chain.residues_mut().skip(60) {
+            residue.set_serial_number(residue.serial_number() - 1);
+        }
+        let gaps = chain.gaps();
+        assert_eq!(gaps.len(), 4);
+        assert_eq!(gaps[0].residues, [(20, None), (121, None)]);
+        assert!(!gaps[0].broken);
+        assert!(gaps[0].distance.unwrap() < 1.5);
+        assert_eq!(gaps[0].numbering, NumberingStep::Jump);
+        assert_eq!(
+            gaps[1].residues,
+            [(140, None), (140, Some("A".to_string()))]
+        );
+        assert_eq!(gaps[1].numbering, NumberingStep::Insertion);
+        assert!(!gaps[1].broken);
+        assert_eq!(
+            gaps[2].residues,
+            [(140, Some("A".to_string())), (142, None)]
+        );
+        assert_eq!(gaps[2].numbering, NumberingStep::Jump);
+        assert_eq!(gaps[3].index, 59);
+        assert!(gaps[3].broken);
+        assert_eq!(gaps[3].numbering, NumberingStep::Consecutive);
+        assert_eq!(chain.segments(), vec![0..60, 60..75]);
+    }
+
+    #[test]
+    fn split() {
+        let mut pdb = ubiquitin();
+        pdb.chain_mut(0).unwrap().remove_residue(50);
+        pdb.chain_mut(0).unwrap().remove_residue(10);
+        let ligand = pdb
+            .residues()
+            .find(|r| r.name() == Some("ALA"))
+            .unwrap()
+            .clone();
+        let mut other = Chain::new("B").unwrap();
+        other.add_residue(ligand);
+        pdb.model_mut(0).unwrap().add_chain(other);
+        let mut second = pdb.model(0).unwrap().clone();
+        second.set_serial_number(2);
+        pdb.add_model(second);
+
+        assert_eq!(pdb.split_chains_at_breaks(), 4);
+        for model in pdb.models() {
+            let ids: Vec<_> = model.chains().map(Chain::id).collect();
+            assert_eq!(ids, ["A", "C", "D", "B"]);
+            let counts: Vec<_> = model.chains().map(Chain::residue_count).collect();
+            assert_eq!(counts, [10, 39, 25, 1]);
+            assert!(model.chains().all(|c| c.gaps().iter().all(|g| !g.broken)));
+        }
+        assert_eq!(
+            pdb.chain(1).unwrap().residue(0).unwrap().serial_number(),
+            12
+        );
+        assert_eq!(pdb.split_chains_at_breaks(), 0);
+    }
+}
@@ -0,0 +1,897 @@
+use crate::error::*;
+use crate::structs::*;
+use std::ops::{Add, Sub};
+use std::str::FromStr;
+
+/// A comparison operator in a selection, like `b > 30`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    /// `<`
+    Less,
+    /// `<=`
+    LessEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterEqual,
+    /// `=` or `==`
+    Equal,
+}
+
+/// The kind of a token in a selection
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    /// A bare word: a keyword, an operator, or a value
+    Word(String),
+    /// A quoted value, this is never interpreted as a keyword
+    Quoted(String),
+    /// An opening parenthesis
+    Open,
+    /// A closing parenthesis
+    Close,
+    /// A comparison operator
+    Compare(Comparison),
+}
+
+/// A token in a selection with its position, to point to it in error messages
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    /// The kind and contents of the token
+    kind: TokenKind,
+    /// The byte offset of the token in the selection
+    offset: usize,
+    /// The length in bytes of the token in the selection
+    length: usize,
+}
+
+/// The properties that can be selected on, with the values given after the keyword
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Keyword {
+    /// The model serial number
+    Model,
+    /// The chain id
+    Chain,
+    /// The residue serial number, optionally with insertion code
+    Residue,
+    /// The residue (conformer) name
+    ResidueName,
+    /// The residue insertion code
+    InsertionCode,
+    /// The conformer alternative location
+    AlternativeLocation,
+    /// The atom name
+    Name,
+    /// The atom serial number
+    Serial,
+    /// The atom element
+    Element,
+    /// The atom B factor
+    BFactor,
+    /// The atom occupancy
+    Occupancy,
+    /// The atom charge
+    Charge,
+    /// The atom segment id
+    Segment,
+    /// The mmCIF label chain id
+    LabelChain,
+    /// The mmCIF label residue number
+    LabelResidue,
+    /// The mmCIF entity id
+    Entity,
+}
+
+/// The keywords of the selection language with their aliases
+const KEYWORDS: &[(&str, Keyword)] = &[
+    ("model", Keyword::Model),
+    ("chain", Keyword::Chain),
+    ("resi", Keyword::Residue),
+    ("resid", Keyword::Residue),
+    ("resn", Keyword::ResidueName),
+    ("resname", Keyword::ResidueName),
+    ("icode", Keyword::InsertionCode),
+    ("altloc", Keyword::AlternativeLocation),
+    ("alt", Keyword::AlternativeLocation),
+    ("name", Keyword::Name),
+    ("serial", Keyword::Serial),
+    ("id", Keyword::Serial),
+    ("elem", Keyword::Element),
+    ("element", Keyword::Element),
+    ("b", Keyword::BFactor),
+    ("beta", Keyword::BFactor),
+    ("q", Keyword::Occupancy),
+    ("occupancy", Keyword::Occupancy),
+    ("charge", Keyword::Charge),
+    ("segid", Keyword::Segment),
+    ("segi", Keyword::Segment),
+    ("label_chain", Keyword::LabelChain),
+    ("label_asym", Keyword::LabelChain),
+    ("label_seq", Keyword::LabelResidue),
+    ("entity", Keyword::Entity),
+];
+
+/// Get the selection for a keyword that does not take a value
+fn flag(word: &str) -> Option<Search> {
+    match word {
+        "all" => Some(Search::Known(true)),
+        "none" => Some(Search::Known(false)),
+        "backbone" | "bb" => Some(Search::Single(Term::Backbone)),
+        "sidechain" | "sc" => Some(Search::Single(Term::SideChain)),
+        "hetatm" | "hetero" => Some(Search::Single(Term::Hetero)),
+        "water" | "solvent" => Some(Search::Single(Term::Water)),
+        "ion" | "ions" => Some(Search::Single(Term::Ion)),
+        "ligand" | "organic" => Some(Search::Single(Term::Ligand) & !Term::Ion),
+        "protein" => Some(Search::Single(Term::PolymerType(PolymerType::Protein))),
+        "dna" => Some(Search::Single(Term::PolymerType(PolymerType::Dna))),
+        "rna" => Some(Search::Single(Term::PolymerType(PolymerType::Rna))),
+        "nucleic" => {
+            Some(Term::PolymerType(PolymerType::Dna) | Term::PolymerType(PolymerType::Rna))
+        }
+        _ => None,
+    }
+}
+
+/// The logical operators, in words and symbols
+const OR: &[&str] = &["or", "|"];
+/// The exclusive or operator, in words and symbols
+const XOR: &[&str] = &["xor", "^"];
+/// The and operator, in words and symbols
+const AND: &[&str] = &["and", "&"];
+/// The not operator, in words and symbols
+const NOT: &[&str] = &["not", "!"];
+/// The word separating the ends of a range
+const TO: &str = "to";
+/// The spatial operator, followed by a distance, [`OF`], and a selection
+const WITHIN: &str = "within";
+/// The word separating the distance and selection of [`WITHIN`]
+const OF: &str = "of";
+/// The operator selecting whole residues
+const BYRES: &str = "byres";
+/// The operator selecting whole chains
+const BYCHAIN: &str = "bychain";
+/// The operator selecting whole entities, followed by [`ENTITY`] and [`AS`]
+const SAME: &str = "same";
+/// The second word of the [`SAME`] operator
+const ENTITY: &str = "entity";
+/// The last word of the [`SAME`] operator
+const AS: &str = "as";
+
+/// Check if the given word has a special meaning and so cannot be used as an unquoted value
+fn reserved(word: &str) -> bool {
+    [OR, XOR, AND, NOT].iter().any(|ops| ops.contains(&word))
+        || [TO, WITHIN, OF, BYRES, BYCHAIN, SAME].contains(&word)
+        || KEYWORDS.iter().any(|(name, _)| *name == word)
+        || flag(word).is_some()
+}
+
+/// Split the given selection into tokens
+fn tokenize(text: &str) -> Result<Vec<Token>, PDBError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let kind = match c {
+            c if c.is_whitespace() => continue,
+            '(' => TokenKind::Open,
+            ')' => TokenKind::Close,
+            '<' | '>' | '=' => {
+                let equal = chars.next_if(|(_, c)| *c == '=').is_some();
+                TokenKind::Compare(match (c, equal) {
+                    ('<', false) => Comparison::Less,
+                    ('<', true) => Comparison::LessEqual,
+                    ('>', false) => Comparison::Greater,
+                    ('>', true) => Comparison::GreaterEqual,
+                    _ => Comparison::Equal,
+                })
+            }
+            '&' | '|' | '^' | '!' => TokenKind::Word(c.to_string()),
+            '"' | '\'' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, q)) if q == c => break,
+                        Some((_, x)) => value.push(x),
+                        None => {
+                            return Err(error(
+                                text,
+                                start,
+                                text.len() - start,
+                                "This quoted value is not closed.",
+                            ))
+                        }
+                    }
+                }
+                TokenKind::Quoted(value)
+            }
+            _ => {
+                let mut value = c.to_string();
+                while let Some((_, x)) =
+                    chars.next_if(|(_, x)| !x.is_whitespace() && !"()<>=&|^!".contains(*x))
+                {
+                    value.push(x);
+                }
+                TokenKind::Word(value)
+            }
+        };
+        let end = chars.peek().map_or(text.len(), |(i, _)| *i);
+        tokens.push(Token {
+            kind,
+            offset: start,
+            length: end - start,
+        });
+    }
+    Ok(tokens)
+}
+
+/// Create an error pointing to the given part of the selection
+fn error(
+    text: &str,
+    offset: usize,
+    length: usize,
+    message: impl std::string::ToString,
+) -> PDBError {
+    PDBError::new(
+        ErrorLevel::InvalidatingError,
+        "Invalid selection",
+        message,
+        Context::line(1, text, offset, length.max(1)),
+    )
+}
+
+/// Get the range of numbers satisfying the comparison with the given number, or `None` if no
+/// number within the bounds satisfies it
+fn bounds<T>(comparison: Comparison, value: T, min: T, max: T) -> Option<(T, T)>
+where
+    T: Copy + PartialEq + Add<Output = T> + Sub<Output = T> + From<u8>,
+{
+    let one = T::from(1);
+    match comparison {
+        Comparison::Less => (value != min).then(|| (min, value - one)),
+        Comparison::LessEqual => Some((min, value)),
+        Comparison::Greater => (value != max).then(|| (value + one, max)),
+        Comparison::GreaterEqual => Some((value, max)),
+        Comparison::Equal => Some((value, value)),
+    }
+}
+
+/// A recursive descent parser for selections, binding `not` tightest, followed by `and`, `xor`,
+/// and `or`
+struct Parser<'a> {
+    /// The full selection, for error messages
+    text: &'a str,
+    /// The tokens of the selection
+    tokens: Vec<Token>,
+    /// The index of the next token
+    index: usize,
+}
+
+impl Parser<'_> {
+    /// Get the next token without consuming it
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+
+    /// Consume the next token
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    /// Consume the next token if it is one of the given operators
+    fn operator(&mut self, operators: &[&str]) -> bool {
+        let found = matches!(self.peek(), Some(Token { kind: TokenKind::Word(word), .. }) if operators.contains(&word.as_str()));
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    /// Create an error pointing to the given token
+    fn error(&self, token: &Token, message: impl std::string::ToString) -> PDBError {
+        error(self.text, token.offset, token.length, message)
+    }
+
+    /// Create an error pointing to the next token, or the end of the selection if there is none
+    fn error_next(&self, message: impl std::string::ToString) -> PDBError {
+        match self.peek() {
+            Some(next) => self.error(next, message),
+            None => self.error_end(message),
+        }
+    }
+
+    /// Create an error pointing to the end of the selection
+    fn error_end(&self, message: impl std::string::ToString) -> PDBError {
+        error(self.text, self.text.len(), 1, message)
+    }
+
+    /// Parse a full selection
+    fn selection(&mut self) -> Result<Search, PDBError> {
+        if self.tokens.is_empty() {
+            return Err(self.error_end("The selection is empty."));
+        }
+        let search = self.or()?;
+        match self.peek() {
+            None => Ok(search),
+            Some(token) if token.kind == TokenKind::Close => Err(self.error(
+                token,
+                "This closing parenthesis has no opening parenthesis.",
+            )),
+            Some(token) => Err(self.error(
+                token,
+                "Expected an operator (and, or, xor) to combine this with the previous selection.",
+            )),
+        }
+    }
+
+    /// Parse selections combined with `or`
+    fn or(&mut self) -> Result<Search, PDBError> {
+        let mut search = self.xor()?;
+        while self.operator(OR) {
+            search = search | self.xor()?;
+        }
+        Ok(search)
+    }
+
+    /// Parse selections combined with `xor`
+    fn xor(&mut self) -> Result<Search, PDBError> {
+        let mut search = self.and()?;
+        while self.operator(XOR) {
+            search = search ^ self.and()?;
+        }
+        Ok(search)
+    }
+
+    /// Parse selections combined with `and`
+    fn and(&mut self) -> Result<Search, PDBError> {
+        let mut search = self.not()?;
+        while self.operator(AND) {
+            search = search & self.not()?;
+        }
+        Ok(search)
+    }
+
+    /// Parse a possibly negated selection
+    fn not(&mut self) -> Result<Search, PDBError> {
+        if self.operator(NOT) {
+            Ok(!self.not()?)
+        } else if self.operator(&[WITHIN]) {
+            self.within()
+        } else if self.operator(&[BYRES]) {
+            Ok(Search::Single(Term::ByResidue(Box::new(self.not()?))))
+        } else if self.operator(&[BYCHAIN]) {
+            Ok(Search::Single(Term::ByChain(Box::new(self.not()?))))
+        } else if self.operator(&[SAME]) {
+            if !(self.operator(&[ENTITY]) && self.operator(&[AS])) {
+                return Err(self.error_next(format!("Expected '{ENTITY} {AS}' after '{SAME}'.")));
+            }
+            Ok(Search::Single(Term::SameEntity(Box::new(self.not()?))))
+        } else {
+            self.primary()
+        }
+    }
+
+    /// Parse the distance and selection after `within`
+    fn within(&mut self) -> Result<Search, PDBError> {
+        let token = self
+            .value()
+            .ok_or_else(|| self.error_next(format!("Expected a distance after '{WITHIN}'.")))?;
+        let distance = match &token.kind {
+            TokenKind::Word(s) | TokenKind::Quoted(s) => self.number(WITHIN, s, &token)?,
+            _ => unreachable!(),
+        };
+        if !self.operator(&[OF]) {
+            return Err(
+                self.error_next(format!("Expected '{OF}' after the distance of '{WITHIN}'."))
+            );
+        }
+        Ok(Search::Single(Term::WithinSearch(
+            Box::new(self.not()?),
+            distance,
+        )))
+    }
+
+    /// Parse a single selection: a keyword with values, a flag, or a parenthesised selection
+    fn primary(&mut self) -> Result<Search, PDBError> {
+        let token = match self.next() {
+            Some(token) => token,
+            None => return Err(self.error_end("Expected a selection here.")),
+        };
+        match &token.kind {
+            TokenKind::Open => {
+                let search = self.or()?;
+                match self.next() {
+                    Some(Token {
+                        kind: TokenKind::Close,
+                        ..
+                    }) => Ok(search),
+                    Some(other) => Err(self.error(&other, "Expected a closing parenthesis.")),
+                    None => Err(self.error(&token, "This parenthesis is not closed.")),
+                }
+            }
+            TokenKind::Word(word) => {
+                if let Some(search) = flag(word) {
+                    Ok(search)
+                } else if let Some((_, keyword)) = KEYWORDS.iter().find(|(name, _)| name == word) {
+                    self.values(*keyword, &token, word)
+                } else {
+                    Err(self.error(
+                        &token,
+                        format!("Unknown keyword '{word}', expected a keyword like chain, resi, resn, name, or elem."),
+                    ))
+                }
+            }
+            _ => Err(self.error(&token, "Expected a selection keyword here.")),
+        }
+    }
+
+    /// Parse the values of a keyword, either a comparison or a list of values and ranges
+    fn values(&mut self, keyword: Keyword, token: &Token, name: &str) -> Result<Search, PDBError> {
+        if let Some(operator) = self.peek().cloned() {
+            if let TokenKind::Compare(comparison) = operator.kind {
+                self.index += 1;
+                let value = self.value().ok_or_else(|| {
+                    self.error_end(format!("Expected a value to compare '{name}' to."))
+                })?;
+                let text = match &value.kind {
+                    TokenKind::Word(s) | TokenKind::Quoted(s) => s.clone(),
+                    _ => unreachable!(),
+                };
+                return self.compare(keyword, name, comparison, &text, &operator, &value);
+            }
+        }
+
+        let mut search: Option<Search> = None;
+        while let Some(value) = self.value() {
+            let term = match &value.kind {
+                TokenKind::Word(word) if self.operator(&[TO]) => {
+                    let high = self.value().ok_or_else(|| {
+                        self.error_end(format!("Expected the end of the range after '{TO}'."))
+                    })?;
+                    match &high.kind {
+                        TokenKind::Word(high) | TokenKind::Quoted(high) => {
+                            self.range(keyword, name, word, high, &value)?
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+                TokenKind::Word(word) => {
+                    let mut terms = None;
+                    for part in word.split('+').filter(|p| !p.is_empty()) {
+                        let term = match split_range(keyword, part) {
+                            Some((low, high)) => self.range(keyword, name, low, high, &value)?,
+                            None => self.term(keyword, name, part, &value)?,
+                        };
+                        terms = Some(match terms {
+                            Some(terms) => terms | term,
+                            None => term,
+                        });
+                    }
+                    match terms {
+                        Some(terms) => terms,
+                        None => return Err(self.error(&value, "Expected a value here.")),
+                    }
+                }
+                TokenKind::Quoted(text) => self.term(keyword, name, text, &value)?,
+                _ => unreachable!(),
+            };
+            search = Some(match search {
+                Some(search) => search | term,
+                None => term,
+            });
+        }
+        search.ok_or_else(|| {
+            let message = format!("Expected a value after '{name}'.");
+            match self.peek() {
+                Some(next) => self.error(next, message),
+                None => self.error(token, message),
+            }
+        })
+    }
+
+    /// Consume the next token if it is a value: a quoted value or a word without special meaning
+    fn value(&mut self) -> Option<Token> {
+        let is_value = match &self.peek()?.kind {
+            TokenKind::Quoted(_) => true,
+            TokenKind::Word(word) => !reserved(word),
+            _ => false,
+        };
+        if is_value {
+            self.next()
+        } else {
+            None
+        }
+    }
+
+    /// Parse a number for the given keyword
+    fn number<T: FromStr>(&self, name: &str, value: &str, token: &Token) -> Result<T, PDBError> {
+        value.parse().map_err(|_| {
+            self.error(
+                token,
+                format!("Could not parse '{value}' as a valid number for '{name}'."),
+            )
+        })
+    }
+
+    /// Create the search for a single value of a keyword
+    fn term(
+        &self,
+        keyword: Keyword,
+        name: &str,
+        value: &str,
+        token: &Token,
+    ) -> Result<Search, PDBError> {
+        let optional = || (!value.is_empty()).then(|| value.to_string());
+        let term = match keyword {
+            Keyword::Model => Term::ModelSerialNumber(self.number(name, value, token)?),
+            Keyword::Chain => Term::ChainId(value.to_string()),
+            Keyword::Residue => {
+                let split = value
+                    .char_indices()
+                    .find(|(i, c)| !(c.is_ascii_digit() || (*i == 0 && *c == '-')))
+                    .map_or(value.len(), |(i, _)| i);
+                let number = self.number(
+                    name,
+                    if split == 0 { value } else { &value[..split] },
+                    token,
+                )?;
+                if split == value.len() {
+                    Term::ResidueSerialNumber(number)
+                } else {
+                    Term::ResidueId(number, Some(value[split..].to_string()))
+                }
+            }
+            Keyword::ResidueName => Term::ConformerName(value.to_string()),
+            Keyword::InsertionCode => Term::ResidueInsertionCode(optional()),
+            Keyword::AlternativeLocation => Term::ConformerAlternativeLocation(optional()),
+            Keyword::Name => Term::AtomName(value.to_string()),
+            Keyword::Serial => Term::AtomSerialNumber(self.number(name, value, token)?),
+            Keyword::Element => Term::Element(Element::try_from(value).map_err(|_| {
+                self.error(token, format!("Unknown element '{value}' for '{name}'."))
+            })?),
+            Keyword::BFactor => Term::BFactor(self.number(name, value, token)?),
+            Keyword::Occupancy => Term::Occupancy(self.number(name, value, token)?),
+            Keyword::Charge => Term::Charge(self.number(name, value, token)?),
+            Keyword::Segment => Term::SegmentId(optional()),
+            Keyword::LabelChain => Term::LabelChainId(value.to_string()),
+            Keyword::LabelResidue => Term::LabelResidueNumber(self.number(name, value, token)?),
+            Keyword::Entity => Term::EntityId(value.to_string()),
+        };
+        Ok(Search::Single(term))
+    }
+
+    /// Create the search for an inclusive range of values of a keyword
+    fn range(
+        &self,
+        keyword: Keyword,
+        name: &str,
+        low: &str,
+        high: &str,
+        token: &Token,
+    ) -> Result<Search, PDBError> {
+        let term = match keyword {
+            Keyword::Model => Term::ModelSerialNumberRange(
+                self.number(name, low, token)?,
+                self.number(name, high, token)?,
+            ),
+            Keyword::Chain => Term::ChainIdRange(low.to_string(), high.to_string()),
+            Keyword::InsertionCode => {
+                Term::ResidueInsertionCodeRange(low.to_string(), high.to_string())
+            }
+            Keyword::Residue => Term::ResidueSerialNumberRange(
+                self.number(name, low, token)?,
+                self.number(name, high, token)?,
+            ),
+            Keyword::Serial => Term::AtomSerialNumberRange(
+                self.number(name, low, token)?,
+                self.number(name, high, token)?,
+            ),
+            Keyword::BFactor => Term::BFactorRange(
+                self.number(name, low, token)?,
+                self.number(name, high, token)?,
+            ),
+            Keyword::Occupancy => Term::OccupancyRange(
+                self.number(name, low, token)?,
+                self.number(name, high, token)?,
+            ),
+            Keyword::Charge => Term::ChargeRange(
+                self.number(name, low, token)?,
+                self.number(name, high, token)?,
+            ),
+            Keyword::LabelResidue => Term::LabelResidueNumberRange(
+                self.number(name, low, token)?,
+                self.number(name, high, token)?,
+            ),
+            _ => return Err(self.error(token, format!("'{name}' does not support ranges."))),
+        };
+        Ok(Search::Single(term))
+    }
+
+    /// Create the search for a comparison of a numeric keyword with a value
+    fn compare(
+        &self,
+        keyword: Keyword,
+        name: &str,
+        comparison: Comparison,
+        value: &str,
+        operator: &Token,
+        token: &Token,
+    ) -> Result<Search, PDBError> {
+        let float = |exact: fn(f64) -> Term, range: fn(f64, f64) -> Term| {
+            let value: f64 = self.number(name, value, token)?;
+            Ok(match comparison {
+                Comparison::Less => range(f64::NEG_INFINITY, value) & !exact(value),
+                Comparison::LessEqual => Search::Single(range(f64::NEG_INFINITY, value)),
+                Comparison::Greater => range(value, f64::INFINITY) & !exact(value),
+                Comparison::GreaterEqual => Search::Single(range(value, f64::INFINITY)),
+                Comparison::Equal => Search::Single(exact(value)),
+            })
+        };
+        match keyword {
+            Keyword::Model => {
+                let value = self.number(name, value, token)?;
+                Ok(bounds(comparison, value, 0, usize::MAX).map_or(Search::Known(false), |(low, high)| {
+                    Search::Single(Term::ModelSerialNumberRange(low, high))
+                }))
+            }
+            Keyword::Residue => {
+                let value = self.number(name, value, token)?;
+                Ok(bounds(comparison, value, isize::MIN, isize::MAX).map_or(Search::Known(false), |(low, high)| {
+                    Search::Single(Term::ResidueSerialNumberRange(low, high))
+                }))
+            }
+            Keyword::Serial => {
+                let value = self.number(name, value, token)?;
+                Ok(bounds(comparison, value, 0, usize::MAX).map_or(Search::Known(false), |(low, high)| {
+                    Search::Single(Term::AtomSerialNumberRange(low, high))
+                }))
+            }
+            Keyword::Charge => {
+                let value = self.number(name, value, token)?;
+                Ok(bounds(comparison, value, isize::MIN, isize::MAX).map_or(Search::Known(false), |(low, high)| {
+                    Search::Single(Term::ChargeRange(low, high))
+                }))
+            }
+            Keyword::LabelResidue => {
+                let value = self.number(name, value, token)?;
+                Ok(bounds(comparison, value, isize::MIN, isize::MAX).map_or(Search::Known(false), |(low, high)| {
+                    Search::Single(Term::LabelResidueNumberRange(low, high))
+                }))
+            }
+            Keyword::BFactor => float(Term::BFactor, Term::BFactorRange),
+            Keyword::Occupancy => float(Term::Occupancy, Term::OccupancyRange),
+            _ => Err(self.error(
+                operator,
+                format!("'{name}' cannot be compared, only numeric properties (model, resi, label_seq, serial, b, q, charge) can."),
+            )),
+        }
+    }
+}
+
+/// Split a value into the ends of a range if it is written as one, `low:high` or for numeric
+/// keywords also `low-high`
+fn split_range(keyword: Keyword, value: &str) -> Option<(&str, &str)> {
+    if let Some(split) = value.split_once(':') {
+        return Some(split);
+    }
+    if matches!(
+        keyword,
+        Keyword::Model
+            | Keyword::Residue
+            | Keyword::Serial
+            | Keyword::BFactor
+            | Keyword::Occupancy
+            | Keyword::Charge
+            | Keyword::LabelResidue
+    ) {
+        // Skip a leading minus sign and only split on a minus after a digit, so `-5--2` works
+        let position = value
+            .char_indices()
+            .skip(1)
+            .find(|(i, c)| *c == '-' && value[..*i].ends_with(|c: char| c.is_ascii_digit()))?
+            .0;
+        Some((&value[..position], &value[position + 1..]))
+    } else {
+        None
+    }
+}
+
+/// Parse a selection in a language similar to the one used by PyMOL and VMD into a [`Search`].
+/// A selection combines keywords with `and`, `or`, `xor`, and `not` (or `&`, `|`, `^`, and
+/// `!`), with `not` binding tightest followed by `and`, `xor`, and `or`, and parentheses to
+/// group. These keywords are supported:
+///
+/// | Keyword | Selects on |
+/// |---|---|
+/// | `model` | the model serial number |
+/// | `chain` | the (author) chain id |
+/// | `resi`, `resid` | the (author) residue serial number, with an optional insertion code (`52A`) |
+/// | `resn`, `resname` | the residue name |
+/// | `icode` | the insertion code |
+/// | `altloc`, `alt` | the alternative location |
+/// | `name` | the atom name |
+/// | `serial`, `id` | the atom serial number |
+/// | `elem`, `element` | the element |
+/// | `b`, `beta` | the B factor |
+/// | `q`, `occupancy` | the occupancy |
+/// | `charge` | the atom charge |
+/// | `segid`, `segi` | the segment id |
+/// | `label_chain`, `label_asym` | the mmCIF label chain id (`label_asym_id`) |
+/// | `label_seq` | the mmCIF label residue number (`label_seq_id`) |
+/// | `entity` | the mmCIF entity id (`label_entity_id`) |
+/// | `backbone`, `bb`, `sidechain`, `sc`, `hetatm`, `hetero`, `all`, `none` | these take no values |
+/// | `water`, `solvent`, `ion`, `ions`, `ligand`, `organic` | these take no values, `ligand` excludes ions, see [`Term::Ligand`] |
+/// | `protein`, `dna`, `rna`, `nucleic` | these take no values, see [`Term::PolymerType`] |
+///
+/// A keyword is followed by one or more values, separated by spaces or `+`, selecting atoms
+/// matching any of the values. Numeric keywords also accept inclusive ranges (`10-20`,
+/// `10:20`, or `10 to 20`) and comparisons (`b > 30`), chain ids and insertion codes accept
+/// ranges written as `A:C`. Values can be quoted to use a keyword as value or to give an empty
+/// value (`altloc ""` selects atoms without alternative location). Keywords are case sensitive,
+/// while values are matched exactly as given except for elements.
+///
+/// `within 5 of <selection>` selects all atoms within 5 Å of the atoms matching the selection
+/// in the same model, see [`Term::WithinSearch`]. Like `not` it applies to the selection
+/// directly following it, so use parentheses to combine selections: `within 5 of (resn HEM and
+/// chain A)`. In the same way `byres`, `bychain`, and `same entity as` select all atoms of the
+/// residues, chains, or entities with any atom matching the selection following it, see
+/// [`Term::ByResidue`], [`Term::ByChain`], and [`Term::SameEntity`].
+///
+/// ```
+/// use pdbtbx::*;
+/// let (pdb, _errors) = open("example-pdbs/1ubq.pdb", StrictnessLevel::Medium).unwrap();
+/// let search: Search = "chain A and resi 10-20 and name CA".parse().unwrap();
+/// assert_eq!(pdb.find(search).count(), 11);
+/// let error = "chain A and resi ten".parse::<Search>().unwrap_err();
+/// assert_eq!(error.short_description(), "Invalid selection");
+/// ```
+impl FromStr for Search {
+    type Err = PDBError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser {
+            text: s,
+            tokens: tokenize(s)?,
+            index: 0,
+        }
+        .selection()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{open_pdb, StrictnessLevel};
+
+    fn count(pdb: &PDB, selection: &str) -> usize {
+        pdb.find(selection.parse().unwrap()).count()
+    }
+
+    fn fails(selection: &str, offset: usize, message: &str) {
+        let error = selection.parse::<Search>().expect_err(selection);
+        assert_eq!(error.short_description(), "Invalid selection");
+        assert!(
+            error.long_description().contains(message),
+            "{}",
+            error.long_description()
+        );
+        match error.context() {
+            Context::Line { offset: o, .. } => assert_eq!(*o, offset, "{selection}"),
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn selections() {
+        let (pdb, _) = open_pdb("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+        assert_eq!(count(&pdb, "all"), pdb.atom_count());
+        assert_eq!(count(&pdb, "none"), 0);
+        assert_eq!(count(&pdb, "name CA"), 76);
+        assert_eq!(count(&pdb, "chain A and resi 10-20 and name CA"), 11);
+        assert_eq!(count(&pdb, "resi 10:20 & name CA"), 11);
+        assert_eq!(count(&pdb, "resi 10 to 20 and name CA"), 11);
+        assert_eq!(count(&pdb, "name CA and resi 1+3+5 10-12"), 6);
+        assert_eq!(count(&pdb, "name CA and resi < 11"), 10);
+        assert_eq!(count(&pdb, "name CA and resi <= 11"), 11);
+        assert_eq!(count(&pdb, "name CA and resi >= 70"), 7);
+        assert_eq!(count(&pdb, "name CA and resi > -5"), 76);
+        assert_eq!(count(&pdb, "name CA and resi 0"), 0);
+        assert_eq!(count(&pdb, "resn GLY and name CA"), 6);
+        assert_eq!(count(&pdb, "resname ALA GLY and name CA"), 8);
+        assert_eq!(count(&pdb, "resn HOH"), 40);
+        assert_eq!(count(&pdb, "hetatm"), 40);
+        assert_eq!(count(&pdb, "not hetatm"), 1231);
+        assert_eq!(count(&pdb, "!hetatm and not (backbone or sidechain)"), 0);
+        assert_eq!(
+            count(&pdb, "backbone xor name CA"),
+            pdb.find(Search::Single(Term::Backbone)).count() - 76
+        );
+        assert_eq!(count(&pdb, "elem S"), 1);
+        assert_eq!(count(&pdb, "element o and not resn HOH"), 118);
+        assert_eq!(
+            count(&pdb, "b > 30"),
+            pdb.atoms().filter(|a| a.b_factor() > 30.0).count()
+        );
+        assert_eq!(
+            count(&pdb, "b < 10 or b 30 to 40"),
+            pdb.atoms()
+                .filter(|a| a.b_factor() < 10.0 || (30.0..=40.0).contains(&a.b_factor()))
+                .count()
+        );
+        assert_eq!(
+            count(&pdb, "q = 1.0"),
+            pdb.atoms().filter(|a| a.occupancy() == 1.0).count()
+        );
+        assert_eq!(count(&pdb, "serial 1-10"), 10);
+        assert_eq!(count(&pdb, "id > 600"), 671);
+        assert_eq!(count(&pdb, "model 0 and chain A:B and altloc \"\""), 1271);
+        assert_eq!(count(&pdb, "model 1"), 0);
+        assert_eq!(count(&pdb, "chain B"), 0);
+        assert_eq!(count(&pdb, "icode A"), 0);
+        assert_eq!(count(&pdb, "name 'CA' or name \"N\" and resi 1"), 77);
+        assert_eq!(count(&pdb, "(name CA or name N) and resi 1"), 2);
+        assert_eq!(count(&pdb, "within 0 of resi 1"), count(&pdb, "resi 1"));
+        assert_eq!(
+            count(
+                &pdb,
+                "resn HOH and within 3.5 of (resi 1-20 and not hetatm)"
+            ),
+            pdb.find(
+                Term::ConformerName("HOH".to_string())
+                    & Term::WithinSearch(
+                        Box::new(Term::ResidueSerialNumberRange(1, 20) & !Term::Hetero),
+                        3.5
+                    )
+            )
+            .count()
+        );
+    }
+
+    #[test]
+    fn insertion_codes() {
+        let (mut pdb, _) = open_pdb("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+        pdb.residue_mut(10).unwrap().set_insertion_code("A");
+        assert_eq!(count(&pdb, "resi 11A"), 22);
+        assert_eq!(count(&pdb, "icode A"), 22);
+        assert_eq!(count(&pdb, "resi 11"), 22);
+        assert_eq!(count(&pdb, "resi 11 and icode \"\""), 0);
+        assert_eq!(count(&pdb, "resi -1"), 0);
+    }
+
+    #[test]
+    fn errors() {
+        fails("", 0, "empty");
+        fails("chain", 0, "Expected a value after 'chain'");
+        fails("chain and name CA", 6, "Expected a value after 'chain'");
+        fails("chain A name CA", 8, "Expected an operator");
+        fails("chian A", 0, "Unknown keyword 'chian'");
+        fails("resi ten", 5, "'ten' as a valid number for 'resi'");
+        fails("resi 1-x", 5, "'x' as a valid number");
+        fails("name CA:CB", 5, "does not support ranges");
+        fails("name > CA", 5, "cannot be compared");
+        fails("elem Xx", 5, "Unknown element 'Xx'");
+        fails("(name CA", 0, "not closed");
+        fails("name CA)", 7, "no opening parenthesis");
+        fails("name 'CA", 5, "quoted value is not closed");
+        fails("name CA and", 11, "Expected a selection");
+        fails("not", 3, "Expected a selection");
+        fails("b >", 3, "Expected a value to compare");
+        fails("resi 1 to", 9, "end of the range");
+        fails("= 5", 0, "Expected a selection keyword");
+        fails("within name CA", 7, "Expected a distance");
+        fails(
+            "within five of name CA",
+            7,
+            "'five' as a valid number for 'within'",
+        );
+        fails("within 5 name CA", 9, "Expected 'of'");
+        fails("within 5 of", 11, "Expected a selection");
+        fails("byres", 5, "Expected a selection");
+        fails("same resi 1", 5, "Expected 'entity as' after 'same'");
+        fails(
+            "same entity resi 1",
+            12,
+            "Expected 'entity as' after 'same'",
+        );
+    }
+}
@@ -0,0 +1,406 @@
+use super::geometry::*;
+use super::rebuild;
+use crate::reference_tables;
+use crate::structs::*;
+use std::collections::HashSet;
+
+/// The maximal distance (in Å) between the C and N of two residues to consider them bonded
+const PEPTIDE_BOND: f64 = 2.0;
+
+/// A torsion angle of an amino acid residue
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Torsion {
+    /// The backbone dihedral C(i-1)-N-CA-C
+    Phi,
+    /// The backbone dihedral N-CA-C-N(i+1)
+    Psi,
+    /// The dihedral of the peptide bond preceding this residue CA(i-1)-C(i-1)-N-CA
+    Omega,
+    /// The first side chain dihedral, N-CA-CB-CG for most amino acids
+    Chi1,
+    /// The second side chain dihedral, CA-CB-CG-CD for most amino acids
+    Chi2,
+    /// The third side chain dihedral, CB-CG-CD-CE for most amino acids
+    Chi3,
+    /// The fourth side chain dihedral, CG-CD-CE-NZ for lysine and CG-CD-NE-CZ for arginine
+    Chi4,
+}
+
+impl Torsion {
+    /// Get the index of the side chain dihedral (0 for chi1), if this is a side chain dihedral
+    const fn chi(self) -> Option<usize> {
+        match self {
+            Self::Chi1 => Some(0),
+            Self::Chi2 => Some(1),
+            Self::Chi3 => Some(2),
+            Self::Chi4 => Some(3),
+            _ => None,
+        }
+    }
+}
+
+/// The torsion angles of a single conformer of an amino acid, in degrees in the range
+/// `-180..=180`. Angles that cannot be determined, because atoms are missing, the residue is at
+/// a chain break, or the residue does not have this side chain dihedral, are `None`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Torsions {
+    /// The backbone dihedral C(i-1)-N-CA-C
+    pub phi: Option<f64>,
+    /// The backbone dihedral N-CA-C-N(i+1)
+    pub psi: Option<f64>,
+    /// The dihedral of the peptide bond preceding this residue CA(i-1)-C(i-1)-N-CA
+    pub omega: Option<f64>,
+    /// The side chain dihedrals chi1 to chi4
+    pub chi: [Option<f64>; 4],
+}
+
+impl Torsions {
+    /// Get the value of the given torsion angle
+    pub const fn get(&self, torsion: Torsion) -> Option<f64> {
+        match torsion {
+            Torsion::Phi => self.phi,
+            Torsion::Psi => self.psi,
+            Torsion::Omega => self.omega,
+            Torsion::Chi1 => self.chi[0],
+            Torsion::Chi2 => self.chi[1],
+            Torsion::Chi3 => self.chi[2],
+            Torsion::Chi4 => self.chi[3],
+        }
+    }
+}
+
+/// The torsion angles of a single conformer in a structure, see [`PDB::torsion_table`]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResidueTorsions {
+    /// The serial number of the model
+    pub model: usize,
+    /// The id of the chain
+    pub chain: String,
+    /// The serial number and insertion code of the residue
+    pub residue: (isize, Option<String>),
+    /// The name and alternative location of the conformer
+    pub conformer: (String, Option<String>),
+    /// The torsion angles
+    pub torsions: Torsions,
+}
+
+/// Get the position of the atom with the given name
+fn position(conformer: &Conformer, name: &str) -> Option<Vector> {
+    conformer.atoms().find(|a| a.name() == name).map(Atom::pos)
+}
+
+/// Get the conformer of the given residue with the same alternative location as the given
+/// conformer, or the first conformer if there is no such conformer
+fn matching_conformer<'a>(residue: &'a Residue, conformer: &Conformer) -> Option<&'a Conformer> {
+    residue
+        .conformers()
+        .find(|c| c.alternative_location() == conformer.alternative_location())
+        .or_else(|| residue.conformers().next())
+}
+
+/// Determine if the C of the first conformer is bonded to the N of the second conformer
+fn peptide_bonded(previous: &Conformer, next: &Conformer) -> bool {
+    position(previous, "C")
+        .zip(position(next, "N"))
+        .map_or(false, |(c, n)| length(sub(c, n)) < PEPTIDE_BOND)
+}
+
+/// Get the conformer of the residue before the residue at the given index that is bonded to
+/// the given conformer
+fn previous<'a>(chain: &'a Chain, index: usize, conformer: &Conformer) -> Option<&'a Conformer> {
+    chain
+        .residue(index.checked_sub(1)?)
+        .and_then(|r| matching_conformer(r, conformer))
+        .filter(|p| peptide_bonded(p, conformer))
+}
+
+/// Get the conformer of the residue after the residue at the given index that is bonded to the
+/// given conformer
+pub(crate) fn next<'a>(
+    chain: &'a Chain,
+    index: usize,
+    conformer: &Conformer,
+) -> Option<&'a Conformer> {
+    chain
+        .residue(index + 1)
+        .and_then(|r| matching_conformer(r, conformer))
+        .filter(|n| peptide_bonded(conformer, n))
+}
+
+/// Get the four atom positions defining the given torsion for the given conformer of the
+/// residue at the given index in the chain
+fn torsion_atoms(
+    chain: &Chain,
+    index: usize,
+    conformer: &Conformer,
+    torsion: Torsion,
+) -> Option<[Vector; 4]> {
+    let own = |name| position(conformer, name);
+    let before = |name| previous(chain, index, conformer).and_then(|p| position(p, name));
+    let after = |name| next(chain, index, conformer).and_then(|n| position(n, name));
+    match torsion {
+        Torsion::Phi => Some([before("C")?, own("N")?, own("CA")?, own("C")?]),
+        Torsion::Psi => Some([own("N")?, own("CA")?, own("C")?, after("N")?]),
+        Torsion::Omega => Some([before("CA")?, before("C")?, own("N")?, own("CA")?]),
+        _ => {
+            let names = rebuild::chi_atoms(conformer.name(), torsion.chi()?)?;
+            Some([
+                own(names[0])?,
+                own(names[1])?,
+                own(names[2])?,
+                own(names[3])?,
+            ])
+        }
+    }
+}
+
+/// Calculate the given torsion for the given conformer of the residue at the given index
+pub(crate) fn torsion(
+    chain: &Chain,
+    index: usize,
+    conformer: &Conformer,
+    torsion: Torsion,
+) -> Option<f64> {
+    torsion_atoms(chain, index, conformer, torsion).map(|[a, b, c, d]| dihedral(a, b, c, d))
+}
+
+/// Calculate all torsions for the given conformer of the residue at the given index
+pub(crate) fn torsions(chain: &Chain, index: usize, conformer: &Conformer) -> Torsions {
+    let get = |t| torsion(chain, index, conformer, t);
+    Torsions {
+        phi: get(Torsion::Phi),
+        psi: get(Torsion::Psi),
+        omega: get(Torsion::Omega),
+        chi: [
+            get(Torsion::Chi1),
+            get(Torsion::Chi2),
+            get(Torsion::Chi3),
+            get(Torsion::Chi4),
+        ],
+    }
+}
+
+/// Get the names of the atoms in the side chain that are on the `c` side of the bond `b-c`,
+/// based on the residue template. Returns `None` if the bond is part of a ring.
+fn side_chain_downstream(name: &str, b: &str, c: &str) -> Option<HashSet<&'static str>> {
+    let template = reference_tables::get_residue_template(name)?;
+    let mut found = HashSet::new();
+    let mut queue = vec![c];
+    while let Some(current) = queue.pop() {
+        for (other, _) in template.bonded_atoms(current) {
+            if other == b && current != c {
+                return None;
+            }
+            if other != b && found.insert(other) {
+                queue.push(other);
+            }
+        }
+    }
+    Some(found)
+}
+
+/// Set the given torsion of the residue at the given index to the target value by rotating the
+/// downstream atoms. Side chain torsions are set for every conformer separately, backbone
+/// torsions are measured on the first conformer and rotate all atoms in the C terminal direction
+/// up to the next chain break. Returns the previous value (of the first conformer).
+pub(crate) fn set_torsion(
+    chain: &mut Chain,
+    index: usize,
+    torsion: Torsion,
+    target: f64,
+) -> Option<f64> {
+    let residue = chain.residue(index)?;
+    let mut rotations = Vec::new();
+    if let Some(chi) = torsion.chi() {
+        for conformer in residue.conformers() {
+            let [a, b, c, d] = match torsion_atoms(chain, index, conformer, torsion) {
+                Some(atoms) => atoms,
+                None => continue,
+            };
+            let names = rebuild::chi_atoms(conformer.name(), chi)?;
+            let downstream = side_chain_downstream(conformer.name(), names[1], names[2])?;
+            let moving: HashSet<usize> = conformer
+                .atoms()
+                .filter(|a| downstream.contains(a.name()))
+                .map(Atom::counter)
+                .collect();
+            rotations.push((moving, b, sub(c, b), dihedral(a, b, c, d)));
+        }
+    } else {
+        let first = residue.conformer(0)?;
+        let [a, b, c, d] = torsion_atoms(chain, index, first, torsion)?;
+        if torsion == Torsion::Phi && first.name() == "PRO" {
+            return None;
+        }
+        let moves = |atom: &Atom| match torsion {
+            Torsion::Phi => !["N", "H", "H1", "H2", "H3"].contains(&atom.name()),
+            Torsion::Psi => ["O", "OXT", "HXT"].contains(&atom.name()),
+            _ => true,
+        };
+        let mut moving: HashSet<usize> = residue
+            .atoms()
+            .filter(|a| moves(a))
+            .map(Atom::counter)
+            .collect();
+        // All atoms in the following residues up to the next chain break
+        let mut last = first;
+        for following in (index + 1..chain.residue_count()).filter_map(|i| chain.residue(i)) {
+            match matching_conformer(following, last) {
+                Some(conformer) if peptide_bonded(last, conformer) => {
+                    moving.extend(following.atoms().map(Atom::counter));
+                    last = conformer;
+                }
+                _ => break,
+            }
+        }
+        rotations.push((moving, b, sub(c, b), dihedral(a, b, c, d)));
+    }
+
+    let previous = rotations.first().map(|r| r.3)?;
+    for (moving, origin, axis, current) in rotations {
+        let mut change = target - current;
+        change -= 360.0 * (change / 360.0).round();
+        for atom in chain.atoms_mut().filter(|a| moving.contains(&a.counter())) {
+            atom.set_pos(rotate(atom.pos(), origin, axis, change))
+                .ok()?;
+        }
+    }
+    Some(previous)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{open_pdb, StrictnessLevel};
+
+    fn ubiquitin() -> PDB {
+        open_pdb("example-pdbs/1ubq.pdb", StrictnessLevel::Loose)
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn backbone_torsions() {
+        let pdb = ubiquitin();
+        let chain = pdb.chain(0).unwrap();
+        assert_eq!(chain.phi(0), None);
+        assert!(chain.psi(0).is_some());
+        assert_eq!(chain.omega(0), None);
+        assert_eq!(chain.psi(75), None);
+        // The unsigned dihedral of the atoms gives the same magnitude
+        let atom = |index: usize, name: &str| {
+            chain
+                .residue(index)
+                .unwrap()
+                .atoms()
+                .find(|a| a.name() == name)
+                .unwrap()
+                .clone()
+        };
+        let phi = atom(29, "C").dihedral(&atom(30, "N"), &atom(30, "CA"), &atom(30, "C"));
+        assert!((chain.phi(30).unwrap().abs() - phi).abs() < 1e-6);
+        // Residues 24 to 32 are in the alpha helix
+        for index in 23..=31 {
+            let torsions = chain.torsions(index)[0];
+            assert!((-90.0..-40.0).contains(&torsions.phi.unwrap()));
+            assert!((-60.0..-10.0).contains(&torsions.psi.unwrap()));
+            assert!(torsions.omega.unwrap().abs() > 160.0);
+        }
+        // LYS 6
+        let lys = chain.torsions(5)[0];
+        assert!(lys.chi.iter().all(Option::is_some));
+        assert_eq!(lys.get(Torsion::Chi1), lys.chi[0]);
+        let chi1 = atom(5, "N").dihedral(&atom(5, "CA"), &atom(5, "CB"), &atom(5, "CG"));
+        assert!((lys.chi[0].unwrap().abs() - chi1).abs() < 1e-6);
+        // GLY 10 has no side chain torsions, VAL 5 only chi1
+        assert!(chain.torsions(9)[0].chi.iter().all(Option::is_none));
+        assert_eq!(chain.torsions(4)[0].chi.iter().flatten().count(), 1);
+    }
+
+    #[test]
+    fn chain_breaks() {
+        let mut pdb = ubiquitin();
+        let chain = pdb.chain_mut(0).unwrap();
+        chain.remove_residue(10);
+        assert!(chain.phi(9).is_some());
+        assert_eq!(chain.psi(9), None);
+        assert_eq!(chain.phi(10), None);
+        assert_eq!(chain.omega(10), None);
+        assert!(chain.psi(10).is_some());
+    }
+
+    #[test]
+    fn set_torsions() {
+        let mut pdb = ubiquitin();
+        let chain = pdb.chain_mut(0).unwrap();
+        let before = chain.clone();
+        let distance = |chain: &Chain, a: (usize, &str), b: (usize, &str)| {
+            let find = |(index, name): (usize, &str)| {
+                chain
+                    .residue(index)
+                    .unwrap()
+                    .atoms()
+                    .find(|atom| atom.name() == name)
+                    .unwrap()
+                    .pos()
+            };
+            length(sub(find(a), find(b)))
+        };
+
+        let old = chain.psi(30).unwrap();
+        assert_eq!(chain.set_torsion(30, Torsion::Psi, 120.0), Some(old));
+        assert!((chain.psi(30).unwrap() - 120.0).abs() < 1e-6);
+        assert!((chain.phi(30).unwrap() - before.phi(30).unwrap()).abs() < 1e-6);
+        assert!((chain.phi(31).unwrap() - before.phi(31).unwrap()).abs() < 1e-6);
+        assert!(
+            (distance(chain, (30, "C"), (31, "N")) - distance(&before, (30, "C"), (31, "N"))).abs()
+                < 1e-6
+        );
+        assert!(
+            (distance(chain, (40, "CA"), (50, "CA")) - distance(&before, (40, "CA"), (50, "CA")))
+                .abs()
+                < 1e-6
+        );
+        assert!(
+            (distance(chain, (20, "CA"), (40, "CA")) - distance(&before, (20, "CA"), (40, "CA")))
+                .abs()
+                > 0.1
+        );
+        assert_eq!(chain.residue(10), before.residue(10));
+
+        chain.set_torsion(30, Torsion::Phi, -150.0).unwrap();
+        assert!((chain.phi(30).unwrap() + 150.0).abs() < 1e-6);
+        assert!((chain.psi(30).unwrap() - 120.0).abs() < 1e-6);
+        chain.set_torsion(30, Torsion::Omega, 0.0).unwrap();
+        assert!(chain.omega(30).unwrap().abs() < 1e-6);
+
+        // LYS 6
+        let chi2 = chain.torsion(5, Torsion::Chi2).unwrap();
+        chain.set_torsion(5, Torsion::Chi1, 60.0).unwrap();
+        assert!((chain.torsion(5, Torsion::Chi1).unwrap() - 60.0).abs() < 1e-6);
+        assert!((chain.torsion(5, Torsion::Chi2).unwrap() - chi2).abs() < 1e-6);
+        assert!((chain.phi(5).unwrap() - before.phi(5).unwrap()).abs() < 1e-6);
+
+        // PRO 19 has its side chain and phi in a ring
+        assert_eq!(chain.residue(18).unwrap().name(), Some("PRO"));
+        assert_eq!(chain.set_torsion(18, Torsion::Phi, -60.0), None);
+        assert_eq!(chain.set_torsion(18, Torsion::Chi1, 30.0), None);
+        assert!(chain.set_torsion(18, Torsion::Psi, 150.0).is_some());
+        assert_eq!(chain.set_torsion(9, Torsion::Chi1, 60.0), None);
+    }
+
+    #[test]
+    fn torsion_table() {
+        let pdb = ubiquitin();
+        let table = pdb.torsion_table();
+        assert_eq!(table.len(), 76);
+        assert_eq!(table[0].conformer, ("MET".to_string(), None));
+        assert_eq!(table[0].residue, (1, None));
+        assert_eq!(table[0].torsions.phi, None);
+        assert_eq!(table[5].torsions, pdb.chain(0).unwrap().torsions(5)[0]);
+    }
+}
@@ -0,0 +1,545 @@
+use crate::error::*;
+use crate::reference_tables;
+use crate::structs::*;
+
+/// An ideal bond length: the names of both atoms, the length in Å and its standard deviation.
+/// Atom names prefixed with `-` are taken from the previous residue.
+type IdealBond = (&'static str, &'static str, f64, f64);
+
+/// An ideal bond angle: the names of the three atoms, the angle in degrees and its standard
+/// deviation. Atom names prefixed with `-` are taken from the previous residue.
+type IdealAngle = (&'static str, &'static str, &'static str, f64, f64);
+
+/// The absolute Z-score above which a bond length or angle is reported
+const Z_SCORE_CUTOFF: f64 = 4.0;
+
+/// The maximal deviation in degrees of the peptide bond (omega) from planarity
+const PLANARITY_CUTOFF: f64 = 30.0;
+
+/// The maximal length in Å of a peptide bond, longer C-N distances are reported as chain breaks
+const PEPTIDE_BOND_CUTOFF: f64 = 2.0;
+
+/// The minimal absolute chiral volume in Å³ of CA, smaller volumes indicate a flattened CA
+const CHIRAL_VOLUME_CUTOFF: f64 = 1.0;
+
+// All ideal values are from Engh & Huber (2001) International Tables for Crystallography F,
+// 18.3. The peptide bond is part of the residue containing its N, so the values for proline
+// describe the peptide bond preceding a proline.
+
+/// The backbone bonds of all amino acids except glycine and proline
+const BACKBONE_BONDS: &[IdealBond] = &[
+    ("N", "CA", 1.459, 0.020),
+    ("CA", "C", 1.525, 0.026),
+    ("C", "O", 1.229, 0.019),
+    ("-C", "N", 1.336, 0.023),
+];
+
+/// The backbone angles of all amino acids except glycine and proline
+const BACKBONE_ANGLES: &[IdealAngle] = &[
+    ("N", "CA", "C", 111.0, 2.7),
+    ("CA", "C", "O", 120.1, 2.1),
+    ("-CA", "-C", "N", 117.2, 2.2),
+    ("-O", "-C", "N", 122.7, 1.6),
+    ("-C", "N", "CA", 121.7, 2.5),
+];
+
+/// The backbone bonds of glycine
+const GLYCINE_BONDS: &[IdealBond] = &[
+    ("N", "CA", 1.456, 0.015),
+    ("CA", "C", 1.514, 0.016),
+    ("C", "O", 1.232, 0.016),
+    ("-C", "N", 1.326, 0.018),
+];
+
+/// The backbone angles of glycine
+const GLYCINE_ANGLES: &[IdealAngle] = &[
+    ("N", "CA", "C", 113.1, 2.5),
+    ("CA", "C", "O", 120.6, 1.8),
+    ("-CA", "-C", "N", 116.2, 2.0),
+    ("-O", "-C", "N", 123.2, 1.7),
+    ("-C", "N", "CA", 122.3, 2.1),
+];
+
+/// The backbone bonds of proline
+const PROLINE_BONDS: &[IdealBond] = &[
+    ("N", "CA", 1.468, 0.017),
+    ("CA", "C", 1.524, 0.020),
+    ("C", "O", 1.228, 0.020),
+    ("-C", "N", 1.341, 0.016),
+];
+
+/// The backbone angles of proline
+const PROLINE_ANGLES: &[IdealAngle] = &[
+    ("N", "CA", "C", 112.1, 2.6),
+    ("CA", "C", "O", 120.2, 2.4),
+    ("-CA", "-C", "N", 117.1, 2.8),
+    ("-O", "-C", "N", 121.1, 1.9),
+    ("-C", "N", "CA", 119.3, 1.5),
+    ("-C", "N", "CD", 128.4, 2.1),
+];
+
+/// The side chain bonds and angles, including CB, of the standard amino acids
+#[rustfmt::skip]
+const SIDE_CHAINS: &[(&str, &[IdealBond], &[IdealAngle])] = &[
+    ("ALA", &[
+        ("CA", "CB", 1.520, 0.021),
+    ], &[
+        ("N", "CA", "CB", 110.1, 1.4), ("CB", "CA", "C", 110.1, 1.5),
+    ]),
+    ("ARG", &[
+        ("CA", "CB", 1.530, 0.020), ("CB", "CG", 1.521, 0.027), ("CG", "CD", 1.515, 0.025),
+        ("CD", "NE", 1.460, 0.017), ("NE", "CZ", 1.326, 0.013), ("CZ", "NH1", 1.326, 0.013),
+        ("CZ", "NH2", 1.326, 0.013),
+    ], &[
+        ("N", "CA", "CB", 110.6, 1.8), ("CB", "CA", "C", 110.4, 2.0),
+        ("CA", "CB", "CG", 113.3, 2.2), ("CB", "CG", "CD", 111.3, 2.3),
+        ("CG", "CD", "NE", 112.0, 2.2), ("CD", "NE", "CZ", 124.2, 1.5),
+        ("NE", "CZ", "NH1", 120.0, 1.9), ("NE", "CZ", "NH2", 120.0, 1.9),
+    ]),
+    ("ASN", &[
+        ("CA", "CB", 1.530, 0.020), ("CB", "CG", 1.506, 0.023), ("CG", "OD1", 1.235, 0.022),
+        ("CG", "ND2", 1.324, 0.025),
+    ], &[
+        ("N", "CA", "CB", 110.6, 1.8), ("CB", "CA", "C", 110.4, 2.0),
+        ("CA", "CB", "CG", 113.4, 2.2), ("CB", "CG", "OD1", 121.6, 2.0),
+        ("CB", "CG", "ND2", 116.7, 2.4), ("OD1", "CG", "ND2", 121.9, 2.3),
+    ]),
+    ("ASP", &[
+        ("CA", "CB", 1.530, 0.020), ("CB", "CG", 1.513, 0.021), ("CG", "OD1", 1.249, 0.023),
+        ("CG", "OD2", 1.249, 0.023),
+    ], &[
+        ("N", "CA", "CB", 110.6, 1.8), ("CB", "CA", "C", 110.4, 2.0),
+        ("CA", "CB", "CG", 113.4, 2.2), ("CB", "CG", "OD1", 118.3, 0.9),
+        ("CB", "CG", "OD2", 118.3, 0.9), ("OD1", "CG", "OD2", 123.3, 1.9),
+    ]),
+    ("CYS", &[
+        ("CA", "CB", 1.530, 0.020), ("CB", "SG", 1.812, 0.016),
+    ], &[
+        ("N", "CA", "CB", 110.8, 1.5), ("CB", "CA", "C", 111.5, 1.2),
+        ("CA", "CB", "SG", 114.0, 1.8),
+    ]),
+    ("GLN", &[
+        ("CA", "CB", 1.530, 0.020), ("CB", "CG", 1.521, 0.027), ("CG", "CD", 1.506, 0.023),
+        ("CD", "OE1", 1.235, 0.022), ("CD", "NE2", 1.324, 0.025),
+    ], &[
+        ("N", "CA", "CB", 110.6, 1.8), ("CB", "CA", "C", 110.4, 2.0),
+        ("CA", "CB", "CG", 113.3, 2.2), ("CB", "CG", "CD", 112.6, 2.4),
+        ("CG", "CD", "OE1", 121.6, 2.0), ("CG", "CD", "NE2", 116.7, 2.4),
+        ("OE1", "CD", "NE2", 121.9, 2.3),
+    ]),
+    ("GLU", &[
+        ("CA", "CB", 1.530, 0.020), ("CB", "CG", 1.517, 0.019), ("CG", "CD", 1.515, 0.015),
+        ("CD", "OE1", 1.252, 0.011), ("CD", "OE2", 1.252, 0.011),
+    ], &[
+        ("N", "CA", "CB", 110.6, 1.8), ("CB", "CA", "C", 110.4, 2.0),
+        ("CA", "CB", "CG", 113.3, 2.2), ("CB", "CG", "CD", 113.3, 2.2),
+        ("CG", "CD", "OE1", 118.3, 2.0), ("CG", "CD", "OE2", 118.3, 2.0),
+        ("OE1", "CD", "OE2", 123.3, 1.2),
+    ]),
+    ("HIS", &[
+        ("CA", "CB", 1.530, 0.020), ("CB", "CG", 1.496, 0.018), ("CG", "ND1", 1.383, 0.022),
+        ("CG", "CD2", 1.353, 0.017), ("ND1", "CE1", 1.323, 0.015), ("CD2", "NE2", 1.415, 0.021),
+        ("CE1", "NE2", 1.321, 0.010),
+    ], &[
+        ("N", "CA", "CB", 110.6, 1.8), ("CB", "CA", "C", 110.4, 2.0),
+        ("CA", "CB", "CG", 113.6, 1.7), ("CB", "CG", "ND1", 123.2, 2.5),
+        ("CB", "CG", "CD2", 130.8, 3.1), ("ND1", "CG", "CD2", 106.0, 1.4),
+        ("CG", "ND1", "CE1", 109.0, 1.0), ("ND1", "CE1", "NE2", 111.7, 1.3),
+        ("CG", "CD2", "NE2", 107.0, 1.0), ("CD2", "NE2", "CE1", 106.3, 1.1),
+    ]),
+    ("ILE", &[
+        ("CA", "CB", 1.540, 0.027), ("CB", "CG1", 1.530, 0.020), ("CB", "CG2", 1.521, 0.033),
+        ("CG1", "CD1", 1.513, 0.039),
+    ], &[
+        ("N", "CA", "CB", 111.5, 1.7), ("CB", "CA", "C", 109.1, 2.2),
+        ("CA", "CB", "CG1", 110.4, 1.7), ("CA", "CB", "CG2", 110.5, 1.7),
+        ("CG1", "CB", "CG2", 110.7, 3.0), ("CB", "CG1", "CD1", 113.8, 2.1),
+    ]),
+    ("LEU", &[
+        ("CA", "CB", 1.530, 0.020), ("CB", "CG", 1.530, 0.020), ("CG", "CD1", 1.521, 0.033),
+        ("CG", "CD2", 1.521, 0.033),
+    ], &[
+        ("N", "CA", "CB", 110.4, 2.0), ("CB", "CA", "C", 110.2, 1.9),
+        ("CA", "CB", "CG", 116.3, 3.5), ("CB", "CG", "CD1", 110.7, 3.0),
+        ("CB", "CG", "CD2", 110.7, 3.0), ("CD1", "CG", "CD2", 110.8, 2.2),
+    ]),
+    ("LYS", &[
+        ("CA", "CB", 1.530, 0.020), ("CB", "CG", 1.520, 0.030), ("CG", "CD", 1.520, 0.030),
+        ("CD", "CE", 1.520, 0.030), ("CE", "NZ", 1.489, 0.030),
+    ], &[
+        ("N", "CA", "CB", 110.6, 1.8), ("CB", "CA", "C", 110.4, 2.0),
+        ("CA", "CB", "CG", 113.3, 2.2), ("CB", "CG", "CD", 111.3, 2.3),
+        ("CG", "CD", "CE", 111.3, 2.3), ("CD", "CE", "NZ", 111.9, 3.2),
+    ]),
+    ("MET", &[
+        ("CA", "CB", 1.530, 0.020), ("CB", "CG", 1.520, 0.030), ("CG", "SD", 1.807, 0.026),
+        ("SD", "CE", 1.774, 0.056),
+    ], &[
+        ("N", "CA", "CB", 110.6, 1.8), ("CB", "CA", "C", 110.4, 2.0),
+        ("CA", "CB", "CG", 113.3, 2.2), ("CB", "CG", "SD", 112.4, 3.0),
+        ("CG", "SD", "CE", 100.2, 1.6),
+    ]),
+    ("PHE", &[
+        ("CA", "CB", 1.530, 0.020), ("CB", "CG", 1.502, 0.023), ("CG", "CD1", 1.384, 0.021),
+        ("CG", "CD2", 1.384, 0.021), ("CD1", "CE1", 1.382, 0.030), ("CD2", "CE2", 1.382, 0.030),
+        ("CE1", "CZ", 1.382, 0.030), ("CE2", "CZ", 1.382, 0.030),
+    ], &[
+        ("N", "CA", "CB", 110.6, 1.8), ("CB", "CA", "C", 110.4, 2.0),
+        ("CA", "CB", "CG", 113.8, 1.0), ("CB", "CG", "CD1", 120.7, 1.7),
+        ("CB", "CG", "CD2", 120.7, 1.7), ("CD1", "CG", "CD2", 118.6, 1.5),
+        ("CG", "CD1", "CE1", 120.7, 1.7), ("CG", "CD2", "CE2", 120.7, 1.7),
+        ("CD1", "CE1", "CZ", 120.7, 1.7), ("CD2", "CE2", "CZ", 120.7, 1.7),
+        ("CE1", "CZ", "CE2", 120.0, 1.8),
+    ]),
+    ("PRO", &[
+        ("CA", "CB", 1.531, 0.020), ("CB", "CG", 1.495, 0.050), ("CG", "CD", 1.502, 0.033),
+        ("N", "CD", 1.474, 0.014),
+    ], &[
+        ("N", "CA", "CB", 103.3, 1.2), ("CB", "CA", "C", 111.7, 2.1),
+        ("CA", "CB", "CG", 104.8, 1.9), ("CB", "CG", "CD", 106.5, 3.9),
+        ("N", "CD", "CG", 103.2, 1.5), ("CA", "N", "CD", 111.7, 1.4),
+    ]),
+    ("SER", &[
+        ("CA", "CB", 1.530, 0.020), ("CB", "OG", 1.417, 0.020),
+    ], &[
+        ("N", "CA", "CB", 110.5, 1.5), ("CB", "CA", "C", 110.1, 1.9),
+        ("CA", "CB", "OG", 111.2, 2.7),
+    ]),
+    ("THR", &[
+        ("CA", "CB", 1.540, 0.027), ("CB", "OG1", 1.433, 0.016), ("CB", "CG2", 1.521, 0.033),
+    ], &[
+        ("N", "CA", "CB", 111.5, 1.7), ("CB", "CA", "C", 109.1, 2.2),
+        ("CA", "CB", "OG1", 109.6, 1.5), ("CA", "CB", "CG2", 110.5, 1.7),
+        ("OG1", "CB", "CG2", 109.3, 2.0),
+    ]),
+    ("TRP", &[
+        ("CA", "CB", 1.530, 0.020), ("CB", "CG", 1.498, 0.031), ("CG", "CD1", 1.365, 0.025),
+        ("CG", "CD2", 1.433, 0.018), ("CD1", "NE1", 1.374, 0.021), ("NE1", "CE2", 1.370, 0.011),
+        ("CD2", "CE2", 1.409, 0.012), ("CD2", "CE3", 1.398, 0.016), ("CE2", "CZ2", 1.394, 0.021),
+        ("CE3", "CZ3", 1.382, 0.022), ("CZ2", "CH2", 1.368, 0.019), ("CZ3", "CH2", 1.400, 0.025),
+    ], &[
+        ("N", "CA", "CB", 110.6, 1.8), ("CB", "CA", "C", 110.4, 2.0),
+        ("CA", "CB", "CG", 113.6, 1.9), ("CB", "CG", "CD1", 126.9, 1.5),
+        ("CB", "CG", "CD2", 126.8, 1.4), ("CD1", "CG", "CD2", 106.3, 0.8),
+        ("CG", "CD1", "NE1", 110.1, 1.0), ("CD1", "NE1", "CE2", 109.0, 0.9),
+        ("CD2", "CE2", "NE1", 107.3, 1.0), ("CG", "CD2", "CE2", 107.3, 0.8),
+        ("CE2", "CD2", "CE3", 118.8, 1.0), ("CD2", "CE2", "CZ2", 122.3, 1.2),
+        ("CD2", "CE3", "CZ3", 118.8, 1.0), ("CE2", "CZ2", "CH2", 117.4, 1.0),
+        ("CE3", "CZ3", "CH2", 121.1, 1.2), ("CZ2", "CH2", "CZ3", 121.6, 1.2),
+    ]),
+    ("TYR", &[
+        ("CA", "CB", 1.530, 0.020), ("CB", "CG", 1.512, 0.015), ("CG", "CD1", 1.389, 0.015),
+        ("CG", "CD2", 1.389, 0.015), ("CD1", "CE1", 1.382, 0.013), ("CD2", "CE2", 1.382, 0.013),
+        ("CE1", "CZ", 1.381, 0.013), ("CE2", "CZ", 1.381, 0.013), ("CZ", "OH", 1.376, 0.021),
+    ], &[
+        ("N", "CA", "CB", 110.6, 1.8), ("CB", "CA", "C", 110.4, 2.0),
+        ("CA", "CB", "CG", 113.9, 1.8), ("CB", "CG", "CD1", 120.8, 1.5),
+        ("CB", "CG", "CD2", 120.8, 1.5), ("CD1", "CG", "CD2", 117.9, 1.1),
+        ("CG", "CD1", "CE1", 121.3, 0.8), ("CG", "CD2", "CE2", 121.3, 0.8),
+        ("CD1", "CE1", "CZ", 119.8, 0.9), ("CD2", "CE2", "CZ", 119.8, 0.9),
+        ("CE1", "CZ", "CE2", 119.8, 1.6), ("CE1", "CZ", "OH", 120.1, 2.7),
+        ("CE2", "CZ", "OH", 120.1, 2.7),
+    ]),
+    ("VAL", &[
+        ("CA", "CB", 1.540, 0.027), ("CB", "CG1", 1.521, 0.033), ("CB", "CG2", 1.521, 0.033),
+    ], &[
+        ("N", "CA", "CB", 111.5, 1.7), ("CB", "CA", "C", 109.1, 2.2),
+        ("CA", "CB", "CG1", 110.7, 1.6), ("CA", "CB", "CG2", 110.4, 1.7),
+        ("CG1", "CB", "CG2", 110.9, 1.6),
+    ]),
+];
+
+/// Validate the geometry of all standard amino acids against the ideal values of Engh & Huber
+/// (2001). It returns `PDBError`s with the warning messages, with a context showing the
+/// offending residue.
+///
+/// ## Checks
+/// * Bond lengths and bond angles with an absolute Z-score above 4, including the peptide bond
+///   to the previous residue.
+/// * Peptide bonds (omega) deviating more than 30° from planarity (trans or cis).
+/// * The chirality of CA, which should be the L configuration, based on the chiral volume of
+///   CA, N, C, and CB.
+/// * Chain breaks, where the C of a residue and the N of the next residue are more than 2 Å
+///   apart.
+///
+/// Residues without a residue template (like ligands) and missing atoms are skipped, see
+/// [`validate`](crate::validate) for the missing atoms.
+#[must_use]
+pub fn validate_geometry(pdb: &PDB) -> Vec<PDBError> {
+    let mut errors = Vec::new();
+    for model in pdb.models() {
+        for chain in model.chains() {
+            for (index, residue) in chain.residues().enumerate() {
+                for conformer in residue.conformers() {
+                    let name = match reference_tables::get_residue_template(conformer.name()) {
+                        Some(template) if conformer.is_amino_acid() => template.name,
+                        _ => continue,
+                    };
+                    let context = Context::show(format!(
+                        "Model {}, chain {}, residue {}{} {}{}",
+                        model.serial_number(),
+                        chain.id(),
+                        residue.serial_number(),
+                        residue.insertion_code().unwrap_or(""),
+                        conformer.name(),
+                        conformer
+                            .alternative_location()
+                            .map_or_else(String::new, |alt| format!(
+                                " (alternative location {alt})"
+                            ))
+                    ));
+                    let previous = index
+                        .checked_sub(1)
+                        .and_then(|i| chain.residue(i))
+                        .and_then(|r| {
+                            r.conformers()
+                                .find(|c| {
+                                    c.alternative_location() == conformer.alternative_location()
+                                })
+                                .or_else(|| r.conformers().next())
+                        })
+                        .filter(|c| c.is_amino_acid());
+                    let mut residue_errors = Vec::new();
+                    let previous = previous
+                        .filter(|previous| !chain_break(previous, conformer, &mut residue_errors));
+                    let lookup = |name: &str| match name.strip_prefix('-') {
+                        Some(name) => previous.and_then(|p| atom(p, name)),
+                        None => atom(conformer, name),
+                    };
+                    validate_residue(name, lookup, &mut residue_errors);
+                    errors.extend(residue_errors.into_iter().map(|(short, long)| {
+                        PDBError::new(ErrorLevel::GeneralWarning, short, long, context.clone())
+                    }));
+                }
+            }
+        }
+    }
+    errors
+}
+
+/// A short and long description of a geometry problem
+type Problem = (&'static str, String);
+
+/// Find the atom with the given name in the given conformer
+fn atom<'a>(conformer: &'a Conformer, name: &str) -> Option<&'a Atom> {
+    conformer.atoms().find(|a| a.name() == name)
+}
+
+/// Check if there is a chain break between the given conformers, if so the problem is added
+fn chain_break(previous: &Conformer, conformer: &Conformer, problems: &mut Vec<Problem>) -> bool {
+    let distance = atom(previous, "C")
+        .zip(atom(conformer, "N"))
+        .map_or(0.0, |(c, n)| c.distance(n));
+    if distance > PEPTIDE_BOND_CUTOFF {
+        problems.push((
+            "Chain break",
+            format!(
+                "The C of the previous residue and the N of this residue are {distance:.2} Å apart, which is too far for a peptide bond."
+            ),
+        ));
+        true
+    } else {
+        false
+    }
+}
+
+/// Validate the geometry of a single amino acid with the given standard name, the atoms are
+/// found with the given lookup function
+fn validate_residue<'a>(
+    name: &str,
+    lookup: impl Fn(&str) -> Option<&'a Atom>,
+    problems: &mut Vec<Problem>,
+) {
+    let (backbone_bonds, backbone_angles) = match name {
+        "GLY" => (GLYCINE_BONDS, GLYCINE_ANGLES),
+        "PRO" => (PROLINE_BONDS, PROLINE_ANGLES),
+        _ => (BACKBONE_BONDS, BACKBONE_ANGLES),
+    };
+    let (side_chain_bonds, side_chain_angles) = SIDE_CHAINS
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .map_or((&[][..], &[][..]), |(_, bonds, angles)| (*bonds, *angles));
+
+    for (a, b, ideal, sigma) in backbone_bonds.iter().chain(side_chain_bonds) {
+        if let Some((a_atom, b_atom)) = lookup(a).zip(lookup(b)) {
+            let length = a_atom.distance(b_atom);
+            let z = (length - ideal) / sigma;
+            if z.abs() > Z_SCORE_CUTOFF {
+                let (a, b) = (display(a), display(b));
+                problems.push((
+                    "Bond length outlier",
+                    format!("The bond {a}-{b} has a length of {length:.3} Å where {ideal:.3} ± {sigma:.3} Å is expected (Z-score {z:.1})."),
+                ));
+            }
+        }
+    }
+    for (a, b, c, ideal, sigma) in backbone_angles.iter().chain(side_chain_angles) {
+        if let Some(((a_atom, b_atom), c_atom)) = lookup(a).zip(lookup(b)).zip(lookup(c)) {
+            let angle = a_atom.angle(b_atom, c_atom);
+            let z = (angle - ideal) / sigma;
+            if z.abs() > Z_SCORE_CUTOFF {
+                let (a, b, c) = (display(a), display(b), display(c));
+                problems.push((
+                    "Bond angle outlier",
+                    format!("The angle {a}-{b}-{c} is {angle:.1}° where {ideal:.1} ± {sigma:.1}° is expected (Z-score {z:.1})."),
+                ));
+            }
+        }
+    }
+
+    if let Some([ca1, c, n, ca2]) = lookup_four(["-CA", "-C", "N", "CA"], &lookup) {
+        let omega = ca1.dihedral(c, n, ca2);
+        let deviation = omega.min(180.0 - omega);
+        if deviation > PLANARITY_CUTOFF {
+            problems.push((
+                "Nonplanar peptide bond",
+                format!("The peptide bond preceding this residue has an omega of {omega:.1}°, which deviates {deviation:.1}° from planarity."),
+            ));
+        }
+    }
+
+    if let Some([ca, n, c, cb]) = lookup_four(["CA", "N", "C", "CB"], &lookup) {
+        let volume = chiral_volume(ca, n, c, cb);
+        if volume < CHIRAL_VOLUME_CUTOFF {
+            problems.push((
+                "CA chirality outlier",
+                if volume < -CHIRAL_VOLUME_CUTOFF {
+                    format!("The CA has a chiral volume of {volume:.2} Å³, indicating the D instead of the L configuration.")
+                } else {
+                    format!("The CA has a chiral volume of {volume:.2} Å³, indicating a flattened tetrahedral geometry.")
+                },
+            ));
+        }
+    }
+}
+
+/// Show an atom name from the ideal values, the atoms of the previous residue are shown as
+/// `C(-1)` instead of `-C`
+fn display(name: &str) -> String {
+    name.strip_prefix('-')
+        .map_or_else(|| name.to_string(), |name| format!("{name}(-1)"))
+}
+
+/// Look up the four atoms with the given names, only if all atoms are found
+fn lookup_four<'a>(
+    names: [&str; 4],
+    lookup: &impl Fn(&str) -> Option<&'a Atom>,
+) -> Option<[&'a Atom; 4]> {
+    Some([
+        lookup(names[0])?,
+        lookup(names[1])?,
+        lookup(names[2])?,
+        lookup(names[3])?,
+    ])
+}
+
+/// The chiral volume of the given center with its three substituents, this is positive for CA
+/// with N, C, and CB in the L configuration
+fn chiral_volume(center: &Atom, a: &Atom, b: &Atom, c: &Atom) -> f64 {
+    let vector = |atom: &Atom| {
+        (
+            atom.x() - center.x(),
+            atom.y() - center.y(),
+            atom.z() - center.z(),
+        )
+    };
+    let (a, b, c) = (vector(a), vector(b), vector(c));
+    a.0 * b.2.mul_add(-c.1, b.1 * c.2)
+        + a.1 * b.0.mul_add(-c.2, b.2 * c.0)
+        + a.2 * b.1.mul_add(-c.0, b.0 * c.1)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{open_pdb, StrictnessLevel};
+
+    fn ubiquitin() -> PDB {
+        open_pdb("example-pdbs/1ubq.pdb", StrictnessLevel::Loose)
+            .unwrap()
+            .0
+    }
+
+    fn count(errors: &[PDBError], short: &str) -> usize {
+        errors
+            .iter()
+            .filter(|e| e.short_description() == short)
+            .count()
+    }
+
+    #[test]
+    fn ubiquitin_geometry() {
+        let errors = validate_geometry(&ubiquitin());
+        assert!(errors
+            .iter()
+            .all(|e| e.level() == ErrorLevel::GeneralWarning));
+        // Only the strained side chains of LYS 33 and ARG 72 deviate
+        assert_eq!(count(&errors, "Bond length outlier"), 1);
+        assert_eq!(count(&errors, "Bond angle outlier"), 4);
+        assert_eq!(errors.len(), 5);
+        assert_eq!(
+            errors[0].context(),
+            &Context::show("Model 0, chain A, residue 33 LYS")
+        );
+    }
+
+    #[test]
+    fn chain_break() {
+        let mut pdb = ubiquitin();
+        pdb.chain_mut(0).unwrap().remove_residue(10);
+        let errors = validate_geometry(&pdb);
+        assert_eq!(count(&errors, "Chain break"), 1);
+        assert_eq!(
+            errors
+                .iter()
+                .find(|e| e.short_description() == "Chain break")
+                .unwrap()
+                .context(),
+            &Context::show("Model 0, chain A, residue 12 THR")
+        );
+    }
+
+    #[test]
+    fn distorted_geometry() {
+        let mut pdb = ubiquitin();
+        let chain = pdb.chain_mut(0).unwrap();
+        // Twist the peptide bond between MET 1 and GLN 2
+        chain.set_torsion(1, Torsion::Omega, 90.0).unwrap();
+        // Stretch the CA-CB bond of VAL 5
+        let cb = chain
+            .residue_mut(4)
+            .unwrap()
+            .atoms_mut()
+            .find(|a| a.name() == "CB")
+            .unwrap();
+        cb.set_x(cb.x() + 0.5).unwrap();
+        let errors = validate_geometry(&pdb);
+        assert_eq!(count(&errors, "Nonplanar peptide bond"), 1);
+        assert!(errors
+            .iter()
+            .any(|e| e.short_description() == "Bond length outlier"
+                && e.long_description().starts_with("The bond CA-CB")
+                && e.context() == &Context::show("Model 0, chain A, residue 5 VAL")));
+    }
+
+    #[test]
+    fn mirrored() {
+        let mut pdb = ubiquitin();
+        pdb.atoms_mut().for_each(|a| a.set_x(-a.x()).unwrap());
+        let errors = validate_geometry(&pdb);
+        let chiral = pdb
+            .conformers()
+            .filter(|c| c.is_amino_acid() && c.atoms().any(|a| a.name() == "CB"))
+            .count();
+        assert_eq!(count(&errors, "CA chirality outlier"), chiral);
+        assert!(errors
+            .iter()
+            .filter(|e| e.short_description() == "CA chirality outlier")
+            .all(|e| e
+                .long_description()
+                .contains("the D instead of the L configuration")));
+        // Mirroring does not change any of the other geometry
+        assert_eq!(errors.len(), chiral + 5);
+    }
+}
@@ -1,16 +1,32 @@
 use crate::error::*;
 use crate::structs::*;
 
+/// To validate the geometry of amino acids
+mod geometry;
+pub use geometry::validate_geometry;
+
 /// Validate a given PDB file in terms of invariants that should be held up.
 /// It returns `PDBError`s with the warning messages.
 ///
 /// ## Invariants Tested
 /// * With multiple models the models should all contain atoms that correspond.
+/// * Residues with a standard residue template should contain all heavy atoms of that template,
+///   see [`Conformer::missing_atoms`]. The phosphate of the first nucleotide in a chain is
+///   optional, and residues without atoms are skipped.
 ///
 /// ## Invariants Not Tested
 /// * Numbering of all structs, serial numbers should be unique. To enforce this the `renumber()` function should be called on the PDB struct.
 #[must_use]
 pub fn validate(pdb: &PDB) -> Vec<PDBError> {
+    let mut errors = validate_invariants(pdb);
+    errors.append(&mut validate_missing_atoms(pdb));
+    errors
+}
+
+/// Validate the invariants needed to read and save a PDB, these are the invariants tested by
+/// [`validate`] except for the missing atoms, as truncated residues are common in deposited
+/// structures.
+pub(crate) fn validate_invariants(pdb: &PDB) -> Vec<PDBError> {
     let mut errors = Vec::new();
     if pdb.model_count() > 1 {
         errors.append(&mut validate_models(pdb));
@@ -29,7 +45,8 @@ pub fn validate(pdb: &PDB) -> Vec<PDBError> {
 
 /// Validates this models specifically for the PDB format.
 /// It returns `PDBError`s with the warning messages.
-/// It extends the validation specified in the [`validate`] function with PDB specific validations.
+/// It extends the validation specified in the [`validate`] function with PDB specific validations,
+/// the check for missing atoms is not included as it is not specific to the PDB format.
 ///
 /// ## Invariants Tested
 /// * Values fitting in the range of the PDB format columns, both numbers and textual values.
@@ -38,7 +55,7 @@ pub fn validate(pdb: &PDB) -> Vec<PDBError> {
 /// * Numbering of all structs, serial numbers should be unique. To enforce this the `renumber()` function should be called on the PDB struct.
 #[must_use]
 pub fn validate_pdb(pdb: &PDB) -> Vec<PDBError> {
-    let mut errors = validate(pdb);
+    let mut errors = validate_invariants(pdb);
     for model in pdb.models() {
         if model.serial_number() > 9999 {
             errors.push(PDBError::new(
@@ -149,6 +166,17 @@ pub fn validate_pdb(pdb: &PDB) -> Vec<PDBError> {
                                 Context::None,
                             ));
                         }
+                        if atom.segment_id().map_or(false, |s| s.len() > 4) {
+                            errors.push(PDBError::new(
+                                ErrorLevel::LooseWarning,
+                                "Atom segment id too long",
+                                format!(
+                                    "Atom {} has a segment id which is too long, max 4 chars.",
+                                    atom.serial_number()
+                                ),
+                                Context::None,
+                            ));
+                        }
                         if atom.serial_number() > 99999 {
                             errors.push(PDBError::new(
                                 ErrorLevel::LooseWarning,
@@ -236,58 +264,95 @@ pub fn validate_pdb(pdb: &PDB) -> Vec<PDBError> {
 
 /// Validate the models by enforcing that all models should contain the same atoms (with possibly different data).
 /// It checks this by matching all atoms (not hetatoms) for each model to see if they correspond (`Atom::correspond`).
-#[allow(clippy::unwrap_used)]
 fn validate_models(pdb: &PDB) -> Vec<PDBError> {
     let mut errors = Vec::new();
-    let total_atoms = pdb.model(0).unwrap().atom_count();
-    let normal_atoms = pdb
-        .model(0)
-        .unwrap()
-        .atoms()
-        .filter(|a| !a.hetero())
-        .count();
-    for model in pdb.models().skip(1) {
-        if model.atom_count() != total_atoms {
-            errors.push(PDBError::new(
-                ErrorLevel::LooseWarning,
-                "Invalid Model",
-                format!(
-                    "Model {} does not have the same amount of atoms (Normal + Hetero) ({}) as the first model ({}).",
-                    model.serial_number(),
-                    model.atom_count(),
-                    total_atoms
-                ),
-                Context::None,
-            ));
-            continue;
-        } else if model.atoms().filter(|a| !a.hetero()).count() != normal_atoms {
+    if let Some(first) = pdb.model(0) {
+        for model in pdb.models().skip(1) {
+            errors.append(&mut validate_model(first, model));
+        }
+    }
+    errors
+}
+
+/// Validate that the given model contains atoms that correspond to the atoms of the first model
+pub(crate) fn validate_model(first: &Model, model: &Model) -> Vec<PDBError> {
+    let mut errors = Vec::new();
+    let total_atoms = first.atom_count();
+    let normal_atoms = first.atoms().filter(|a| !a.hetero()).count();
+    if model.atom_count() != total_atoms {
+        errors.push(PDBError::new(
+            ErrorLevel::LooseWarning,
+            "Invalid Model",
+            format!(
+                "Model {} does not have the same amount of atoms (Normal + Hetero) ({}) as the first model ({}).",
+                model.serial_number(),
+                model.atom_count(),
+                total_atoms
+            ),
+            Context::None,
+        ));
+        return errors;
+    } else if model.atoms().filter(|a| !a.hetero()).count() != normal_atoms {
+        errors.push(PDBError::new(
+            ErrorLevel::StrictWarning,
+            "Invalid Model",
+            format!(
+                "Model {} does not have the same amount of atoms ({}) as the first model ({}).",
+                model.serial_number(),
+                model.atoms().filter(|a| !a.hetero()).count(),
+                normal_atoms
+            ),
+            Context::None,
+        ));
+        return errors;
+    }
+    for (current_atom, standard_atom) in model.atoms().zip(first.atoms()) {
+        if !standard_atom.corresponds(current_atom) {
             errors.push(PDBError::new(
                 ErrorLevel::StrictWarning,
-                "Invalid Model",
+                "Atoms in Models not corresponding",
                 format!(
-                    "Model {} does not have the same amount of atoms ({}) as the first model ({}).",
-                    model.serial_number(),
-                    model.atoms().filter(|a| !a.hetero()).count(),
-                    normal_atoms
+                    "Atom {} in Model {} does not correspond to the respective Atom in the first model.",
+                    current_atom.serial_number(),
+                    model.serial_number()
                 ),
                 Context::None,
             ));
-            continue;
         }
-        for index in 0..model.atom_count() {
-            let current_atom = model.atom(index).unwrap();
-            let standard_atom = pdb.model(0).unwrap().atom(index).unwrap();
-            if !standard_atom.corresponds(current_atom) {
-                errors.push(PDBError::new(
-                    ErrorLevel::StrictWarning,
-                    "Atoms in Models not corresponding",
-                    format!(
-                        "Atom {} in Model {} does not correspond to the respective Atom in the first model.",
-                        current_atom.serial_number(),
-                        model.serial_number()
-                    ),
-                    Context::None,
-                ));
+    }
+    errors
+}
+
+/// Validate that the residues contain all heavy atoms of their residue template. Residues
+/// without any atoms (like unobserved residues from SEQRES) are skipped.
+fn validate_missing_atoms(pdb: &PDB) -> Vec<PDBError> {
+    let mut errors = Vec::new();
+    for model in pdb.models() {
+        for chain in model.chains() {
+            for (index, residue) in chain.residues().enumerate() {
+                for conformer in residue.conformers().filter(|c| c.atom_count() > 0) {
+                    let missing: Vec<&str> = conformer
+                        .missing_atoms()
+                        .into_iter()
+                        .filter(|name| index > 0 || !["P", "OP1", "OP2"].contains(name))
+                        .collect();
+                    if !missing.is_empty() {
+                        errors.push(PDBError::new(
+                            ErrorLevel::GeneralWarning,
+                            "Missing atoms",
+                            format!(
+                                "Residue {}{} {} in chain {} of model {} is missing the atoms: {}.",
+                                residue.serial_number(),
+                                residue.insertion_code().unwrap_or(""),
+                                conformer.name(),
+                                chain.id(),
+                                model.serial_number(),
+                                missing.join(", ")
+                            ),
+                            Context::None,
+                        ));
+                    }
+                }
             }
         }
     }
@@ -301,24 +366,30 @@ fn validate_models(pdb: &PDB) -> Vec<PDBError> {
 /// Conformers.
 pub fn reshuffle_conformers(pdb: &mut PDB) {
     for residue in pdb.residues_mut() {
-        let count = residue.conformer_count();
-        if count > 1 {
-            let mut blank = None;
-            for (index, conformer) in residue.conformers().enumerate() {
-                if conformer.alternative_location().is_none() {
-                    blank = Some(index);
-                }
+        reshuffle_residue_conformers(residue);
+    }
+}
+
+/// Copy all atoms in the blank alternative conformer of this residue into the other conformers,
+/// see [`reshuffle_conformers`].
+pub fn reshuffle_residue_conformers(residue: &mut Residue) {
+    let count = residue.conformer_count();
+    if count > 1 {
+        let mut blank = None;
+        for (index, conformer) in residue.conformers().enumerate() {
+            if conformer.alternative_location().is_none() {
+                blank = Some(index);
             }
-            #[allow(clippy::unwrap_used, clippy::cast_precision_loss)]
-            if let Some(index) = blank {
-                let mut shared = residue.conformer(index).unwrap().clone();
-                shared
-                    .atoms_mut()
-                    .for_each(|a| a.set_occupancy(a.occupancy() / (count as f64)).unwrap());
-                residue.remove_conformer(index);
-                for conformer in residue.conformers_mut() {
-                    conformer.join(shared.clone());
-                }
+        }
+        #[allow(clippy::unwrap_used, clippy::cast_precision_loss)]
+        if let Some(index) = blank {
+            let mut shared = residue.conformer(index).unwrap().clone();
+            shared
+                .atoms_mut()
+                .for_each(|a| a.set_occupancy(a.occupancy() / (count as f64)).unwrap());
+            residue.remove_conformer(index);
+            for conformer in residue.conformers_mut() {
+                conformer.join(shared.clone());
             }
         }
     }
@@ -0,0 +1,78 @@
+#![cfg(feature = "bcif")]
+use pdbtbx::*;
+use std::io::BufWriter;
+
+#[test]
+fn bcif_round_trip() {
+    let (pdb, _) = open_mmcif("example-pdbs/1ubq.cif", StrictnessLevel::Loose).unwrap();
+    let mut buffer = Vec::new();
+    save_bcif_raw(&pdb, BufWriter::new(&mut buffer));
+    let (reread, errors) = open_bcif_raw(&buffer, StrictnessLevel::Strict).unwrap();
+    assert!(errors.is_empty());
+    assert_eq!(reread.identifier, pdb.identifier);
+    assert_eq!(reread.unit_cell, pdb.unit_cell);
+    assert_eq!(reread.symmetry, pdb.symmetry);
+    assert_eq!(reread.bonds().count(), pdb.bonds().count());
+    assert!(reread.models().eq(pdb.models()));
+    // The kept items of generated categories are written together with the generated items
+    let sorted = |pdb: &PDB| {
+        let mut items: Vec<cif::Item> = pdb.cif_items().cloned().collect();
+        items.sort_by_key(|item| item.category().map(str::to_string));
+        items
+    };
+    assert_eq!(sorted(&reread), sorted(&pdb));
+    let labels = |pdb: &PDB| -> Vec<(Option<String>, Option<isize>)> {
+        pdb.residues()
+            .map(|r| (r.label_asym_id().map(str::to_string), r.label_seq_id()))
+            .collect()
+    };
+    assert_eq!(labels(&reread), labels(&pdb));
+}
+
+#[test]
+fn bcif_by_extension() {
+    let root = env!("CARGO_MANIFEST_DIR");
+    std::fs::create_dir_all(format!("{root}/dump")).unwrap();
+    let (pdb, _) = open("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+    save(&pdb, "dump/1ubq.bcif", StrictnessLevel::Loose).unwrap();
+    let (reread, _) = open("dump/1ubq.bcif", StrictnessLevel::Loose).unwrap();
+    assert_eq!(reread.atom_count(), pdb.atom_count());
+    assert!(reread.atoms().eq(pdb.atoms()));
+
+    // The binary file is a lot smaller than the textual file
+    save(&pdb, "dump/1ubq_binary.cif", StrictnessLevel::Loose).unwrap();
+    let size = |file: &str| std::fs::metadata(file).unwrap().len();
+    assert!(size("dump/1ubq.bcif") * 3 < size("dump/1ubq_binary.cif"));
+
+    let blocks = cif::open_binary("dump/1ubq.bcif").unwrap();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(
+        blocks[0].category("atom_site").map(|c| c.len()),
+        Some(pdb.atom_count())
+    );
+}
+
+#[test]
+fn external_bcif() {
+    // Not written by pdbtbx, the atom_site, cell, symmetry, and entry categories of 1ubq.cif
+    // encoded with the delta, run length, integer packing, fixed point, string array, and mask
+    // encodings the same way the ModelServer does
+    let (pdb, errors) = open("example-pdbs/1ubq.bcif", StrictnessLevel::Strict).unwrap();
+    assert!(errors.is_empty());
+    let (original, _) = open_mmcif("example-pdbs/1ubq.cif", StrictnessLevel::Loose).unwrap();
+    assert_eq!(pdb.identifier, original.identifier);
+    assert_eq!(pdb.unit_cell, original.unit_cell);
+    assert_eq!(pdb.symmetry, original.symmetry);
+    assert_eq!(pdb.atom_count(), 660);
+    assert!(pdb.atoms().eq(original.atoms()));
+    assert!(pdb
+        .residues()
+        .map(Residue::name)
+        .eq(original.residues().map(Residue::name)));
+}
+
+#[test]
+fn invalid_bcif() {
+    let errors = open_bcif_raw(b"data_1UBQ", StrictnessLevel::Loose).unwrap_err();
+    assert_eq!(errors[0].short_description(), "Invalid BinaryCIF");
+}
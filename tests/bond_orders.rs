@@ -0,0 +1,126 @@
+use pdbtbx::*;
+use std::io::BufWriter;
+
+/// Acetonitrile bound to a carbonyl, with the double and triple bonds given by duplicated CONECT records
+const LIGAND: &str =
+    "HETATM    1  C1  LIG A   1       0.000   0.000   0.000  1.00  0.00           C
+HETATM    2  O1  LIG A   1       1.200   0.000   0.000  1.00  0.00           O
+HETATM    3  C2  LIG A   1      -0.800   1.200   0.000  1.00  0.00           C
+HETATM    4  C3  LIG A   1      -1.600   2.400   0.000  1.00  0.00           C
+HETATM    5  N1  LIG A   1      -2.200   3.300   0.000  1.00  0.00           N
+CONECT    1    2    2    3
+CONECT    2    1    1
+CONECT    3    1    4
+CONECT    4    3    5    5    5
+CONECT    5    4    4    4
+END
+";
+
+fn orders(pdb: &PDB) -> Vec<(usize, usize, Bond, Option<BondOrder>)> {
+    let mut orders: Vec<_> = pdb
+        .bonds()
+        .map(|(a, b, bond, order)| {
+            let (a, b) = (a.serial_number(), b.serial_number());
+            (a.min(b), a.max(b), bond, order)
+        })
+        .collect();
+    orders.sort_by_key(|(a, b, ..)| (*a, *b));
+    orders
+}
+
+#[test]
+fn conect_bond_orders() {
+    let (pdb, errors) = open_pdb_raw(
+        std::io::BufReader::new(LIGAND.as_bytes()),
+        Context::none(),
+        StrictnessLevel::Loose,
+    )
+    .unwrap();
+    assert!(errors.is_empty());
+    let expected = vec![
+        (1, 2, Bond::Covalent, Some(BondOrder::Double)),
+        (1, 3, Bond::Covalent, Some(BondOrder::Single)),
+        (3, 4, Bond::Covalent, Some(BondOrder::Single)),
+        (4, 5, Bond::Covalent, Some(BondOrder::Triple)),
+    ];
+    assert_eq!(orders(&pdb), expected);
+
+    let mut buffer = Vec::new();
+    save_pdb_raw(&pdb, BufWriter::new(&mut buffer), StrictnessLevel::Loose);
+    let text = String::from_utf8(buffer).unwrap();
+    assert!(text.contains("CONECT4    3    5    5    5"));
+    let (reread, _) = open_pdb_raw(
+        std::io::BufReader::new(text.as_bytes()),
+        Context::none(),
+        StrictnessLevel::Loose,
+    )
+    .unwrap();
+    assert_eq!(orders(&reread), expected);
+}
+
+#[test]
+fn mmcif_bond_orders() {
+    let (mut pdb, _) = open_pdb_raw(
+        std::io::BufReader::new(LIGAND.as_bytes()),
+        Context::none(),
+        StrictnessLevel::Loose,
+    )
+    .unwrap();
+    pdb.add_bond((2, None), (5, None), Bond::Hydrogen).unwrap();
+    pdb.add_bond_with_order((3, None), (5, None), Bond::Covalent, BondOrder::Delocalised)
+        .unwrap();
+    let expected = orders(&pdb);
+
+    let mut buffer = Vec::new();
+    save_mmcif_raw(&pdb, BufWriter::new(&mut buffer));
+    let text = String::from_utf8(buffer).unwrap();
+    assert!(text.contains("_struct_conn.pdbx_value_order"));
+    let (reread, errors) = open_mmcif_raw(&text, StrictnessLevel::Loose).unwrap();
+    assert!(errors.is_empty());
+    assert_eq!(orders(&reread), expected);
+}
+
+#[test]
+fn mmcif_struct_conn() {
+    let (pdb, _) = open_mmcif("example-pdbs/3nig.cif", StrictnessLevel::Loose).unwrap();
+    assert_eq!(
+        pdb.bonds()
+            .filter(|(a, b, bond, order)| *bond == Bond::Disulfide
+                && a.name() == "SG"
+                && b.name() == "SG"
+                && *order == Some(BondOrder::Single))
+            .count(),
+        37
+    );
+    assert!(pdb
+        .bonds()
+        .any(|(.., bond, _)| bond == Bond::MetalCoordination));
+}
+
+#[test]
+fn conect_only_hetero_and_links() {
+    let conect_lines = |pdb: &PDB| {
+        let mut buffer = Vec::new();
+        save_pdb_raw(pdb, BufWriter::new(&mut buffer), StrictnessLevel::Loose);
+        String::from_utf8(buffer)
+            .unwrap()
+            .lines()
+            .filter(|line| line.starts_with("CONECT"))
+            .count()
+    };
+    // The bonds within and between standard residues are not written
+    let (mut pdb, _) = open("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+    assert!(pdb.add_template_bonds() > 500);
+    assert_eq!(conect_lines(&pdb), 0);
+
+    let (mut pdb, _) = open("example-pdbs/3nig.pdb", StrictnessLevel::Loose).unwrap();
+    let written = conect_lines(&pdb);
+    assert!(written > 0);
+    pdb.add_template_bonds();
+    assert_eq!(conect_lines(&pdb), written);
+
+    // Serial numbers that do not fit in five columns are skipped
+    pdb.atoms_mut()
+        .for_each(|a| a.set_serial_number(a.serial_number() + 100_000));
+    assert_eq!(conect_lines(&pdb), 0);
+}
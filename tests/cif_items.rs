@@ -0,0 +1,132 @@
+use pdbtbx::*;
+use std::io::BufWriter;
+
+#[test]
+fn uninterpreted_categories_round_trip() {
+    let (pdb, _) = open_mmcif("example-pdbs/1ubq.cif", StrictnessLevel::Loose).unwrap();
+    let helices = pdb.cif_category("struct_conf").unwrap();
+    assert_eq!(helices.len(), 2);
+    assert_eq!(
+        helices
+            .get(1, "beg_auth_seq_id")
+            .and_then(cif::Value::as_f64),
+        Some(56.0)
+    );
+    assert_eq!(
+        helices.get(0, "conf_type_id").and_then(cif::Value::as_text),
+        Some("HELX_P")
+    );
+    // Interpreted categories are not kept
+    assert!(pdb.cif_category("atom_site").is_none());
+    assert!(pdb.cif_items().all(|item| item.category() != Some("entry")));
+    // Only the interpreted items of partly interpreted categories are not kept
+    let cell = pdb.cif_category("cell").unwrap();
+    assert_eq!(
+        cell.columns,
+        [
+            "pdbx_unique_axis",
+            "length_a_esd",
+            "length_b_esd",
+            "length_c_esd",
+            "angle_alpha_esd",
+            "angle_beta_esd",
+            "angle_gamma_esd"
+        ]
+    );
+    let symmetry = pdb.cif_category("symmetry").unwrap();
+    assert_eq!(symmetry.columns, ["cell_setting"]);
+    let atom_sites = pdb.cif_category("atom_sites").unwrap();
+    assert_eq!(atom_sites.len(), 1);
+    assert_eq!(
+        atom_sites
+            .get(0, "fract_transf_matrix[1][1]")
+            .and_then(cif::Value::as_f64),
+        Some(0.01967)
+    );
+    assert!(atom_sites.column_index("entry_id").is_none());
+
+    let mut buffer = Vec::new();
+    save_mmcif_raw(&pdb, BufWriter::new(&mut buffer));
+    let text = String::from_utf8(buffer).unwrap();
+    assert!(text.contains("_struct_conf.conf_type_id"));
+    assert!(text.contains("_atom_sites.fract_transf_vector[3]"));
+    let blocks = cif::parse(&text).unwrap();
+    let cell = blocks[0].category("cell").unwrap();
+    assert_eq!(cell.columns.len(), 8 + 7);
+    assert_eq!(
+        cell.get(0, "length_a").and_then(cif::Value::as_f64),
+        Some(50.84)
+    );
+    let (reread, _) = open_mmcif_raw(&text, StrictnessLevel::Loose).unwrap();
+    assert_eq!(reread.cif_item_count(), pdb.cif_item_count());
+    // The kept items of generated categories are written together with the generated items
+    let sorted = |pdb: &PDB| {
+        let mut items: Vec<cif::Item> = pdb.cif_items().cloned().collect();
+        items.sort_by_key(|item| item.category().map(str::to_string));
+        items
+    };
+    assert_eq!(sorted(&reread), sorted(&pdb));
+}
+
+#[test]
+fn edit_cif_items() {
+    let (mut pdb, _) = open_mmcif("example-pdbs/1ubq.cif", StrictnessLevel::Loose).unwrap();
+    pdb.delete_cif_items_by(|item| item.category().map_or(false, |c| c.starts_with("struct_")));
+    assert!(pdb.cif_category("struct_conf").is_none());
+    pdb.add_cif_item(cif::Item::DataItem(cif::DataItem::Single(cif::Single {
+        name: "pdbx_remark.text".to_string(),
+        content: cif::Value::Text("Edited by 'hand'".to_string()),
+    })));
+
+    let mut buffer = Vec::new();
+    save_mmcif_raw(&pdb, BufWriter::new(&mut buffer));
+    let text = String::from_utf8(buffer).unwrap();
+    let (reread, _) = open_mmcif_raw(&text, StrictnessLevel::Loose).unwrap();
+    assert!(reread.cif_category("struct_conf").is_none());
+    assert_eq!(
+        reread.cif_category("pdbx_remark").and_then(|c| c
+            .get(0, "text")
+            .and_then(cif::Value::as_text)
+            .map(str::to_string)),
+        Some("Edited by 'hand'".to_string())
+    );
+}
+
+#[test]
+fn multiple_data_blocks() {
+    let first = std::fs::read_to_string("example-pdbs/1ubq.cif").unwrap();
+    let text = format!(
+        "{first}
+data_SECOND
+_entry.id SECOND
+loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.label_atom_id
+_atom_site.label_comp_id
+_atom_site.label_asym_id
+_atom_site.label_seq_id
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+_atom_site.occupancy
+_atom_site.B_iso_or_equiv
+_atom_site.pdbx_PDB_model_num
+HETATM 1 NA NA NA A . 1.0 2.0 3.0 1.0 10.0 1
+"
+    );
+    let (pdb, errors) = open_mmcif_raw(&text, StrictnessLevel::Loose).unwrap();
+    assert_eq!(pdb.atom_count(), 660);
+    assert!(errors
+        .iter()
+        .any(|e| e.short_description() == "Multiple data blocks"));
+
+    let blocks = cif::parse(&text).unwrap();
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(blocks[1].name, "SECOND");
+    let (second, _) = parse_mmcif(&blocks[1], StrictnessLevel::Loose).unwrap();
+    assert_eq!(second.identifier.as_deref(), Some("SECOND"));
+    assert_eq!(second.atom_count(), 1);
+    assert_eq!(second.atom(0).unwrap().pos(), (1.0, 2.0, 3.0));
+}
@@ -0,0 +1,109 @@
+use pdbtbx::*;
+
+/// The path of a file in the dump folder, creating the folder if needed
+fn dump(name: &str) -> String {
+    let root = env!("CARGO_MANIFEST_DIR");
+    std::fs::create_dir_all(format!("{root}/dump")).unwrap();
+    format!("{root}/dump/{name}")
+}
+
+#[test]
+fn extension_aliases() {
+    let (pdb, _) = open("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+    for name in ["pdb1ubq.ent", "1UBQ.PDB", "1ubq.mmcif", "1UBQ.CIF"] {
+        save(&pdb, dump(name), StrictnessLevel::Loose).unwrap();
+        let (reread, _) = open(dump(name), StrictnessLevel::Loose).unwrap();
+        assert_eq!(reread.atom_count(), pdb.atom_count(), "{name}");
+    }
+    assert!(std::fs::read_to_string(dump("1ubq.mmcif"))
+        .unwrap()
+        .starts_with("data_"));
+}
+
+#[test]
+fn detect_contents() {
+    std::fs::copy("example-pdbs/1ubq.pdb", dump("1ubq_pdb.txt")).unwrap();
+    std::fs::copy("example-pdbs/1ubq.cif", dump("1ubq_cif.txt")).unwrap();
+    let (original, _) = open("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+    let (pdb, _) = open(dump("1ubq_pdb.txt"), StrictnessLevel::Loose).unwrap();
+    assert!(pdb.atoms().eq(original.atoms()));
+    let (original, _) = open("example-pdbs/1ubq.cif", StrictnessLevel::Loose).unwrap();
+    let (cif, _) = open(dump("1ubq_cif.txt"), StrictnessLevel::Loose).unwrap();
+    assert!(cif.atoms().eq(original.atoms()));
+
+    let file = std::fs::File::open("example-pdbs/1ubq.cif").unwrap();
+    let (raw, _) = open_raw(std::io::BufReader::new(file), StrictnessLevel::Loose).unwrap();
+    assert!(raw.atoms().eq(original.atoms()));
+
+    std::fs::write(dump("image.txt"), b"\x89PNG\r\n").unwrap();
+    let errors = open(dump("image.txt"), StrictnessLevel::Loose).unwrap_err();
+    assert_eq!(
+        errors[0].short_description(),
+        "Could not determine file type"
+    );
+}
+
+#[test]
+fn pdbqt() {
+    std::fs::write(
+        dump("ligand.pdbqt"),
+        "ROOT
+ATOM      1  C1  LIG A   1      11.000  12.000  13.000  1.00  0.00     0.123 C 
+ATOM      2  O1  LIG A   1      12.000  12.000  13.000  1.00  0.00    -0.456 OA
+ENDROOT
+TORSDOF 0
+",
+    )
+    .unwrap();
+    let (pdb, _) = open(dump("ligand.pdbqt"), StrictnessLevel::Loose).unwrap();
+    assert_eq!(pdb.atom_count(), 2);
+    assert_eq!(pdb.atom(1).unwrap().element(), Some(&Element::O));
+    let errors = save(&pdb, dump("ligand_saved.pdbqt"), StrictnessLevel::Loose).unwrap_err();
+    assert_eq!(errors[0].short_description(), "Format not supported");
+}
+
+#[test]
+fn compression() {
+    let (pdb, _) = open("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+    let mut names = vec!["1ubq.pdb.gz", "1ubq.mmcif.GZ"];
+    if cfg!(feature = "bzip2") {
+        names.extend(["1ubq.pdb.bz2", "1ubq.cif.bz2"]);
+    }
+    if cfg!(feature = "zstd") {
+        names.extend(["1ubq.ent.zst", "1ubq.cif.zst"]);
+    }
+    if cfg!(feature = "xz") {
+        names.extend(["1ubq.pdb.xz", "1ubq.cif.xz"]);
+    }
+    for name in names {
+        save(&pdb, dump(name), StrictnessLevel::Loose).unwrap();
+        assert!(
+            std::fs::metadata(dump(name)).unwrap().len() < 100_000,
+            "{name}"
+        );
+        let (reread, _) = open(dump(name), StrictnessLevel::Loose).unwrap();
+        assert_eq!(reread.atom_count(), pdb.atom_count(), "{name}");
+        assert!(reread.atoms().eq(pdb.atoms()), "{name}");
+    }
+
+    // The type of compressed files without a known extension is detected from the contents
+    std::fs::copy(dump("1ubq.pdb.gz"), dump("1ubq.gz")).unwrap();
+    let (reread, _) = open(dump("1ubq.gz"), StrictnessLevel::Loose).unwrap();
+    assert_eq!(reread.atom_count(), pdb.atom_count());
+    let (reread, _) = open_gz(dump("1ubq.mmcif.GZ"), StrictnessLevel::Loose).unwrap();
+    assert_eq!(reread.atom_count(), pdb.atom_count());
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn compression_feature_missing() {
+    let (pdb, _) = open("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+    let errors = save(
+        &pdb,
+        dump("missing_feature.pdb.zst"),
+        StrictnessLevel::Loose,
+    )
+    .unwrap_err();
+    assert_eq!(errors[0].short_description(), "Compression not supported");
+    assert!(!std::path::Path::new(&dump("missing_feature.pdb.zst")).exists());
+}
@@ -0,0 +1,60 @@
+use pdbtbx::*;
+use std::io::BufWriter;
+
+fn label_ids(pdb: &PDB) -> Vec<(Option<&str>, Option<isize>, Option<&str>)> {
+    pdb.residues()
+        .map(|r| (r.label_asym_id(), r.label_seq_id(), r.label_entity_id()))
+        .collect()
+}
+
+#[test]
+fn label_ids_round_trip() {
+    let (mut pdb, _) = open_mmcif("example-pdbs/1ubq.cif", StrictnessLevel::Loose).unwrap();
+    let water = pdb.residues().find(|r| r.name() == Some("HOH")).unwrap();
+    assert_eq!(water.serial_number(), 77);
+    assert_eq!(water.label_asym_id(), Some("B"));
+    assert_eq!(water.label_seq_id(), None);
+    assert_eq!(water.label_entity_id(), Some("2"));
+    let first = pdb.residue(0).unwrap();
+    assert_eq!(first.label_asym_id(), Some("A"));
+    assert_eq!(first.label_seq_id(), Some(1));
+    assert_eq!(first.label_entity_id(), Some("1"));
+
+    // Both the author and label identifiers can be searched
+    let count = |pdb: &PDB, selection: &str| pdb.find(selection.parse().unwrap()).count();
+    assert_eq!(count(&pdb, "chain A"), pdb.atom_count());
+    assert_eq!(count(&pdb, "label_chain B"), 58);
+    assert_eq!(count(&pdb, "entity 2"), 58);
+    assert_eq!(count(&pdb, "label_seq 1-76"), count(&pdb, "resi 1-76"));
+    assert_eq!(count(&pdb, "label_seq > 76"), 0);
+
+    // Renumber the author residue numbers, the label residue numbers stay the same
+    for residue in pdb.residues_mut() {
+        residue.set_serial_number(residue.serial_number() + 100);
+    }
+    assert_eq!(count(&pdb, "label_seq 1 and resi 101"), 8);
+
+    let mut buffer = Vec::new();
+    save_mmcif_raw(&pdb, BufWriter::new(&mut buffer));
+    let text = String::from_utf8(buffer).unwrap();
+    let (reread, _) = open_mmcif_raw(&text, StrictnessLevel::Loose).unwrap();
+    assert_eq!(label_ids(&reread), label_ids(&pdb));
+    assert_eq!(reread.residue(0).unwrap().serial_number(), 101);
+}
+
+#[test]
+fn generated_label_ids() {
+    let (pdb, _) = open_pdb("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+    assert!(pdb.residues().all(|r| r.label_asym_id().is_none()
+        && r.label_seq_id().is_none()
+        && r.label_entity_id().is_none()));
+
+    let mut buffer = Vec::new();
+    save_mmcif_raw(&pdb, BufWriter::new(&mut buffer));
+    let text = String::from_utf8(buffer).unwrap();
+    let (reread, _) = open_mmcif_raw(&text, StrictnessLevel::Loose).unwrap();
+    let last = reread.residues().last().unwrap();
+    assert_eq!(last.label_asym_id(), Some("B"));
+    assert_eq!(last.label_seq_id(), Some(reread.residue_count() as isize));
+    assert_eq!(last.label_entity_id(), Some("1"));
+}
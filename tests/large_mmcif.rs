@@ -0,0 +1,60 @@
+use pdbtbx::*;
+
+/// Generate an mmCIF file with enough atoms to be parsed in multiple chunks, with an invalid
+/// atom type in the given rows
+fn generate(rows: usize, invalid: &[usize]) -> String {
+    let mut text = "data_LARGE
+loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.label_atom_id
+_atom_site.label_comp_id
+_atom_site.label_asym_id
+_atom_site.label_seq_id
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+"
+    .to_string();
+    for row in 0..rows {
+        let group = if invalid.contains(&row) {
+            "ATOMX"
+        } else {
+            "ATOM"
+        };
+        text.push_str(&format!(
+            "{group} {} C CA ALA A {} {}.5 1.0 -2.25\n",
+            row + 1,
+            row / 4 + 1,
+            row % 100
+        ));
+    }
+    text
+}
+
+#[test]
+fn large_atom_site_loop() {
+    let (pdb, errors) = open_mmcif_raw(&generate(5000, &[]), StrictnessLevel::Loose).unwrap();
+    assert!(errors.is_empty());
+    assert_eq!(pdb.atom_count(), 5000);
+    assert_eq!(pdb.residue_count(), 1250);
+
+    // The atoms and errors are in the same order as the rows
+    let serial_numbers: Vec<usize> = pdb.atoms().map(Atom::serial_number).collect();
+    assert_eq!(serial_numbers, (1..=5000).collect::<Vec<_>>());
+    assert_eq!(pdb.atom(2999).unwrap().x(), 99.5);
+
+    let invalid = [3, 1500, 2047, 2048, 4999];
+    let errors = open_mmcif_raw(&generate(5000, &invalid), StrictnessLevel::Loose).unwrap_err();
+    let expected: Vec<Context> = invalid
+        .iter()
+        .map(|row| Context::show(format!("Main atomic data loop row: {row}")))
+        .collect();
+    let found: Vec<Context> = errors
+        .iter()
+        .filter(|e| e.short_description() == "Atom type not correct")
+        .map(|e| e.context().clone())
+        .collect();
+    assert_eq!(found, expected);
+}
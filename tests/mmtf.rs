@@ -0,0 +1,34 @@
+#![cfg(feature = "mmtf")]
+use pdbtbx::*;
+
+#[test]
+fn external_mmtf() {
+    // Not written by pdbtbx, 1ubq.cif encoded with the codecs the RCSB uses for full MMTF files,
+    // the waters are a separate MMTF chain with the same name as the protein chain
+    let (pdb, errors) = open("example-pdbs/1ubq.mmtf", StrictnessLevel::Strict).unwrap();
+    assert!(errors.is_empty(), "{errors:?}");
+    let (original, _) = open_mmcif("example-pdbs/1ubq.cif", StrictnessLevel::Loose).unwrap();
+    assert_eq!(pdb.identifier, original.identifier);
+    // The unit cell is stored in single precision
+    let (cell, expected) = (
+        pdb.unit_cell.as_ref().unwrap(),
+        original.unit_cell.as_ref().unwrap(),
+    );
+    assert!((cell.a() - expected.a()).abs() < 1e-5);
+    assert!((cell.b() - expected.b()).abs() < 1e-5);
+    assert!((cell.c() - expected.c()).abs() < 1e-5);
+    assert_eq!(
+        (cell.alpha(), cell.beta(), cell.gamma()),
+        (expected.alpha(), expected.beta(), expected.gamma())
+    );
+    assert_eq!(pdb.symmetry, original.symmetry);
+    assert_eq!(pdb.chain_count(), 1);
+    assert_eq!(pdb.residue_count(), original.residue_count());
+    assert_eq!(pdb.atom_count(), 660);
+    assert!(pdb.atoms().eq(original.atoms()));
+    assert!(pdb
+        .residues()
+        .map(Residue::name)
+        .eq(original.residues().map(Residue::name)));
+    assert!(pdb.bonds().count() > 600);
+}
@@ -0,0 +1,52 @@
+use pdbtbx::*;
+
+/// Bonds refer to the unique counters of atoms, so separately read files are compared without these
+fn assert_same(a: &PDB, b: &PDB) {
+    assert_eq!(a.identifier, b.identifier);
+    assert_eq!(a.unit_cell, b.unit_cell);
+    assert_eq!(a.remark_count(), b.remark_count());
+    assert_eq!(a.bonds().count(), b.bonds().count());
+    assert!(a.models().eq(b.models()));
+}
+
+#[test]
+fn bytes_match_full_read() {
+    for file in [
+        "example-pdbs/1ubq.pdb",
+        "example-pdbs/1yyf.pdb",
+        "example-pdbs/models.pdb",
+        "example-pdbs/insertion_codes.pdb",
+    ] {
+        let (pdb, errors) = open_pdb(file, StrictnessLevel::Loose).unwrap();
+        let contents = std::fs::read(file).unwrap();
+        let (bytes, bytes_errors) =
+            open_pdb_bytes(&contents, Context::show(file), StrictnessLevel::Loose).unwrap();
+        assert_same(&bytes, &pdb);
+        assert_eq!(bytes_errors.len(), errors.len());
+
+        // Windows line endings give the same result
+        let windows = String::from_utf8(contents).unwrap().replace('\n', "\r\n");
+        let (bytes, _) =
+            open_pdb_bytes(windows.as_bytes(), Context::none(), StrictnessLevel::Loose).unwrap();
+        assert_same(&bytes, &pdb);
+    }
+}
+
+#[test]
+fn invalid_utf8() {
+    let mut contents = std::fs::read("example-pdbs/1ubq.pdb").unwrap();
+    contents.extend_from_slice(b"REMARK \xff\xfe\n");
+    let errors = open_pdb_bytes(&contents, Context::none(), StrictnessLevel::Loose).unwrap_err();
+    assert!(errors
+        .iter()
+        .any(|e| e.level() == ErrorLevel::BreakingError));
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mmap_matches_full_read() {
+    let (pdb, _) = open_pdb("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+    let (mapped, _) = open_pdb_mmap("example-pdbs/1ubq.pdb", StrictnessLevel::Loose).unwrap();
+    assert_same(&mapped, &pdb);
+    assert!(open_pdb_mmap("example-pdbs/missing.pdb", StrictnessLevel::Loose).is_err());
+}
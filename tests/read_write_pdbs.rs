@@ -25,7 +25,12 @@ fn run_pdbs() {
         let entry = entry.unwrap();
         let path = entry.path();
         let metadata = fs::metadata(&path).unwrap();
-        if metadata.is_file() {
+        let bcif = path.extension().map_or(false, |e| e == "bcif");
+        let mmtf = path.extension().map_or(false, |e| e == "mmtf");
+        if metadata.is_file()
+            && (cfg!(feature = "bcif") || !bcif)
+            && (cfg!(feature = "mmtf") || !mmtf)
+        {
             do_something(
                 &path.clone().into_os_string().into_string().unwrap(),
                 &dump_dir,
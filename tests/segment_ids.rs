@@ -0,0 +1,71 @@
+use pdbtbx::*;
+use std::io::BufWriter;
+
+/// Two water molecules and an ion as written by CHARMM, with the segment ids in columns 73-76
+const SOLVENT: &str =
+    "ATOM      1  OH2 TIP3W   1       0.000   0.000   0.000  1.00  0.00      WT1  O
+ATOM      2  H1  TIP3W   1       0.957   0.000   0.000  1.00  0.00      WT1  H
+ATOM      3  OH2 TIP3W   2       5.000   0.000   0.000  1.00  0.00      WT2  O
+ATOM      4  H1  TIP3W   2       5.957   0.000   0.000  1.00  0.00      WT2  H
+HETATM    5 SOD  SOD I   1      10.000   0.000   0.000  1.00  0.00          NA
+END
+";
+
+fn segment_ids(pdb: &PDB) -> Vec<Option<&str>> {
+    pdb.atoms().map(Atom::segment_id).collect()
+}
+
+#[test]
+fn segment_ids_round_trip() {
+    let (pdb, errors) = open_pdb_raw(
+        std::io::BufReader::new(SOLVENT.as_bytes()),
+        Context::none(),
+        StrictnessLevel::Loose,
+    )
+    .unwrap();
+    assert!(errors.is_empty());
+    let expected = vec![Some("WT1"), Some("WT1"), Some("WT2"), Some("WT2"), None];
+    assert_eq!(segment_ids(&pdb), expected);
+    assert_eq!(pdb.find("segid WT2".parse().unwrap()).count(), 2);
+    assert_eq!(pdb.find("segid \"\"".parse().unwrap()).count(), 1);
+
+    let mut buffer = Vec::new();
+    save_pdb_raw(&pdb, BufWriter::new(&mut buffer), StrictnessLevel::Loose);
+    let text = String::from_utf8(buffer).unwrap();
+    assert_eq!(
+        text.lines()
+            .filter(|line| line.get(72..76) == Some("WT2 "))
+            .count(),
+        2
+    );
+    let (reread, _) = open_pdb_raw(
+        std::io::BufReader::new(text.as_bytes()),
+        Context::none(),
+        StrictnessLevel::Loose,
+    )
+    .unwrap();
+    assert_eq!(segment_ids(&reread), expected);
+
+    let mut buffer = Vec::new();
+    save_mmcif_raw(&pdb, BufWriter::new(&mut buffer));
+    let text = String::from_utf8(buffer).unwrap();
+    assert!(text.contains("_atom_site.auth_seg_id"));
+    let (reread, _) = open_mmcif_raw(&text, StrictnessLevel::Loose).unwrap();
+    assert_eq!(segment_ids(&reread), expected);
+}
+
+#[test]
+fn invalid_segment_id() {
+    let line = SOLVENT.lines().next().unwrap().replace("WT1 ", "A\tB ");
+    let (pdb, errors) = open_pdb_raw(
+        std::io::BufReader::new(line.as_bytes()),
+        Context::none(),
+        StrictnessLevel::Loose,
+    )
+    .unwrap();
+    assert_eq!(pdb.atom_count(), 1);
+    assert_eq!(segment_ids(&pdb), vec![None]);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].short_description(), "Invalid segment id");
+    assert_eq!(errors[0].level(), ErrorLevel::LooseWarning);
+}